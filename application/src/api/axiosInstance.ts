@@ -9,5 +9,5 @@ const prodBaseURL = "http://192.168.1.56:3030"; // Production URL
 export const baseURL = isDev === true ? devBaseURL : prodBaseURL;
 
 export const axiosInstance = axios.create({
-  baseURL: baseURL,
+  baseURL: `${baseURL}/api/v1`,
 });
@@ -6,10 +6,10 @@ import { Input } from "@/components/ui/input";
 import { useGetUserProfile } from "@/hooks/data/queries/useGetProfileInfo";
 
 interface NoteFormProps {
-  id: string;
+  id: number;
   onSuccess: () => void;
   createNote: (data: {
-    id: string;
+    id: number;
     note: { submitter: string; message: string };
   }) => Promise<void>;
 }
@@ -29,7 +29,7 @@ const formSchema = z.object({
 interface CreatePartFormProps {
   onSuccess: () => void;
   onCancel: () => void;
-  exhibitId?: string;
+  exhibitId?: number;
 }
 
 export function CreatePartForm({
@@ -55,7 +55,7 @@ export function CreatePartForm({
       const newPart: NewPart = {
         name: values.name,
         link: values.link,
-        exhibit_ids: exhibitId ? [exhibitId] : [],
+        exhibit_ids: exhibitId !== undefined ? [exhibitId] : [],
         notes: [],
       };
       await createPartMutation.mutateAsync(newPart);
@@ -15,7 +15,7 @@ import { Input } from "@/components/ui/input";
 import { Button } from "@/components/ui/button";
 import { useQueryClient } from "@tanstack/react-query";
 import useEditPart from "@/hooks/data/mutations/parts/useEditPart";
-import type { Part } from "@/types";
+import type { Part } from "@/types/models";
 
 const formSchema = z.object({
   name: z.string().min(2, {
@@ -46,7 +46,7 @@ export function EditPartForm({ part, onSuccess }: EditPartFormProps) {
   async function onSubmit(values: z.infer<typeof formSchema>) {
     try {
       await editPartMutation.mutateAsync({
-        id: part.id,
+        id: part.id!,
         payload: values,
       });
       toast({
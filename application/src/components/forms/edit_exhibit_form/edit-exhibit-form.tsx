@@ -8,7 +8,7 @@ import { Button } from "@/components/ui/button";
 import { useQueryClient } from "@tanstack/react-query";
 import { useGetUniqueClustersAndLocations } from "@/hooks/util/useGetUniqueClustersAndLocations";
 import useEditExhibit from "@/hooks/data/mutations/exhibits/useEditExhibit";
-import type { Exhibit } from "@/types";
+import type { Exhibit } from "@/types/models";
 import { BasicInfoStep } from "./steps/basic-info-step";
 import { DetailsStep } from "./steps/details-step";
 import { ImageUploadStep } from "./steps/image-upload-step";
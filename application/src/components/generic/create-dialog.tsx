@@ -14,7 +14,7 @@ import { CreateExhibitForm } from "@/components/forms/create_exhibit_form/create
 
 interface CreateDialogProps {
   type: "exhibit" | "part";
-  exhibitId?: string;
+  exhibitId?: number;
 }
 
 export function CreateDialog({ type, exhibitId }: CreateDialogProps) {
@@ -10,10 +10,10 @@ import { EditExhibitForm } from "@/components/forms/edit_exhibit_form/edit-exhib
 import { EditPartForm } from "@/components/forms/edit-part-form";
 import useGetExhibit from "@/hooks/data/queries/exhibits/useGetExhibit";
 import useGetPart from "@/hooks/data/queries/parts/useGetPart";
-import type { Part, Exhibit } from "@/types";
+import type { Part, Exhibit } from "@/types/models";
 
 interface GenericEditDialogProps {
-  id: string;
+  id: number;
   type: "exhibit" | "part";
   isOpen: boolean;
   onClose: () => void;
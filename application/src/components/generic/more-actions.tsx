@@ -22,7 +22,7 @@ import useDeleteExhibit from "@/hooks/data/mutations/exhibits/useDeleteExhibit";
 import { GenericEditDialog } from "@/components/generic/edit-dialogue";
 
 interface MoreActionsProps {
-  id: string;
+  id: number;
   type: "exhibit" | "part";
 }
 
@@ -3,10 +3,10 @@ import useCreateExhibitNote from "@/hooks/data/mutations/exhibits/useCreateExhib
 import useDeleteExhibitNote from "@/hooks/data/mutations/exhibits/useDeleteExhibitNote";
 import useCreatePartNote from "@/hooks/data/mutations/parts/useCreatePartNote";
 import useDeletePartNote from "@/hooks/data/mutations/parts/useDeletePartNote";
-import type { Note } from "@/types";
+import type { Note } from "@/types/models";
 
 interface NotesButtonProps {
-  id: string;
+  id: number;
   type: "exhibit" | "part";
   name: string;
   notes: Array<Note>;
@@ -28,7 +28,7 @@ export function NotesButton({
   const deletePartNoteMutation = useDeletePartNote();
 
   const createNote = async (data: {
-    id: string;
+    id: number;
     note: { submitter: string; message: string };
   }) => {
     if (type === "exhibit") {
@@ -44,7 +44,7 @@ export function NotesButton({
     }
   };
 
-  const deleteNote = async (data: { id: string; noteId: string }) => {
+  const deleteNote = async (data: { id: number; noteId: number }) => {
     if (type === "exhibit") {
       await deleteExhibitNoteMutation.mutateAsync({
         exhibitId: data.id,
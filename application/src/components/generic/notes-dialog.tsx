@@ -27,22 +27,22 @@ import {
 } from "@/components/ui/dialog";
 import { Badge } from "@/components/ui/badge";
 import { Separator } from "@/components/ui/separator";
-import type { Note } from "@/types";
+import type { Note } from "@/types/models";
 import { NoteForm } from "@/components/forms/create-note-form";
 import { Input } from "@/components/ui/input";
 
 interface NotesDialogProps<T extends "part" | "exhibit"> {
-  id: string;
+  id: number;
   type: T;
   name?: string;
   notes: Note[];
   onNoteAdded?: () => void;
   onNoteDeleted?: () => void;
   createNote: (data: {
-    id: string;
+    id: number;
     note: { submitter: string; message: string };
   }) => Promise<void>;
-  deleteNote: (data: { id: string; noteId: string }) => Promise<void>;
+  deleteNote: (data: { id: number; noteId: number }) => Promise<void>;
 }
 
 export function NotesDialog<T extends "part" | "exhibit">({
@@ -68,7 +68,7 @@ export function NotesDialog<T extends "part" | "exhibit">({
     }
   }, [isAddingNote]);
 
-  const handleDelete = async (noteId: string) => {
+  const handleDelete = async (noteId: number) => {
     setIsDeleting(true);
     try {
       await deleteNote({ id, noteId });
@@ -183,7 +183,7 @@ export function NotesDialog<T extends "part" | "exhibit">({
 
 interface NotesListProps {
   notes: Note[];
-  handleDelete: (noteId: string) => void;
+  handleDelete: (noteId: number) => void;
   isDeleting: boolean;
 }
 
@@ -202,10 +202,7 @@ function NotesList({ notes, handleDelete, isDeleting }: NotesListProps) {
         <div className="space-y-4">
           {notes
             .sort((a, b) =>
-              compareDesc(
-                new Date(a.timestamp.date + " " + a.timestamp.time),
-                new Date(b.timestamp.date + " " + b.timestamp.time)
-              )
+              compareDesc(new Date(a.created_at), new Date(b.created_at))
             )
             .map((note) => (
               <NoteCard
@@ -223,16 +220,13 @@ function NotesList({ notes, handleDelete, isDeleting }: NotesListProps) {
 
 interface NoteCardProps {
   note: Note;
-  handleDelete: (noteId: string) => void;
+  handleDelete: (noteId: number) => void;
   isDeleting: boolean;
 }
 
 function NoteCard({ note, handleDelete, isDeleting }: NoteCardProps) {
   const [showConfirm, setShowConfirm] = useState(false);
-  const formattedDate = format(
-    new Date(note.timestamp.date + " " + note.timestamp.time),
-    "PPpp"
-  );
+  const formattedDate = format(new Date(note.created_at), "PPpp");
 
   const onDelete = () => {
     setShowConfirm(true);
@@ -48,9 +48,14 @@ export function NavSecondary({
   );
 }
 
-import { axiosInstance } from "@/api/axiosInstance";
+import { axiosInstance, baseURL } from "@/api/axiosInstance";
 import { DatabaseZap, DatabaseBackup } from "lucide-react";
 
+// Must match `dev_tools_token` in the backend's Rocket.toml
+const devToolsHeaders = {
+  headers: { "X-Confirm-Token": import.meta.env.VITE_DEV_TOOLS_TOKEN ?? "" },
+};
+
 function DevTools() {
   return (
     <>
@@ -58,7 +63,13 @@ function DevTools() {
         <SidebarMenuButton
           size="sm"
           className="text-muted-foreground hover:text-foreground"
-          onClick={() => axiosInstance.get("/exhibits/fill-dummy")}
+          onClick={() =>
+            axiosInstance.post(
+              `${baseURL}/dev/exhibits/fill-dummy`,
+              null,
+              devToolsHeaders
+            )
+          }
         >
           <DatabaseBackup />
           Fill DB
@@ -68,7 +79,9 @@ function DevTools() {
         <SidebarMenuButton
           size="sm"
           className="text-muted-foreground hover:text-foreground"
-          onClick={() => axiosInstance.get("/reset")}
+          onClick={() =>
+            axiosInstance.post(`${baseURL}/dev/reset`, null, devToolsHeaders)
+          }
         >
           <DatabaseZap />
           Flush DB
@@ -26,7 +26,7 @@ import {
 import { CreatePartDialog } from "@/components/create-part-dialog";
 import { motion, AnimatePresence } from "framer-motion";
 import { NotesDialog } from "@/components/NotesDialog";
-import type { Part } from "@/types";
+import type { Part } from "@/types/models";
 
 export function PartsButton({
   name,
@@ -34,8 +34,8 @@ export function PartsButton({
   exhibitId,
 }: {
   name: string;
-  parts: string[];
-  exhibitId: string;
+  parts: number[];
+  exhibitId: number;
 }) {
   const [isOpen, setIsOpen] = useState(false);
 
@@ -62,7 +62,7 @@ export function PartsButton({
   );
 }
 
-function PartsInnerDialog({ parts }: { parts: string[] }) {
+function PartsInnerDialog({ parts }: { parts: number[] }) {
   const { data, isLoading, isError, refetch } = useGetExhibitParts(parts);
 
   if (isLoading) {
@@ -9,8 +9,8 @@ import { LinkDisplay } from "@/components/link-display";
 
 interface PartsListProps {
   refetchPartIds: () => void;
-  partIds: string[];
-  exhibitId: string;
+  partIds: number[];
+  exhibitId: number;
 }
 
 export function PartsList({
@@ -25,9 +25,9 @@ export function PartsList({
     refetch,
   } = useGetExhibitParts(partIds);
   const editPartMutation = useEditPart();
-  const [removingPartId, setRemovingPartId] = useState<string | null>(null);
+  const [removingPartId, setRemovingPartId] = useState<number | null>(null);
 
-  const handleRemovePart = async (partId: string) => {
+  const handleRemovePart = async (partId: number) => {
     setRemovingPartId(partId);
     try {
       const partToUpdate = parts?.find((part) => part.id === partId);
@@ -37,9 +37,9 @@ export function PartsList({
           payload: {
             name: partToUpdate.name,
             link: partToUpdate.link,
-            exhibitIds: partToUpdate.exhibit_ids
-              .filter((id) => id !== exhibitId)
-              .map((id) => Number(id)),
+            exhibitIds: partToUpdate.exhibit_ids.filter(
+              (id) => id !== exhibitId
+            ),
           },
         });
         refetch();
@@ -103,7 +103,7 @@ export function PartsList({
                     <Button
                       variant="ghost"
                       size="icon"
-                      onClick={() => handleRemovePart(part.id)}
+                      onClick={() => handleRemovePart(part.id!)}
                       disabled={removingPartId === part.id}
                       className="h-8 w-8"
                     >
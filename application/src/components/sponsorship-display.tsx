@@ -1,14 +1,14 @@
 import { Card, CardContent } from "@/components/ui/card";
 import { Star } from "lucide-react";
 import { calculateTimeUntilExpiration } from "@/lib/date";
-import type { Sponsorship } from "@/types";
+import type { Sponsor } from "@/types/models";
 
 interface SponsorshipDisplayProps {
-  sponsorship?: Sponsorship;
+  sponsor: Sponsor | null;
 }
 
-export function SponsorshipDisplay({ sponsorship }: SponsorshipDisplayProps) {
-  if (!sponsorship) {
+export function SponsorshipDisplay({ sponsor }: SponsorshipDisplayProps) {
+  if (!sponsor) {
     return null;
   }
 
@@ -21,17 +21,17 @@ export function SponsorshipDisplay({ sponsorship }: SponsorshipDisplayProps) {
         </div>
         <div className="space-y-1 text-sm">
           <p>
-            <strong>Sponsor:</strong> {sponsorship.sponsorName}
+            <strong>Sponsor:</strong> {sponsor.name}
           </p>
           <p>
-            <strong>Start Date:</strong> {sponsorship.startDate}
+            <strong>Start Date:</strong> {sponsor.start_date}
           </p>
           <p>
-            <strong>End Date:</strong> {sponsorship.endDate}
+            <strong>End Date:</strong> {sponsor.end_date}
           </p>
           <p>
             <strong>Time until expiration:</strong>{" "}
-            {calculateTimeUntilExpiration(sponsorship.endDate)}
+            {calculateTimeUntilExpiration(sponsor.end_date)}
           </p>
         </div>
       </CardContent>
@@ -13,7 +13,7 @@ import { ScrollArea } from "@/components/ui/scroll-area";
 import { ChevronDown, ChevronRight, Plus } from "lucide-react";
 import { motion, AnimatePresence } from "framer-motion";
 import { cn } from "@/lib/utils";
-import type { Exhibit } from "@/types";
+import type { Exhibit } from "@/types/models";
 import { MoreActions } from "@/components/generic/more-actions";
 import { NotesButton } from "@/components/generic/notes-button";
 import { PartsList } from "@/components/parts-list";
@@ -47,7 +47,7 @@ import {
 import { Check } from "lucide-react";
 
 interface ExpandedState {
-  [key: string]: boolean;
+  [key: number]: boolean;
 }
 
 export function ExhibitsTable({
@@ -60,7 +60,7 @@ export function ExhibitsTable({
   const addExistingPartMutation = useAddExistingPart();
   const [expandedRows, setExpandedRows] = useState<ExpandedState>({});
   const [isAddPartModalOpen, setIsAddPartModalOpen] = useState(false);
-  const [selectedExhibitId, setSelectedExhibitId] = useState<string | null>(
+  const [selectedExhibitId, setSelectedExhibitId] = useState<number | null>(
     null
   );
   const [selectedPartId, setSelectedPartId] = useState<string | null>(null);
@@ -71,7 +71,7 @@ export function ExhibitsTable({
     return [...exhibits].sort((a, b) => a.name.localeCompare(b.name));
   }, [exhibits]);
 
-  const toggleRow = useCallback((id: string) => {
+  const toggleRow = useCallback((id: number) => {
     setExpandedRows((prev) => ({ ...prev, [id]: !prev[id] }));
   }, []);
 
@@ -87,7 +87,7 @@ export function ExhibitsTable({
     );
   }, []);
 
-  const handleAddPart = (exhibitId: string) => {
+  const handleAddPart = (exhibitId: number) => {
     setSelectedExhibitId(exhibitId);
     setIsAddPartModalOpen(true);
   };
@@ -100,11 +100,11 @@ export function ExhibitsTable({
   };
 
   const handleAddExistingPart = async () => {
-    if (selectedExhibitId && selectedPartId) {
+    if (selectedExhibitId !== null && selectedPartId) {
       try {
         await addExistingPartMutation.mutateAsync({
           exhibitId: selectedExhibitId,
-          partId: selectedPartId,
+          partId: Number(selectedPartId),
         });
         handleAddPartSuccess();
       } catch (error) {
@@ -113,7 +113,7 @@ export function ExhibitsTable({
     }
   };
 
-  const handleStatusChange = (exhibitId: string, newStatus: ExhibitStatus) => {
+  const handleStatusChange = (exhibitId: number, newStatus: ExhibitStatus) => {
     if (
       exhibits.find((exhibit) => exhibit.id === exhibitId)?.status === newStatus
     )
@@ -267,9 +267,7 @@ export function ExhibitsTable({
                               {exhibit.description ||
                                 "No description available."}
                             </p>
-                            <SponsorshipDisplay
-                              sponsorship={exhibit.sponsorship}
-                            />
+                            <SponsorshipDisplay sponsor={exhibit.sponsor} />
                             <div className="flex justify-between items-center mb-2">
                               <h4 className="text-sm font-semibold">Parts</h4>
                               <Button
@@ -331,7 +329,7 @@ export function ExhibitsTable({
                 {allParts?.map((part) => (
                   <SelectItem
                     key={part.id}
-                    value={part.id}
+                    value={String(part.id)}
                     className="cursor-pointer hover:bg-muted/50 transition-colors"
                   >
                     {part.name}
@@ -344,7 +342,7 @@ export function ExhibitsTable({
             <CreatePartForm
               onSuccess={handleAddPartSuccess}
               onCancel={() => setIsAddPartModalOpen(false)}
-              exhibitId={selectedExhibitId || undefined}
+              exhibitId={selectedExhibitId ?? undefined}
             />
           ) : (
             <Button onClick={handleAddExistingPart} disabled={!selectedPartId}>
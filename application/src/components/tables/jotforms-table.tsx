@@ -1,4 +1,5 @@
 import { useState, useMemo } from "react";
+import { format } from "date-fns";
 import { ScrollArea } from "@/components/ui/scroll-area";
 import {
   Table,
@@ -27,7 +28,7 @@ import {
   DropdownMenuTrigger,
 } from "@/components/ui/dropdown-menu";
 import useChangeStatus from "@/hooks/data/mutations/jotforms/useChangeStatus";
-import { Jotform } from "@/types";
+import { Jotform } from "@/types/models";
 import type {
   Status,
   NewStatusRequest,
@@ -61,8 +62,7 @@ export function JotformsTable({ jotforms }: { jotforms: Array<Jotform> }) {
 
       // If in the same group, sort by date (most recent first)
       return (
-        new Date(b.created_at.date).getTime() -
-        new Date(a.created_at.date).getTime()
+        new Date(b.created_at).getTime() - new Date(a.created_at).getTime()
       );
     });
   }, [jotforms]);
@@ -177,16 +177,10 @@ export function JotformsTable({ jotforms }: { jotforms: Array<Jotform> }) {
                   {jotform.submitter_name.first}
                 </TableCell>
                 <TableCell className="text-muted-foreground">
-                  {jotform.created_at.date}
+                  {format(new Date(jotform.created_at), "yyyy-MM-dd")}
                 </TableCell>
                 <TableCell className="text-muted-foreground">
-                  {(() => {
-                    const [hours, minutes] = jotform.created_at.time.split(":");
-                    const hour = parseInt(hours);
-                    const ampm = hour >= 12 ? "PM" : "AM";
-                    const hour12 = hour % 12 || 12;
-                    return `${hour12}:${minutes} ${ampm}`;
-                  })()}
+                  {format(new Date(jotform.created_at), "h:mm a")}
                 </TableCell>
                 <TableCell>{jotform.location}</TableCell>
                 <TableCell>{jotform.exhibit_name}</TableCell>
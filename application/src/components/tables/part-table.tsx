@@ -12,7 +12,7 @@ import { Atom } from "lucide-react";
 import { MoreActions } from "@/components/generic/more-actions";
 import { LinkDisplay } from "@/components/link-display";
 import { NotesButton } from "@/components/generic/notes-button";
-import type { Part } from "@/types";
+import type { Part } from "@/types/models";
 
 interface PartTableProps {
   parts: Part[];
@@ -49,14 +49,14 @@ export function PartTable({ parts }: PartTableProps) {
               </TableCell>
               <TableCell>
                 <NotesButton
-                  id={part.id}
+                  id={part.id!}
                   type="part"
                   name={part.name}
                   notes={part.notes}
                 />
               </TableCell>
               <TableCell>
-                <MoreActions id={part.id} type="part" />
+                <MoreActions id={part.id!} type="part" />
               </TableCell>
             </TableRow>
           ))}
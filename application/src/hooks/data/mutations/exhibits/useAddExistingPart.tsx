@@ -3,8 +3,8 @@ import { axiosInstance } from "@/api/axiosInstance";
 import { toast } from "react-hot-toast";
 
 interface AddExistingPartPayload {
-  exhibitId: string;
-  partId: string;
+  exhibitId: number;
+  partId: number;
 }
 
 async function addExistingPart({ exhibitId, partId }: AddExistingPartPayload) {
@@ -5,7 +5,7 @@ import { toast } from "react-hot-toast";
 export type ExhibitStatus = "Operational" | "Needs Repair" | "Out of Service";
 
 export interface ChangeExhibitStatusRequest {
-  exhibitId: string;
+  exhibitId: number;
   newStatus: ExhibitStatus;
 }
 
@@ -1,5 +1,5 @@
 import { useMutation, useQueryClient } from "@tanstack/react-query";
-import type { Exhibit } from "@/types";
+import type { Exhibit } from "@/types/models";
 import { axiosInstance } from "@/api/axiosInstance";
 import { toast } from "react-hot-toast";
 
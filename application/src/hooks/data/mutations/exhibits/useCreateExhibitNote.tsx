@@ -21,7 +21,7 @@ type NewExhibitNote = {
 };
 
 export interface NewExhibitNoteRequest {
-  exhibitId: string;
+  exhibitId: number;
   note: NewExhibitNote;
 }
 
@@ -2,7 +2,7 @@ import { useMutation, useQueryClient } from "@tanstack/react-query";
 import { axiosInstance } from "@/api/axiosInstance";
 import { toast } from "react-hot-toast";
 
-async function deleteExhibit(exhibit_id: string) {
+async function deleteExhibit(exhibit_id: number) {
   const response = await axiosInstance.delete("/exhibits/" + exhibit_id);
 
   if (response.status !== 204) {
@@ -17,7 +17,7 @@ export default function useDeleteExhibit() {
 
   return useMutation({
     mutationKey: ["deleteExhibit"],
-    mutationFn: (exhibit_id: string) =>
+    mutationFn: (exhibit_id: number) =>
       toast.promise(deleteExhibit(exhibit_id), {
         loading: "Deleting exhibit...",
         success: "Exhibit deleted successfully",
@@ -17,8 +17,8 @@ async function deleteExhibitNote(
 }
 
 interface DeleteExhibitNoteRequest {
-  exhibitId: string;
-  noteId: string;
+  exhibitId: number;
+  noteId: number;
 }
 
 export default function useDeleteExhibitNote() {
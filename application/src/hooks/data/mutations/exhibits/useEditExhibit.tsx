@@ -11,7 +11,7 @@ import { axiosInstance } from "@/api/axiosInstance";
 import { toast } from "react-hot-toast";
 
 async function editExhibit(
-  id: string,
+  id: number,
   updateExhibitPayload: UpdateExhibitPayload
 ) {
   const response = await axiosInstance.put(
@@ -35,7 +35,7 @@ export default function useEditExhibit() {
       id,
       payload,
     }: {
-      id: string;
+      id: number;
       payload: UpdateExhibitPayload;
     }) =>
       toast.promise(editExhibit(id, payload), {
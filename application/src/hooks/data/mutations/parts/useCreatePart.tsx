@@ -5,8 +5,8 @@ import { toast } from "react-hot-toast";
 export interface NewPart {
   name: string;
   link: string;
-  exhibit_ids?: Array<string>;
-  notes?: Array<{ timestamp: string; note: string }>;
+  exhibit_ids?: Array<number>;
+  notes?: Array<{ submitter: string; message: string }>;
 }
 
 async function createPart(part: NewPart) {
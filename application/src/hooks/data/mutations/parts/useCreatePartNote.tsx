@@ -21,7 +21,7 @@ type NewPartNote = {
 };
 
 export interface NewPartNoteRequest {
-  partId: string;
+  partId: number;
   note: NewPartNote;
 }
 
@@ -2,7 +2,7 @@ import { useMutation, useQueryClient } from "@tanstack/react-query";
 import { axiosInstance } from "@/api/axiosInstance";
 import { toast } from "react-hot-toast";
 
-async function deletePart(part_id: string) {
+async function deletePart(part_id: number) {
   const response = await axiosInstance.delete("/parts/" + part_id);
 
   if (response.status !== 204) {
@@ -17,7 +17,7 @@ export default function useDeletePart() {
 
   return useMutation({
     mutationKey: ["deletePart"],
-    mutationFn: (part_id: string) =>
+    mutationFn: (part_id: number) =>
       toast.promise(deletePart(part_id), {
         loading: "Deleting part...",
         success: "Part deleted successfully",
@@ -15,8 +15,8 @@ async function deletePartNote(deletePartNoteRequest: DeletePartNoteRequest) {
 }
 
 interface DeletePartNoteRequest {
-  partId: string;
-  noteId: string;
+  partId: number;
+  noteId: number;
 }
 
 export default function useDeletePartNote() {
@@ -8,7 +8,7 @@ import { useMutation, useQueryClient } from "@tanstack/react-query";
 import { axiosInstance } from "@/api/axiosInstance";
 import { toast } from "react-hot-toast";
 
-async function editPart(id: string, updatePartPayload: UpdatePartPayload) {
+async function editPart(id: number, updatePartPayload: UpdatePartPayload) {
   const response = await axiosInstance.put(`/parts/${id}`, updatePartPayload);
 
   if (response.status !== 200) {
@@ -23,7 +23,7 @@ export default function useEditPart() {
 
   return useMutation({
     mutationKey: ["editPart"],
-    mutationFn: ({ id, payload }: { id: string; payload: UpdatePartPayload }) =>
+    mutationFn: ({ id, payload }: { id: number; payload: UpdatePartPayload }) =>
       toast.promise(editPart(id, payload), {
         loading: "Editing part...",
         success: "Part edited successfully",
@@ -1,12 +1,12 @@
 import { useQuery } from "@tanstack/react-query";
 import { axiosInstance } from "@/api/axiosInstance";
-import type { Exhibit } from "@/types";
+import type { Exhibit } from "@/types/models";
 
 /// This is the dumbest implementation for this probably ever
 /// but i don't have the time or energy to care right now
 /// please for the love of god come and fix this later with a real
 /// backend endpoint
-async function getExhibitById(id: string): Promise<Exhibit> {
+async function getExhibitById(id: number): Promise<Exhibit> {
   const response = await axiosInstance.get<Exhibit[]>("/exhibits");
 
   // Find the exhibit with the matching ID
@@ -24,7 +24,7 @@ interface UseGetExhibitOptions {
 }
 
 export default function useGetExhibit(
-  id: string,
+  id: number,
   options: UseGetExhibitOptions = {}
 ) {
   return useQuery<Exhibit>({
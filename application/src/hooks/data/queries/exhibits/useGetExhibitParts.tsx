@@ -1,8 +1,8 @@
 import { useQuery } from "@tanstack/react-query";
-import type { Part } from "@/types";
+import type { Part } from "@/types/models";
 import { axiosInstance } from "@/api/axiosInstance";
 
-export async function getPartsByIds(partIds: string[]): Promise<Part[]> {
+export async function getPartsByIds(partIds: number[]): Promise<Part[]> {
   const response = await axiosInstance.post<Part[]>("/parts/batch", partIds, {
     headers: {
       "Content-Type": "application/json",
@@ -12,7 +12,7 @@ export async function getPartsByIds(partIds: string[]): Promise<Part[]> {
 }
 
 ///
-export default function useGetExhibitParts(partIds: string[]) {
+export default function useGetExhibitParts(partIds: number[]) {
   return useQuery<Part[]>({
     queryKey: ["parts", partIds],
     queryFn: () => getPartsByIds(partIds),
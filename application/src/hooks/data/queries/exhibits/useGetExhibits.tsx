@@ -1,6 +1,6 @@
 import { useQuery } from "@tanstack/react-query";
 import { axiosInstance } from "@/api/axiosInstance";
-import type { Exhibit } from "@/types";
+import type { Exhibit } from "@/types/models";
 
 async function getExhibits() {
   const response = await axiosInstance.get<Exhibit[]>("/exhibits");
@@ -1,5 +1,5 @@
 import { useQuery } from "@tanstack/react-query";
-import type { Exhibit } from "@/types";
+import type { Exhibit } from "@/types/models";
 import { axiosInstance } from "@/api/axiosInstance";
 
 async function getRandomExhibit() {
@@ -1,6 +1,6 @@
 import { useQuery } from "@tanstack/react-query";
 import { axiosInstance } from "@/api/axiosInstance";
-import { Jotform } from "@/types";
+import { Jotform } from "@/types/models";
 
 async function getJotforms() {
   const response = await axiosInstance.get<Jotform[]>("/jotforms");
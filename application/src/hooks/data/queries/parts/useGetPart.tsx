@@ -1,12 +1,12 @@
 import { useQuery } from "@tanstack/react-query";
 import { axiosInstance } from "@/api/axiosInstance";
-import type { Part } from "@/types";
+import type { Part } from "@/types/models";
 
 /// This is the dumbest implementation for this probably ever
 /// but i don't have the time or energy to care right now
 /// please for the love of god come and fix this later with a real
 /// backend endpoint
-async function getPartById(id: string): Promise<Part> {
+async function getPartById(id: number): Promise<Part> {
   const response = await axiosInstance.get<Part[]>("/parts");
 
   // Find the exhibit with the matching ID
@@ -24,7 +24,7 @@ interface UseGetPartOptions {
 }
 
 export default function useGetPart(
-  id: string,
+  id: number,
   options: UseGetPartOptions = {}
 ) {
   return useQuery<Part>({
@@ -1,5 +1,5 @@
 import { useQuery } from "@tanstack/react-query";
-import type { Part } from "@/types";
+import type { Part } from "@/types/models";
 import { axiosInstance } from "@/api/axiosInstance";
 
 async function getParts() {
@@ -1,5 +1,5 @@
 import { useState, useMemo } from "react";
-import type { Exhibit } from "@/types";
+import type { Exhibit } from "@/types/models";
 
 export function useExhibitFilters(exhibits: Exhibit[]) {
   const [searchTerm, setSearchTerm] = useState("");
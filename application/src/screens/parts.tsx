@@ -37,12 +37,15 @@ export default function PartsInventory() {
         ) {
           return false;
         }
-        if (exhibitFilter && !part.exhibit_ids.includes(exhibitFilter)) {
+        if (
+          exhibitFilter &&
+          !part.exhibit_ids.includes(Number(exhibitFilter))
+        ) {
           return false;
         }
         if (
           connectedExhibitFilter &&
-          !part.exhibit_ids.includes(connectedExhibitFilter)
+          !part.exhibit_ids.includes(Number(connectedExhibitFilter))
         ) {
           return false;
         }
@@ -79,10 +82,10 @@ export default function PartsInventory() {
     {
       value: exhibitFilter,
       onChange: setExhibitFilter,
-      options: uniqueExhibits.map((e) => e.id),
+      options: uniqueExhibits.map((e) => String(e.id)),
       placeholder: "Filter by Exhibit",
       labelFunction: (value) =>
-        uniqueExhibits.find((e) => e.id === value)?.name || value,
+        uniqueExhibits.find((e) => String(e.id) === value)?.name || value,
     },
   ];
 
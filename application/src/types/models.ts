@@ -0,0 +1,325 @@
+// Generated from the backend's models. Run `UPDATE_TYPES=1 cargo test` in backend/ after changing them.
+// This file has been generated by Specta. DO NOT EDIT.
+
+/**
+ * A database backup in the backups directory.
+ */
+export type BackupInfo = { 
+/**
+ * The backup's file name, used to download or restore it.
+ */
+name: string; created_at: string; size_bytes: number; 
+/**
+ * Whether the backup has a copy of the images directory as well as the database.
+ */
+includes_images: boolean }
+
+export type BugReport = { name: string; title: string; description: string; 
+/**
+ * The version of the app the bug was seen in.
+ */
+app_version: string | null; 
+/**
+ * The operating system the app was running on.
+ */
+os: string | null; 
+/**
+ * A PNG or JPEG screenshot, base64-encoded, optionally as a `data:` URL.
+ */
+screenshot: string | null }
+
+/**
+ * Where a bug report is up to.
+ */
+export type BugReportStatus = 
+/**
+ * Waiting to be filed on GitHub, for the first time or after a failed attempt.
+ */
+"pending" | 
+/**
+ * Filed as a GitHub issue.
+ */
+"filed" | 
+/**
+ * Every attempt to file it failed, so it won't be retried.
+ */
+"failed"
+
+/**
+ * Where a delivery is up to.
+ */
+export type DeliveryStatus = 
+/**
+ * Waiting for its first attempt or a retry.
+ */
+"pending" | "delivered" | 
+/**
+ * Every attempt failed, so it won't be retried.
+ */
+"failed"
+
+export type Exhibit = { id: number; name: string; cluster: string; location: string; description: string; status: string; part_ids: number[]; notes: Note[]; image_url: string; sponsor: Sponsor | null; 
+/**
+ * Incremented on every write; exposed as the exhibit's ETag.
+ */
+version: number }
+
+/**
+ * A sponsorship that ends soon, with what's needed to follow up on it.
+ */
+export type ExpiringSponsorship = ({ id: number; exhibit_id: number; sponsor_id: number; sponsor_name: string; start_date: string; end_date: string; 
+/**
+ * The pledged amount in cents, if known.
+ */
+amount_cents: number | null; notes: string | null }) & { exhibit_name: string; contact_name: string | null; contact_email: string | null; contact_phone: string | null; days_remaining: number }
+
+export type FullName = { first: string; last: string }
+
+/**
+ * The unit of a maintenance task's repeat interval.
+ */
+export type IntervalUnit = "days" | "weeks" | "months"
+
+export type Jotform = { id: string; submitter_name: FullName; 
+/**
+ * When it was submitted, or the Unix epoch if that couldn't be read.
+ */
+created_at: string; location: string; exhibit_name: string; description: string; priority_level: string; department: string; status: string }
+
+/**
+ * A record of someone completing a maintenance task.
+ */
+export type MaintenanceCompletion = { id: number; task_id: number; completed_by: string; completed_at: string; 
+/**
+ * The due date this completion satisfied.
+ */
+due_date: string; notes: string | null }
+
+/**
+ * A recurring maintenance task on an exhibit, e.g. "clean lenses every 14 days".
+ * 
+ * `next_due_date` is recalculated from the completion date each time the task is done.
+ */
+export type MaintenanceTask = { id: number; exhibit_id: number; title: string; description: string; interval_every: number; interval_unit: IntervalUnit; next_due_date: string; 
+/**
+ * Whether a ticket should be opened automatically when the task comes due.
+ */
+auto_create_ticket: boolean }
+
+export type Note = { id: number; submitter: string; message: string; created_at: string; 
+/**
+ * When the message was last edited, if ever.
+ */
+edited_at: string | null; attachments: NoteAttachment[] }
+
+/**
+ * A file or photo attached to a note. The contents are downloaded separately.
+ */
+export type NoteAttachment = { id: number; note_id: number; file_name: string; content_type: string; size_bytes: number; created_at: string }
+
+/**
+ * A previous version of a note's message, recorded when the note is edited.
+ */
+export type NoteRevision = { id: number; note_id: number; 
+/**
+ * The message as it was before the edit.
+ */
+message: string; edited_by: string; edited_at: string }
+
+/**
+ * The kind of record a note is attached to.
+ */
+export type NoteTarget = "exhibit" | "part" | 
+/**
+ * A Jotform ticket, including tickets opened by maintenance schedules.
+ */
+"ticket" | 
+/**
+ * A maintenance task.
+ */
+"work_order"
+
+/**
+ * A maintenance task that is past its due date.
+ */
+export type OverdueMaintenanceTask = ({ id: number; exhibit_id: number; title: string; description: string; interval_every: number; interval_unit: IntervalUnit; next_due_date: string; 
+/**
+ * Whether a ticket should be opened automatically when the task comes due.
+ */
+auto_create_ticket: boolean }) & { exhibit_name: string; exhibit_location: string; days_overdue: number }
+
+export type Part = { id: number | null; name: string; link: string; exhibit_ids: number[]; notes: Note[]; 
+/**
+ * Incremented on every write; exposed as the part's ETag.
+ */
+version: number }
+
+/**
+ * Whether the app is ready to serve, with the checks that decided it.
+ */
+export type Readiness = { 
+/**
+ * Whether every check passed.
+ */
+ready: boolean; checks: ReadinessCheck[] }
+
+/**
+ * The outcome of one of GET /readyz's checks.
+ */
+export type ReadinessCheck = { 
+/**
+ * `database`, `images_dir`, `backup_dir`, `jotform_sync` or `backup`.
+ */
+name: string; ok: boolean; 
+/**
+ * What was found, or what went wrong.
+ */
+detail: string; 
+/**
+ * For the sync and backup checks, how long ago the last success was, in seconds.
+ */
+age_secs: number | null }
+
+/**
+ * How many records a restore brought in.
+ */
+export type RestoreSummary = { exhibits: number; parts: number; sponsors: number; maintenance_tasks: number; tickets: number; notes: number; attachments: number; images: number; 
+/**
+ * Images that couldn't be moved into the images directory. The records were restored
+ * regardless, so these need copying over by hand.
+ */
+images_not_restored: string[] }
+
+/**
+ * The exhibit's current (or next upcoming) sponsorship, in the shape the app displays.
+ * 
+ * The full history lives in the `exhibit_sponsorships` table; see `Sponsorship`.
+ */
+export type Sponsor = { name: string; start_date: string; end_date: string }
+
+/**
+ * An organization or person that sponsors exhibits, with contact details for the
+ * development office.
+ */
+export type SponsorProfile = { id: number; name: string; contact_name: string | null; contact_email: string | null; contact_phone: string | null; notes: string | null }
+
+/**
+ * One sponsor's support of one exhibit over a period of time.
+ * 
+ * Sponsorships are never overwritten when a sponsor changes, so an exhibit's rows form
+ * its sponsorship history.
+ */
+export type Sponsorship = { id: number; exhibit_id: number; sponsor_id: number; sponsor_name: string; start_date: string; end_date: string; 
+/**
+ * The pledged amount in cents, if known.
+ */
+amount_cents: number | null; notes: string | null }
+
+/**
+ * A bug report as it's stored, along with how filing it on GitHub is going.
+ */
+export type StoredBugReport = { id: number; name: string; title: string; description: string; app_version: string | null; os: string | null; 
+/**
+ * Whether a screenshot came with it, served at GET /bug-reports/<id>/screenshot.
+ */
+has_screenshot: boolean; status: BugReportStatus; 
+/**
+ * Attempts made to file it on GitHub.
+ */
+attempts: number; 
+/**
+ * When it'll next be tried, while it's pending.
+ */
+next_attempt_at: string | null; 
+/**
+ * Why the last attempt failed.
+ */
+last_error: string | null; issue_number: number | null; 
+/**
+ * The GitHub issue it was filed as.
+ */
+issue_url: string | null; created_at: string; filed_at: string | null }
+
+/**
+ * The kinds of records that go to the trash when deleted.
+ */
+export type TrashEntity = "exhibit" | "part" | 
+/**
+ * A Jotform ticket.
+ */
+"ticket"
+
+/**
+ * A deleted record waiting in the trash.
+ */
+export type TrashItem = { entity: TrashEntity; 
+/**
+ * The record's ID. Ticket IDs aren't numeric, so every ID is a string here.
+ */
+id: string; 
+/**
+ * The exhibit or part name, or the exhibit named on a ticket.
+ */
+name: string; deleted_at: string; 
+/**
+ * When the purge job will permanently delete the record.
+ */
+purge_after: string }
+
+/**
+ * A partial update for an exhibit, following JSON Merge Patch (RFC 7396) semantics.
+ * 
+ * Omitted fields are left alone. `null` clears a field: `sponsor` is removed, `description`
+ * becomes empty and `image_url` falls back to the default image. `name`, `cluster`,
+ * `location` and `status` are required on an exhibit and can't be set to `null`.
+ * `part_ids`, when present, replaces the exhibit's whole part list.
+ */
+export type UpdateExhibit = { name?: string | null; cluster?: string | null; location?: string | null; description?: string | null; status?: string | null; image_url?: string | null; sponsor?: Sponsor | null; part_ids?: number[] | null }
+
+export type UpdatePart = { name: string; link: string; exhibitIds: number[] }
+
+/**
+ * An outside system that's sent change events as they happen.
+ * 
+ * The secret the payloads are signed with is never sent back out.
+ */
+export type Webhook = { id: number; 
+/**
+ * Where events are POSTed.
+ */
+url: string; 
+/**
+ * The events sent, as filters like those GET /events takes, e.g. `exhibit.updated` or
+ * `jotform.created:priority_level=High`.
+ */
+events: string[]; 
+/**
+ * Inactive webhooks keep their settings and delivery log but aren't sent anything.
+ */
+active: boolean; created_at: string }
+
+/**
+ * One event sent, or to be sent, to a webhook.
+ */
+export type WebhookDelivery = { id: number; webhook_id: number; 
+/**
+ * The event's topic, e.g. `exhibit.updated`.
+ */
+topic: string; 
+/**
+ * The JSON body that's POSTed.
+ */
+payload: string; status: DeliveryStatus; attempts: number; 
+/**
+ * When it'll next be tried, while it's pending.
+ */
+next_attempt_at: string | null; 
+/**
+ * The HTTP status of the last attempt, if the receiver answered.
+ */
+last_status_code: number | null; 
+/**
+ * Why the last attempt failed.
+ */
+last_error: string | null; created_at: string; delivered_at: string | null }
+
@@ -0,0 +1,165 @@
+use crate::backup::{self, BackupError, BackupSettings};
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::models::BackupInfo;
+use log::error;
+use rocket::fs::NamedFile;
+use rocket::http::Header;
+use rocket::serde::json::Json;
+use rocket::{get, post, Responder, State};
+
+/// A backup file, named for download.
+#[derive(Responder)]
+pub struct BackupDownload {
+    file: NamedFile,
+    disposition: Header<'static>,
+}
+
+fn api_error(e: BackupError) -> ApiError {
+    match e {
+        BackupError::NotFound => ApiError::NotFound,
+        BackupError::Corrupt(message) => {
+            ApiError::InvalidInput(format!("Backup failed its integrity check: {}", message))
+        }
+        e => {
+            error!("Backup operation failed: {}", e);
+            ApiError::InternalServerError
+        }
+    }
+}
+
+/// Handles the GET /admin/backups endpoint.
+///
+/// # Arguments
+/// * `settings` - Where backups are kept.
+///
+/// # Returns
+/// * `Result<Json<Vec<BackupInfo>>, ApiError>` - The backups, newest first.
+///
+/// # Errors
+/// Returns an `ApiError` if the backups directory can't be read.
+#[utoipa::path(
+    tag = "Admin",
+    responses(
+        (status = 200, description = "The backups, newest first", body = Vec<BackupInfo>)
+    )
+)]
+#[get("/admin/backups")]
+pub async fn list_backups_handler(
+    settings: &State<BackupSettings>,
+) -> Result<Json<Vec<BackupInfo>>, ApiError> {
+    let backups = backup::list_backups(&settings.dir).map_err(|e| api_error(e.into()))?;
+
+    Ok(Json(backups))
+}
+
+/// Handles the POST /admin/backups endpoint.
+///
+/// Takes a backup now, in addition to the scheduled ones. Older backups beyond the number
+/// to keep are deleted afterwards.
+///
+/// # Arguments
+/// * `db_pool` - Database connection pool.
+/// * `settings` - Where backups are kept and whether they include images.
+/// * `config` - Where images are stored.
+///
+/// # Returns
+/// * `Result<Json<BackupInfo>, ApiError>` - The new backup.
+///
+/// # Errors
+/// Returns an `ApiError` if the backup can't be taken or fails its integrity check.
+#[utoipa::path(
+    tag = "Admin",
+    responses(
+        (status = 200, description = "The new backup", body = BackupInfo)
+    )
+)]
+#[post("/admin/backups")]
+pub async fn create_backup_handler(
+    db_pool: &State<DbPool>,
+    settings: &State<BackupSettings>,
+    config: &State<AppConfig>,
+) -> Result<Json<BackupInfo>, ApiError> {
+    let backup = backup::create_backup(db_pool.inner(), settings, &config.images_dir)
+        .await
+        .map_err(api_error)?;
+
+    Ok(Json(backup))
+}
+
+/// Handles the GET /admin/backups/<name> endpoint.
+///
+/// # Arguments
+/// * `name` - The backup's file name, as listed by GET /admin/backups.
+/// * `settings` - Where backups are kept.
+///
+/// # Returns
+/// * `Result<BackupDownload, ApiError>` - The backup file.
+///
+/// # Errors
+/// Returns an `ApiError` if there's no such backup.
+#[utoipa::path(
+    tag = "Admin",
+    responses(
+        (status = 200, description = "The backup archive", content_type = "application/zip")
+    )
+)]
+#[get("/admin/backups/<name>")]
+pub async fn download_backup_handler(
+    name: &str,
+    settings: &State<BackupSettings>,
+) -> Result<BackupDownload, ApiError> {
+    let path = backup::find_backup(&settings.dir, name).ok_or(ApiError::NotFound)?;
+    let file = NamedFile::open(&path)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+
+    Ok(BackupDownload {
+        file,
+        disposition: Header::new(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", name),
+        ),
+    })
+}
+
+/// Handles the POST /admin/backups/<name>/restore endpoint.
+///
+/// Replaces all data with the backup's, and copies back its images if it has any. The
+/// current data is backed up first, so a restore can itself be undone by restoring that
+/// backup.
+///
+/// # Arguments
+/// * `name` - The backup's file name, as listed by GET /admin/backups.
+/// * `db_pool` - Database connection pool.
+/// * `settings` - Where backups are kept.
+/// * `config` - Where images are stored.
+///
+/// # Returns
+/// * `Result<Json<BackupInfo>, ApiError>` - The backup of the data that was replaced.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - There's no such backup.
+/// - The backup fails its integrity check.
+/// - The restore fails, in which case the data is left as it was.
+#[utoipa::path(
+    tag = "Admin",
+    responses(
+        (status = 200, description = "The backup of the data that was replaced", body = BackupInfo)
+    )
+)]
+#[post("/admin/backups/<name>/restore")]
+pub async fn restore_backup_handler(
+    name: &str,
+    db_pool: &State<DbPool>,
+    settings: &State<BackupSettings>,
+    config: &State<AppConfig>,
+) -> Result<Json<BackupInfo>, ApiError> {
+    let previous = backup::restore_backup(db_pool.inner(), settings, name, &config.images_dir)
+        .await
+        .map_err(api_error)?;
+
+    Ok(Json(previous))
+}
@@ -0,0 +1,172 @@
+use crate::api::json_body::JsonBody;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::models::{BugReport, BugReportStatus, StoredBugReport};
+use crate::repo::bug_report_repo;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use rocket::http::ContentType;
+use rocket::serde::json::Json;
+use rocket::State;
+use rocket::{get, post};
+use validator::Validate;
+
+/// The largest screenshot a bug report can have.
+const MAX_SCREENSHOT_MIB: usize = 5;
+
+/// Decodes a base64 screenshot, optionally given as a `data:` URL, returning its content
+/// type and contents. Only PNGs and JPEGs are accepted.
+fn decode_screenshot(screenshot: &str) -> Result<(&'static str, Vec<u8>), ApiError> {
+    let encoded = match screenshot.split_once(";base64,") {
+        Some((prefix, encoded)) if prefix.starts_with("data:") => encoded,
+        _ => screenshot,
+    };
+    let data = STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| ApiError::InvalidInput("Screenshot must be base64".into()))?;
+
+    if data.len() > MAX_SCREENSHOT_MIB * 1024 * 1024 {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "Screenshots can be at most {} MiB",
+            MAX_SCREENSHOT_MIB
+        )));
+    }
+
+    let content_type = if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else {
+        return Err(ApiError::InvalidInput(
+            "Screenshot must be a PNG or JPEG".into(),
+        ));
+    };
+
+    Ok((content_type, data))
+}
+
+/// Handles the POST /report-bug endpoint.
+///
+/// Stores the bug report, which is then filed as an issue on GitHub in the background. A
+/// report is never lost if GitHub is down or isn't configured: it's retried until it's
+/// filed, and can be seen at GET /bug-reports either way.
+///
+/// # Arguments
+/// * `report` - The bug report, with the app version, OS and a screenshot if there is one.
+/// * `db_pool` - Database connection pool.
+///
+/// # Returns
+/// * `Result<Json<StoredBugReport>, ApiError>` - The stored report, waiting to be filed.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails, including a screenshot that isn't a PNG or JPEG.
+/// - The screenshot is larger than 5 MiB.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Bug reports",
+    request_body = BugReport,
+    responses(
+        (status = 200, description = "The stored report, waiting to be filed", body = StoredBugReport)
+    )
+)]
+#[post("/report-bug", format = "json", data = "<report>")]
+pub async fn report_bug_handler(
+    report: JsonBody<BugReport>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<StoredBugReport>, ApiError> {
+    let report = report.into_inner();
+    report.validate()?;
+
+    let screenshot = report
+        .screenshot
+        .as_deref()
+        .map(decode_screenshot)
+        .transpose()?;
+
+    let pool = db_pool.inner();
+    let id = bug_report_repo::create_bug_report(
+        &report,
+        screenshot
+            .as_ref()
+            .map(|(content_type, data)| (*content_type, data.as_slice())),
+        Utc::now(),
+        pool,
+    )
+    .await?;
+
+    match bug_report_repo::get_bug_report(id, pool).await? {
+        Some(report) => Ok(Json(report)),
+        None => Err(ApiError::InternalServerError),
+    }
+}
+
+/// Handles the GET /bug-reports?<status> endpoint.
+///
+/// # Arguments
+/// * `status` - Only list reports that are `pending`, `filed` or `failed`.
+/// * `db_pool` - Database connection pool.
+///
+/// # Returns
+/// * `Result<Json<Vec<StoredBugReport>>, ApiError>` - Bug reports, newest first, with where
+///   filing each on GitHub is up to and the issue it was filed as.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The status is unknown.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Bug reports",
+    responses(
+        (status = 200, description = "Bug reports, newest first, with where filing each on GitHub is up to and the issue it was filed as", body = Vec<StoredBugReport>)
+    )
+)]
+#[get("/bug-reports?<status>")]
+pub async fn list_bug_reports_handler(
+    status: Option<&str>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<StoredBugReport>>, ApiError> {
+    let status = match status {
+        None => None,
+        Some("pending") => Some(BugReportStatus::Pending),
+        Some("filed") => Some(BugReportStatus::Filed),
+        Some("failed") => Some(BugReportStatus::Failed),
+        Some(_) => {
+            return Err(ApiError::InvalidInput(
+                "`status` must be pending, filed or failed".into(),
+            ))
+        }
+    };
+
+    let reports = bug_report_repo::get_bug_reports(status, db_pool.inner()).await?;
+
+    Ok(Json(reports))
+}
+
+/// Handles the GET /bug-reports/<id>/screenshot endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The report is not found or has no screenshot.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Bug reports",
+    responses(
+        (status = 200, description = "The screenshot, as a PNG or JPEG", content_type = "image/*")
+    )
+)]
+#[get("/bug-reports/<id>/screenshot")]
+pub async fn get_bug_report_screenshot_handler(
+    id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let screenshot = bug_report_repo::get_screenshot(id, db_pool.inner())
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let content_type =
+        ContentType::parse_flexible(&screenshot.content_type).unwrap_or(ContentType::Binary);
+
+    Ok((content_type, screenshot.data))
+}
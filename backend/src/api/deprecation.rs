@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::{Request, Response};
+
+/// Routes clients should move off, and when they're expected to stop working.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    /// The base the routes are mounted at, e.g. `/` for the unversioned routes.
+    pub base: &'static str,
+    /// The one route deprecated, by handler name. `None` deprecates every route under `base`.
+    pub route: Option<&'static str>,
+    pub since: DateTime<Utc>,
+    pub sunset: Option<DateTime<Utc>>,
+    /// Where the same routes are mounted now, linked to as the successor of each response.
+    pub successor_base: Option<&'static str>,
+}
+
+impl Deprecation {
+    fn matches(&self, request: &Request<'_>) -> bool {
+        request.route().is_some_and(|route| {
+            route.uri.base() == self.base
+                && self
+                    .route
+                    .is_none_or(|name| route.name.as_deref() == Some(name))
+        })
+    }
+
+    /// The `Deprecation`, `Sunset` and `Link` headers for a response to `path`.
+    pub fn headers(&self, path: &str) -> Vec<Header<'static>> {
+        // RFC 9745 and RFC 8594
+        let mut headers = vec![Header::new(
+            "Deprecation",
+            format!("@{}", self.since.timestamp()),
+        )];
+        if let Some(sunset) = self.sunset {
+            headers.push(Header::new(
+                "Sunset",
+                sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+            ));
+        }
+        if let Some(successor_base) = self.successor_base {
+            let rest = path
+                .strip_prefix(self.base.trim_end_matches('/'))
+                .unwrap_or(path);
+            headers.push(Header::new(
+                "Link",
+                format!("<{}{}>; rel=\"successor-version\"", successor_base, rest),
+            ));
+        }
+        headers
+    }
+}
+
+/// Adds the deprecation headers to responses from deprecated routes, so clients can tell
+/// they need to move before the routes go away.
+pub struct Deprecations(pub Vec<Deprecation>);
+
+#[rocket::async_trait]
+impl Fairing for Deprecations {
+    fn info(&self) -> Info {
+        Info {
+            name: "Deprecation Headers",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        if let Some(deprecation) = self.0.iter().find(|d| d.matches(request)) {
+            for header in deprecation.headers(request.uri().path().as_str()) {
+                response.set_header(header);
+            }
+        }
+    }
+}
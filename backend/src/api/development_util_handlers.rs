@@ -1,12 +1,14 @@
+use crate::config::AppConfig;
 use crate::db;
 use crate::db::DbPool;
 use crate::errors::ApiError;
-use log::error;
-use rocket::get;
+use crate::events::{self, Action, ChangeEvent, Entity};
+use log::{error, warn};
+use rocket::request::{FromRequest, Outcome, Request};
 use rocket::serde::json::serde_json;
 use rocket::serde::json::Json;
-use rocket::State;
-use sqlx::query;
+use rocket::{post, State};
+use sqlx::{Connection, SqliteConnection};
 
 /// Generates and inserts 100 dummy exhibits into the database.
 ///
@@ -30,14 +32,85 @@ pub async fn generate_and_insert_exhibits(pool: &DbPool) -> Result<(), ApiError>
     Ok(())
 }
 
-/// Handles the GET /exhibits/dummy endpoint.
-///
-/// This endpoint is only a GET and not a POST because it's easier to test with the frontend.
+/// The header the configured `dev_tools_token` is sent in, to confirm a request.
+pub const CONFIRM_TOKEN_HEADER: &str = "X-Confirm-Token";
+
+/// The Rocket profiles the development utilities run under: Rocket's own default for debug
+/// builds, and the `development` profile in Rocket.toml.
+const DEVELOPMENT_PROFILES: [&str; 2] = ["debug", "development"];
+
+/// Permission to use the development utilities, checked before each one runs.
+///
+/// They're only compiled with the `dev-tools` feature, and then refused unless Rocket is
+/// running a development profile and the request confirms itself with the configured
+/// `dev_tools_token`.
+pub struct DevTools {
+    profile: String,
+    sent_token: Option<String>,
+}
+
+impl DevTools {
+    pub fn check(
+        profile: &str,
+        token: Option<&str>,
+        sent_token: Option<&str>,
+    ) -> Result<(), ApiError> {
+        if !DEVELOPMENT_PROFILES.contains(&profile) {
+            return Err(ApiError::Forbidden(format!(
+                "the development utilities are turned off in the `{}` profile",
+                profile
+            )));
+        }
+        let Some(token) = token else {
+            return Err(ApiError::NotConfigured(
+                "set dev_tools_token to use the development utilities".into(),
+            ));
+        };
+        if sent_token != Some(token) {
+            return Err(ApiError::Forbidden(format!(
+                "send the configured dev_tools_token in the {} header",
+                CONFIRM_TOKEN_HEADER
+            )));
+        }
+        Ok(())
+    }
+
+    fn allow(&self, config: &AppConfig) -> Result<(), ApiError> {
+        let allowed = DevTools::check(
+            &self.profile,
+            config.dev_tools_token.as_deref(),
+            self.sent_token.as_deref(),
+        );
+        if let Err(e) = &allowed {
+            warn!("Refused a development utility: {}", e);
+        }
+        allowed
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for DevTools {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(DevTools {
+            profile: request.rocket().config().profile.to_string(),
+            sent_token: request
+                .headers()
+                .get_one(CONFIRM_TOKEN_HEADER)
+                .map(str::to_string),
+        })
+    }
+}
+
+/// Handles the POST /dev/exhibits/fill-dummy endpoint.
 ///
 /// This endpoint generates and inserts 100 dummy exhibits into the database.
 /// It returns a success message upon successful creation.
 ///
 /// # Arguments
+/// * `dev_tools` - The profile and confirmation token, checked before anything is done.
+/// * `config` - App configuration, with the expected `dev_tools_token`.
 /// * `db_pool` - A reference to the database connection pool.
 ///
 /// # Returns
@@ -45,11 +118,15 @@ pub async fn generate_and_insert_exhibits(pool: &DbPool) -> Result<(), ApiError>
 ///
 /// # Errors
 /// Returns an `ApiError` if:
+/// - Rocket isn't running a development profile, or the token is missing or wrong.
 /// - A database operation fails.
-#[get("/exhibits/fill-dummy")]
+#[post("/exhibits/fill-dummy")]
 pub async fn create_dummy_exhibits_handler(
+    dev_tools: DevTools,
+    config: &State<AppConfig>,
     db_pool: &State<DbPool>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
+    dev_tools.allow(config)?;
     let pool = db_pool.inner().clone();
 
     generate_and_insert_exhibits(&pool).await.map_err(|e| {
@@ -65,7 +142,8 @@ pub async fn create_dummy_exhibits_handler(
 /// Resets the database by wiping all data and setting up tables.
 ///
 /// This function performs a complete reset of the database by removing all existing data
-/// and reinitializing the necessary tables.
+/// and reinitializing the necessary tables. Both happen in one transaction, along with
+/// recording the reset for webhooks, so a failure leaves the database as it was.
 ///
 /// # Arguments
 /// * `pool` - A reference to the database connection pool.
@@ -74,21 +152,33 @@ pub async fn create_dummy_exhibits_handler(
 /// * `Result<(), ApiError>` - Returns `Ok(())` if the reset is successful.
 /// * `ApiError` - Returns an error if any database operation fails.
 pub async fn reset_database(pool: &DbPool) -> Result<(), ApiError> {
-    wipe_database(pool).await?;
-    db::setup_database(pool).await.map_err(|e| {
+    let mut conn = pool.acquire().await?;
+    let mut tx = conn.begin().await?;
+
+    wipe_database(&mut tx).await.map_err(|e| {
+        error!("Failed to wipe database: {}", e);
+        ApiError::DatabaseError("Failed to wipe database".into())
+    })?;
+    db::create_schema(&mut tx).await.map_err(|e| {
         error!("Failed to setup database: {}", e);
         ApiError::DatabaseError("Failed to setup database".into())
     })?;
+    let event = ChangeEvent::new(Entity::Data, Action::Replaced, None);
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+    events::publish(event);
 
     Ok(())
 }
 
-/// Handles the GET /reset endpoint.
+/// Handles the POST /dev/reset endpoint.
 ///
 /// This endpoint resets the database by wiping all existing data and setting up the necessary tables.
 /// It returns a success message upon completion.
 ///
 /// # Arguments
+/// * `dev_tools` - The profile and confirmation token, checked before anything is done.
+/// * `config` - App configuration, with the expected `dev_tools_token`.
 /// * `db_pool` - A reference to the database connection pool.
 ///
 /// # Returns
@@ -96,62 +186,44 @@ pub async fn reset_database(pool: &DbPool) -> Result<(), ApiError> {
 ///
 /// # Errors
 /// Returns an `ApiError` if:
+/// - Rocket isn't running a development profile, or the token is missing or wrong.
 /// - A database operation fails.
-#[get("/reset")]
-pub async fn handle_reset_db(db_pool: &State<DbPool>) -> Result<Json<serde_json::Value>, ApiError> {
+#[post("/reset")]
+pub async fn handle_reset_db(
+    dev_tools: DevTools,
+    config: &State<AppConfig>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    dev_tools.allow(config)?;
     let pool = db_pool.inner().clone();
 
-    reset_database(&pool).await.map_err(|e| {
-        error!("Failed to reset database: {}", e);
-        ApiError::DatabaseError("Failed to reset database".into())
-    })?;
+    reset_database(&pool).await?;
 
     Ok(Json(serde_json::json!({
         "message": "Database reset successful"
     })))
 }
 
-/// Wipes the database by dropping all tables.
-async fn wipe_database(pool: &DbPool) -> Result<(), ApiError> {
-    query("DROP TABLE IF EXISTS exhibit_parts")
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            error!("Failed to drop exhibit_parts table: {}", e);
-            ApiError::DatabaseError("Failed to drop exhibit_parts table".into())
-        })?;
-
-    query("DROP TABLE IF EXISTS exhibit_notes")
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            error!("Failed to drop exhibit_notes table: {}", e);
-            ApiError::DatabaseError("Failed to drop exhibit_notes table".into())
-        })?;
-
-    query("DROP TABLE IF EXISTS part_notes")
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            error!("Failed to drop part_notes table: {}", e);
-            ApiError::DatabaseError("Failed to drop part_notes table".into())
-        })?;
-
-    query("DROP TABLE IF EXISTS parts")
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            error!("Failed to drop parts table: {}", e);
-            ApiError::DatabaseError("Failed to drop parts table".into())
-        })?;
-
-    query("DROP TABLE IF EXISTS exhibits")
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            error!("Failed to drop exhibits table: {}", e);
-            ApiError::DatabaseError("Failed to drop exhibits table".into())
-        })?;
+/// Wipes the database by dropping every table, whatever tables there are.
+///
+/// Foreign keys can't be turned off inside a transaction, so their checks are deferred to
+/// the commit instead, by which point the tables on both sides of every key are gone.
+async fn wipe_database(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for table in &tables {
+        sqlx::query(&format!("DROP TABLE IF EXISTS \"{}\"", table))
+            .execute(&mut *conn)
+            .await?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,75 @@
+use crate::events::{self, ChangeEvent};
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::json::json;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{get, Shutdown};
+
+/// What a listener's next message is.
+enum Next {
+    Change(ChangeEvent),
+    /// The listener fell behind and missed this many events.
+    Lagged(u64),
+    Done,
+}
+
+/// Handles the GET /events?topics=<topics> endpoint.
+///
+/// Streams changes to the data as Server-Sent Events, so open apps can refresh what they're
+/// showing without polling. Each event is named after its topic (e.g. `exhibit.updated`,
+/// `part.created`, `note.added`, `jotform.synced`) and its data is the `ChangeEvent` as
+/// JSON. A `data.replaced` event, sent after a backup or snapshot is restored, means
+/// everything should be re-fetched.
+///
+/// A listener that falls too far behind gets a `lagged` event with how many changes it
+/// missed, and should re-fetch everything too.
+///
+/// # Arguments
+/// * `topics` - Comma-separated entities (`exhibit`) or topics (`exhibit.updated`) to listen
+///   for, each optionally with a condition on the event's data, such as
+///   `exhibit:status=out of service`. Everything is sent if omitted.
+/// * `shutdown` - Ends the stream when the server shuts down.
+///
+/// # Returns
+/// * `EventStream![]` - The events, as they happen.
+#[utoipa::path(
+    tag = "Events",
+    responses(
+        (status = 200, description = "Change events, as they happen", content_type = "text/event-stream")
+    )
+)]
+#[get("/events?<topics>")]
+pub fn events_handler(topics: Option<&str>, mut shutdown: Shutdown) -> EventStream![] {
+    let filters: Vec<String> = topics
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|topic| !topic.is_empty())
+        .map(str::to_string)
+        .collect();
+    let mut changes = events::subscribe();
+
+    EventStream! {
+        loop {
+            let next = select! {
+                change = changes.recv() => match change {
+                    Ok(change) => Next::Change(change),
+                    Err(RecvError::Lagged(missed)) => Next::Lagged(missed),
+                    Err(RecvError::Closed) => Next::Done,
+                },
+                _ = &mut shutdown => Next::Done,
+            };
+
+            match next {
+                Next::Change(change) if change.matches(&filters) => {
+                    yield Event::json(&change).event(change.topic.clone());
+                }
+                Next::Change(_) => {}
+                Next::Lagged(missed) => {
+                    yield Event::json(&json!({ "missed": missed })).event("lagged");
+                }
+                Next::Done => break,
+            }
+        }
+    }
+}
@@ -1,40 +1,41 @@
+use crate::api::json_body::JsonBody;
+use crate::api::note_handlers::{create_target_note, NewNote};
+use crate::api::preconditions::{ConditionalError, IfMatch, Tagged};
 use crate::db::DbPool;
 use crate::errors::ApiError;
-use crate::models::{Exhibit, Note, UpdateExhibit};
-use crate::repo::exhibit_repo;
+use crate::exhibit_import::{
+    parse_sheet, ImportFormat, ImportOutcome, ImportReport, ImportRowResult,
+};
+use crate::models::{validate_exhibit_status, Exhibit, Note, NoteTarget, UpdateExhibit};
+use crate::repo::{exhibit_repo, note_repo, part_repo};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use log::error;
 use rand::prelude::SliceRandom;
-use rocket::http::Status;
+use rocket::data::{Data, ToByteUnit};
+use rocket::http::{ContentType, Status};
 use rocket::serde::json::Json;
-use rocket::serde::Deserialize;
+use rocket::serde::{Deserialize, Serialize};
 use rocket::State;
-use rocket::{delete, get, post, put};
-use std::fs;
+use rocket::{delete, get, post, put, FromForm};
+use std::collections::HashMap;
 use std::sync::OnceLock;
-use validator::Validate;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationErrors};
 
 static DEFAULT_IMAGE_BASE64: OnceLock<String> = OnceLock::new();
 
-fn get_default_image_base64() -> &'static String {
+pub(crate) fn get_default_image_base64() -> &'static String {
     DEFAULT_IMAGE_BASE64.get_or_init(|| {
         // Read the default image file and encode it as base64
         let image_data = include_bytes!("../../images/DEFAULT_IMAGE.png");
-        let base64_data = base64::encode(&image_data);
+        let base64_data = STANDARD.encode(image_data);
 
         // Add the data URL prefix
         format!("data:image/jpeg;base64,{}", base64_data)
     })
 }
 
-#[derive(Debug, Deserialize, Validate)]
-pub struct NewNote {
-    pub submitter: String,
-    #[validate(length(min = 1, message = "Note cannot be empty"))]
-    pub message: String,
-}
-
 /// Creates a new note with associated exhibit.
 ///
 /// # Arguments
@@ -42,29 +43,37 @@ pub struct NewNote {
 /// * `db_pool` - Database connection pool
 ///
 /// # Returns
-/// * `Result<Json<i64>, ApiError>` - The ID of the newly created note
+/// * `Result<Json<Note>, ApiError>` - The newly created note
 ///
 /// # Errors
 /// Returns `ApiError` if:
 /// * Database operations fail
 /// * Input validation fails
+#[utoipa::path(
+    tag = "Exhibits",
+    request_body = NewNote,
+    responses(
+        (status = 200, description = "The newly created note", body = Note)
+    )
+)]
 #[post("/exhibits/<id>/notes", format = "json", data = "<new_note>")]
 pub async fn create_exhibit_note_handler(
     id: i64,
-    new_note: Json<NewNote>,
+    new_note: JsonBody<NewNote>,
     db_pool: &State<DbPool>,
-) -> Result<(), ApiError> {
-    let note = new_note.into_inner();
-    note.validate()?;
-
-    let pool = db_pool.inner().clone();
-
-    exhibit_repo::create_exhibit_note(id, note.submitter, note.message, &pool).await?;
-
-    Ok(())
+) -> Result<Json<Note>, ApiError> {
+    let note = create_target_note(
+        NoteTarget::Exhibit,
+        &id.to_string(),
+        new_note.into_inner(),
+        db_pool.inner(),
+    )
+    .await?;
+
+    Ok(Json(note))
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 pub struct NewExhibit {
     pub name: String,
     pub cluster: String,
@@ -74,7 +83,20 @@ pub struct NewExhibit {
     pub image_url: Option<String>,
     pub sponsor: Option<crate::models::Sponsor>,
     pub part_ids: Vec<i64>,
-    pub notes: Vec<crate::models::Note>,
+    #[serde(default)]
+    pub notes: Vec<NewNote>,
+}
+
+/// Filters accepted by GET /exhibits and the exhibit export.
+#[derive(Debug, Default, Clone, FromForm, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExhibitFilter {
+    /// Only exhibits whose name contains this text, ignoring case.
+    pub search: Option<String>,
+    pub cluster: Option<String>,
+    pub location: Option<String>,
+    /// Only exhibits with this status, ignoring case.
+    pub status: Option<String>,
 }
 
 /// Creates a new exhibit with associated parts and notes.
@@ -90,9 +112,16 @@ pub struct NewExhibit {
 /// Returns `ApiError` if:
 /// * Database operations fail
 /// * Input validation fails
+#[utoipa::path(
+    tag = "Exhibits",
+    request_body = NewExhibit,
+    responses(
+        (status = 200, description = "The exhibit was created")
+    )
+)]
 #[post("/exhibits", format = "json", data = "<new_exhibit>")]
 pub async fn create_exhibit_handler(
-    new_exhibit: Json<NewExhibit>,
+    new_exhibit: JsonBody<NewExhibit>,
     db_pool: &State<DbPool>,
 ) -> Result<(), ApiError> {
     let mut exhibit = new_exhibit.into_inner();
@@ -108,6 +137,12 @@ pub async fn create_exhibit_handler(
     Ok(())
 }
 
+#[utoipa::path(
+    tag = "Exhibits",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
 #[delete("/exhibits/<exhibit_id>/notes/<note_id>")]
 pub async fn delete_exhibit_note_handler(
     exhibit_id: i64,
@@ -116,8 +151,16 @@ pub async fn delete_exhibit_note_handler(
 ) -> Result<Status, ApiError> {
     let pool = db_pool.inner().clone();
 
-    match exhibit_repo::delete_exhibit_note(exhibit_id, note_id, &pool).await {
-        Ok(_) => Ok(Status::NoContent),
+    match note_repo::delete_target_note(
+        NoteTarget::Exhibit,
+        &exhibit_id.to_string(),
+        note_id,
+        &pool,
+    )
+    .await
+    {
+        Ok(true) => Ok(Status::NoContent),
+        Ok(false) => Err(ApiError::NotFound),
         Err(e) => {
             error!("Failed to delete exhibit note: {}", e);
             Err(ApiError::DatabaseError(
@@ -140,24 +183,24 @@ pub async fn delete_exhibit_note_handler(
 /// Returns `ApiError` if:
 /// * The exhibit is not found
 /// * A database operation fails
+#[utoipa::path(
+    tag = "Exhibits",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
 #[delete("/exhibits/<id>")]
 pub async fn delete_exhibit_handler(id: i64, db_pool: &State<DbPool>) -> Result<Status, ApiError> {
     let pool = db_pool.inner().clone();
 
-    match exhibit_repo::get_exhibit(id, &pool).await {
-        Ok(Some(_)) => match exhibit_repo::delete_exhibit(id, &pool).await {
-            Ok(_) => Ok(Status::NoContent),
-            Err(e) => {
-                error!("Failed to delete exhibit: {}", e);
-                Err(ApiError::DatabaseError(
-                    "Failed to delete exhibit".to_string(),
-                ))
-            }
-        },
-        Ok(None) => Err(ApiError::NotFound),
+    match exhibit_repo::delete_exhibit(id, &pool).await {
+        Ok(_) => Ok(Status::NoContent),
+        Err(sqlx::Error::RowNotFound) => Err(ApiError::NotFound),
         Err(e) => {
-            error!("Failed to get exhibit: {}", e);
-            Err(ApiError::DatabaseError("Failed to get exhibit".to_string()))
+            error!("Failed to delete exhibit: {}", e);
+            Err(ApiError::DatabaseError(
+                "Failed to delete exhibit".to_string(),
+            ))
         }
     }
 }
@@ -178,6 +221,12 @@ pub async fn delete_exhibit_handler(id: i64, db_pool: &State<DbPool>) -> Result<
 /// Returns an `ApiError` if:
 /// - The note is not found.
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Exhibits",
+    responses(
+        (status = 200, description = "The note", body = Note)
+    )
+)]
 #[get("/exhibits/<exhibit_id>/notes/<note_id>")]
 pub async fn get_exhibit_note_handler(
     exhibit_id: i64,
@@ -185,7 +234,9 @@ pub async fn get_exhibit_note_handler(
     db_pool: &State<DbPool>,
 ) -> Result<Json<Note>, ApiError> {
     let pool = db_pool.inner().clone();
-    let note = exhibit_repo::get_exhibit_note(exhibit_id, note_id, &pool).await?;
+    let note =
+        note_repo::get_target_note(NoteTarget::Exhibit, &exhibit_id.to_string(), note_id, &pool)
+            .await?;
 
     match note {
         Some(note) => Ok(Json(note)),
@@ -200,22 +251,32 @@ pub async fn get_exhibit_note_handler(
 /// * `db_pool` - Database connection pool
 ///
 /// # Returns
-/// * `Result<Json<Exhibit>, ApiError>` - The requested exhibit
+/// * `Result<Tagged<Json<Exhibit>>, ApiError>` - The requested exhibit, with its version as the `ETag`
 ///
 /// # Errors
 /// Returns an `ApiError` if:
 /// - The exhibit is not found.
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Exhibits",
+    responses(
+        (status = 200, description = "The requested exhibit, with its version as the `ETag`", body = Exhibit,
+            headers(("ETag" = String, description = "The record's version, for `If-Match`")))
+    )
+)]
 #[get("/exhibits/<id>")]
 pub async fn get_exhibit_handler(
     id: i64,
     db_pool: &State<DbPool>,
-) -> Result<Json<Exhibit>, ApiError> {
+) -> Result<Tagged<Json<Exhibit>>, ApiError> {
     let pool = db_pool.inner();
-    let exhibit = exhibit_repo::get_exhibit(id, &pool).await?;
+    let exhibit = exhibit_repo::get_exhibit(id, pool).await?;
 
     match exhibit {
-        Some(exhibit) => Ok(Json(exhibit)),
+        Some(exhibit) => Ok(Tagged {
+            version: exhibit.version,
+            inner: Json(exhibit),
+        }),
         None => Err(ApiError::NotFound),
     }
 }
@@ -233,18 +294,21 @@ pub async fn get_exhibit_handler(
 ///
 /// # Errors
 /// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Exhibits",
+    responses(
+        (status = 200, description = "A JSON array of notes associated with the exhibit", body = Vec<Note>)
+    )
+)]
 #[get("/exhibits/<exhibit_id>/notes")]
 pub async fn list_exhibit_notes_handler(
     exhibit_id: i64,
     db_pool: &State<DbPool>,
 ) -> Result<Json<Vec<Note>>, ApiError> {
     let pool = db_pool.inner().clone();
-    let notes = exhibit_repo::get_all_exhibit_notes(exhibit_id, &pool).await?;
+    let notes = note_repo::get_notes(NoteTarget::Exhibit, &exhibit_id.to_string(), &pool).await?;
 
-    match notes {
-        Some(notes) => Ok(Json(notes)),
-        None => Err(ApiError::NotFound),
-    }
+    Ok(Json(notes))
 }
 
 /// Handles the GET /exhibits endpoint.
@@ -252,6 +316,7 @@ pub async fn list_exhibit_notes_handler(
 /// This endpoint retrieves a list of all exhibits from the database.
 ///
 /// # Arguments
+/// * `filter` - Optional `search`, `cluster`, `location` and `status` filters.
 /// * `db_pool` - A reference to the database connection pool.
 ///
 /// # Returns
@@ -259,19 +324,30 @@ pub async fn list_exhibit_notes_handler(
 ///
 /// # Errors
 /// Returns an `ApiError` if a database operation fails.
-#[get("/exhibits")]
+#[utoipa::path(
+    tag = "Exhibits",
+    params(ExhibitFilter),
+    responses(
+        (status = 200, description = "The matching exhibits", body = Vec<Exhibit>)
+    )
+)]
+#[get("/exhibits?<filter..>")]
 pub async fn list_exhibits_handler(
+    filter: ExhibitFilter,
     db_pool: &State<DbPool>,
 ) -> Result<Json<Vec<Exhibit>>, ApiError> {
     let pool = db_pool.inner().clone();
-    let exhibits = exhibit_repo::get_all_exhibits(&pool).await?;
+    let exhibits = exhibit_repo::find_exhibits(&filter, 0, None, &pool).await?;
 
-    match exhibits {
-        Some(exhibits) => Ok(Json(exhibits)),
-        None => Err(ApiError::NotFound),
-    }
+    Ok(Json(exhibits))
 }
 
+#[utoipa::path(
+    tag = "Exhibits",
+    responses(
+        (status = 200, description = "A randomly chosen exhibit", body = Exhibit)
+    )
+)]
 #[get("/exhibits/random")]
 pub async fn handle_random_exhibit(db_pool: &State<DbPool>) -> Result<Json<Exhibit>, ApiError> {
     let pool = db_pool.inner().clone();
@@ -291,42 +367,87 @@ pub async fn handle_random_exhibit(db_pool: &State<DbPool>) -> Result<Json<Exhib
 
 /// Handles the PUT /exhibits/<id> endpoint.
 ///
-/// This endpoint updates an existing exhibit with the provided data. It updates the exhibit's
-/// details as well as its associated parts and notes.
+/// This endpoint applies a partial update to an exhibit using JSON Merge Patch semantics:
+/// omitted fields are untouched and `null` clears a field. Besides the basic details it
+/// can set or clear the sponsor, change the status and replace the part list.
+///
+/// If the request carries an `If-Match` header the update only applies when it matches the
+/// exhibit's current `ETag`; otherwise the current exhibit is returned with `412`.
 ///
 /// # Arguments
 /// * `id` - The ID of the exhibit to update.
-/// * `updated_exhibit` - JSON payload containing the updated exhibit data.
+/// * `if_match` - The parsed `If-Match` header, if any.
+/// * `updated_exhibit` - JSON payload containing the fields to change.
 /// * `db_pool` - Database connection pool.
 ///
 /// # Returns
-/// * `Result<Status, ApiError>` - HTTP status indicating the result of the operation.
+/// * `Result<Tagged<()>, ConditionalError<Exhibit>>` - An empty response carrying the new `ETag`.
 ///
 /// # Errors
-/// Returns an `ApiError` if:
+/// Returns a `ConditionalError` if:
+/// - Input validation fails, or `part_ids` names parts that don't exist.
 /// - The exhibit is not found.
+/// - The `If-Match` header is stale.
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Exhibits",
+    request_body = UpdateExhibit,
+    responses(
+        (status = 200, description = "Updated",
+            headers(("ETag" = String, description = "The new version"))),
+        (status = 412, description = "`If-Match` is out of date. The current record is returned, with its `ETag`", body = Exhibit)
+    )
+)]
 #[put("/exhibits/<id>", format = "json", data = "<updated_exhibit>")]
 pub async fn update_exhibit_handler(
     id: i64,
-    updated_exhibit: Json<UpdateExhibit>,
+    if_match: IfMatch,
+    updated_exhibit: JsonBody<UpdateExhibit>,
     db_pool: &State<DbPool>,
-) -> Result<(), ApiError> {
+) -> Result<Tagged<()>, ConditionalError<Exhibit>> {
     let pool = db_pool.inner().clone();
-    let exhibit = updated_exhibit.into_inner();
+    let mut exhibit = updated_exhibit.into_inner();
+    exhibit.validate().map_err(ApiError::from)?;
 
-    // Update the exhibit
-    exhibit_repo::update_exhibit(&id, &exhibit, &pool)
-        .await
-        .map_err(|e| {
-            error!("Failed to update exhibit: {}", e);
-            ApiError::DatabaseError("Failed to update exhibit".into())
-        })?;
+    // Statuses are stored lowercase, like the status endpoint does
+    if let Some(Some(status)) = &mut exhibit.status {
+        *status = status.trim().to_lowercase();
+    }
 
-    Ok(())
+    // Clearing the image falls back to the default image, like on creation
+    if let Some(None) = exhibit.image_url {
+        exhibit.image_url = Some(Some(get_default_image_base64().clone()));
+    }
+
+    if let Some(Some(part_ids)) = &exhibit.part_ids {
+        let missing = part_repo::find_missing_part_ids(part_ids, &pool).await?;
+        if !missing.is_empty() {
+            return Err(ApiError::InvalidInput(format!("Unknown part IDs: {:?}", missing)).into());
+        }
+    }
+
+    // Update the exhibit
+    let new_version =
+        exhibit_repo::update_exhibit(&id, &exhibit, if_match.expected_version(), &pool)
+            .await
+            .map_err(|e| {
+                error!("Failed to update exhibit: {}", e);
+                ApiError::DatabaseError("Failed to update exhibit".into())
+            })?;
+
+    match new_version {
+        Some(version) => Ok(Tagged { inner: (), version }),
+        None => match exhibit_repo::get_exhibit(id, &pool).await? {
+            Some(current) => Err(ConditionalError::Stale {
+                version: current.version,
+                current,
+            }),
+            None => Err(ApiError::NotFound.into()),
+        },
+    }
 }
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, ToSchema)]
 pub struct AddExistingPartPayload {
     pub part_id: i64,
 }
@@ -348,10 +469,17 @@ pub struct AddExistingPartPayload {
 /// - The exhibit is not found.
 /// - The part is not found.
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Exhibits",
+    request_body = AddExistingPartPayload,
+    responses(
+        (status = 200, description = "The part was added")
+    )
+)]
 #[post("/exhibits/<id>/add_part", format = "json", data = "<part_payload>")]
 pub async fn add_existing_part_handler(
     id: i64,
-    part_payload: Json<AddExistingPartPayload>,
+    part_payload: JsonBody<AddExistingPartPayload>,
     db_pool: &State<DbPool>,
 ) -> Result<Status, ApiError> {
     let pool = db_pool.inner().clone();
@@ -368,15 +496,23 @@ pub async fn add_existing_part_handler(
     Ok(Status::Ok)
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct ChangeStatusRequest {
+    #[validate(custom(function = "validate_exhibit_status"))]
     pub new_status: String,
 }
 
+#[utoipa::path(
+    tag = "Exhibits",
+    request_body = ChangeStatusRequest,
+    responses(
+        (status = 200, description = "The status was changed")
+    )
+)]
 #[post("/exhibits/<id>/status", format = "json", data = "<data>")]
 pub async fn change_exhibit_status_handler(
     id: i64,
-    data: Json<ChangeStatusRequest>,
+    data: JsonBody<ChangeStatusRequest>,
     db_pool: &State<DbPool>,
 ) -> Result<(), ApiError> {
     data.validate()?;
@@ -397,3 +533,264 @@ pub async fn change_exhibit_status_handler(
 
     Ok(())
 }
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExhibitBatchAction {
+    ChangeStatus {
+        status: String,
+    },
+    Move {
+        cluster: Option<String>,
+        location: Option<String>,
+    },
+    Delete,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ExhibitBatchRequest {
+    #[validate(length(
+        min = 1,
+        max = 500,
+        message = "Between 1 and 500 exhibit IDs must be provided"
+    ))]
+    pub ids: Vec<i64>,
+    pub action: ExhibitBatchAction,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl ExhibitBatchRequest {
+    /// Trims the action's text fields and checks them the same way the single-exhibit
+    /// endpoints would.
+    fn normalize(&mut self) -> Result<(), ApiError> {
+        match &mut self.action {
+            ExhibitBatchAction::ChangeStatus { status } => {
+                *status = status.trim().to_lowercase();
+                if let Err(e) = validate_exhibit_status(status) {
+                    let mut errors = ValidationErrors::new();
+                    errors.add("action.status", e);
+                    return Err(errors.into());
+                }
+            }
+            ExhibitBatchAction::Move { cluster, location } => {
+                if cluster.is_none() && location.is_none() {
+                    return Err(ApiError::InvalidInput(
+                        "A move requires a cluster, a location, or both".into(),
+                    ));
+                }
+                for value in [cluster, location].into_iter().flatten() {
+                    *value = value.trim().to_string();
+                    if value.is_empty() || value.len() > 100 {
+                        return Err(ApiError::InvalidInput(
+                            "Cluster and location must be between 1 and 100 characters".into(),
+                        ));
+                    }
+                }
+            }
+            ExhibitBatchAction::Delete => {}
+        }
+
+        // Drop duplicate IDs so each exhibit is reported (and changed) exactly once
+        let mut seen = std::collections::HashSet::new();
+        self.ids.retain(|id| seen.insert(*id));
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExhibitBatchOutcome {
+    Updated,
+    Unchanged,
+    Deleted,
+    NotFound,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, ToSchema)]
+pub struct ExhibitFieldChange {
+    pub field: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, ToSchema)]
+pub struct ExhibitBatchItemResult {
+    pub id: i64,
+    pub outcome: ExhibitBatchOutcome,
+    pub changes: Vec<ExhibitFieldChange>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExhibitBatchResponse {
+    pub dry_run: bool,
+    pub results: Vec<ExhibitBatchItemResult>,
+}
+
+/// Handles the POST /exhibits/batch endpoint.
+///
+/// This endpoint applies a single action (status change, cluster/location move or delete)
+/// to a list of exhibits inside one transaction. When `dry_run` is set nothing is written
+/// and the response previews what would have changed.
+///
+/// # Arguments
+/// * `request` - JSON payload containing the exhibit IDs, the action and the dry-run flag.
+/// * `db_pool` - Database connection pool.
+///
+/// # Returns
+/// * `Result<Json<ExhibitBatchResponse>, ApiError>` - The outcome for each requested ID.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - A database operation fails, in which case no exhibit is changed.
+#[utoipa::path(
+    tag = "Exhibits",
+    request_body = ExhibitBatchRequest,
+    responses(
+        (status = 200, description = "The outcome for each requested ID", body = ExhibitBatchResponse)
+    )
+)]
+#[post("/exhibits/batch", format = "json", data = "<request>")]
+pub async fn batch_exhibits_handler(
+    request: JsonBody<ExhibitBatchRequest>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<ExhibitBatchResponse>, ApiError> {
+    let mut request = request.into_inner();
+    request.validate()?;
+    request.normalize()?;
+
+    let pool = db_pool.inner().clone();
+
+    let results = exhibit_repo::apply_exhibit_batch(&request, &pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to apply exhibit batch: {}", e);
+            ApiError::DatabaseError("Failed to apply exhibit batch".into())
+        })?;
+
+    Ok(Json(ExhibitBatchResponse {
+        dry_run: request.dry_run,
+        results,
+    }))
+}
+
+/// The largest spreadsheet that can be imported.
+const MAX_IMPORT_MIB: u64 = 10;
+
+/// Handles the POST /exhibits/import?format=<format>&dry_run=<bool> endpoint.
+///
+/// The request body is a CSV or XLSX file (the first sheet is read). Columns are matched by
+/// header, including the floor team's old spreadsheet headers, and each row is checked with
+/// the `Exhibit` validators. Valid rows create a new exhibit or update the one with the same
+/// name; a `Notes` column is added as a note. Invalid rows are reported and skipped. With
+/// `dry_run` set nothing is written and the response previews the import.
+///
+/// # Arguments
+/// * `format` - `csv` or `xlsx`. Defaults to the request's `Content-Type`.
+/// * `dry_run` - Validate and report without writing anything.
+/// * `data` - The spreadsheet file.
+/// * `db_pool` - Database connection pool.
+///
+/// # Returns
+/// * `Result<Json<ImportReport>, ApiError>` - Totals and the outcome of every row.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The format is unknown or the file can't be read.
+/// - A required column (name, cluster, location, status) is missing.
+/// - The file is larger than 10 MiB.
+/// - A database operation fails, in which case no exhibit is changed.
+#[utoipa::path(
+    tag = "Exhibits",
+    request_body(content = String, description = "A CSV or XLSX file, as `format` says", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Totals and the outcome of every row", body = ImportReport)
+    )
+)]
+#[post("/exhibits/import?<format>&<dry_run>", data = "<data>")]
+pub async fn import_exhibits_handler(
+    format: Option<&str>,
+    dry_run: Option<bool>,
+    content_type: Option<&ContentType>,
+    data: Data<'_>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<ImportReport>, ApiError> {
+    let format = match format {
+        Some(format) => ImportFormat::from_name(format),
+        None => content_type.and_then(ImportFormat::from_content_type),
+    }
+    .ok_or_else(|| ApiError::InvalidInput("`format` must be csv or xlsx".into()))?;
+    let dry_run = dry_run.unwrap_or(false);
+
+    let contents = data
+        .open(MAX_IMPORT_MIB.mebibytes())
+        .into_bytes()
+        .await
+        .map_err(|_| ApiError::InvalidRequestBody)?;
+    if !contents.is_complete() {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "Imports can be at most {} MiB",
+            MAX_IMPORT_MIB
+        )));
+    }
+
+    let sheet = parse_sheet(format, &contents).map_err(ApiError::InvalidInput)?;
+    let default_image_url = get_default_image_base64();
+
+    // Work out which rows can be imported; later rows repeating a name are rejected
+    let mut results = Vec::with_capacity(sheet.rows.len());
+    let mut valid_rows = Vec::new();
+    let mut seen_names: HashMap<String, usize> = HashMap::new();
+
+    for row in &sheet.rows {
+        let mut errors = row.validate(default_image_url);
+        if let Some(first_line) = seen_names.get(&row.name.to_lowercase()) {
+            errors.push(format!("Duplicate of the exhibit on line {}", first_line));
+        } else if !row.name.is_empty() {
+            seen_names.insert(row.name.to_lowercase(), row.line);
+        }
+
+        if errors.is_empty() {
+            valid_rows.push(row.clone());
+        }
+
+        results.push(ImportRowResult {
+            line: row.line,
+            name: row.name.clone(),
+            outcome: ImportOutcome::Invalid,
+            errors,
+        });
+    }
+
+    let pool = db_pool.inner().clone();
+
+    let outcomes = exhibit_repo::import_exhibits(&valid_rows, default_image_url, dry_run, &pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to import exhibits: {}", e);
+            ApiError::DatabaseError("Failed to import exhibits".into())
+        })?;
+
+    let mut outcomes = outcomes.into_iter();
+    for result in results.iter_mut().filter(|result| result.errors.is_empty()) {
+        result.outcome = outcomes.next().unwrap_or(ImportOutcome::Invalid);
+    }
+
+    let count = |outcome| {
+        results
+            .iter()
+            .filter(|result| result.outcome == outcome)
+            .count()
+    };
+
+    Ok(Json(ImportReport {
+        dry_run,
+        created: count(ImportOutcome::Created),
+        updated: count(ImportOutcome::Updated),
+        invalid: count(ImportOutcome::Invalid),
+        ignored_columns: sheet.ignored_columns,
+        rows: results,
+    }))
+}
@@ -0,0 +1,216 @@
+use crate::api::exhibit_handlers::ExhibitFilter;
+use crate::api::jotform_handlers::JotformFilter;
+use crate::api::part_handlers::PartFilter;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::export::{self, paged, ExportFormat, Exportable, EXPORT_PAGE_SIZE};
+use crate::models::{Exhibit, Jotform, Part};
+use crate::repo::{exhibit_repo, jotform_repo, part_repo};
+use crate::time_zone::site_today;
+use log::error;
+use rocket::futures::future;
+use rocket::futures::stream::{self, BoxStream, StreamExt};
+use rocket::get;
+use rocket::http::{ContentType, Header};
+use rocket::response::stream::ReaderStream;
+use rocket::response::{self, Responder, Response};
+use rocket::{Request, State};
+use std::io::Cursor;
+
+/// An export file, sent as it's produced and named for download.
+pub struct ExportDownload {
+    body: BoxStream<'static, Vec<u8>>,
+    content_type: ContentType,
+    disposition: Header<'static>,
+}
+
+impl<'r> Responder<'r, 'static> for ExportDownload {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(self.content_type)
+            .header(self.disposition)
+            .streamed_body(ReaderStream::from(self.body.map(Cursor::new)))
+            .ok()
+    }
+}
+
+fn parse_format(format: Option<&str>) -> Result<ExportFormat, ApiError> {
+    match format {
+        Some(format) => ExportFormat::from_name(format)
+            .ok_or_else(|| ApiError::InvalidInput("`format` must be csv, xlsx or json".into())),
+        None => Ok(ExportFormat::Csv),
+    }
+}
+
+/// Encodes the records in `format` and names the file after `entity` and today's date.
+async fn download<T: Exportable>(
+    entity: &str,
+    format: ExportFormat,
+    pages: BoxStream<'static, Vec<T>>,
+) -> Result<ExportDownload, ApiError> {
+    let body = match format {
+        ExportFormat::Csv => export::csv_stream(pages),
+        ExportFormat::Json => export::json_stream(pages),
+        ExportFormat::Xlsx => {
+            let workbook = export::xlsx_workbook(pages).await.map_err(|e| {
+                error!("Failed to write {} workbook: {}", entity, e);
+                ApiError::InternalServerError
+            })?;
+            stream::once(future::ready(workbook)).boxed()
+        }
+    };
+
+    Ok(ExportDownload {
+        body,
+        content_type: format.content_type(),
+        disposition: Header::new(
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"{}-{}.{}\"",
+                entity,
+                site_today(),
+                format.extension()
+            ),
+        ),
+    })
+}
+
+/// Handles the GET /export/exhibits?format=<format> endpoint.
+///
+/// Accepts the same filters as GET /exhibits. Exhibits are loaded a page at a time and
+/// streamed out, so large exports don't have to fit in memory.
+///
+/// # Arguments
+/// * `format` - `csv` (the default), `xlsx` or `json`.
+/// * `filter` - Optional `search`, `cluster`, `location` and `status` filters.
+/// * `db_pool` - Database connection pool.
+///
+/// # Returns
+/// * `Result<ExportDownload, ApiError>` - The export file.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The format is unknown.
+/// - The XLSX workbook can't be written.
+#[utoipa::path(
+    tag = "Export",
+    params(ExhibitFilter),
+    responses(
+        (status = 200, description = "The exhibits as CSV, XLSX or JSON, depending on `format`", content_type = "application/octet-stream")
+    )
+)]
+#[get("/export/exhibits?<format>&<filter..>")]
+pub async fn export_exhibits_handler(
+    format: Option<&str>,
+    filter: ExhibitFilter,
+    db_pool: &State<DbPool>,
+) -> Result<ExportDownload, ApiError> {
+    let format = parse_format(format)?;
+    let pool = db_pool.inner().clone();
+
+    let pages = paged(
+        0,
+        |exhibit: &Exhibit| exhibit.id,
+        move |after_id| {
+            let filter = filter.clone();
+            let pool = pool.clone();
+            async move {
+                exhibit_repo::find_exhibits(&filter, after_id, Some(EXPORT_PAGE_SIZE), &pool).await
+            }
+        },
+    );
+
+    download("exhibits", format, pages).await
+}
+
+/// Handles the GET /export/parts?format=<format> endpoint.
+///
+/// Accepts the same filters as GET /parts and streams parts out a page at a time.
+///
+/// # Arguments
+/// * `format` - `csv` (the default), `xlsx` or `json`.
+/// * `filter` - Optional `search` and `exhibit_id` filters.
+/// * `db_pool` - Database connection pool.
+///
+/// # Returns
+/// * `Result<ExportDownload, ApiError>` - The export file.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The format is unknown.
+/// - The XLSX workbook can't be written.
+#[utoipa::path(
+    tag = "Export",
+    params(PartFilter),
+    responses(
+        (status = 200, description = "The parts as CSV, XLSX or JSON, depending on `format`", content_type = "application/octet-stream")
+    )
+)]
+#[get("/export/parts?<format>&<filter..>")]
+pub async fn export_parts_handler(
+    format: Option<&str>,
+    filter: PartFilter,
+    db_pool: &State<DbPool>,
+) -> Result<ExportDownload, ApiError> {
+    let format = parse_format(format)?;
+    let pool = db_pool.inner().clone();
+
+    let pages = paged(
+        0,
+        |part: &Part| part.id.unwrap_or_default(),
+        move |after_id| {
+            let filter = filter.clone();
+            let pool = pool.clone();
+            async move { part_repo::find_parts(&filter, after_id, Some(EXPORT_PAGE_SIZE), &pool).await }
+        },
+    );
+
+    download("parts", format, pages).await
+}
+
+/// Handles the GET /export/jotforms?format=<format> endpoint.
+///
+/// Accepts the same filters as GET /jotforms and streams tickets out a page at a time.
+///
+/// # Arguments
+/// * `format` - `csv` (the default), `xlsx` or `json`.
+/// * `filter` - Optional `search`, `status`, `department` and `priority_level` filters.
+/// * `db_pool` - Database connection pool.
+///
+/// # Returns
+/// * `Result<ExportDownload, ApiError>` - The export file.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The format is unknown.
+/// - The XLSX workbook can't be written.
+#[utoipa::path(
+    tag = "Export",
+    params(JotformFilter),
+    responses(
+        (status = 200, description = "The tickets as CSV, XLSX or JSON, depending on `format`", content_type = "application/octet-stream")
+    )
+)]
+#[get("/export/jotforms?<format>&<filter..>")]
+pub async fn export_jotforms_handler(
+    format: Option<&str>,
+    filter: JotformFilter,
+    db_pool: &State<DbPool>,
+) -> Result<ExportDownload, ApiError> {
+    let format = parse_format(format)?;
+    let pool = db_pool.inner().clone();
+
+    let pages = paged(
+        String::new(),
+        |jotform: &Jotform| jotform.id.clone(),
+        move |after_id| {
+            let filter = filter.clone();
+            let pool = pool.clone();
+            async move {
+                jotform_repo::find_jotforms(&filter, &after_id, Some(EXPORT_PAGE_SIZE), &pool).await
+            }
+        },
+    );
+
+    download("jotforms", format, pages).await
+}
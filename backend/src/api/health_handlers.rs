@@ -0,0 +1,183 @@
+use crate::backup::{self, BackupSettings};
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::logging;
+use crate::metrics;
+use crate::models::{Readiness, ReadinessCheck};
+use chrono::{DateTime, TimeDelta, Utc};
+use rocket::http::Status;
+use rocket::serde::json::{json, Json, Value};
+use rocket::{get, State};
+use std::fs;
+use std::path::Path;
+
+/// How stale the Jotform sync and backups can get before GET /readyz fails.
+pub struct ReadinessSettings {
+    pub max_sync_age: TimeDelta,
+    pub max_backup_age: TimeDelta,
+    /// When the app started, which stands in for the last success until there's been one.
+    pub started_at: DateTime<Utc>,
+}
+
+fn check(name: &str, ok: bool, detail: String) -> ReadinessCheck {
+    ReadinessCheck {
+        name: name.to_string(),
+        ok,
+        detail,
+        age_secs: None,
+    }
+}
+
+/// Describes a threshold in whole hours where it can, e.g. `72 hours` or `90 minutes`.
+fn describe(age: TimeDelta) -> String {
+    match age.num_minutes() {
+        minutes if minutes % 60 == 0 => format!("{} hours", minutes / 60),
+        minutes => format!("{} minutes", minutes),
+    }
+}
+
+/// Checks that something last succeeded within `max_age`.
+///
+/// Until it first succeeds the age is counted from `started_at`, so a restart doesn't make
+/// the app unready before the first sync or backup has had a chance to run.
+pub fn age_check(
+    name: &str,
+    last_success: Option<DateTime<Utc>>,
+    started_at: DateTime<Utc>,
+    max_age: TimeDelta,
+    now: DateTime<Utc>,
+) -> ReadinessCheck {
+    let age = now - last_success.unwrap_or(started_at);
+    let ok = age <= max_age;
+    let detail = match (last_success, ok) {
+        (Some(at), true) => format!("Last succeeded at {}", at.to_rfc3339()),
+        (Some(at), false) => format!(
+            "Last succeeded at {}, more than {} ago",
+            at.to_rfc3339(),
+            describe(max_age)
+        ),
+        (None, true) => "Hasn't run since the app started".to_string(),
+        (None, false) => format!(
+            "Hasn't succeeded in the {} since the app started",
+            describe(max_age)
+        ),
+    };
+
+    ReadinessCheck {
+        age_secs: Some(age.num_seconds()),
+        ..check(name, ok, detail)
+    }
+}
+
+/// Checks that a file can be created in `dir`, creating `dir` if need be.
+fn writable_check(name: &str, dir: &Path) -> ReadinessCheck {
+    let probe = dir.join(format!(".readyz-{}", logging::new_id()));
+    let written = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&probe, b""))
+        .and_then(|_| fs::remove_file(&probe));
+
+    match written {
+        Ok(()) => check(name, true, format!("{} is writable", dir.display())),
+        Err(e) => check(
+            name,
+            false,
+            format!("{} isn't writable: {}", dir.display(), e),
+        ),
+    }
+}
+
+/// Handles the GET /healthz endpoint.
+///
+/// Answers as long as the process is up, without touching the database, for liveness
+/// probes. Use GET /readyz to check the app can actually do its job.
+///
+/// # Returns
+/// * `Json<Value>` - `{"status": "ok"}`.
+#[utoipa::path(
+    tag = "Health",
+    responses(
+        (status = 200, description = "The app is up", body = Object)
+    )
+)]
+#[get("/healthz")]
+pub fn healthz_handler() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Handles the GET /readyz endpoint.
+///
+/// Checks that:
+/// - The database answers a query.
+/// - The images and backups directories are writable.
+/// - Jotform last synced within `readiness_max_sync_age_mins`, if syncing is turned on.
+/// - The newest backup is within `readiness_max_backup_age_hours`.
+///
+/// # Arguments
+/// * `db_pool` - Database connection pool.
+/// * `settings` - How stale syncs and backups can get.
+/// * `backup_settings` - Where backups are kept.
+/// * `config` - Where images are stored and whether Jotform sync is turned on.
+///
+/// # Returns
+/// * `(Status, Json<Readiness>)` - Every check's result, with 200 OK if they all passed and
+///   503 Service Unavailable if any failed.
+#[utoipa::path(
+    tag = "Health",
+    responses(
+        (status = 200, description = "Every check passed", body = Readiness),
+        (status = 503, description = "At least one check failed", body = Readiness)
+    )
+)]
+#[get("/readyz")]
+pub async fn readyz_handler(
+    db_pool: &State<DbPool>,
+    settings: &State<ReadinessSettings>,
+    backup_settings: &State<BackupSettings>,
+    config: &State<AppConfig>,
+) -> (Status, Json<Readiness>) {
+    let now = Utc::now();
+    let mut checks = Vec::new();
+
+    checks.push(
+        match sqlx::query_scalar::<_, i64>("SELECT 1")
+            .fetch_one(db_pool.inner())
+            .await
+        {
+            Ok(_) => check("database", true, "Answered a query".to_string()),
+            Err(e) => check("database", false, format!("Query failed: {}", e)),
+        },
+    );
+
+    checks.push(writable_check("images_dir", &config.images_dir));
+    checks.push(writable_check("backup_dir", &backup_settings.dir));
+
+    checks.push(match config.jotform() {
+        Some(_) => age_check(
+            "jotform_sync",
+            metrics::jotform_last_success(),
+            settings.started_at,
+            settings.max_sync_age,
+            now,
+        ),
+        None => check("jotform_sync", true, "Turned off".to_string()),
+    });
+
+    checks.push(match backup::list_backups(&backup_settings.dir) {
+        Ok(backups) => age_check(
+            "backup",
+            backups.first().map(|backup| backup.created_at),
+            settings.started_at,
+            settings.max_backup_age,
+            now,
+        ),
+        Err(e) => check("backup", false, format!("Couldn't list backups: {}", e)),
+    });
+
+    let ready = checks.iter().all(|check| check.ok);
+    let status = match ready {
+        true => Status::Ok,
+        false => Status::ServiceUnavailable,
+    };
+
+    (status, Json(Readiness { ready, checks }))
+}
@@ -1,3 +1,4 @@
+use crate::api::json_body::JsonBody;
 use crate::db::DbPool;
 use crate::errors::ApiError;
 use crate::models::Jotform;
@@ -5,8 +6,15 @@ use crate::repo::jotform_repo;
 use log::error;
 use rocket::serde::{json::Json, Deserialize};
 use rocket::State;
-use rocket::{delete, get, post};
-
+use rocket::{delete, get, post, FromForm};
+use utoipa::{IntoParams, ToSchema};
+
+#[utoipa::path(
+    tag = "Tickets",
+    responses(
+        (status = 200, description = "The ticket", body = Jotform)
+    )
+)]
 #[get("/jotforms/<id>")]
 pub async fn get_jotform_handler(
     id: String,
@@ -25,49 +33,67 @@ pub async fn get_jotform_handler(
     }
 }
 
-#[get("/jotforms")]
+/// Filters accepted by GET /jotforms and the ticket export.
+#[derive(Debug, Default, Clone, FromForm, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct JotformFilter {
+    /// Only tickets mentioning this text in the submitter, location, exhibit or description.
+    pub search: Option<String>,
+    pub status: Option<String>,
+    pub department: Option<String>,
+    pub priority_level: Option<String>,
+}
+
+#[utoipa::path(
+    tag = "Tickets",
+    params(JotformFilter),
+    responses(
+        (status = 200, description = "The matching tickets", body = Vec<Jotform>)
+    )
+)]
+#[get("/jotforms?<filter..>")]
 pub async fn list_jotforms_handler(
+    filter: JotformFilter,
     db_pool: &State<DbPool>,
 ) -> Result<Json<Vec<Jotform>>, ApiError> {
     let pool = db_pool.inner().clone();
-    let jotforms = jotform_repo::get_all_jotforms(&pool).await?;
+    let jotforms = jotform_repo::find_jotforms(&filter, "", None, &pool).await?;
 
-    match jotforms {
-        Some(jotforms) => Ok(Json(jotforms)),
-        None => {
-            log::error!("No jotforms found in the database.");
-            Err(ApiError::NotFound)
-        }
-    }
+    Ok(Json(jotforms))
 }
 
+#[utoipa::path(
+    tag = "Tickets",
+    responses(
+        (status = 200, description = "Deleted")
+    )
+)]
 #[delete("/jotforms/<id>")]
-pub async fn delete_jotform_handler(id: i64, db_pool: &State<DbPool>) -> Result<(), ApiError> {
+pub async fn delete_jotform_handler(id: &str, db_pool: &State<DbPool>) -> Result<(), ApiError> {
     let pool = db_pool.inner().clone();
-    jotform_repo::delete_jotform(id.to_string(), &pool).await?;
-    Ok(())
+    match jotform_repo::delete_jotform(id.to_string(), &pool).await {
+        Err(sqlx::Error::RowNotFound) => Err(ApiError::NotFound),
+        result => Ok(result?),
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ChangeStatusRequest {
     pub new_status: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ChangeDepartmentRequest {
-    pub new_department: String,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct ChangePriorityRequest {
-    pub new_priority: String,
-}
-
+#[utoipa::path(
+    tag = "Tickets",
+    request_body = ChangeStatusRequest,
+    responses(
+        (status = 200, description = "The status was changed")
+    )
+)]
 #[post("/jotforms/<id>/status", data = "<data>")]
 pub async fn change_status_handler(
     db_pool: &State<DbPool>,
-    id: i64,
-    data: Json<ChangeStatusRequest>,
+    id: &str,
+    data: JsonBody<ChangeStatusRequest>,
 ) -> Result<(), ApiError> {
     let new_status = data.new_status.trim().to_string();
     let pool = db_pool.inner().clone();
@@ -76,31 +102,3 @@ pub async fn change_status_handler(
 
     Ok(())
 }
-
-#[post("/jotforms/<id>/department", data = "<data>")]
-pub async fn change_department_handler(
-    db_pool: &State<DbPool>,
-    id: i64,
-    data: Json<ChangeDepartmentRequest>,
-) -> Result<(), ApiError> {
-    let new_department = data.new_department.trim().to_string();
-    let pool = db_pool.inner().clone();
-
-    jotform_repo::change_jotform_department(id.to_string(), new_department, &pool).await?;
-
-    Ok(())
-}
-
-#[post("/jotforms/<id>/priority", data = "<data>")]
-pub async fn change_priority_handler(
-    db_pool: &State<DbPool>,
-    id: i64,
-    data: Json<ChangePriorityRequest>,
-) -> Result<(), ApiError> {
-    let new_priority = data.new_priority.trim().to_string();
-    let pool = db_pool.inner().clone();
-
-    jotform_repo::change_jotform_priority(id.to_string(), new_priority, &pool).await?;
-
-    Ok(())
-}
\ No newline at end of file
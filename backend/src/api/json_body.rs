@@ -0,0 +1,47 @@
+use crate::errors::BodyError;
+use rocket::data::{Data, FromData, Outcome};
+use rocket::request::Request;
+use rocket::serde::json::{self, Json};
+use serde::Deserialize;
+use std::ops::Deref;
+
+/// A JSON request body.
+///
+/// Reads the body just as `Json` does, but when it can't be read it keeps the reason for the
+/// 400 and 422 catchers, which otherwise only get the status.
+#[derive(Debug)]
+pub struct JsonBody<T>(pub T);
+
+impl<T> JsonBody<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for JsonBody<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T: Deserialize<'r>> FromData<'r> for JsonBody<T> {
+    type Error = json::Error<'r>;
+
+    async fn from_data(request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self> {
+        match <Json<T> as FromData<'r>>::from_data(request, data).await {
+            Outcome::Success(Json(value)) => Outcome::Success(JsonBody(value)),
+            Outcome::Error((status, error)) => {
+                let reason = match &error {
+                    json::Error::Io(error) => error.to_string(),
+                    json::Error::Parse(_, error) => error.to_string(),
+                };
+                request.local_cache(|| BodyError(Some(reason)));
+                Outcome::Error((status, error))
+            }
+            Outcome::Forward(forward) => Outcome::Forward(forward),
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::api::json_body::JsonBody;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::models::{IntervalUnit, MaintenanceCompletion, MaintenanceTask, OverdueMaintenanceTask};
+use crate::repo::maintenance_repo;
+use crate::time_zone::{site_today, to_site_date};
+use chrono::{DateTime, NaiveDate, Utc};
+use log::error;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::serde::Deserialize;
+use rocket::State;
+use rocket::{delete, get, post, put};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct NewMaintenanceTask {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Title must be between 1 and 100 characters"
+    ))]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[validate(range(
+        min = 1,
+        max = 365,
+        message = "Interval must be between 1 and 365 units"
+    ))]
+    pub interval_every: u32,
+    pub interval_unit: IntervalUnit,
+    /// When the task is first (or next) due. Defaults to one interval from today.
+    pub next_due_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub auto_create_ticket: bool,
+}
+
+impl NewMaintenanceTask {
+    /// The explicit due date if one was given, otherwise one interval from `today`.
+    fn due_date_from(&self, today: NaiveDate) -> Result<NaiveDate, ApiError> {
+        match self.next_due_date {
+            Some(date) => Ok(date),
+            None => self
+                .interval_unit
+                .advance(today, self.interval_every)
+                .ok_or_else(|| ApiError::InvalidInput("Interval is too large".into())),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CompleteMaintenanceRequest {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Completed by must be between 1 and 100 characters"
+    ))]
+    pub completed_by: String,
+    /// When the work was done. Defaults to now; set it when logging work after the fact.
+    pub completed_at: Option<DateTime<Utc>>,
+    pub notes: Option<String>,
+}
+
+fn map_maintenance_error(e: sqlx::Error, action: &str) -> ApiError {
+    match &e {
+        sqlx::Error::RowNotFound => ApiError::NotFound,
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => ApiError::NotFound,
+        _ => {
+            error!("Failed to {}: {}", action, e);
+            ApiError::DatabaseError(format!("Failed to {}", action))
+        }
+    }
+}
+
+/// Handles the GET /exhibits/<exhibit_id>/maintenance endpoint.
+///
+/// # Returns
+/// * `Result<Json<Vec<MaintenanceTask>>, ApiError>` - The exhibit's maintenance tasks, soonest due first.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Maintenance",
+    responses(
+        (status = 200, description = "The exhibit's maintenance tasks, soonest due first", body = Vec<MaintenanceTask>)
+    )
+)]
+#[get("/exhibits/<exhibit_id>/maintenance")]
+pub async fn list_exhibit_maintenance_handler(
+    exhibit_id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<MaintenanceTask>>, ApiError> {
+    let pool = db_pool.inner().clone();
+    let tasks = maintenance_repo::get_exhibit_tasks(exhibit_id, &pool).await?;
+
+    Ok(Json(tasks))
+}
+
+/// Handles the POST /exhibits/<exhibit_id>/maintenance endpoint.
+///
+/// Creates a recurring maintenance task. Unless `next_due_date` is given, the task is first
+/// due one interval from today.
+///
+/// # Returns
+/// * `Result<Json<MaintenanceTask>, ApiError>` - The newly created task.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - The exhibit is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Maintenance",
+    request_body = NewMaintenanceTask,
+    responses(
+        (status = 200, description = "The newly created task", body = MaintenanceTask)
+    )
+)]
+#[post(
+    "/exhibits/<exhibit_id>/maintenance",
+    format = "json",
+    data = "<new_task>"
+)]
+pub async fn create_maintenance_task_handler(
+    exhibit_id: i64,
+    new_task: JsonBody<NewMaintenanceTask>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<MaintenanceTask>, ApiError> {
+    let task = new_task.into_inner();
+    task.validate()?;
+
+    let next_due_date = task.due_date_from(site_today())?;
+    let pool = db_pool.inner().clone();
+
+    let id = maintenance_repo::create_task(exhibit_id, &task, next_due_date, &pool)
+        .await
+        .map_err(|e| map_maintenance_error(e, "create maintenance task"))?;
+
+    match maintenance_repo::get_task(id, &pool).await? {
+        Some(task) => Ok(Json(task)),
+        None => Err(ApiError::InternalServerError),
+    }
+}
+
+/// Handles the GET /maintenance/<id> endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The task is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Maintenance",
+    responses(
+        (status = 200, description = "The task", body = MaintenanceTask)
+    )
+)]
+#[get("/maintenance/<id>")]
+pub async fn get_maintenance_task_handler(
+    id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Json<MaintenanceTask>, ApiError> {
+    let pool = db_pool.inner().clone();
+
+    match maintenance_repo::get_task(id, &pool).await? {
+        Some(task) => Ok(Json(task)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Handles the PUT /maintenance/<id> endpoint.
+///
+/// Replaces the task's details and schedule. If `next_due_date` is left out the current due
+/// date is kept.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - The task is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Maintenance",
+    request_body = NewMaintenanceTask,
+    responses(
+        (status = 200, description = "Updated")
+    )
+)]
+#[put("/maintenance/<id>", format = "json", data = "<updated_task>")]
+pub async fn update_maintenance_task_handler(
+    id: i64,
+    updated_task: JsonBody<NewMaintenanceTask>,
+    db_pool: &State<DbPool>,
+) -> Result<(), ApiError> {
+    let task = updated_task.into_inner();
+    task.validate()?;
+
+    let pool = db_pool.inner().clone();
+
+    let current = maintenance_repo::get_task(id, &pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let next_due_date = task.next_due_date.unwrap_or(current.next_due_date);
+
+    maintenance_repo::update_task(id, &task, next_due_date, &pool)
+        .await
+        .map_err(|e| map_maintenance_error(e, "update maintenance task"))?;
+
+    Ok(())
+}
+
+/// Handles the DELETE /maintenance/<id> endpoint.
+///
+/// Deleting a task also deletes its completion log.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Maintenance",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
+#[delete("/maintenance/<id>")]
+pub async fn delete_maintenance_task_handler(
+    id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Status, ApiError> {
+    let pool = db_pool.inner().clone();
+
+    maintenance_repo::delete_task(id, &pool)
+        .await
+        .map_err(|e| map_maintenance_error(e, "delete maintenance task"))?;
+
+    Ok(Status::NoContent)
+}
+
+/// Handles the POST /maintenance/<id>/complete endpoint.
+///
+/// Logs who completed the task and when, then schedules the next due date one interval
+/// after the completion date. Any ticket auto-created for the completed due date is closed.
+///
+/// # Returns
+/// * `Result<Json<MaintenanceTask>, ApiError>` - The task with its new due date.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - The task is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Maintenance",
+    request_body = CompleteMaintenanceRequest,
+    responses(
+        (status = 200, description = "The task with its new due date", body = MaintenanceTask)
+    )
+)]
+#[post("/maintenance/<id>/complete", format = "json", data = "<request>")]
+pub async fn complete_maintenance_task_handler(
+    id: i64,
+    request: JsonBody<CompleteMaintenanceRequest>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<MaintenanceTask>, ApiError> {
+    let request = request.into_inner();
+    request.validate()?;
+
+    let pool = db_pool.inner().clone();
+
+    let task = maintenance_repo::get_task(id, &pool)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let completed_at = request.completed_at.unwrap_or_else(Utc::now);
+    let completed_on = to_site_date(completed_at);
+    let next_due_date = task
+        .interval_unit
+        .advance(completed_on, task.interval_every)
+        .ok_or(ApiError::InternalServerError)?;
+
+    maintenance_repo::complete_task(
+        &task,
+        request.completed_by.trim(),
+        completed_at,
+        request.notes.as_deref(),
+        next_due_date,
+        &pool,
+    )
+    .await
+    .map_err(|e| map_maintenance_error(e, "complete maintenance task"))?;
+
+    match maintenance_repo::get_task(id, &pool).await? {
+        Some(task) => Ok(Json(task)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Handles the GET /maintenance/<id>/completions endpoint.
+///
+/// # Returns
+/// * `Result<Json<Vec<MaintenanceCompletion>>, ApiError>` - The task's completion log, newest first.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Maintenance",
+    responses(
+        (status = 200, description = "The task's completion log, newest first", body = Vec<MaintenanceCompletion>)
+    )
+)]
+#[get("/maintenance/<id>/completions")]
+pub async fn list_maintenance_completions_handler(
+    id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<MaintenanceCompletion>>, ApiError> {
+    let pool = db_pool.inner().clone();
+    let completions = maintenance_repo::get_completions(id, &pool).await?;
+
+    Ok(Json(completions))
+}
+
+/// Handles the GET /maintenance/overdue endpoint.
+///
+/// # Returns
+/// * `Result<Json<Vec<OverdueMaintenanceTask>>, ApiError>` - Every overdue task across all exhibits, most overdue first.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Maintenance",
+    responses(
+        (status = 200, description = "Every overdue task across all exhibits, most overdue first", body = Vec<OverdueMaintenanceTask>)
+    )
+)]
+#[get("/maintenance/overdue")]
+pub async fn list_overdue_maintenance_handler(
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<OverdueMaintenanceTask>>, ApiError> {
+    let pool = db_pool.inner().clone();
+    let tasks = maintenance_repo::get_overdue_tasks(site_today(), &pool).await?;
+
+    Ok(Json(tasks))
+}
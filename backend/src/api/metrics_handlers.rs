@@ -0,0 +1,98 @@
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::metrics::Exposition;
+use crate::models::EXHIBIT_STATUSES;
+use crate::repo::metrics_repo;
+use rocket::http::ContentType;
+use rocket::{get, State};
+
+/// Ticket priorities, so each has a series even with no open tickets.
+const PRIORITY_LEVELS: [&str; 3] = ["Low", "Medium", "High"];
+
+/// Adds one gauge sample per value of `label`, with zero for any of `known` not in `counts`.
+fn labelled_counts(
+    exposition: &mut Exposition,
+    name: &str,
+    label: &str,
+    known: &[&str],
+    mut counts: Vec<(String, i64)>,
+) {
+    for value in known {
+        if !counts.iter().any(|(v, _)| v == value) {
+            counts.push((value.to_string(), 0));
+        }
+    }
+    counts.sort();
+
+    for (value, count) in counts {
+        exposition.sample(name, &[(label, &value)], count as f64);
+    }
+}
+
+/// Handles the GET /metrics endpoint.
+///
+/// Reports, in the Prometheus text format, request latency, database pool usage, Jotform
+/// sync durations and failures, the Jotform API calls left, how backups are going, and
+/// counts of exhibits by status and open tickets by priority.
+///
+/// # Arguments
+/// * `db_pool` - Database connection pool.
+///
+/// # Returns
+/// * `Result<(ContentType, String), ApiError>` - The metrics.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Metrics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain")
+    )
+)]
+#[get("/metrics")]
+pub async fn metrics_handler(db_pool: &State<DbPool>) -> Result<(ContentType, String), ApiError> {
+    let mut exposition = Exposition::default();
+    exposition.recorded();
+
+    exposition.gauge(
+        "db_pool_connections",
+        "Open database connections, idle or in use.",
+        db_pool.size() as f64,
+    );
+    exposition.gauge(
+        "db_pool_idle_connections",
+        "Open database connections not in use.",
+        db_pool.num_idle() as f64,
+    );
+
+    exposition.family(
+        "exhibits",
+        "gauge",
+        "Exhibits by status, not counting the trash.",
+    );
+    labelled_counts(
+        &mut exposition,
+        "exhibits",
+        "status",
+        &EXHIBIT_STATUSES,
+        metrics_repo::count_exhibits_by_status(db_pool.inner()).await?,
+    );
+
+    exposition.family(
+        "open_tickets",
+        "gauge",
+        "Open and in-progress tickets by priority, not counting the trash.",
+    );
+    labelled_counts(
+        &mut exposition,
+        "open_tickets",
+        "priority",
+        &PRIORITY_LEVELS,
+        metrics_repo::count_open_tickets_by_priority(db_pool.inner()).await?,
+    );
+
+    let content_type =
+        ContentType::new("text", "plain").with_params([("version", "0.0.4"), ("charset", "utf-8")]);
+
+    Ok((content_type, exposition.into_string()))
+}
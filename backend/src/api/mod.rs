@@ -1,5 +1,128 @@
+pub mod backup_handlers;
+pub mod bug_report_handlers;
+pub mod deprecation;
+#[cfg(feature = "dev-tools")]
 pub mod development_util_handlers;
+pub mod event_handlers;
 pub mod exhibit_handlers;
-pub mod github_handlers;
+pub mod export_handlers;
+pub mod health_handlers;
 pub mod jotform_handlers;
+pub mod json_body;
+pub mod maintenance_handlers;
+pub mod metrics_handlers;
+pub mod note_handlers;
+pub mod openapi_handlers;
 pub mod part_handlers;
+pub mod preconditions;
+pub mod snapshot_handlers;
+pub mod sponsorship_handlers;
+#[cfg(test)]
+mod tests;
+pub mod trash_handlers;
+pub mod webhook_handlers;
+
+/// Where the API is mounted. The same routes are also mounted at `/` until the desktop app
+/// has moved over, marked deprecated.
+pub const API_BASE: &str = "/api/v1";
+
+/// Where the development utilities are mounted.
+#[cfg(feature = "dev-tools")]
+pub const DEV_BASE: &str = "/dev";
+
+/// Every API route, as mounted.
+pub fn routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        bug_report_handlers::report_bug_handler,
+        bug_report_handlers::list_bug_reports_handler,
+        bug_report_handlers::get_bug_report_screenshot_handler,
+        event_handlers::events_handler,
+        health_handlers::healthz_handler,
+        health_handlers::readyz_handler,
+        exhibit_handlers::get_exhibit_handler,
+        exhibit_handlers::list_exhibits_handler,
+        exhibit_handlers::handle_random_exhibit,
+        exhibit_handlers::get_exhibit_note_handler,
+        exhibit_handlers::list_exhibit_notes_handler,
+        exhibit_handlers::create_exhibit_handler,
+        exhibit_handlers::create_exhibit_note_handler,
+        exhibit_handlers::update_exhibit_handler,
+        exhibit_handlers::add_existing_part_handler,
+        exhibit_handlers::delete_exhibit_handler,
+        exhibit_handlers::change_exhibit_status_handler,
+        exhibit_handlers::batch_exhibits_handler,
+        exhibit_handlers::import_exhibits_handler,
+        exhibit_handlers::delete_exhibit_note_handler,
+        part_handlers::get_part_handler,
+        part_handlers::list_parts_handler,
+        part_handlers::get_part_note_handler,
+        part_handlers::list_part_notes_handler,
+        part_handlers::get_parts_by_ids_handler,
+        part_handlers::update_part_handler,
+        part_handlers::create_part_handler,
+        part_handlers::create_part_note_handler,
+        part_handlers::delete_part_handler,
+        part_handlers::delete_part_note_handler,
+        sponsorship_handlers::list_sponsors_handler,
+        sponsorship_handlers::get_sponsor_handler,
+        sponsorship_handlers::create_sponsor_handler,
+        sponsorship_handlers::update_sponsor_handler,
+        sponsorship_handlers::delete_sponsor_handler,
+        sponsorship_handlers::list_exhibit_sponsorships_handler,
+        sponsorship_handlers::create_sponsorship_handler,
+        sponsorship_handlers::update_sponsorship_handler,
+        sponsorship_handlers::delete_sponsorship_handler,
+        sponsorship_handlers::list_expiring_sponsorships_handler,
+        maintenance_handlers::list_exhibit_maintenance_handler,
+        maintenance_handlers::create_maintenance_task_handler,
+        maintenance_handlers::get_maintenance_task_handler,
+        maintenance_handlers::update_maintenance_task_handler,
+        maintenance_handlers::delete_maintenance_task_handler,
+        maintenance_handlers::complete_maintenance_task_handler,
+        maintenance_handlers::list_maintenance_completions_handler,
+        maintenance_handlers::list_overdue_maintenance_handler,
+        metrics_handlers::metrics_handler,
+        note_handlers::list_ticket_notes_handler,
+        note_handlers::create_ticket_note_handler,
+        note_handlers::list_work_order_notes_handler,
+        note_handlers::create_work_order_note_handler,
+        note_handlers::get_note_handler,
+        note_handlers::update_note_handler,
+        note_handlers::delete_note_handler,
+        note_handlers::list_note_history_handler,
+        note_handlers::upload_note_attachment_handler,
+        note_handlers::get_note_attachment_handler,
+        note_handlers::delete_note_attachment_handler,
+        jotform_handlers::list_jotforms_handler,
+        jotform_handlers::get_jotform_handler,
+        jotform_handlers::change_status_handler,
+        jotform_handlers::delete_jotform_handler,
+        export_handlers::export_exhibits_handler,
+        export_handlers::export_parts_handler,
+        export_handlers::export_jotforms_handler,
+        trash_handlers::list_trash_handler,
+        trash_handlers::restore_exhibit_handler,
+        trash_handlers::restore_part_handler,
+        trash_handlers::restore_jotform_handler,
+        backup_handlers::list_backups_handler,
+        backup_handlers::create_backup_handler,
+        backup_handlers::download_backup_handler,
+        backup_handlers::restore_backup_handler,
+        snapshot_handlers::get_snapshot_handler,
+        snapshot_handlers::restore_snapshot_handler,
+        webhook_handlers::list_webhooks_handler,
+        webhook_handlers::create_webhook_handler,
+        webhook_handlers::update_webhook_handler,
+        webhook_handlers::delete_webhook_handler,
+        webhook_handlers::list_webhook_deliveries_handler,
+    ]
+}
+
+/// The development utilities, mounted at `DEV_BASE` in builds with the `dev-tools` feature.
+#[cfg(feature = "dev-tools")]
+pub fn dev_routes() -> Vec<rocket::Route> {
+    rocket::routes![
+        development_util_handlers::handle_reset_db,
+        development_util_handlers::create_dummy_exhibits_handler,
+    ]
+}
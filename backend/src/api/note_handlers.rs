@@ -0,0 +1,502 @@
+use crate::api::json_body::JsonBody;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::models::{Note, NoteAttachment, NoteRevision, NoteTarget};
+use crate::repo::note_repo;
+use chrono::Utc;
+use rocket::data::{Data, ToByteUnit};
+use rocket::http::{ContentType, Header, Status};
+use rocket::serde::json::{json, Json};
+use rocket::serde::Deserialize;
+use rocket::State;
+use rocket::{delete, get, post, put, Responder};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// The largest file that can be attached to a note.
+const MAX_ATTACHMENT_MIB: u64 = 10;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct NewNote {
+    pub submitter: String,
+    #[validate(length(min = 1, message = "Note cannot be empty"))]
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct EditNote {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Edited by must be between 1 and 100 characters"
+    ))]
+    pub edited_by: String,
+    #[validate(length(min = 1, message = "Note cannot be empty"))]
+    pub message: String,
+}
+
+/// Attachment types safe to show in the browser. Anything else, HTML and SVG included, is
+/// downloaded as plain bytes so an uploaded file can't run script on this site.
+const INLINE_ATTACHMENT_TYPES: [&str; 6] = [
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/avif",
+    "application/pdf",
+];
+
+/// An attachment's contents, served with its file name, and with its original content type
+/// when that's one the browser can safely show.
+#[derive(Responder)]
+pub struct AttachmentDownload {
+    data: Vec<u8>,
+    content_type: ContentType,
+    disposition: Header<'static>,
+    no_sniff: Header<'static>,
+}
+
+impl AttachmentDownload {
+    pub fn new(file_name: &str, content_type: &str, data: Vec<u8>) -> AttachmentDownload {
+        let inline = ContentType::parse_flexible(content_type).filter(|content_type| {
+            let media_type = format!("{}/{}", content_type.top(), content_type.sub());
+            INLINE_ATTACHMENT_TYPES.contains(&media_type.to_ascii_lowercase().as_str())
+        });
+        let disposition = if inline.is_some() {
+            "inline"
+        } else {
+            "attachment"
+        };
+
+        AttachmentDownload {
+            data,
+            content_type: inline.unwrap_or(ContentType::Binary),
+            disposition: Header::new(
+                "Content-Disposition",
+                format!("{}; {}", disposition, disposition_file_name(file_name)),
+            ),
+            no_sniff: Header::new("X-Content-Type-Options", "nosniff"),
+        }
+    }
+}
+
+/// The `filename` parameters of a Content-Disposition header.
+///
+/// Control characters, quotes and backslashes are dropped. Names that aren't plain ASCII get
+/// an RFC 5987 `filename*` too, with `_` standing in for the rest in the plain `filename`.
+fn disposition_file_name(file_name: &str) -> String {
+    let file_name: String = file_name
+        .chars()
+        .filter(|c| !c.is_control() && !matches!(c, '"' | '\\'))
+        .collect();
+    let file_name = match file_name.trim() {
+        "" => "attachment",
+        trimmed => trimmed,
+    };
+
+    if file_name.is_ascii() {
+        return format!("filename=\"{}\"", file_name);
+    }
+    let fallback: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '_' })
+        .collect();
+    format!(
+        "filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        urlencoding::encode(file_name)
+    )
+}
+
+/// Adds a note to an exhibit, part, ticket or work order and returns it.
+pub(crate) async fn create_target_note(
+    target: NoteTarget,
+    target_id: &str,
+    new_note: NewNote,
+    pool: &DbPool,
+) -> Result<Note, ApiError> {
+    new_note.validate()?;
+
+    if !note_repo::target_exists(target, target_id, pool).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    let mut tx = pool.begin().await?;
+    let id = note_repo::create_note(
+        target,
+        target_id,
+        &new_note.submitter,
+        &new_note.message,
+        Utc::now(),
+        &mut *tx,
+    )
+    .await?;
+    let event = ChangeEvent::new(Entity::Note, Action::Added, Some(id.to_string()))
+        .with_data(json!({ "target_type": target, "target_id": target_id }));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    note_repo::get_note(id, pool)
+        .await?
+        .ok_or(ApiError::InternalServerError)
+}
+
+/// Handles the GET /jotforms/<id>/notes endpoint.
+///
+/// # Returns
+/// * `Result<Json<Vec<Note>>, ApiError>` - The ticket's notes, oldest first.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    responses(
+        (status = 200, description = "The ticket's notes, oldest first", body = Vec<Note>)
+    )
+)]
+#[get("/jotforms/<id>/notes")]
+pub async fn list_ticket_notes_handler(
+    id: &str,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<Note>>, ApiError> {
+    let notes = note_repo::get_notes(NoteTarget::Ticket, id, db_pool.inner()).await?;
+
+    Ok(Json(notes))
+}
+
+/// Handles the POST /jotforms/<id>/notes endpoint.
+///
+/// # Returns
+/// * `Result<Json<Note>, ApiError>` - The newly created note.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - The ticket is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    request_body = NewNote,
+    responses(
+        (status = 200, description = "The newly created note", body = Note)
+    )
+)]
+#[post("/jotforms/<id>/notes", format = "json", data = "<new_note>")]
+pub async fn create_ticket_note_handler(
+    id: &str,
+    new_note: JsonBody<NewNote>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Note>, ApiError> {
+    let note = create_target_note(
+        NoteTarget::Ticket,
+        id,
+        new_note.into_inner(),
+        db_pool.inner(),
+    )
+    .await?;
+
+    Ok(Json(note))
+}
+
+/// Handles the GET /maintenance/<id>/notes endpoint.
+///
+/// # Returns
+/// * `Result<Json<Vec<Note>>, ApiError>` - The work order's notes, oldest first.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    responses(
+        (status = 200, description = "The work order's notes, oldest first", body = Vec<Note>)
+    )
+)]
+#[get("/maintenance/<id>/notes")]
+pub async fn list_work_order_notes_handler(
+    id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<Note>>, ApiError> {
+    let notes =
+        note_repo::get_notes(NoteTarget::WorkOrder, &id.to_string(), db_pool.inner()).await?;
+
+    Ok(Json(notes))
+}
+
+/// Handles the POST /maintenance/<id>/notes endpoint.
+///
+/// # Returns
+/// * `Result<Json<Note>, ApiError>` - The newly created note.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - The maintenance task is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    request_body = NewNote,
+    responses(
+        (status = 200, description = "The newly created note", body = Note)
+    )
+)]
+#[post("/maintenance/<id>/notes", format = "json", data = "<new_note>")]
+pub async fn create_work_order_note_handler(
+    id: i64,
+    new_note: JsonBody<NewNote>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Note>, ApiError> {
+    let note = create_target_note(
+        NoteTarget::WorkOrder,
+        &id.to_string(),
+        new_note.into_inner(),
+        db_pool.inner(),
+    )
+    .await?;
+
+    Ok(Json(note))
+}
+
+/// Handles the GET /notes/<id> endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The note is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    responses(
+        (status = 200, description = "The note", body = Note)
+    )
+)]
+#[get("/notes/<id>")]
+pub async fn get_note_handler(id: i64, db_pool: &State<DbPool>) -> Result<Json<Note>, ApiError> {
+    match note_repo::get_note(id, db_pool.inner()).await? {
+        Some(note) => Ok(Json(note)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Handles the PUT /notes/<id> endpoint.
+///
+/// Replaces the note's message. The previous message is kept in the note's history.
+///
+/// # Returns
+/// * `Result<Json<Note>, ApiError>` - The edited note.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - The note is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    request_body = EditNote,
+    responses(
+        (status = 200, description = "The edited note", body = Note)
+    )
+)]
+#[put("/notes/<id>", format = "json", data = "<edit>")]
+pub async fn update_note_handler(
+    id: i64,
+    edit: JsonBody<EditNote>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Note>, ApiError> {
+    let edit = edit.into_inner();
+    edit.validate()?;
+
+    let pool = db_pool.inner();
+
+    if !note_repo::edit_note(id, edit.edited_by.trim(), &edit.message, Utc::now(), pool).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    match note_repo::get_note(id, pool).await? {
+        Some(note) => Ok(Json(note)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Handles the DELETE /notes/<id> endpoint.
+///
+/// Deleting a note also deletes its history and attachments.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The note is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
+#[delete("/notes/<id>")]
+pub async fn delete_note_handler(id: i64, db_pool: &State<DbPool>) -> Result<Status, ApiError> {
+    if !note_repo::delete_note(id, db_pool.inner()).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Status::NoContent)
+}
+
+/// Handles the GET /notes/<id>/history endpoint.
+///
+/// # Returns
+/// * `Result<Json<Vec<NoteRevision>>, ApiError>` - The note's previous messages, newest edit first.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The note is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    responses(
+        (status = 200, description = "The note's previous messages, newest edit first", body = Vec<NoteRevision>)
+    )
+)]
+#[get("/notes/<id>/history")]
+pub async fn list_note_history_handler(
+    id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<NoteRevision>>, ApiError> {
+    let pool = db_pool.inner();
+
+    if note_repo::get_note(id, pool).await?.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Json(note_repo::get_note_history(id, pool).await?))
+}
+
+/// Handles the POST /notes/<id>/attachments?file_name=<name> endpoint.
+///
+/// The request body is the raw file; its `Content-Type` is stored and served back with it.
+///
+/// # Returns
+/// * `Result<Json<NoteAttachment>, ApiError>` - The new attachment's details.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The file name is empty or the file is empty.
+/// - The file is larger than 10 MiB.
+/// - The note is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    request_body(content = String, description = "The file, sent with its content type", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "The new attachment's details", body = NoteAttachment)
+    )
+)]
+#[post("/notes/<id>/attachments?<file_name>", data = "<data>")]
+pub async fn upload_note_attachment_handler(
+    id: i64,
+    file_name: &str,
+    content_type: Option<&ContentType>,
+    data: Data<'_>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<NoteAttachment>, ApiError> {
+    // Keep only the final path component of whatever the client sent
+    let file_name = file_name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim();
+    if file_name.is_empty() {
+        return Err(ApiError::InvalidInput("File name cannot be empty".into()));
+    }
+
+    let pool = db_pool.inner();
+
+    if note_repo::get_note(id, pool).await?.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    let contents = data
+        .open(MAX_ATTACHMENT_MIB.mebibytes())
+        .into_bytes()
+        .await
+        .map_err(|_| ApiError::InvalidRequestBody)?;
+    if !contents.is_complete() {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "Attachments can be at most {} MiB",
+            MAX_ATTACHMENT_MIB
+        )));
+    }
+    if contents.is_empty() {
+        return Err(ApiError::InvalidInput("Attachment cannot be empty".into()));
+    }
+
+    let content_type = content_type
+        .cloned()
+        .unwrap_or(ContentType::Binary)
+        .to_string();
+
+    let attachment_id =
+        note_repo::create_attachment(id, file_name, &content_type, &contents, pool).await?;
+
+    match note_repo::get_attachment(id, attachment_id, pool).await? {
+        Some(attachment) => Ok(Json(attachment)),
+        None => Err(ApiError::InternalServerError),
+    }
+}
+
+/// Handles the GET /notes/<id>/attachments/<attachment_id> endpoint.
+///
+/// # Returns
+/// * `Result<AttachmentDownload, ApiError>` - The attachment's contents.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The attachment is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    responses(
+        (status = 200, description = "The attachment. Images and PDFs keep their content type; anything else is sent as a download", content_type = "application/octet-stream")
+    )
+)]
+#[get("/notes/<id>/attachments/<attachment_id>")]
+pub async fn get_note_attachment_handler(
+    id: i64,
+    attachment_id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<AttachmentDownload, ApiError> {
+    let attachment = note_repo::get_attachment_content(id, attachment_id, db_pool.inner())
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(AttachmentDownload::new(
+        &attachment.file_name,
+        &attachment.content_type,
+        attachment.data,
+    ))
+}
+
+/// Handles the DELETE /notes/<id>/attachments/<attachment_id> endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The attachment is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Notes",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
+#[delete("/notes/<id>/attachments/<attachment_id>")]
+pub async fn delete_note_attachment_handler(
+    id: i64,
+    attachment_id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Status, ApiError> {
+    if !note_repo::delete_attachment(id, attachment_id, db_pool.inner()).await? {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Status::NoContent)
+}
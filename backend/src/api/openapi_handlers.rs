@@ -0,0 +1,206 @@
+use crate::errors::ErrorResponse;
+use rocket::get;
+use rocket::response::content::RawHtml;
+use rocket::serde::json::Json;
+use utoipa::openapi::path::Operation;
+use utoipa::openapi::{ContentBuilder, OpenApi as OpenApiDocument, Ref, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+use super::*;
+
+/// The API description, built from the `#[utoipa::path]` on each handler in [`super::routes`].
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Exhibit Manager API",
+        description = "Exhibits, their parts and notes, maintenance, sponsorships and repair tickets."
+    ),
+    servers((url = "/api/v1")),
+    paths(
+        bug_report_handlers::report_bug_handler,
+        bug_report_handlers::list_bug_reports_handler,
+        bug_report_handlers::get_bug_report_screenshot_handler,
+        event_handlers::events_handler,
+        health_handlers::healthz_handler,
+        health_handlers::readyz_handler,
+        exhibit_handlers::get_exhibit_handler,
+        exhibit_handlers::list_exhibits_handler,
+        exhibit_handlers::handle_random_exhibit,
+        exhibit_handlers::get_exhibit_note_handler,
+        exhibit_handlers::list_exhibit_notes_handler,
+        exhibit_handlers::create_exhibit_handler,
+        exhibit_handlers::create_exhibit_note_handler,
+        exhibit_handlers::update_exhibit_handler,
+        exhibit_handlers::add_existing_part_handler,
+        exhibit_handlers::delete_exhibit_handler,
+        exhibit_handlers::change_exhibit_status_handler,
+        exhibit_handlers::batch_exhibits_handler,
+        exhibit_handlers::import_exhibits_handler,
+        exhibit_handlers::delete_exhibit_note_handler,
+        part_handlers::get_part_handler,
+        part_handlers::list_parts_handler,
+        part_handlers::get_part_note_handler,
+        part_handlers::list_part_notes_handler,
+        part_handlers::get_parts_by_ids_handler,
+        part_handlers::update_part_handler,
+        part_handlers::create_part_handler,
+        part_handlers::create_part_note_handler,
+        part_handlers::delete_part_handler,
+        part_handlers::delete_part_note_handler,
+        sponsorship_handlers::list_sponsors_handler,
+        sponsorship_handlers::get_sponsor_handler,
+        sponsorship_handlers::create_sponsor_handler,
+        sponsorship_handlers::update_sponsor_handler,
+        sponsorship_handlers::delete_sponsor_handler,
+        sponsorship_handlers::list_exhibit_sponsorships_handler,
+        sponsorship_handlers::create_sponsorship_handler,
+        sponsorship_handlers::update_sponsorship_handler,
+        sponsorship_handlers::delete_sponsorship_handler,
+        sponsorship_handlers::list_expiring_sponsorships_handler,
+        maintenance_handlers::list_exhibit_maintenance_handler,
+        maintenance_handlers::create_maintenance_task_handler,
+        maintenance_handlers::get_maintenance_task_handler,
+        maintenance_handlers::update_maintenance_task_handler,
+        maintenance_handlers::delete_maintenance_task_handler,
+        maintenance_handlers::complete_maintenance_task_handler,
+        maintenance_handlers::list_maintenance_completions_handler,
+        maintenance_handlers::list_overdue_maintenance_handler,
+        metrics_handlers::metrics_handler,
+        note_handlers::list_ticket_notes_handler,
+        note_handlers::create_ticket_note_handler,
+        note_handlers::list_work_order_notes_handler,
+        note_handlers::create_work_order_note_handler,
+        note_handlers::get_note_handler,
+        note_handlers::update_note_handler,
+        note_handlers::delete_note_handler,
+        note_handlers::list_note_history_handler,
+        note_handlers::upload_note_attachment_handler,
+        note_handlers::get_note_attachment_handler,
+        note_handlers::delete_note_attachment_handler,
+        jotform_handlers::list_jotforms_handler,
+        jotform_handlers::get_jotform_handler,
+        jotform_handlers::change_status_handler,
+        jotform_handlers::delete_jotform_handler,
+        export_handlers::export_exhibits_handler,
+        export_handlers::export_parts_handler,
+        export_handlers::export_jotforms_handler,
+        trash_handlers::list_trash_handler,
+        trash_handlers::restore_exhibit_handler,
+        trash_handlers::restore_part_handler,
+        trash_handlers::restore_jotform_handler,
+        backup_handlers::list_backups_handler,
+        backup_handlers::create_backup_handler,
+        backup_handlers::download_backup_handler,
+        backup_handlers::restore_backup_handler,
+        snapshot_handlers::get_snapshot_handler,
+        snapshot_handlers::restore_snapshot_handler,
+        webhook_handlers::list_webhooks_handler,
+        webhook_handlers::create_webhook_handler,
+        webhook_handlers::update_webhook_handler,
+        webhook_handlers::delete_webhook_handler,
+        webhook_handlers::list_webhook_deliveries_handler,
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&ErrorResponses),
+    tags(
+        (name = "Exhibits"),
+        (name = "Parts"),
+        (name = "Notes", description = "Notes on tickets and work orders, and their attachments"),
+        (name = "Tickets", description = "Repair tickets synced from Jotform"),
+        (name = "Maintenance", description = "Recurring maintenance tasks on exhibits"),
+        (name = "Sponsorships"),
+        (name = "Trash", description = "Deleted exhibits, parts and tickets"),
+        (name = "Export"),
+        (name = "Admin", description = "Backups, snapshots and webhooks"),
+        (name = "Bug reports"),
+        (name = "Events", description = "Changes to the data, as they happen"),
+        (name = "Health"),
+        (name = "Metrics")
+    )
+)]
+pub struct ApiDoc;
+
+/// The OpenAPI document served at GET /openapi.json.
+pub fn document() -> OpenApiDocument {
+    let mut document = ApiDoc::openapi();
+    // utoipa fills the license in from Cargo.toml, which doesn't declare one.
+    document.info.license = None;
+    document
+}
+
+/// Adds the `ErrorResponse` body every handler answers with when it fails.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+            ];
+            for operation in operations.into_iter().flatten() {
+                add_error_responses(operation);
+            }
+        }
+    }
+}
+
+fn add_error_responses(operation: &mut Operation) {
+    for (status, description) in [
+        (
+            "4XX",
+            "The request was invalid, or what it names doesn't exist",
+        ),
+        ("5XX", "Something went wrong on the server"),
+    ] {
+        let response = ResponseBuilder::new()
+            .description(description)
+            .content(
+                "application/json",
+                ContentBuilder::new()
+                    .schema(Some(Ref::from_schema_name("ErrorResponse")))
+                    .build(),
+            )
+            .build();
+        operation
+            .responses
+            .responses
+            .entry(status.to_string())
+            .or_insert(response.into());
+    }
+}
+
+/// Handles the GET /openapi.json endpoint.
+///
+/// # Returns
+/// * `Json<utoipa::openapi::OpenApi>` - The OpenAPI 3.1 description of every other endpoint.
+#[get("/openapi.json")]
+pub fn openapi_handler() -> Json<OpenApiDocument> {
+    Json(document())
+}
+
+/// Handles the GET /docs endpoint.
+///
+/// # Returns
+/// * `RawHtml<&'static str>` - A page that renders GET /openapi.json with Redoc.
+#[get("/docs")]
+pub fn docs_handler() -> RawHtml<&'static str> {
+    RawHtml(
+        r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>Exhibit Manager API</title>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+  </head>
+  <body>
+    <redoc spec-url="/api/v1/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/latest/bundles/redoc.standalone.js"></script>
+  </body>
+</html>
+"#,
+    )
+}
@@ -1,14 +1,27 @@
+use crate::api::json_body::JsonBody;
+use crate::api::note_handlers::{create_target_note, NewNote};
+use crate::api::preconditions::{ConditionalError, IfMatch, Tagged};
 use crate::db::DbPool;
 use crate::errors::ApiError;
-use crate::models::{Note, Part, UpdatePart};
-use crate::repo::part_repo;
+use crate::models::{Note, NoteTarget, Part, UpdatePart};
+use crate::repo::{note_repo, part_repo};
 use log::{error, info};
 use rocket::http::Status;
 use rocket::serde::json::Json;
 use rocket::serde::Deserialize;
 use rocket::State;
-use rocket::{delete, get, post, put};
-use validator::Validate;
+use rocket::{delete, get, post, put, FromForm};
+use utoipa::{IntoParams, ToSchema};
+
+/// Filters accepted by GET /parts and the part export.
+#[derive(Debug, Default, Clone, FromForm, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PartFilter {
+    /// Only parts whose name contains this text, ignoring case.
+    pub search: Option<String>,
+    /// Only parts used by this exhibit.
+    pub exhibit_id: Option<i64>,
+}
 
 /// Handles the GET /parts/<id> endpoint.
 ///
@@ -19,30 +32,36 @@ use validator::Validate;
 /// * `db_pool` - Database connection pool.
 ///
 /// # Returns
-/// * `Result<Json<Part>, ApiError>` - The requested part.
+/// * `Result<Tagged<Json<Part>>, ApiError>` - The requested part, with its version as the `ETag`.
 ///
 /// # Errors
 /// Returns an `ApiError` if:
 /// - The part is not found.
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Parts",
+    responses(
+        (status = 200, description = "The requested part, with its version as the `ETag`", body = Part,
+            headers(("ETag" = String, description = "The record's version, for `If-Match`")))
+    )
+)]
 #[get("/parts/<id>")]
-pub async fn get_part_handler(id: i64, db_pool: &State<DbPool>) -> Result<Json<Part>, ApiError> {
+pub async fn get_part_handler(
+    id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Tagged<Json<Part>>, ApiError> {
     let pool = db_pool.inner().clone();
     let part = part_repo::get_part(id, &pool).await?;
 
     match part {
-        Some(part) => Ok(Json(part)),
+        Some(part) => Ok(Tagged {
+            version: part.version,
+            inner: Json(part),
+        }),
         None => Err(ApiError::NotFound),
     }
 }
 
-#[derive(Debug, Deserialize, Validate)]
-pub struct NewNote {
-    pub submitter: String,
-    #[validate(length(min = 1, message = "Note cannot be empty"))]
-    pub message: String,
-}
-
 /// Creates a new note with associated part.
 ///
 /// # Arguments
@@ -50,32 +69,43 @@ pub struct NewNote {
 /// * `db_pool` - Database connection pool
 ///
 /// # Returns
-/// * `Result<Json<i64>, ApiError>` - The ID of the newly created note
+/// * `Result<Json<Note>, ApiError>` - The newly created note
 ///
 /// # Errors
 /// Returns `ApiError` if:
 /// * Database operations fail
 /// * Input validation fails
+#[utoipa::path(
+    tag = "Parts",
+    request_body = NewNote,
+    responses(
+        (status = 200, description = "The newly created note", body = Note)
+    )
+)]
 #[post("/parts/<id>/notes", format = "json", data = "<new_note>")]
 pub async fn create_part_note_handler(
     id: i64,
-    new_note: Json<NewNote>,
+    new_note: JsonBody<NewNote>,
     db_pool: &State<DbPool>,
-) -> Result<(), ApiError> {
-    let note = new_note.into_inner();
-    let pool = db_pool.inner().clone();
-
-    part_repo::create_part_note(id, note.submitter, note.message, &pool).await?;
+) -> Result<Json<Note>, ApiError> {
+    let note = create_target_note(
+        NoteTarget::Part,
+        &id.to_string(),
+        new_note.into_inner(),
+        db_pool.inner(),
+    )
+    .await?;
 
-    Ok(())
+    Ok(Json(note))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct NewPart {
     pub name: String,
     pub link: String,
     pub exhibit_ids: Vec<i64>,
-    pub notes: Vec<Note>,
+    #[serde(default)]
+    pub notes: Vec<NewNote>,
 }
 
 /// Handles the POST /parts endpoint.
@@ -93,9 +123,16 @@ pub struct NewPart {
 /// # Errors
 /// Returns an `ApiError` if:
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Parts",
+    request_body = NewPart,
+    responses(
+        (status = 200, description = "The part was created")
+    )
+)]
 #[post("/parts", format = "json", data = "<new_part>")]
 pub async fn create_part_handler(
-    new_part: Json<NewPart>,
+    new_part: JsonBody<NewPart>,
     db_pool: &State<DbPool>,
 ) -> Result<(), ApiError> {
     let pool = db_pool.inner().clone();
@@ -105,6 +142,12 @@ pub async fn create_part_handler(
     Ok(())
 }
 
+#[utoipa::path(
+    tag = "Parts",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
 #[delete("/parts/<part_id>/notes/<note_id>")]
 pub async fn delete_part_note_handler(
     part_id: i64,
@@ -113,8 +156,11 @@ pub async fn delete_part_note_handler(
 ) -> Result<Status, ApiError> {
     let pool = db_pool.inner().clone();
 
-    match part_repo::delete_part_note(part_id, note_id, &pool).await {
-        Ok(_) => Ok(Status::NoContent),
+    match note_repo::delete_target_note(NoteTarget::Part, &part_id.to_string(), note_id, &pool)
+        .await
+    {
+        Ok(true) => Ok(Status::NoContent),
+        Ok(false) => Err(ApiError::NotFound),
         Err(e) => {
             error!("Failed to delete exhibit note: {}", e);
             Err(ApiError::DatabaseError(
@@ -139,12 +185,19 @@ pub async fn delete_part_note_handler(
 /// Returns an `ApiError` if:
 /// - The part is not found.
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Parts",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
 #[delete("/parts/<id>")]
 pub async fn delete_part_handler(id: i64, db_pool: &State<DbPool>) -> Result<Status, ApiError> {
     let pool = db_pool.inner().clone();
 
     match part_repo::delete_part(id, &pool).await {
         Ok(_) => Ok(Status::NoContent),
+        Err(sqlx::Error::RowNotFound) => Err(ApiError::NotFound),
         Err(e) => {
             error!("Failed to delete part note: {}", e);
             Err(ApiError::DatabaseError(
@@ -170,6 +223,12 @@ pub async fn delete_part_handler(id: i64, db_pool: &State<DbPool>) -> Result<Sta
 /// Returns an `ApiError` if:
 /// - The note is not found.
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Parts",
+    responses(
+        (status = 200, description = "The note", body = Note)
+    )
+)]
 #[get("/parts/<part_id>/notes/<note_id>")]
 pub async fn get_part_note_handler(
     part_id: i64,
@@ -177,7 +236,8 @@ pub async fn get_part_note_handler(
     db_pool: &State<DbPool>,
 ) -> Result<Json<Note>, ApiError> {
     let pool = db_pool.inner().clone();
-    let note = part_repo::get_part_note(part_id, note_id, &pool).await?;
+    let note =
+        note_repo::get_target_note(NoteTarget::Part, &part_id.to_string(), note_id, &pool).await?;
 
     match note {
         Some(note) => Ok(Json(note)),
@@ -199,19 +259,24 @@ pub async fn get_part_note_handler(
 ///
 /// # Errors
 /// Returns an `ApiError` if:
-/// - The request body is invalid.
+/// - Any of the IDs doesn't match a part.
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Parts",
+    responses(
+        (status = 200, description = "A JSON array of parts corresponding to the provided IDs", body = Vec<Part>)
+    )
+)]
 #[post("/parts/batch", format = "json", data = "<part_ids>")]
 pub async fn get_parts_by_ids_handler(
-    part_ids: Json<Vec<i64>>,
+    part_ids: JsonBody<Vec<i64>>,
     db_pool: &State<DbPool>,
 ) -> Result<Json<Vec<Part>>, ApiError> {
     let part_ids = part_ids.into_inner();
     info!("Received /parts/batch request with IDs: {:?}", part_ids);
 
     if part_ids.is_empty() {
-        info!("Empty part_ids received.");
-        return Err(ApiError::InvalidRequestBody);
+        return Ok(Json(Vec::new()));
     }
 
     let pool = db_pool.inner().clone();
@@ -236,18 +301,21 @@ pub async fn get_parts_by_ids_handler(
 ///
 /// # Errors
 /// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Parts",
+    responses(
+        (status = 200, description = "A JSON array of notes associated with the part", body = Vec<Note>)
+    )
+)]
 #[get("/parts/<part_id>/notes")]
 pub async fn list_part_notes_handler(
     part_id: i64,
     db_pool: &State<DbPool>,
 ) -> Result<Json<Vec<Note>>, ApiError> {
     let pool = db_pool.inner().clone();
-    let notes = part_repo::get_all_part_notes(part_id, &pool).await?;
+    let notes = note_repo::get_notes(NoteTarget::Part, &part_id.to_string(), &pool).await?;
 
-    match notes {
-        Some(notes) => Ok(Json(notes)),
-        None => Err(ApiError::NotFound),
-    }
+    Ok(Json(notes))
 }
 
 /// Handles the GET /parts endpoint.
@@ -255,6 +323,7 @@ pub async fn list_part_notes_handler(
 /// This endpoint retrieves a list of all parts from the database.
 ///
 /// # Arguments
+/// * `filter` - Optional `search` and `exhibit_id` filters.
 /// * `db_pool` - Database connection pool.
 ///
 /// # Returns
@@ -262,15 +331,22 @@ pub async fn list_part_notes_handler(
 ///
 /// # Errors
 /// Returns an `ApiError` if a database operation fails.
-#[get("/parts")]
-pub async fn list_parts_handler(db_pool: &State<DbPool>) -> Result<Json<Vec<Part>>, ApiError> {
+#[utoipa::path(
+    tag = "Parts",
+    params(PartFilter),
+    responses(
+        (status = 200, description = "A JSON array of parts", body = Vec<Part>)
+    )
+)]
+#[get("/parts?<filter..>")]
+pub async fn list_parts_handler(
+    filter: PartFilter,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<Part>>, ApiError> {
     let pool = db_pool.inner().clone();
-    let parts = part_repo::get_all_parts(&pool).await?;
+    let parts = part_repo::find_parts(&filter, 0, None, &pool).await?;
 
-    match parts {
-        Some(parts) => Ok(Json(parts)),
-        None => Ok(Json(Vec::new())),
-    }
+    Ok(Json(parts))
 }
 
 /// Handles the PUT /parts/<id> endpoint.
@@ -278,27 +354,57 @@ pub async fn list_parts_handler(db_pool: &State<DbPool>) -> Result<Json<Vec<Part
 /// This endpoint updates an existing part with the provided data. It updates the part's
 /// details as well as its associated exhibits and notes.
 ///
+/// If the request carries an `If-Match` header the update only applies when it matches the
+/// part's current `ETag`; otherwise the current part is returned with `412`.
+///
 /// # Arguments
 /// * `id` - The ID of the part to update.
+/// * `if_match` - The parsed `If-Match` header, if any.
 /// * `updated_part` - JSON payload containing the updated part data.
 /// * `db_pool` - Database connection pool.
 ///
 /// # Returns
-/// * `Result<Status, ApiError>` - HTTP status indicating the result of the operation.
+/// * `Result<Tagged<()>, ConditionalError<Part>>` - An empty response carrying the new `ETag`.
 ///
 /// # Errors
-/// Returns an `ApiError` if:
+/// Returns a `ConditionalError` if:
 /// - The part is not found.
+/// - The `If-Match` header is stale.
 /// - A database operation fails.
+#[utoipa::path(
+    tag = "Parts",
+    request_body = UpdatePart,
+    responses(
+        (status = 200, description = "Updated",
+            headers(("ETag" = String, description = "The new version"))),
+        (status = 412, description = "`If-Match` is out of date. The current record is returned, with its `ETag`", body = Part)
+    )
+)]
 #[put("/parts/<id>", format = "json", data = "<updated_part>")]
 pub async fn update_part_handler(
     id: i64,
-    updated_part: Json<UpdatePart>,
+    if_match: IfMatch,
+    updated_part: JsonBody<UpdatePart>,
     db_pool: &State<DbPool>,
-) -> Result<(), ApiError> {
+) -> Result<Tagged<()>, ConditionalError<Part>> {
     let pool = db_pool.inner().clone();
 
-    part_repo::update_part(&id, &updated_part.into_inner(), &pool).await?;
+    let new_version = part_repo::update_part(
+        &id,
+        &updated_part.into_inner(),
+        if_match.expected_version(),
+        &pool,
+    )
+    .await?;
 
-    Ok(())
+    match new_version {
+        Some(version) => Ok(Tagged { inner: (), version }),
+        None => match part_repo::get_part(id, &pool).await? {
+            Some(current) => Err(ConditionalError::Stale {
+                version: current.version,
+                current,
+            }),
+            None => Err(ApiError::NotFound.into()),
+        },
+    }
 }
@@ -0,0 +1,112 @@
+use crate::errors::ApiError;
+use rocket::http::{Header, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{Responder, Response};
+use rocket::serde::json::Json;
+use serde::Serialize;
+
+/// Formats a row version as a strong entity tag, e.g. `"3"`.
+pub fn etag_for(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// The parsed `If-Match` request header.
+///
+/// Requests without the header are treated as unconditional so older clients keep working.
+#[derive(Debug, PartialEq, Eq)]
+pub enum IfMatch {
+    Absent,
+    Any,
+    Tags(Vec<String>),
+}
+
+impl IfMatch {
+    pub fn parse(value: &str) -> Self {
+        if value.trim() == "*" {
+            return IfMatch::Any;
+        }
+
+        let tags = value
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/").to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+
+        IfMatch::Tags(tags)
+    }
+
+    /// Returns the version the client expects, if it sent exactly one usable entity tag.
+    ///
+    /// `None` means the update should not be conditional. A header that names several tags
+    /// or tags we never issued maps to `Some(-1)`, which can never match a stored version.
+    pub fn expected_version(&self) -> Option<i64> {
+        match self {
+            IfMatch::Absent | IfMatch::Any => None,
+            IfMatch::Tags(tags) => match tags.as_slice() {
+                [tag] => Some(tag.trim_matches('"').parse().unwrap_or(-1)),
+                _ => Some(-1),
+            },
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request.headers().get_one("If-Match") {
+            Some(value) => Outcome::Success(IfMatch::parse(value)),
+            None => Outcome::Success(IfMatch::Absent),
+        }
+    }
+}
+
+/// Wraps a response and attaches an `ETag` header for the given version.
+pub struct Tagged<R> {
+    pub inner: R,
+    pub version: i64,
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Tagged<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> Result<Response<'o>, Status> {
+        Response::build_from(self.inner.respond_to(request)?)
+            .header(Header::new("ETag", etag_for(self.version)))
+            .ok()
+    }
+}
+
+/// Error type for conditional updates.
+///
+/// `Stale` answers `412 Precondition Failed` with the current representation and its
+/// `ETag`, so the client can merge and retry without another round trip.
+pub enum ConditionalError<T> {
+    Stale { current: T, version: i64 },
+    Api(ApiError),
+}
+
+impl<T> From<ApiError> for ConditionalError<T> {
+    fn from(error: ApiError) -> Self {
+        ConditionalError::Api(error)
+    }
+}
+
+impl<T> From<sqlx::Error> for ConditionalError<T> {
+    fn from(error: sqlx::Error) -> Self {
+        ConditionalError::Api(error.into())
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for ConditionalError<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> Result<Response<'static>, Status> {
+        match self {
+            ConditionalError::Stale { current, version } => {
+                Response::build_from(Json(current).respond_to(request)?)
+                    .status(Status::PreconditionFailed)
+                    .header(Header::new("ETag", etag_for(version)))
+                    .ok()
+            }
+            ConditionalError::Api(error) => error.respond_to(request),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::config::AppConfig;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::models::RestoreSummary;
+use crate::repo::snapshot_repo;
+use crate::snapshot;
+use crate::time_zone::site_today;
+use chrono::Utc;
+use log::{error, info, warn};
+use rocket::data::{Data, ToByteUnit};
+use rocket::http::{ContentType, Header};
+use rocket::serde::json::Json;
+use rocket::tokio::fs::{self, File};
+use rocket::tokio::task;
+use rocket::{get, post, Responder, State};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The largest snapshot archive that can be restored.
+const MAX_SNAPSHOT_MIB: u64 = 1024;
+
+/// A snapshot archive, named for download.
+#[derive(Responder)]
+pub struct SnapshotDownload {
+    file: File,
+    content_type: ContentType,
+    disposition: Header<'static>,
+}
+
+/// A fresh path in the system's temporary directory.
+fn temp_path(prefix: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "{}-{}",
+        prefix,
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ))
+}
+
+/// Where an uploaded snapshot and its images are kept while it's restored: next to the
+/// images directory, so the images can be renamed into place rather than copied.
+fn staging_dir(images_dir: &Path) -> PathBuf {
+    images_dir.with_file_name(format!(
+        ".snapshot-restore-{}",
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ))
+}
+
+/// Handles the GET /admin/snapshot endpoint.
+///
+/// Produces a zip archive of the whole database (exhibits, parts and their links, sponsors,
+/// maintenance schedules, tickets, notes with their history and attachments, and the trash)
+/// plus every uploaded image, for moving to another site with POST /admin/snapshot.
+///
+/// # Arguments
+/// * `db_pool` - Database connection pool.
+/// * `config` - Where images are stored.
+///
+/// # Returns
+/// * `Result<SnapshotDownload, ApiError>` - The snapshot archive.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - A database operation fails.
+/// - The images can't be read or the archive can't be written.
+#[utoipa::path(
+    tag = "Admin",
+    responses(
+        (status = 200, description = "The snapshot archive", content_type = "application/zip")
+    )
+)]
+#[get("/admin/snapshot")]
+pub async fn get_snapshot_handler(
+    db_pool: &State<DbPool>,
+    config: &State<AppConfig>,
+) -> Result<SnapshotDownload, ApiError> {
+    let (snapshot, attachments) = snapshot_repo::read_snapshot(db_pool.inner()).await?;
+    let images_dir = config.images_dir.clone();
+    let path = temp_path("exhibit-manager-snapshot.zip");
+
+    // Compressing everything is slow, blocking work, and the archive can be too big to hold
+    // in memory, so it's written to a file
+    let written = {
+        let path = path.clone();
+        task::spawn_blocking(move || {
+            snapshot::write_archive(&snapshot, &attachments, &images_dir, &path)
+        })
+        .await
+        .map_err(|_| ApiError::InternalServerError)?
+    };
+    let file = match written {
+        Ok(()) => File::open(&path).await,
+        Err(e) => Err(io::Error::other(e)),
+    };
+    // The open file can still be read once its name is gone, and the space is freed as soon
+    // as it's closed
+    let _ = fs::remove_file(&path).await;
+    let file = file.map_err(|e| {
+        error!("Failed to write the snapshot archive: {}", e);
+        ApiError::InternalServerError
+    })?;
+
+    Ok(SnapshotDownload {
+        file,
+        content_type: ContentType::ZIP,
+        disposition: Header::new(
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"exhibit-manager-snapshot-{}.zip\"",
+                site_today()
+            ),
+        ),
+    })
+}
+
+/// Handles the POST /admin/snapshot endpoint.
+///
+/// The request body is an archive from GET /admin/snapshot. It can only be restored into an
+/// empty database, such as a new site's, so nothing is ever merged or overwritten. Records
+/// get new IDs and the references between them are rewritten to match. The upload is saved
+/// to disk and its images unpacked beside the images directory, then moved into place once
+/// the database has been restored. Images that can't be moved are listed in the summary.
+///
+/// Exhibit image URLs are kept as they are, so images referenced by the old site's address
+/// need that address updated.
+///
+/// # Arguments
+/// * `data` - The snapshot archive.
+/// * `db_pool` - Database connection pool.
+/// * `config` - Where images are stored.
+///
+/// # Returns
+/// * `Result<Json<RestoreSummary>, ApiError>` - How many records and images were restored.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The archive is larger than 1 GiB, isn't a snapshot, or is from a newer version.
+/// - The database already has records in it.
+/// - A database operation fails, in which case nothing is restored.
+/// - The upload can't be saved or unpacked, in which case nothing is restored.
+#[utoipa::path(
+    tag = "Admin",
+    request_body(content = String, description = "An archive from GET /admin/snapshot", content_type = "application/zip"),
+    responses(
+        (status = 200, description = "How many records and images were restored", body = RestoreSummary)
+    )
+)]
+#[post("/admin/snapshot", data = "<data>")]
+pub async fn restore_snapshot_handler(
+    data: Data<'_>,
+    db_pool: &State<DbPool>,
+    config: &State<AppConfig>,
+) -> Result<Json<RestoreSummary>, ApiError> {
+    let staging = staging_dir(&config.images_dir);
+    let restored = restore_staged(data, db_pool.inner(), &config.images_dir, &staging).await;
+    if let Err(e) = fs::remove_dir_all(&staging).await {
+        warn!("Failed to remove {}: {}", staging.display(), e);
+    }
+    let summary = restored?;
+
+    info!(
+        "Restored a snapshot with {} exhibits, {} parts, {} tickets and {} images",
+        summary.exhibits, summary.parts, summary.tickets, summary.images
+    );
+
+    Ok(Json(summary))
+}
+
+/// Saves the upload into `staging`, unpacks its images there, restores the records, and
+/// only then moves the images into place.
+async fn restore_staged(
+    data: Data<'_>,
+    pool: &DbPool,
+    images_dir: &Path,
+    staging: &Path,
+) -> Result<RestoreSummary, ApiError> {
+    let upload = staging.join("snapshot.zip");
+    let staged_images = staging.join("images");
+    fs::create_dir_all(&staged_images).await.map_err(|e| {
+        error!("Failed to create {}: {}", staged_images.display(), e);
+        ApiError::InternalServerError
+    })?;
+
+    let written = data
+        .open(MAX_SNAPSHOT_MIB.mebibytes())
+        .into_file(&upload)
+        .await
+        .map_err(|_| ApiError::InvalidRequestBody)?;
+    if !written.is_complete() {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "Snapshots can be at most {} MiB",
+            MAX_SNAPSHOT_MIB
+        )));
+    }
+    drop(written);
+
+    let archive = {
+        let staged_images = staged_images.clone();
+        task::spawn_blocking(move || snapshot::read_archive(&upload, &staged_images))
+            .await
+            .map_err(|_| ApiError::InternalServerError)?
+            .map_err(ApiError::InvalidInput)?
+    };
+
+    let mut summary =
+        snapshot_repo::restore_snapshot(&archive.snapshot, &archive.attachments, pool)
+            .await?
+            .ok_or_else(|| {
+                ApiError::Conflict("Snapshots can only be restored into an empty database".into())
+            })?;
+
+    // The records are in, so a failure from here on is reported rather than failing the
+    // whole restore
+    let images = archive.images;
+    let moved = {
+        let (images, images_dir) = (images.clone(), images_dir.to_path_buf());
+        task::spawn_blocking(move || snapshot::move_images(&staged_images, &images_dir, &images))
+            .await
+    };
+    summary.images_not_restored = moved.unwrap_or_else(|e| {
+        error!("Snapshot restored, but moving its images failed: {}", e);
+        images.clone()
+    });
+    summary.images = images.len() - summary.images_not_restored.len();
+
+    Ok(summary)
+}
@@ -0,0 +1,406 @@
+use crate::api::json_body::JsonBody;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::models::{validate_date_range, ExpiringSponsorship, SponsorProfile, Sponsorship};
+use crate::repo::sponsorship_repo;
+use crate::time_zone::site_today;
+use chrono::{Days, NaiveDate};
+use log::error;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::serde::Deserialize;
+use rocket::State;
+use rocket::{delete, get, post, put};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+/// How far ahead `/sponsorships/expiring` looks when no window is given.
+const DEFAULT_EXPIRY_WINDOW_DAYS: u64 = 60;
+
+/// The furthest ahead `/sponsorships/expiring` will look (ten years).
+const MAX_EXPIRY_WINDOW_DAYS: u64 = 3650;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct NewSponsor {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Name must be between 1 and 100 characters"
+    ))]
+    pub name: String,
+    pub contact_name: Option<String>,
+    #[validate(email(message = "Contact email must be a valid email address"))]
+    pub contact_email: Option<String>,
+    pub contact_phone: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[validate(schema(function = "NewSponsorship::validate_date_range"))]
+pub struct NewSponsorship {
+    pub sponsor_id: i64,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    #[validate(range(min = 0, message = "Amount cannot be negative"))]
+    pub amount_cents: Option<i64>,
+    pub notes: Option<String>,
+}
+
+impl NewSponsorship {
+    fn validate_date_range(&self) -> Result<(), ValidationError> {
+        validate_date_range(self.start_date, self.end_date)
+    }
+}
+
+/// Maps constraint violations to client errors and logs everything else.
+fn map_sponsorship_error(e: sqlx::Error, action: &str) -> ApiError {
+    match &e {
+        sqlx::Error::RowNotFound => ApiError::NotFound,
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            ApiError::Conflict("A sponsor with that name already exists".into())
+        }
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            ApiError::InvalidInput("Unknown exhibit or sponsor".into())
+        }
+        _ => {
+            error!("Failed to {}: {}", action, e);
+            ApiError::DatabaseError(format!("Failed to {}", action))
+        }
+    }
+}
+
+/// Parses an expiry window such as `60d`, `8w` or `60` (days) into a number of days.
+fn parse_window(within: &str) -> Option<u64> {
+    let within = within.trim();
+
+    let days = if let Some(weeks) = within.strip_suffix('w') {
+        weeks.parse::<u64>().ok()?.checked_mul(7)?
+    } else {
+        within
+            .strip_suffix('d')
+            .unwrap_or(within)
+            .parse::<u64>()
+            .ok()?
+    };
+
+    (days <= MAX_EXPIRY_WINDOW_DAYS).then_some(days)
+}
+
+/// Handles the GET /sponsors endpoint.
+///
+/// # Returns
+/// * `Result<Json<Vec<SponsorProfile>>, ApiError>` - All sponsors, ordered by name.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    responses(
+        (status = 200, description = "All sponsors, ordered by name", body = Vec<SponsorProfile>)
+    )
+)]
+#[get("/sponsors")]
+pub async fn list_sponsors_handler(
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<SponsorProfile>>, ApiError> {
+    let pool = db_pool.inner().clone();
+    let sponsors = sponsorship_repo::get_all_sponsors(&pool).await?;
+
+    Ok(Json(sponsors))
+}
+
+/// Handles the GET /sponsors/<id> endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The sponsor is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    responses(
+        (status = 200, description = "The sponsor", body = SponsorProfile)
+    )
+)]
+#[get("/sponsors/<id>")]
+pub async fn get_sponsor_handler(
+    id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Json<SponsorProfile>, ApiError> {
+    let pool = db_pool.inner().clone();
+
+    match sponsorship_repo::get_sponsor(id, &pool).await? {
+        Some(sponsor) => Ok(Json(sponsor)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Handles the POST /sponsors endpoint.
+///
+/// # Returns
+/// * `Result<Json<SponsorProfile>, ApiError>` - The newly created sponsor.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - A sponsor with the same name already exists.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    request_body = NewSponsor,
+    responses(
+        (status = 200, description = "The newly created sponsor", body = SponsorProfile)
+    )
+)]
+#[post("/sponsors", format = "json", data = "<new_sponsor>")]
+pub async fn create_sponsor_handler(
+    new_sponsor: JsonBody<NewSponsor>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<SponsorProfile>, ApiError> {
+    let sponsor = new_sponsor.into_inner();
+    sponsor.validate()?;
+
+    let pool = db_pool.inner().clone();
+
+    let id = sponsorship_repo::create_sponsor(&sponsor, &pool)
+        .await
+        .map_err(|e| map_sponsorship_error(e, "create sponsor"))?;
+
+    match sponsorship_repo::get_sponsor(id, &pool).await? {
+        Some(sponsor) => Ok(Json(sponsor)),
+        None => Err(ApiError::InternalServerError),
+    }
+}
+
+/// Handles the PUT /sponsors/<id> endpoint.
+///
+/// Replaces the sponsor's name and contact details.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - The sponsor is not found.
+/// - Another sponsor already has the new name.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    request_body = NewSponsor,
+    responses(
+        (status = 200, description = "Updated")
+    )
+)]
+#[put("/sponsors/<id>", format = "json", data = "<updated_sponsor>")]
+pub async fn update_sponsor_handler(
+    id: i64,
+    updated_sponsor: JsonBody<NewSponsor>,
+    db_pool: &State<DbPool>,
+) -> Result<(), ApiError> {
+    let sponsor = updated_sponsor.into_inner();
+    sponsor.validate()?;
+
+    let pool = db_pool.inner().clone();
+
+    sponsorship_repo::update_sponsor(id, &sponsor, &pool)
+        .await
+        .map_err(|e| map_sponsorship_error(e, "update sponsor"))?;
+
+    Ok(())
+}
+
+/// Handles the DELETE /sponsors/<id> endpoint.
+///
+/// Sponsors that still have sponsorships can't be deleted, so history isn't lost.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The sponsor is not found.
+/// - The sponsor still has sponsorships.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
+#[delete("/sponsors/<id>")]
+pub async fn delete_sponsor_handler(id: i64, db_pool: &State<DbPool>) -> Result<Status, ApiError> {
+    let pool = db_pool.inner().clone();
+
+    sponsorship_repo::delete_sponsor(id, &pool)
+        .await
+        .map_err(|e| match &e {
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                ApiError::Conflict("Sponsor still has sponsorships".into())
+            }
+            _ => map_sponsorship_error(e, "delete sponsor"),
+        })?;
+
+    Ok(Status::NoContent)
+}
+
+/// Handles the GET /exhibits/<exhibit_id>/sponsorships endpoint.
+///
+/// # Returns
+/// * `Result<Json<Vec<Sponsorship>>, ApiError>` - The exhibit's sponsorship history, newest first.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    responses(
+        (status = 200, description = "The exhibit's sponsorship history, newest first", body = Vec<Sponsorship>)
+    )
+)]
+#[get("/exhibits/<exhibit_id>/sponsorships")]
+pub async fn list_exhibit_sponsorships_handler(
+    exhibit_id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<Sponsorship>>, ApiError> {
+    let pool = db_pool.inner().clone();
+    let sponsorships = sponsorship_repo::get_exhibit_sponsorships(exhibit_id, &pool).await?;
+
+    Ok(Json(sponsorships))
+}
+
+/// Handles the POST /exhibits/<exhibit_id>/sponsorships endpoint.
+///
+/// # Returns
+/// * `Result<Json<Sponsorship>, ApiError>` - The newly created sponsorship.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails, including an end date that isn't after the start date.
+/// - The exhibit or sponsor doesn't exist.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    request_body = NewSponsorship,
+    responses(
+        (status = 200, description = "The newly created sponsorship", body = Sponsorship)
+    )
+)]
+#[post(
+    "/exhibits/<exhibit_id>/sponsorships",
+    format = "json",
+    data = "<new_sponsorship>"
+)]
+pub async fn create_sponsorship_handler(
+    exhibit_id: i64,
+    new_sponsorship: JsonBody<NewSponsorship>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Sponsorship>, ApiError> {
+    let sponsorship = new_sponsorship.into_inner();
+    sponsorship.validate()?;
+
+    let pool = db_pool.inner().clone();
+
+    let id = sponsorship_repo::create_sponsorship(exhibit_id, &sponsorship, &pool)
+        .await
+        .map_err(|e| map_sponsorship_error(e, "create sponsorship"))?;
+
+    match sponsorship_repo::get_sponsorship(id, &pool).await? {
+        Some(sponsorship) => Ok(Json(sponsorship)),
+        None => Err(ApiError::InternalServerError),
+    }
+}
+
+/// Handles the PUT /sponsorships/<id> endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - The sponsorship or sponsor doesn't exist.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    request_body = NewSponsorship,
+    responses(
+        (status = 200, description = "Updated")
+    )
+)]
+#[put("/sponsorships/<id>", format = "json", data = "<updated_sponsorship>")]
+pub async fn update_sponsorship_handler(
+    id: i64,
+    updated_sponsorship: JsonBody<NewSponsorship>,
+    db_pool: &State<DbPool>,
+) -> Result<(), ApiError> {
+    let sponsorship = updated_sponsorship.into_inner();
+    sponsorship.validate()?;
+
+    let pool = db_pool.inner().clone();
+
+    sponsorship_repo::update_sponsorship(id, &sponsorship, &pool)
+        .await
+        .map_err(|e| map_sponsorship_error(e, "update sponsorship"))?;
+
+    Ok(())
+}
+
+/// Handles the DELETE /sponsorships/<id> endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The sponsorship is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
+#[delete("/sponsorships/<id>")]
+pub async fn delete_sponsorship_handler(
+    id: i64,
+    db_pool: &State<DbPool>,
+) -> Result<Status, ApiError> {
+    let pool = db_pool.inner().clone();
+
+    sponsorship_repo::delete_sponsorship(id, &pool)
+        .await
+        .map_err(|e| map_sponsorship_error(e, "delete sponsorship"))?;
+
+    Ok(Status::NoContent)
+}
+
+/// Handles the GET /sponsorships/expiring?within=<window> endpoint.
+///
+/// Lists sponsorships ending within the window (e.g. `60d`, `8w`; defaults to 60 days) that
+/// haven't been renewed yet, so the development office can follow up before signage goes
+/// stale.
+///
+/// # Arguments
+/// * `within` - How far ahead to look, as days (`60d` or `60`) or weeks (`8w`).
+/// * `db_pool` - Database connection pool.
+///
+/// # Returns
+/// * `Result<Json<Vec<ExpiringSponsorship>>, ApiError>` - Expiring sponsorships, soonest first.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The window can't be parsed or is longer than ten years.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Sponsorships",
+    responses(
+        (status = 200, description = "Expiring sponsorships, soonest first", body = Vec<ExpiringSponsorship>)
+    )
+)]
+#[get("/sponsorships/expiring?<within>")]
+pub async fn list_expiring_sponsorships_handler(
+    within: Option<&str>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<ExpiringSponsorship>>, ApiError> {
+    let days = match within {
+        Some(within) => parse_window(within).ok_or_else(|| {
+            ApiError::InvalidInput("`within` must look like 60d or 8w, up to 3650 days".into())
+        })?,
+        None => DEFAULT_EXPIRY_WINDOW_DAYS,
+    };
+
+    let today = site_today();
+    let until = today + Days::new(days);
+
+    let pool = db_pool.inner().clone();
+    let sponsorships = sponsorship_repo::get_expiring_sponsorships(today, until, &pool).await?;
+
+    Ok(Json(sponsorships))
+}
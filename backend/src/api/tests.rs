@@ -0,0 +1,1577 @@
+use super::preconditions::IfMatch;
+use rocket::tokio;
+
+#[test]
+fn test_if_match_expected_version() {
+    assert_eq!(IfMatch::Absent.expected_version(), None);
+    assert_eq!(IfMatch::parse("*").expected_version(), None);
+    assert_eq!(IfMatch::parse("\"4\"").expected_version(), Some(4));
+    assert_eq!(IfMatch::parse("W/\"4\"").expected_version(), Some(4));
+    assert_eq!(IfMatch::parse("\"abc\"").expected_version(), Some(-1));
+    assert_eq!(IfMatch::parse("\"1\", \"2\"").expected_version(), Some(-1));
+}
+
+#[test]
+fn test_config_defaults_overrides_and_validation() {
+    use crate::config::AppConfig;
+    use rocket::figment::providers::{Format, Toml};
+    use rocket::figment::Figment;
+
+    let load = |toml: &str| AppConfig::load(&Figment::from(Toml::string(toml)));
+
+    // Nothing configured: integrations are off and everything else has a default
+    let config = load("").unwrap();
+    assert_eq!(config, AppConfig::default());
+    assert!(config.jotform().is_none());
+    assert!(config.github().is_none());
+
+    // Numeric-looking IDs are still read as text, and blank credentials count as unset
+    let config = load(
+        r#"
+        jotform_api_key = "key"
+        jotform_form_id = 241234567890
+        github_token = ""
+        site_timezone = "Europe/London"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.jotform().unwrap().form_id, "241234567890");
+    assert!(config.github().is_none());
+    assert_eq!(config.site_time_zone(), chrono_tz::Europe::London);
+
+    // Every problem is reported at once
+    let errors = load(
+        r#"
+        site_timezone = "Mars/Olympus"
+        backups_to_keep = 0
+        jotform_form_id = "123"
+        github_repo_name = "exhibit_manager"
+        cors_allowed_origins = []
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(errors.len(), 5, "{:?}", errors);
+
+    assert!(load("backup_interval_hours = \"often\"").is_err());
+
+    // Emails need a sender, and recipients for departments that exist
+    let config = load(
+        r#"
+        smtp_host = "smtp.example.org"
+        email_from = "Exhibit Manager <exhibits@example.org>"
+        email_recipients = { Operations = ["facilities@example.org"] }
+        "#,
+    )
+    .unwrap();
+    let email = config.email().unwrap();
+    assert_eq!(email.recipients["Operations"].len(), 1);
+    assert!(email.credentials.is_none());
+
+    let errors = load(
+        r#"
+        smtp_host = "smtp.example.org"
+        smtp_username = "exhibits"
+        email_recipients = { Janitorial = ["not an address"] }
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(errors.len(), 4, "{:?}", errors);
+}
+
+#[test]
+fn test_config_explains_each_problem() {
+    use crate::config::AppConfig;
+    use rocket::figment::providers::{Format, Toml};
+    use rocket::figment::Figment;
+
+    let errors = |toml: &str| AppConfig::load(&Figment::from(Toml::string(toml))).unwrap_err();
+
+    for (toml, expected) in [
+        (
+            "trash_retention_days = -1",
+            "trash_retention_days must be between 0 and 3650",
+        ),
+        (
+            "backup_interval_hours = 0",
+            "backup_interval_hours must be between 1 and 8760",
+        ),
+        (
+            "cors_allowed_origins = [\" \"]",
+            "cors_allowed_origins must list at least one origin",
+        ),
+        (
+            "cors_allowed_origins = [\"https://example.com:99999\"]",
+            "cors_allowed_origins is invalid",
+        ),
+        (
+            "jotform_base_url = \"ftp://jotform.com\"",
+            "jotform_base_url must be an http or https URL",
+        ),
+        (
+            "github_api_base_url = \"api.github.com\"",
+            "github_api_base_url must be an http or https URL",
+        ),
+        (
+            "jotform_sync_interval_secs = 59",
+            "jotform_sync_interval_secs must be at least 60",
+        ),
+        (
+            "email_digest_hour = 24",
+            "email_digest_hour must be between 0 and 23",
+        ),
+        (
+            "smtp_host = \"smtp.example.org\"",
+            "email_from must be set when smtp_host is",
+        ),
+        (
+            "smtp_host = \"smtp.example.org\"\nemail_from = \"exhibits\"",
+            "email_from `exhibits` is not an email address",
+        ),
+        // Readiness would fail between two healthy runs
+        (
+            "jotform_sync_interval_secs = 3600\nreadiness_max_sync_age_mins = 60",
+            "readiness_max_sync_age_mins must be longer than jotform_sync_interval_secs",
+        ),
+        (
+            "backup_interval_hours = 48\nreadiness_max_backup_age_hours = 48",
+            "readiness_max_backup_age_hours must be longer than backup_interval_hours",
+        ),
+        (
+            "readiness_max_sync_age_mins = 0",
+            "readiness_max_sync_age_mins must be between 1 and 10080",
+        ),
+    ] {
+        let errors = errors(toml);
+        assert_eq!(errors.len(), 1, "{}: {:?}", toml, errors);
+        assert!(errors[0].starts_with(expected), "{}: {:?}", toml, errors);
+    }
+
+    // Settings of the wrong type are named, rather than falling back to their default
+    let errors = errors("backups_to_keep = \"several\"");
+    assert!(errors[0].contains("backups_to_keep"), "{:?}", errors);
+}
+
+#[test]
+fn test_log_lines_are_json_with_personal_fields_redacted() {
+    use crate::logging::{json_line, LogSpan, RequestId};
+    use crate::models::FullName;
+    use log::{Level, Record};
+
+    let fields: [(&str, &str); 3] = [
+        ("request_id", "abc123"),
+        ("contact_email", "someone@example.com"),
+        ("status", "200"),
+    ];
+    let span = LogSpan {
+        name: "jotform_sync",
+        id: "0123456789abcdef".to_string(),
+    };
+    let line = json_line(
+        &Record::builder()
+            .args(format_args!("Synced {} jotforms", 3))
+            .level(Level::Info)
+            .target("exhibit_manager_backend::jotform_api")
+            .key_values(&fields)
+            .build(),
+        Some(&span),
+    );
+
+    assert_eq!(line["level"], "INFO");
+    assert_eq!(line["target"], "exhibit_manager_backend::jotform_api");
+    assert_eq!(line["msg"], "Synced 3 jotforms");
+    assert_eq!(line["span"], "jotform_sync");
+    assert_eq!(line["span_id"], "0123456789abcdef");
+    assert_eq!(line["request_id"], "abc123");
+    assert_eq!(line["contact_email"], "[redacted]");
+    assert!(line["ts"].as_str().unwrap().ends_with('Z'));
+
+    // Names never make it into debug output
+    let name = FullName {
+        first: "Ada".to_string(),
+        last: "Lovelace".to_string(),
+    };
+    assert!(!format!("{:?}", name).contains("Ada"));
+
+    // Request IDs from clients are kept only when they're safe to log
+    assert_eq!(RequestId::from_header(Some("req-42")).0, "req-42");
+    assert_eq!(RequestId::from_header(None).0.len(), 16);
+    assert_ne!(RequestId::from_header(Some("a\nb")).0, "a\nb");
+    assert_ne!(RequestId::from_header(Some(&"x".repeat(65))).0.len(), 65);
+}
+
+#[test]
+fn test_metrics_exposition_format() {
+    use crate::metrics::{self, Exposition};
+    use std::time::Duration;
+
+    metrics::record_request("GET", "/exhibits/<id>", 200, Duration::from_millis(20));
+    metrics::record_request("GET", "/exhibits/<id>", 200, Duration::from_secs(60));
+
+    let mut exposition = Exposition::default();
+    exposition.recorded();
+    exposition.family("exhibits", "gauge", "Exhibits by status.");
+    exposition.sample("exhibits", &[("status", "say \"hi\"\n")], 2.0);
+    let text = exposition.into_string();
+
+    let series = r#"method="GET",route="/exhibits/<id>",status="200""#;
+    // Buckets are cumulative, and the minute-long request only lands in +Inf
+    assert!(text.contains(&format!(
+        "exhibit_manager_http_request_duration_seconds_bucket{{{},le=\"0.01\"}} 0\n",
+        series
+    )));
+    assert!(text.contains(&format!(
+        "exhibit_manager_http_request_duration_seconds_bucket{{{},le=\"0.025\"}} 1\n",
+        series
+    )));
+    assert!(text.contains(&format!(
+        "exhibit_manager_http_request_duration_seconds_bucket{{{},le=\"30\"}} 1\n",
+        series
+    )));
+    assert!(text.contains(&format!(
+        "exhibit_manager_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 2\n",
+        series
+    )));
+    assert!(text.contains(&format!(
+        "exhibit_manager_http_request_duration_seconds_count{{{}}} 2\n",
+        series
+    )));
+    assert!(text.contains("# TYPE exhibit_manager_backups_total counter\n"));
+    assert!(text.contains("exhibit_manager_exhibits{status=\"say \\\"hi\\\"\\n\"} 2\n"));
+}
+
+#[tokio::test]
+async fn test_metrics_count_failed_requests() {
+    use super::metrics_handlers;
+    use crate::logging::RequestTracing;
+    use crate::metrics;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+    use sqlx::SqlitePool;
+    use std::time::Duration;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    crate::db::setup_database(&pool).await.unwrap();
+    let rocket = rocket::build()
+        .manage(pool.clone())
+        .attach(RequestTracing)
+        .mount("/", rocket::routes![metrics_handlers::metrics_handler]);
+    let client = Client::untracked(rocket).await.unwrap();
+    let scrape = || async {
+        let response = client.get("/metrics").dispatch().await;
+        (response.status(), response.into_string().await.unwrap())
+    };
+    let value = |text: &str, series: &str| -> f64 {
+        text.lines()
+            .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+            .map_or(0.0, |value| value.parse().unwrap())
+    };
+    let failures = "exhibit_manager_jotform_sync_failures_total";
+
+    let (status, before) = scrape().await;
+    assert_eq!(status, Status::Ok);
+
+    // Requests no route matched share one series, whatever their path
+    client.patch("/metrics-test/1").dispatch().await;
+    client.patch("/metrics-test/2").dispatch().await;
+    metrics::record_jotform_sync(Duration::from_secs(1), false);
+
+    let (_, text) = scrape().await;
+    assert_eq!(
+        value(
+            &text,
+            r#"exhibit_manager_http_request_duration_seconds_count{method="PATCH",route="unmatched",status="404"}"#
+        ),
+        2.0
+    );
+    assert!(value(&text, failures) >= value(&before, failures) + 1.0);
+
+    // A scrape that fails is counted under its own status
+    pool.close().await;
+    let (status, _) = scrape().await;
+    assert_eq!(status, Status::InternalServerError);
+    let mut exposition = metrics::Exposition::default();
+    exposition.recorded();
+    let text = exposition.into_string();
+    assert_eq!(
+        value(
+            &text,
+            r#"exhibit_manager_http_request_duration_seconds_count{method="GET",route="/metrics",status="500"}"#
+        ),
+        1.0
+    );
+}
+
+#[test]
+fn test_readiness_age_check() {
+    use super::health_handlers::age_check;
+    use chrono::{TimeDelta, TimeZone, Utc};
+
+    let started_at = Utc.with_ymd_and_hms(2024, 6, 1, 8, 0, 0).unwrap();
+    let max_age = TimeDelta::hours(2);
+    let at = |hours: i64| started_at + TimeDelta::hours(hours);
+
+    let recent = age_check("jotform_sync", Some(at(3)), started_at, max_age, at(4));
+    assert!(recent.ok);
+    assert_eq!(recent.age_secs, Some(60 * 60));
+
+    let stale = age_check("jotform_sync", Some(at(1)), started_at, max_age, at(4));
+    assert!(!stale.ok);
+    assert!(
+        stale.detail.contains("more than 2 hours ago"),
+        "{}",
+        stale.detail
+    );
+
+    // Never having run is fine until the app has been up longer than the threshold
+    assert!(age_check("backup", None, started_at, max_age, at(1)).ok);
+    let never = age_check("backup", None, started_at, max_age, at(3));
+    assert!(!never.ok);
+    assert_eq!(
+        never.detail,
+        "Hasn't succeeded in the 2 hours since the app started"
+    );
+
+    // Exactly at the threshold is still fine, a second past it isn't
+    assert!(age_check("backup", Some(at(1)), started_at, max_age, at(3)).ok);
+    let late = at(3) + TimeDelta::seconds(1);
+    assert!(!age_check("backup", Some(at(1)), started_at, max_age, late).ok);
+    let stale = age_check(
+        "backup",
+        Some(at(0)),
+        started_at,
+        TimeDelta::minutes(90),
+        at(3),
+    );
+    assert!(
+        stale.detail.ends_with("more than 90 minutes ago"),
+        "{}",
+        stale.detail
+    );
+}
+
+#[tokio::test]
+async fn test_readiness_fails_past_its_thresholds() {
+    use super::health_handlers::{self, ReadinessSettings};
+    use crate::backup::BackupSettings;
+    use crate::config::AppConfig;
+    use chrono::{TimeDelta, Utc};
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use sqlx::SqlitePool;
+
+    let root = std::env::temp_dir().join(format!(
+        "exhibit-readiness-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    std::fs::create_dir_all(&root).unwrap();
+    // A file where the images directory should be can't be written into
+    std::fs::write(root.join("images"), b"").unwrap();
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    let config = AppConfig {
+        images_dir: root.join("images"),
+        ..AppConfig::default()
+    };
+    let rocket = rocket::build()
+        .manage(pool.clone())
+        .manage(config)
+        .manage(ReadinessSettings {
+            max_sync_age: TimeDelta::hours(2),
+            max_backup_age: TimeDelta::hours(72),
+            started_at: Utc::now() - TimeDelta::hours(73),
+        })
+        .manage(BackupSettings {
+            dir: root.join("backups"),
+            interval: std::time::Duration::from_secs(60),
+            keep: 2,
+            include_images: true,
+        })
+        .mount("/", rocket::routes![health_handlers::readyz_handler]);
+    let client = Client::untracked(rocket).await.unwrap();
+    let failed = || async {
+        let response = client.get("/readyz").dispatch().await;
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let readiness: Value = response.into_json().await.unwrap();
+        assert_eq!(readiness["ready"], false);
+        readiness["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|check| check["ok"] == false)
+            .map(|check| check["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // Up longer than a backup may be stale, without having taken one
+    assert_eq!(failed().await, vec!["images_dir", "backup"]);
+
+    // Sync is turned off, so it doesn't count however long it's been
+    pool.close().await;
+    assert_eq!(failed().await, vec!["database", "images_dir", "backup"]);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_openapi_document_covers_every_route() {
+    use rocket::serde::json::{serde_json, Value};
+    use std::collections::BTreeSet;
+
+    let document = serde_json::to_value(super::openapi_handlers::document()).unwrap();
+
+    let routes: BTreeSet<(String, String)> = super::routes()
+        .iter()
+        .map(|route| {
+            // Rocket writes `/exhibits/<id>`, OpenAPI `/exhibits/{id}`
+            let path = route
+                .uri
+                .path()
+                .split('/')
+                .map(|segment| match segment.strip_prefix('<') {
+                    Some(name) => format!("{{{}}}", name.trim_end_matches('>')),
+                    None => segment.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            (route.method.as_str().to_lowercase(), path)
+        })
+        .collect();
+    let documented: BTreeSet<(String, String)> = document["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .keys()
+                .map(move |method| (method.clone(), path.clone()))
+        })
+        .collect();
+    assert_eq!(routes, documented);
+
+    // Every schema a response or request body points at is in the document
+    fn refs(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    found.push(reference.clone());
+                }
+                map.values().for_each(|v| refs(v, found));
+            }
+            Value::Array(items) => items.iter().for_each(|v| refs(v, found)),
+            _ => {}
+        }
+    }
+    let mut found = Vec::new();
+    refs(&document, &mut found);
+    assert!(found.contains(&"#/components/schemas/ErrorResponse".to_string()));
+    for reference in found {
+        let name = reference.trim_start_matches("#/components/schemas/");
+        assert!(
+            document["components"]["schemas"].get(name).is_some(),
+            "{} isn't in the document",
+            reference
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_openapi_error_responses_match_what_is_sent() {
+    use super::{openapi_handlers, sponsorship_handlers};
+    use crate::errors;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::{serde_json, Value};
+    use sqlx::SqlitePool;
+    use std::collections::BTreeSet;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    crate::db::setup_database(&pool).await.unwrap();
+    let rocket = rocket::build()
+        .manage(pool)
+        .mount(
+            "/",
+            rocket::routes![
+                openapi_handlers::openapi_handler,
+                sponsorship_handlers::create_sponsor_handler
+            ],
+        )
+        .register("/", rocket::catchers![errors::not_found]);
+    let client = Client::untracked(rocket).await.unwrap();
+
+    let response = client.get("/openapi.json").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let document: Value = response.into_json().await.unwrap();
+
+    // Every operation says how it fails, without hiding the errors it declares itself
+    for (path, item) in document["paths"].as_object().unwrap() {
+        for (method, operation) in item.as_object().unwrap() {
+            for status in ["4XX", "5XX"] {
+                assert_eq!(
+                    operation["responses"][status]["content"]["application/json"]["schema"]["$ref"],
+                    "#/components/schemas/ErrorResponse",
+                    "{} {} {}",
+                    method,
+                    path,
+                    status
+                );
+            }
+        }
+    }
+    assert_eq!(
+        document["paths"]["/readyz"]["get"]["responses"]["503"]["content"]["application/json"]
+            ["schema"]["$ref"],
+        "#/components/schemas/Readiness"
+    );
+
+    // The documented error body has the same fields as the ones actually sent
+    let schema = &document["components"]["schemas"]["ErrorResponse"];
+    let documented: BTreeSet<&String> = schema["properties"].as_object().unwrap().keys().collect();
+    let required: BTreeSet<&str> = schema["required"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field.as_str().unwrap())
+        .collect();
+    for response in [
+        client.get("/nowhere").dispatch().await,
+        client
+            .post("/sponsors")
+            .header(ContentType::JSON)
+            .body(r#"{"name": ""}"#)
+            .dispatch()
+            .await,
+    ] {
+        let error: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let sent: BTreeSet<&String> = error.as_object().unwrap().keys().collect();
+        assert_eq!(sent, documented);
+        assert_eq!(
+            required,
+            sent.iter()
+                .map(|field| field.as_str())
+                .collect::<BTreeSet<_>>()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_error_envelope_and_empty_lists() {
+    use super::{exhibit_handlers, sponsorship_handlers};
+    use crate::db::setup_database;
+    use crate::errors;
+    use rocket::http::{ContentType, Header, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::{serde_json, Value};
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    setup_database(&pool).await.unwrap();
+    let rocket = rocket::build()
+        .manage(pool)
+        .mount(
+            "/",
+            rocket::routes![
+                exhibit_handlers::list_exhibits_handler,
+                sponsorship_handlers::create_sponsor_handler
+            ],
+        )
+        .register(
+            "/",
+            rocket::catchers![
+                errors::not_found,
+                errors::handle_invalid_request_body,
+                errors::handle_unprocessable_request_body,
+                errors::other_error
+            ],
+        );
+    let client = Client::untracked(rocket).await.unwrap();
+
+    // The Content-Length is the JSON's, not the message's
+    let response = client.get("/nowhere").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    let length = response.body().preset_size().unwrap();
+    let body = response.into_string().await.unwrap();
+    assert_eq!(length, body.len());
+    let error: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(error["code"], "not_found");
+    assert_eq!(error["message"], "Nothing is at GET /nowhere");
+    assert_eq!(error["details"], serde_json::json!([]));
+
+    // Each failed check is reported against its field, with the caller's request ID
+    let response = client
+        .post("/sponsors")
+        .header(ContentType::JSON)
+        .header(Header::new("X-Request-Id", "req-7"))
+        .body(r#"{"name": "", "contact_email": "nope"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let error: Value = response.into_json().await.unwrap();
+    assert_eq!(error["code"], "validation_failed");
+    assert_eq!(error["request_id"], "req-7");
+    assert_eq!(
+        error["details"],
+        serde_json::json!([
+            {
+                "field": "contact_email",
+                "code": "email",
+                "message": "Contact email must be a valid email address"
+            },
+            {
+                "field": "name",
+                "code": "length",
+                "message": "Name must be between 1 and 100 characters"
+            }
+        ])
+    );
+
+    // Bodies that can't be read say why
+    let response = client
+        .post("/sponsors")
+        .header(ContentType::JSON)
+        .body(r#"{"contact_name": "Ada"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let error: Value = response.into_json().await.unwrap();
+    assert_eq!(error["code"], "invalid_request_body");
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("missing field `name`"));
+    assert_eq!(error["request_id"].as_str().unwrap().len(), 16);
+
+    let response = client
+        .post("/sponsors")
+        .header(ContentType::JSON)
+        .body("{")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let error: Value = response.into_json().await.unwrap();
+    assert_eq!(error["code"], "invalid_request_body");
+
+    // No rows is an empty list, not a 404
+    let response = client.get("/exhibits").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().await.unwrap(), "[]");
+}
+
+#[tokio::test]
+async fn test_failed_requests_are_traced_with_their_request_id() {
+    use super::exhibit_handlers;
+    use crate::errors;
+    use crate::logging::RequestTracing;
+    use rocket::http::{Header, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    crate::db::setup_database(&pool).await.unwrap();
+    let rocket = rocket::build()
+        .manage(pool.clone())
+        .attach(RequestTracing)
+        .mount("/", rocket::routes![exhibit_handlers::get_exhibit_handler])
+        .register(
+            "/",
+            rocket::catchers![errors::not_found, errors::other_error],
+        );
+    let client = Client::untracked(rocket).await.unwrap();
+
+    // The response header and the error body carry the same ID, whoever made it up
+    for (header, kept) in [
+        (Some("req-404"), true),
+        (Some("bad id\n"), false),
+        (None, false),
+    ] {
+        let mut request = client.get("/exhibits/1");
+        if let Some(header) = header {
+            request = request.header(Header::new("X-Request-Id", header));
+        }
+        let response = request.dispatch().await;
+        assert_eq!(response.status(), Status::NotFound);
+        let id = response
+            .headers()
+            .get_one("X-Request-Id")
+            .unwrap()
+            .to_string();
+        let error: Value = response.into_json().await.unwrap();
+        assert_eq!(error["request_id"], id.as_str());
+        assert_eq!(kept, Some(id.as_str()) == header, "{}", id);
+    }
+
+    // Unmatched routes and server errors are traced the same way
+    let response = client.get("/nowhere").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    assert!(response.headers().get_one("X-Request-Id").is_some());
+
+    pool.close().await;
+    let response = client
+        .get("/exhibits/1")
+        .header(Header::new("X-Request-Id", "req-500"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::InternalServerError);
+    assert_eq!(response.headers().get_one("X-Request-Id"), Some("req-500"));
+    let error: Value = response.into_json().await.unwrap();
+    assert_eq!(error["request_id"], "req-500");
+}
+
+/// Routes that fail in the ways the catchers have to handle.
+#[rocket::get("/typed?<count>")]
+fn typed(count: u32) -> String {
+    count.to_string()
+}
+
+#[rocket::get("/panics")]
+fn panics() -> &'static str {
+    panic!("a handler bug")
+}
+
+#[rocket::get("/status/<code>")]
+fn status(code: u16) -> rocket::http::Status {
+    rocket::http::Status::new(code)
+}
+
+#[tokio::test]
+async fn test_every_catcher_sends_a_sized_error_envelope() {
+    use super::sponsorship_handlers;
+    use crate::errors;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::{serde_json, Value};
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    crate::db::setup_database(&pool).await.unwrap();
+    let rocket = rocket::build()
+        .manage(pool)
+        .mount(
+            "/",
+            rocket::routes![
+                sponsorship_handlers::create_sponsor_handler,
+                typed,
+                panics,
+                status
+            ],
+        )
+        .register(
+            "/",
+            rocket::catchers![
+                errors::not_found,
+                errors::handle_invalid_request_body,
+                errors::handle_unprocessable_request_body,
+                errors::handle_method_not_allowed,
+                errors::internal_server_error,
+                errors::other_error
+            ],
+        );
+    let client = Client::untracked(rocket).await.unwrap();
+    let post_sponsor = |body: &'static str| {
+        client
+            .post("/sponsors")
+            .header(ContentType::JSON)
+            .body(body)
+    };
+
+    for (request, expected_status, expected_code) in [
+        (client.get("/nowhere"), Status::NotFound, "not_found"),
+        (
+            post_sponsor("{"),
+            Status::BadRequest,
+            "invalid_request_body",
+        ),
+        (client.get("/status/400"), Status::BadRequest, "bad_request"),
+        // Rocket answers 422 for query parameters, which aren't the body
+        (
+            client.get("/typed?count=many"),
+            Status::UnprocessableEntity,
+            "unprocessable_entity",
+        ),
+        (
+            client.get("/typed"),
+            Status::UnprocessableEntity,
+            "unprocessable_entity",
+        ),
+        (
+            post_sponsor(r#"{"contact_name": "Ada"}"#),
+            Status::UnprocessableEntity,
+            "invalid_request_body",
+        ),
+        (
+            client.get("/status/405"),
+            Status::MethodNotAllowed,
+            "method_not_allowed",
+        ),
+        (
+            client.get("/panics"),
+            Status::InternalServerError,
+            "internal_error",
+        ),
+        (
+            client.get("/status/429"),
+            Status::TooManyRequests,
+            "too_many_requests",
+        ),
+        (
+            client.get("/status/503"),
+            Status::ServiceUnavailable,
+            "service_unavailable",
+        ),
+    ] {
+        let response = request.dispatch().await;
+        assert_eq!(response.status(), expected_status, "{}", expected_code);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+        let length = response.body().preset_size();
+        let body = response.into_string().await.unwrap();
+        assert_eq!(length, Some(body.len()), "{}", body);
+
+        let error: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(error["code"], expected_code, "{}", body);
+        assert!(!error["message"].as_str().unwrap().is_empty());
+        assert_eq!(error["details"], serde_json::json!([]));
+        assert_eq!(error["request_id"].as_str().unwrap().len(), 16);
+    }
+}
+
+#[test]
+fn test_field_errors_are_flattened_with_paths() {
+    use super::sponsorship_handlers::NewSponsorship;
+    use crate::errors::{field_errors, FieldError};
+    use crate::models::Exhibit;
+    use chrono::NaiveDate;
+    use validator::Validate;
+
+    let paths = |errors| -> Vec<(Option<String>, String)> {
+        field_errors(&errors)
+            .into_iter()
+            .map(|FieldError { field, code, .. }| (field, code))
+            .collect()
+    };
+
+    let exhibit: Exhibit =
+        rocket::serde::json::serde_json::from_value(rocket::serde::json::serde_json::json!({
+            "id": 1,
+            "name": "Pendulum",
+            "cluster": "Physics",
+            "location": "Main Hall",
+            "description": "",
+            "status": "broken",
+            "part_ids": [],
+            "notes": [{
+                "id": 1,
+                "submitter": "Ada",
+                "message": "",
+                "created_at": "2026-01-01T00:00:00Z",
+                "edited_at": null,
+                "attachments": []
+            }],
+            "image_url": "not a url",
+            "sponsor": null,
+            "version": 1
+        }))
+        .unwrap();
+
+    assert_eq!(
+        paths(exhibit.validate().unwrap_err()),
+        vec![
+            (Some("image_url".into()), "url".into()),
+            (Some("notes[0].message".into()), "length".into()),
+            (
+                Some("status".into()),
+                "Invalid status. Must be 'operational', 'needs repair', or 'out of service'".into()
+            ),
+        ]
+    );
+
+    // Checks on the body as a whole have no field
+    let sponsorship = NewSponsorship {
+        sponsor_id: 1,
+        start_date: NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
+        end_date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+        amount_cents: None,
+        notes: None,
+    };
+    assert_eq!(
+        paths(sponsorship.validate().unwrap_err()),
+        vec![(None, "End date must be after start date".into())]
+    );
+}
+
+#[tokio::test]
+async fn test_attachments_cannot_run_in_the_browser() {
+    use super::note_handlers;
+    use crate::db::setup_database;
+    use crate::models::NoteTarget;
+    use crate::repo::note_repo;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    setup_database(&pool).await.unwrap();
+    let note_id = note_repo::create_note(
+        NoteTarget::Exhibit,
+        "1",
+        "Kenneth",
+        "See attached",
+        chrono::Utc::now(),
+        &pool,
+    )
+    .await
+    .unwrap();
+    let attach = |file_name: &'static str, content_type: &'static str| {
+        let pool = pool.clone();
+        async move {
+            note_repo::create_attachment(note_id, file_name, content_type, b"<svg/>", &pool)
+                .await
+                .unwrap()
+        }
+    };
+    let photo = attach("lens.JPG", "image/jpeg").await;
+    let page = attach("evil.html", "text/html").await;
+    let drawing = attach("\"x\"\r\nSet-Cookie: a=b.svg", "image/svg+xml").await;
+    let manual = attach("Bedienungsanleitung für Kräne.pdf", "application/pdf").await;
+
+    let rocket = rocket::build().manage(pool).mount(
+        "/",
+        rocket::routes![note_handlers::get_note_attachment_handler],
+    );
+    let client = Client::untracked(rocket).await.unwrap();
+    let download = |id: i64| client.get(format!("/notes/{}/attachments/{}", note_id, id));
+
+    // Images and PDFs are shown as themselves
+    let response = download(photo).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    let headers = response.headers();
+    assert_eq!(headers.get_one("Content-Type"), Some("image/jpeg"));
+    assert_eq!(
+        headers.get_one("Content-Disposition"),
+        Some("inline; filename=\"lens.JPG\"")
+    );
+    assert_eq!(headers.get_one("X-Content-Type-Options"), Some("nosniff"));
+
+    // Anything the browser could run is only ever downloaded
+    for (id, file_name) in [(page, "evil.html"), (drawing, "xSet-Cookie: a=b.svg")] {
+        let response = download(id).dispatch().await;
+        let headers = response.headers();
+        assert_eq!(
+            headers.get_one("Content-Type"),
+            Some("application/octet-stream")
+        );
+        assert_eq!(
+            headers.get_one("Content-Disposition"),
+            Some(format!("attachment; filename=\"{}\"", file_name).as_str())
+        );
+        assert_eq!(headers.get_one("X-Content-Type-Options"), Some("nosniff"));
+    }
+
+    // Names outside ASCII keep their spelling in filename*
+    let response = download(manual).dispatch().await;
+    assert_eq!(
+        response.headers().get_one("Content-Disposition"),
+        Some(
+            "inline; filename=\"Bedienungsanleitung f_r Kr_ne.pdf\"; \
+             filename*=UTF-8''Bedienungsanleitung%20f%C3%BCr%20Kr%C3%A4ne.pdf"
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_deleting_a_missing_sponsorship_is_not_found() {
+    use super::sponsorship_handlers;
+    use crate::db::setup_database;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    setup_database(&pool).await.unwrap();
+    let rocket = rocket::build().manage(pool.clone()).mount(
+        "/",
+        rocket::routes![sponsorship_handlers::delete_sponsorship_handler],
+    );
+    let client = Client::untracked(rocket).await.unwrap();
+
+    let response = client.delete("/sponsorships/9999").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+
+    // Nothing was deleted, so nothing is announced
+    let recorded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webhook_outbox")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(recorded, 0);
+}
+
+#[tokio::test]
+async fn test_status_changes_only_accept_known_statuses() {
+    use super::exhibit_handlers;
+    use crate::db::setup_database;
+    use crate::errors;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    setup_database(&pool).await.unwrap();
+    let rocket = rocket::build()
+        .manage(pool)
+        .mount(
+            "/",
+            rocket::routes![
+                exhibit_handlers::change_exhibit_status_handler,
+                exhibit_handlers::batch_exhibits_handler
+            ],
+        )
+        .register("/", rocket::catchers![errors::other_error]);
+    let client = Client::untracked(rocket).await.unwrap();
+    let post = |uri: &'static str, body: &'static str| {
+        client.post(uri).header(ContentType::JSON).body(body)
+    };
+
+    let response = post("/exhibits/1/status", r#"{"new_status": "on fire"}"#)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let error: Value = response.into_json().await.unwrap();
+    assert_eq!(error["details"][0]["field"], "new_status");
+
+    let response = post(
+        "/exhibits/batch",
+        r#"{"ids": [1], "action": {"type": "change_status", "status": "on fire"}}"#,
+    )
+    .dispatch()
+    .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let error: Value = response.into_json().await.unwrap();
+    assert_eq!(error["code"], "validation_failed");
+    assert_eq!(error["details"][0]["field"], "action.status");
+
+    // Known statuses get through to the lookup, in any case
+    let response = post("/exhibits/1/status", r#"{"new_status": " Needs Repair "}"#)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response = post(
+        "/exhibits/batch",
+        r#"{"ids": [1], "action": {"type": "change_status", "status": "Out of Service"}}"#,
+    )
+    .dispatch()
+    .await;
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[tokio::test]
+async fn test_snapshot_round_trip_through_files() {
+    use super::snapshot_handlers;
+    use crate::config::AppConfig;
+    use crate::db::setup_database;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use sqlx::SqlitePool;
+
+    let root = std::env::temp_dir().join(format!(
+        "exhibit-snapshot-handlers-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let images_dir = root.join("images");
+    std::fs::create_dir_all(&images_dir).unwrap();
+    std::fs::write(images_dir.join("photo.jpg"), b"jpeg").unwrap();
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    setup_database(&pool).await.unwrap();
+    let config = AppConfig {
+        images_dir: images_dir.clone(),
+        ..AppConfig::default()
+    };
+    let rocket = rocket::build().manage(pool.clone()).manage(config).mount(
+        "/",
+        rocket::routes![
+            snapshot_handlers::get_snapshot_handler,
+            snapshot_handlers::restore_snapshot_handler
+        ],
+    );
+    let client = Client::untracked(rocket).await.unwrap();
+
+    let response = client.get("/admin/snapshot").dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::ZIP));
+    let archive = response.into_bytes().await.unwrap();
+
+    // An empty database takes the snapshot back, images and all
+    std::fs::remove_file(images_dir.join("photo.jpg")).unwrap();
+    let response = client
+        .post("/admin/snapshot")
+        .body(&archive)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let summary: Value = response.into_json().await.unwrap();
+    assert_eq!(summary["images"], 1);
+    assert_eq!(
+        summary["images_not_restored"],
+        rocket::serde::json::json!([])
+    );
+    assert_eq!(
+        std::fs::read(images_dir.join("photo.jpg")).unwrap(),
+        b"jpeg"
+    );
+
+    // Once it has data, nothing can be restored into it
+    sqlx::query("INSERT INTO sponsors (name) VALUES ('Acme')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = client
+        .post("/admin/snapshot")
+        .body(&archive)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Conflict);
+    let error: Value = response.into_json().await.unwrap();
+    assert_eq!(error["code"], "conflict");
+
+    // Uploads that aren't snapshots are refused, and nothing is left staged either way
+    let response = client
+        .post("/admin/snapshot")
+        .body("not a zip")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let mut left: Vec<_> = std::fs::read_dir(&root)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    left.sort();
+    assert_eq!(left, vec!["images"]);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_imports_refuse_unreadable_files_and_report_bad_rows() {
+    use super::exhibit_handlers;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    crate::db::setup_database(&pool).await.unwrap();
+    let rocket = rocket::build().manage(pool.clone()).mount(
+        "/",
+        rocket::routes![exhibit_handlers::import_exhibits_handler],
+    );
+    let client = Client::untracked(rocket).await.unwrap();
+    let import = |uri: &'static str, body: Vec<u8>| client.post(uri).body(body);
+    let message = |error: Value| error["message"].as_str().unwrap().to_string();
+
+    // Neither the query nor the Content-Type says what the file is
+    let response = import("/exhibits/import", b"Name\n".to_vec())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = import("/exhibits/import?format=ods", b"Name\n".to_vec())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::BadRequest);
+
+    // Files that can't be read, or are missing columns, are refused outright
+    for (uri, body, expected) in [
+        (
+            "/exhibits/import?format=csv",
+            b"".to_vec(),
+            "The file is empty",
+        ),
+        (
+            "/exhibits/import?format=csv",
+            b"Name,Cluster\nLever,Physics\n".to_vec(),
+            "Missing required columns: Location, Status",
+        ),
+        (
+            "/exhibits/import?format=xlsx",
+            b"Name,Cluster,Location,Status\n".to_vec(),
+            "Invalid XLSX",
+        ),
+    ] {
+        let response = import(uri, body).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", expected);
+        let error: Value = response.into_json().await.unwrap();
+        assert_eq!(error["code"], "invalid_input");
+        let message = message(error);
+        assert!(message.contains(expected), "{}", message);
+    }
+
+    let response = import(
+        "/exhibits/import?format=csv",
+        vec![b' '; 10 * 1024 * 1024 + 1],
+    )
+    .dispatch()
+    .await;
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+
+    // Bad rows are reported by line and skipped; the rest still go in
+    let sheet = "Name,Cluster,Location,Status\n\
+                 Lever,Physics,Outside,Operational\n\
+                 lever,Physics,Outside,Operational\n\
+                 Pulley,Physics,Outside,Broken\n";
+    let response = client
+        .post("/exhibits/import")
+        .header(ContentType::CSV)
+        .body(sheet)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    let report: Value = response.into_json().await.unwrap();
+    assert_eq!(report["created"], 1);
+    assert_eq!(report["invalid"], 2);
+    assert_eq!(
+        report["rows"][1]["errors"],
+        rocket::serde::json::json!(["Duplicate of the exhibit on line 2"])
+    );
+    assert_eq!(report["rows"][2]["line"], 4);
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM exhibits")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, 1);
+}
+
+#[tokio::test]
+async fn test_exports_refuse_unknown_formats() {
+    use super::export_handlers;
+    use rocket::http::Status;
+    use rocket::local::asynchronous::Client;
+    use rocket::serde::json::Value;
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    crate::db::setup_database(&pool).await.unwrap();
+    let rocket = rocket::build().manage(pool).mount(
+        "/",
+        rocket::routes![
+            export_handlers::export_exhibits_handler,
+            export_handlers::export_parts_handler,
+            export_handlers::export_jotforms_handler
+        ],
+    );
+    let client = Client::untracked(rocket).await.unwrap();
+
+    for entity in ["exhibits", "parts", "jotforms"] {
+        let response = client
+            .get(format!("/export/{}?format=pdf", entity))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::BadRequest, "{}", entity);
+        let error: Value = response.into_json().await.unwrap();
+        assert_eq!(error["code"], "invalid_input");
+
+        // Nothing to export is still a file, with just the header
+        let response = client.get(format!("/export/{}", entity)).dispatch().await;
+        assert_eq!(response.status(), Status::Ok, "{}", entity);
+        let csv = response.into_string().await.unwrap();
+        assert_eq!(csv.lines().count(), 1, "{}", csv);
+    }
+}
+
+#[tokio::test]
+async fn test_event_stream_filters_topics_and_reports_lag() {
+    use super::event_handlers;
+    use crate::events::{self, Action, ChangeEvent, Entity};
+    use rocket::local::asynchronous::{Client, LocalResponse};
+    use rocket::serde::json::json;
+    use rocket::tokio::io::AsyncReadExt;
+    use std::time::Duration;
+
+    async fn read_until(response: &mut LocalResponse<'_>, end: &str) -> String {
+        let mut text = String::new();
+        let mut chunk = [0; 4096];
+        while !text.contains(end) {
+            let read =
+                rocket::tokio::time::timeout(Duration::from_secs(5), response.read(&mut chunk))
+                    .await
+                    .expect("the stream stalled")
+                    .unwrap();
+            assert!(read > 0, "the stream ended early: {}", text);
+            text.push_str(&String::from_utf8_lossy(&chunk[..read]));
+        }
+        text
+    }
+
+    // Other tests publish events too, so these are marked to tell them apart
+    let marker = format!(
+        "sse-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    );
+    let event = |entity, action, id: &str| {
+        ChangeEvent::new(entity, action, Some(id.to_string())).with_data(json!({ "test": marker }))
+    };
+    let rocket = rocket::build().mount("/", rocket::routes![event_handlers::events_handler]);
+    let client = Client::untracked(rocket).await.unwrap();
+
+    // Only the exhibit updates with this test's marker, however the filter is spaced
+    let mut response = client
+        .get(format!(
+            "/events?topics=part.deleted,%20exhibit.updated:test={},",
+            marker
+        ))
+        .dispatch()
+        .await;
+    events::publish(event(Entity::Exhibit, Action::Created, "skipped-1"));
+    events::publish(event(Entity::Part, Action::Created, "skipped-2"));
+    events::publish(event(Entity::Exhibit, Action::Updated, "sent-1"));
+    events::publish(
+        ChangeEvent::new(
+            Entity::Exhibit,
+            Action::Updated,
+            Some("skipped-3".to_string()),
+        )
+        .with_data(json!({ "test": "another test" })),
+    );
+    events::publish(event(Entity::Exhibit, Action::Updated, "sent-2"));
+    let text = read_until(&mut response, "sent-2").await;
+    assert!(text.contains("event:exhibit.updated\n"), "{}", text);
+    assert!(text.contains("sent-1"), "{}", text);
+    assert!(!text.contains("skipped"), "{}", text);
+
+    // A listener that falls behind is told how much it missed, then carries on
+    let mut response = client.get("/events").dispatch().await;
+    for i in 0..300 {
+        events::publish(event(
+            Entity::Part,
+            Action::Updated,
+            &format!("flood-{}", i),
+        ));
+    }
+    events::publish(event(Entity::Part, Action::Updated, "after-the-flood"));
+    let text = read_until(&mut response, "after-the-flood").await;
+    let lagged = text
+        .split("\n\n")
+        .position(|message| message.starts_with("event:lagged"))
+        .expect("no lagged event");
+    assert_eq!(lagged, 0, "{}", text);
+    assert!(!text.contains("flood-0\""), "{}", text);
+    assert!(text.contains("flood-299\""), "{}", text);
+}
+
+#[tokio::test]
+async fn test_legacy_routes_are_marked_deprecated() {
+    use super::health_handlers;
+    use crate::config::AppConfig;
+    use chrono::NaiveDate;
+    use rocket::local::asynchronous::Client;
+
+    let config = AppConfig {
+        legacy_routes_sunset: NaiveDate::from_ymd_opt(2027, 3, 1),
+        ..AppConfig::default()
+    };
+    let rocket = rocket::build()
+        .attach(config.deprecations())
+        .mount(
+            super::API_BASE,
+            rocket::routes![health_handlers::healthz_handler],
+        )
+        .mount("/", rocket::routes![health_handlers::healthz_handler]);
+    let client = Client::untracked(rocket).await.unwrap();
+
+    let response = client.get("/healthz").dispatch().await;
+    let headers = response.headers();
+    assert_eq!(headers.get_one("Deprecation"), Some("@1792281600"));
+    assert_eq!(
+        headers.get_one("Sunset"),
+        Some("Mon, 01 Mar 2027 00:00:00 GMT")
+    );
+    assert_eq!(
+        headers.get_one("Link"),
+        Some("</api/v1/healthz>; rel=\"successor-version\"")
+    );
+
+    let response = client.get("/api/v1/healthz").dispatch().await;
+    assert_eq!(response.headers().get_one("Deprecation"), None);
+
+    // Without a sunset date there's no Sunset header
+    let rocket = rocket::build()
+        .attach(AppConfig::default().deprecations())
+        .mount("/", rocket::routes![health_handlers::healthz_handler]);
+    let client = Client::untracked(rocket).await.unwrap();
+    let response = client.get("/healthz").dispatch().await;
+    assert!(response.headers().get_one("Deprecation").is_some());
+    assert_eq!(response.headers().get_one("Sunset"), None);
+}
+
+#[tokio::test]
+async fn test_legacy_routes_are_marked_deprecated_when_they_fail() {
+    use super::{exhibit_handlers, sponsorship_handlers};
+    use crate::config::AppConfig;
+    use crate::errors;
+    use rocket::http::{ContentType, Status};
+    use rocket::local::asynchronous::Client;
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    crate::db::setup_database(&pool).await.unwrap();
+    let routes = || {
+        rocket::routes![
+            exhibit_handlers::get_exhibit_handler,
+            sponsorship_handlers::create_sponsor_handler
+        ]
+    };
+    let rocket = rocket::build()
+        .manage(pool)
+        .attach(AppConfig::default().deprecations())
+        .mount(super::API_BASE, routes())
+        .mount("/", routes())
+        .register(
+            "/",
+            rocket::catchers![
+                errors::not_found,
+                errors::handle_invalid_request_body,
+                errors::handle_unprocessable_request_body
+            ],
+        );
+    let client = Client::untracked(rocket).await.unwrap();
+
+    // A legacy route that answers with an error is still a legacy route
+    let response = client.get("/exhibits/999").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    assert!(response.headers().get_one("Deprecation").is_some());
+    assert_eq!(
+        response.headers().get_one("Link"),
+        Some("</api/v1/exhibits/999>; rel=\"successor-version\"")
+    );
+
+    for body in ["{", r#"{"contact_name": "Ada"}"#, r#"{"name": ""}"#] {
+        let response = client
+            .post("/sponsors")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch()
+            .await;
+        assert!(response.status().class().is_client_error(), "{}", body);
+        assert!(
+            response.headers().get_one("Deprecation").is_some(),
+            "{}",
+            body
+        );
+        assert_eq!(
+            response.headers().get_one("Link"),
+            Some("</api/v1/sponsors>; rel=\"successor-version\"")
+        );
+
+        let response = client
+            .post("/api/v1/sponsors")
+            .header(ContentType::JSON)
+            .body(body)
+            .dispatch()
+            .await;
+        assert!(response.status().class().is_client_error(), "{}", body);
+        assert_eq!(response.headers().get_one("Deprecation"), None, "{}", body);
+    }
+
+    // Nothing matched, so there's no route to say is deprecated
+    let response = client.get("/nowhere").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.headers().get_one("Deprecation"), None);
+    let response = client.get("/api/v1/exhibits/999").dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.headers().get_one("Deprecation"), None);
+}
+
+#[cfg(feature = "dev-tools")]
+#[test]
+fn test_dev_tools_need_a_development_profile_and_the_token() {
+    use super::development_util_handlers::DevTools;
+    use crate::errors::ApiError;
+
+    assert!(DevTools::check("debug", Some("s3cret"), Some("s3cret")).is_ok());
+    assert!(DevTools::check("development", Some("s3cret"), Some("s3cret")).is_ok());
+    assert!(matches!(
+        DevTools::check("production", Some("s3cret"), Some("s3cret")),
+        Err(ApiError::Forbidden(_))
+    ));
+    assert!(matches!(
+        DevTools::check("release", Some("s3cret"), Some("s3cret")),
+        Err(ApiError::Forbidden(_))
+    ));
+    assert!(matches!(
+        DevTools::check("debug", None, None),
+        Err(ApiError::NotConfigured(_))
+    ));
+    assert!(matches!(
+        DevTools::check("debug", Some("s3cret"), None),
+        Err(ApiError::Forbidden(_))
+    ));
+    assert!(matches!(
+        DevTools::check("debug", Some("s3cret"), Some("guess")),
+        Err(ApiError::Forbidden(_))
+    ));
+}
+
+#[cfg(feature = "dev-tools")]
+#[tokio::test]
+async fn test_reset_empties_every_table() {
+    use super::development_util_handlers::{generate_and_insert_exhibits, reset_database};
+    use crate::db::{setup_database, table_exists};
+    use crate::models::{FullName, Jotform};
+    use crate::repo::jotform_repo;
+    use chrono::Utc;
+    use sqlx::SqlitePool;
+
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    setup_database(&pool).await.unwrap();
+
+    generate_and_insert_exhibits(&pool).await.unwrap();
+    let ticket = Jotform {
+        id: "240000000000000001".to_string(),
+        submitter_name: FullName {
+            first: "Ada".to_string(),
+            last: "Lovelace".to_string(),
+        },
+        created_at: Utc::now(),
+        location: "Main Hall".to_string(),
+        exhibit_name: "Pendulum".to_string(),
+        description: "Stopped swinging".to_string(),
+        priority_level: "High".to_string(),
+        department: "Exhibits".to_string(),
+        status: "Open".to_string(),
+    };
+    jotform_repo::insert_jotform(&ticket, &pool).await.unwrap();
+    // Tables added later are covered without being listed anywhere
+    sqlx::query("CREATE TABLE future_things (id INTEGER PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    reset_database(&pool).await.unwrap();
+
+    assert!(
+        !table_exists(&mut pool.acquire().await.unwrap(), "future_things")
+            .await
+            .unwrap()
+    );
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert!(tables.contains(&"jotforms".to_string()));
+    for table in tables {
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", table))
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        // All that's left is the reset itself, waiting to go out to webhooks
+        let expected = if table == "webhook_outbox" { 1 } else { 0 };
+        assert_eq!(rows, expected, "{} wasn't emptied", table);
+    }
+    let outbox = crate::repo::webhook_repo::get_outbox(10, &pool)
+        .await
+        .unwrap();
+    assert!(outbox[0].1.contains("\"data.replaced\""));
+}
@@ -0,0 +1,123 @@
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::models::{TrashEntity, TrashItem};
+use crate::repo::trash_repo;
+use chrono::TimeDelta;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+use rocket::{get, post};
+
+/// How the trash is kept, shared with the purge job.
+pub struct TrashSettings {
+    /// How long deleted records stay restorable before they're purged.
+    pub retention: TimeDelta,
+}
+
+fn parse_entity(entity: &str) -> Option<TrashEntity> {
+    match entity {
+        "exhibit" => Some(TrashEntity::Exhibit),
+        "part" => Some(TrashEntity::Part),
+        "ticket" => Some(TrashEntity::Ticket),
+        _ => None,
+    }
+}
+
+async fn restore(entity: TrashEntity, id: &str, pool: &DbPool) -> Result<Status, ApiError> {
+    match trash_repo::restore(entity, id, pool).await? {
+        true => Ok(Status::NoContent),
+        false => Err(ApiError::NotFound),
+    }
+}
+
+/// Handles the GET /trash?entity=<entity> endpoint.
+///
+/// # Arguments
+/// * `entity` - Only list `exhibit`, `part` or `ticket` records. Lists everything if omitted.
+/// * `db_pool` - Database connection pool.
+/// * `settings` - The trash retention period.
+///
+/// # Returns
+/// * `Result<Json<Vec<TrashItem>>, ApiError>` - Deleted records, most recently deleted first.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The entity isn't one of `exhibit`, `part` or `ticket`.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Trash",
+    responses(
+        (status = 200, description = "Deleted records, most recently deleted first", body = Vec<TrashItem>)
+    )
+)]
+#[get("/trash?<entity>")]
+pub async fn list_trash_handler(
+    entity: Option<&str>,
+    db_pool: &State<DbPool>,
+    settings: &State<TrashSettings>,
+) -> Result<Json<Vec<TrashItem>>, ApiError> {
+    let entity = match entity {
+        Some(entity) => Some(parse_entity(entity).ok_or_else(|| {
+            ApiError::InvalidInput("`entity` must be exhibit, part or ticket".into())
+        })?),
+        None => None,
+    };
+
+    let items = trash_repo::get_trash(entity, settings.retention, db_pool.inner()).await?;
+
+    Ok(Json(items))
+}
+
+/// Handles the POST /exhibits/<id>/restore endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The exhibit isn't in the trash.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Trash",
+    responses(
+        (status = 204, description = "Restored")
+    )
+)]
+#[post("/exhibits/<id>/restore")]
+pub async fn restore_exhibit_handler(id: i64, db_pool: &State<DbPool>) -> Result<Status, ApiError> {
+    restore(TrashEntity::Exhibit, &id.to_string(), db_pool.inner()).await
+}
+
+/// Handles the POST /parts/<id>/restore endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The part isn't in the trash.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Trash",
+    responses(
+        (status = 204, description = "Restored")
+    )
+)]
+#[post("/parts/<id>/restore")]
+pub async fn restore_part_handler(id: i64, db_pool: &State<DbPool>) -> Result<Status, ApiError> {
+    restore(TrashEntity::Part, &id.to_string(), db_pool.inner()).await
+}
+
+/// Handles the POST /jotforms/<id>/restore endpoint.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The ticket isn't in the trash.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Trash",
+    responses(
+        (status = 204, description = "Restored")
+    )
+)]
+#[post("/jotforms/<id>/restore")]
+pub async fn restore_jotform_handler(
+    id: &str,
+    db_pool: &State<DbPool>,
+) -> Result<Status, ApiError> {
+    restore(TrashEntity::Ticket, id, db_pool.inner()).await
+}
@@ -0,0 +1,257 @@
+use crate::api::json_body::JsonBody;
+use crate::db::DbPool;
+use crate::errors::ApiError;
+use crate::events;
+use crate::models::{DeliveryStatus, Webhook, WebhookDelivery};
+use crate::repo::webhook_repo;
+use log::error;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::serde::Deserialize;
+use rocket::State;
+use rocket::{delete, get, post, put};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+/// How many deliveries are listed when no limit is given.
+const DEFAULT_DELIVERY_LIMIT: i64 = 100;
+
+/// The most deliveries that can be listed at once.
+const MAX_DELIVERY_LIMIT: i64 = 1000;
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct NewWebhook {
+    #[validate(url(message = "URL must be a valid http or https URL"))]
+    #[validate(custom(function = "validate_http_url"))]
+    pub url: String,
+    #[validate(custom(function = "validate_filters"))]
+    pub events: Vec<String>,
+    /// Required when creating a webhook. Left out when updating one to keep the current
+    /// secret.
+    #[validate(length(
+        min = 16,
+        max = 256,
+        message = "Secret must be between 16 and 256 characters"
+    ))]
+    pub secret: Option<String>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn validate_http_url(url: &str) -> Result<(), ValidationError> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(ValidationError::new("url")
+            .with_message("URL must be a valid http or https URL".into()))
+    }
+}
+
+fn validate_filters(filters: &[String]) -> Result<(), ValidationError> {
+    if filters.is_empty() {
+        return Err(
+            ValidationError::new("events").with_message("At least one event must be given".into())
+        );
+    }
+    match filters.iter().find(|f| !events::is_valid_filter(f)) {
+        Some(filter) => Err(ValidationError::new("events")
+            .with_message(format!("Unknown event `{}`", filter).into())),
+        None => Ok(()),
+    }
+}
+
+fn map_webhook_error(e: sqlx::Error, action: &str) -> ApiError {
+    match e {
+        sqlx::Error::RowNotFound => ApiError::NotFound,
+        _ => {
+            error!("Failed to {}: {}", action, e);
+            ApiError::DatabaseError(format!("Failed to {}", action))
+        }
+    }
+}
+
+/// Handles the GET /admin/webhooks endpoint.
+///
+/// # Returns
+/// * `Result<Json<Vec<Webhook>>, ApiError>` - Every webhook, without its secret.
+///
+/// # Errors
+/// Returns an `ApiError` if a database operation fails.
+#[utoipa::path(
+    tag = "Admin",
+    responses(
+        (status = 200, description = "Every webhook, without its secret", body = Vec<Webhook>)
+    )
+)]
+#[get("/admin/webhooks")]
+pub async fn list_webhooks_handler(
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<Webhook>>, ApiError> {
+    let webhooks = webhook_repo::get_webhooks(db_pool.inner()).await?;
+
+    Ok(Json(webhooks))
+}
+
+/// Handles the POST /admin/webhooks endpoint.
+///
+/// Registers a URL to be POSTed matching change events, the same ones GET /events streams.
+/// Each body is the event as JSON, signed with the secret: the `X-Webhook-Signature-256`
+/// header is `sha256=` followed by the hex HMAC-SHA256 of the body. Failed deliveries are
+/// retried with backoff, and every attempt is logged.
+///
+/// # Returns
+/// * `Result<Json<Webhook>, ApiError>` - The new webhook.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails, or no secret is given.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Admin",
+    request_body = NewWebhook,
+    responses(
+        (status = 200, description = "The new webhook", body = Webhook)
+    )
+)]
+#[post("/admin/webhooks", format = "json", data = "<new_webhook>")]
+pub async fn create_webhook_handler(
+    new_webhook: JsonBody<NewWebhook>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Webhook>, ApiError> {
+    let webhook = new_webhook.into_inner();
+    webhook.validate()?;
+    if webhook.secret.is_none() {
+        return Err(ApiError::InvalidInput(
+            "A secret is needed to sign deliveries".into(),
+        ));
+    }
+
+    let pool = db_pool.inner();
+    let id = webhook_repo::create_webhook(&webhook, pool)
+        .await
+        .map_err(|e| map_webhook_error(e, "create webhook"))?;
+
+    match webhook_repo::get_webhook(id, pool).await? {
+        Some(webhook) => Ok(Json(webhook)),
+        None => Err(ApiError::InternalServerError),
+    }
+}
+
+/// Handles the PUT /admin/webhooks/<id> endpoint.
+///
+/// Replaces the webhook's settings. Its secret is kept if none is given.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - Input validation fails.
+/// - The webhook is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Admin",
+    request_body = NewWebhook,
+    responses(
+        (status = 200, description = "The updated webhook", body = Webhook)
+    )
+)]
+#[put("/admin/webhooks/<id>", format = "json", data = "<updated_webhook>")]
+pub async fn update_webhook_handler(
+    id: i64,
+    updated_webhook: JsonBody<NewWebhook>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Webhook>, ApiError> {
+    let webhook = updated_webhook.into_inner();
+    webhook.validate()?;
+
+    let pool = db_pool.inner();
+    webhook_repo::update_webhook(id, &webhook, pool)
+        .await
+        .map_err(|e| map_webhook_error(e, "update webhook"))?;
+
+    match webhook_repo::get_webhook(id, pool).await? {
+        Some(webhook) => Ok(Json(webhook)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+/// Handles the DELETE /admin/webhooks/<id> endpoint.
+///
+/// Deletes the webhook and its delivery log. Deliveries still waiting to be retried are
+/// dropped.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The webhook is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Admin",
+    responses(
+        (status = 204, description = "Deleted")
+    )
+)]
+#[delete("/admin/webhooks/<id>")]
+pub async fn delete_webhook_handler(id: i64, db_pool: &State<DbPool>) -> Result<Status, ApiError> {
+    webhook_repo::delete_webhook(id, db_pool.inner())
+        .await
+        .map_err(|e| map_webhook_error(e, "delete webhook"))?;
+
+    Ok(Status::NoContent)
+}
+
+/// Handles the GET /admin/webhooks/<id>/deliveries?<status>&<limit> endpoint.
+///
+/// # Arguments
+/// * `status` - Only list deliveries that are `pending`, `delivered` or `failed`.
+/// * `limit` - How many to list, up to 1000. Defaults to 100.
+///
+/// # Returns
+/// * `Result<Json<Vec<WebhookDelivery>>, ApiError>` - The deliveries, newest first.
+///
+/// # Errors
+/// Returns an `ApiError` if:
+/// - The status is unknown or the limit is out of range.
+/// - The webhook is not found.
+/// - A database operation fails.
+#[utoipa::path(
+    tag = "Admin",
+    responses(
+        (status = 200, description = "The deliveries, newest first", body = Vec<WebhookDelivery>)
+    )
+)]
+#[get("/admin/webhooks/<id>/deliveries?<status>&<limit>")]
+pub async fn list_webhook_deliveries_handler(
+    id: i64,
+    status: Option<&str>,
+    limit: Option<i64>,
+    db_pool: &State<DbPool>,
+) -> Result<Json<Vec<WebhookDelivery>>, ApiError> {
+    let status = match status {
+        None => None,
+        Some("pending") => Some(DeliveryStatus::Pending),
+        Some("delivered") => Some(DeliveryStatus::Delivered),
+        Some("failed") => Some(DeliveryStatus::Failed),
+        Some(_) => {
+            return Err(ApiError::InvalidInput(
+                "`status` must be pending, delivered or failed".into(),
+            ))
+        }
+    };
+    let limit = limit.unwrap_or(DEFAULT_DELIVERY_LIMIT);
+    if !(1..=MAX_DELIVERY_LIMIT).contains(&limit) {
+        return Err(ApiError::InvalidInput(format!(
+            "`limit` must be between 1 and {}",
+            MAX_DELIVERY_LIMIT
+        )));
+    }
+
+    let pool = db_pool.inner();
+    if webhook_repo::get_webhook(id, pool).await?.is_none() {
+        return Err(ApiError::NotFound);
+    }
+    let deliveries = webhook_repo::get_deliveries(id, status, limit, pool).await?;
+
+    Ok(Json(deliveries))
+}
@@ -0,0 +1,494 @@
+use crate::db::{setup_database, DbPool};
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::metrics;
+use crate::models::BackupInfo;
+use crate::snapshot::is_plain_file_name;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use rocket::tokio::task;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{ConnectOptions, Connection, SqlitePool};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// The database's name inside a backup archive.
+const DATABASE_ENTRY: &str = "exhibits.db";
+/// Where images go inside a backup archive.
+const IMAGES_ENTRY: &str = "images/";
+
+/// Where, how often and how many backups are kept.
+#[derive(Clone)]
+pub struct BackupSettings {
+    pub dir: PathBuf,
+    pub interval: Duration,
+    /// How many backups to keep; older ones are deleted after each backup.
+    pub keep: usize,
+    /// Whether backups include a copy of the images directory.
+    pub include_images: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("File error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Archive error: {0}")]
+    Zip(#[from] ZipError),
+
+    #[error("Backup failed its integrity check: {0}")]
+    Corrupt(String),
+
+    #[error("No such backup")]
+    NotFound,
+}
+
+/// Whether `name` looks like a file in the backups directory. Backups made before they
+/// were archives are bare `.db` files.
+fn is_backup_name(name: &str) -> bool {
+    is_plain_file_name(name)
+        && name.starts_with("backup_")
+        && (name.ends_with(".zip") || name.ends_with(".db"))
+}
+
+/// Lists the backups in `dir`, newest first.
+pub fn list_backups(dir: &Path) -> io::Result<Vec<BackupInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            if let Some(info) = backup_info(&path)? {
+                backups.push(info);
+            }
+        }
+    }
+
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.name.cmp(&a.name)));
+
+    Ok(backups)
+}
+
+fn backup_info(path: &Path) -> io::Result<Option<BackupInfo>> {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(None);
+    };
+    if !is_backup_name(name) {
+        return Ok(None);
+    }
+
+    let metadata = fs::metadata(path)?;
+    let includes_images = name.ends_with(".zip")
+        && ZipArchive::new(File::open(path)?).is_ok_and(|zip| {
+            zip.file_names()
+                .any(|entry| entry.starts_with(IMAGES_ENTRY))
+        });
+
+    Ok(Some(BackupInfo {
+        name: name.to_string(),
+        created_at: DateTime::<Utc>::from(metadata.modified()?),
+        size_bytes: metadata.len(),
+        includes_images,
+    }))
+}
+
+/// Returns the path of the backup called `name`, if there is one.
+pub fn find_backup(dir: &Path, name: &str) -> Option<PathBuf> {
+    let path = dir.join(name);
+
+    (is_backup_name(name) && path.is_file()).then_some(path)
+}
+
+/// Deletes all but the newest `keep` backups.
+fn prune_backups(dir: &Path, keep: usize) -> io::Result<()> {
+    for old in list_backups(dir)?.iter().skip(keep) {
+        fs::remove_file(dir.join(&old.name))?;
+        info!("Deleted old backup: {}", old.name);
+    }
+
+    Ok(())
+}
+
+/// Runs SQLite's integrity check on the database file at `path`.
+async fn check_integrity(path: &Path) -> Result<(), BackupError> {
+    let corrupt = |e: sqlx::Error| BackupError::Corrupt(e.to_string());
+
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(corrupt)?;
+
+    let results: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&mut conn)
+        .await
+        .map_err(corrupt)?;
+    conn.close().await?;
+
+    if results != ["ok"] {
+        return Err(BackupError::Corrupt(results.join("; ")));
+    }
+
+    Ok(())
+}
+
+/// Backs up the database, and the images in `images_dir` if the settings say so.
+///
+/// The database is copied with `VACUUM INTO`, which takes a consistent copy while the app
+/// keeps running, and the copy is integrity-checked before it's archived. Older backups
+/// beyond the number to keep are then deleted.
+pub async fn create_backup(
+    pool: &DbPool,
+    settings: &BackupSettings,
+    images_dir: &Path,
+) -> Result<BackupInfo, BackupError> {
+    let backup = create_unpruned_backup(pool, settings, images_dir).await?;
+    prune_backups(&settings.dir, settings.keep)?;
+
+    Ok(backup)
+}
+
+/// Backs up as `create_backup` does, but leaves the older backups alone.
+async fn create_unpruned_backup(
+    pool: &DbPool,
+    settings: &BackupSettings,
+    images_dir: &Path,
+) -> Result<BackupInfo, BackupError> {
+    let backup = take_backup(pool, settings, images_dir).await;
+    match &backup {
+        Ok(backup) => metrics::record_backup_succeeded(backup.size_bytes),
+        Err(_) => metrics::record_backup_failed(),
+    }
+
+    backup
+}
+
+async fn take_backup(
+    pool: &DbPool,
+    settings: &BackupSettings,
+    images_dir: &Path,
+) -> Result<BackupInfo, BackupError> {
+    fs::create_dir_all(&settings.dir)?;
+
+    let stem = format!("backup_{}", Utc::now().format("%Y-%m-%d_%H-%M-%S-%3f"));
+    let database = settings.dir.join(format!("{}.db.partial", stem));
+    let path = settings.dir.join(format!("{}.zip", stem));
+
+    sqlx::query("VACUUM INTO ?1")
+        .bind(database.to_string_lossy().as_ref())
+        .execute(pool)
+        .await?;
+
+    let archived = archive_backup(&database, settings, images_dir, &path).await;
+    if let Err(e) = fs::remove_file(&database) {
+        warn!("Failed to remove {}: {}", database.display(), e);
+    }
+    archived?;
+
+    backup_info(&path)?.ok_or(BackupError::NotFound)
+}
+
+async fn archive_backup(
+    database: &Path,
+    settings: &BackupSettings,
+    images_dir: &Path,
+    path: &Path,
+) -> Result<(), BackupError> {
+    check_integrity(database).await?;
+
+    let database = database.to_path_buf();
+    let images_dir = settings.include_images.then(|| images_dir.to_path_buf());
+    let path = path.to_path_buf();
+
+    // Compressing is slow, blocking work
+    task::spawn_blocking(move || write_archive(&database, images_dir.as_deref(), &path))
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// Writes the archive next to `path` and moves it into place once it's complete, so a
+/// half-written archive is never mistaken for a backup.
+fn write_archive(
+    database: &Path,
+    images_dir: Option<&Path>,
+    path: &Path,
+) -> Result<(), BackupError> {
+    let partial = path.with_extension("zip.partial");
+
+    let written = (|| -> Result<(), BackupError> {
+        let mut zip = ZipWriter::new(File::create(&partial)?);
+
+        let deflated = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(true);
+        zip.start_file(DATABASE_ENTRY, deflated)?;
+        io::copy(&mut File::open(database)?, &mut zip)?;
+
+        if let Some(images_dir) = images_dir.filter(|dir| dir.exists()) {
+            // Images are already compressed
+            let stored = SimpleFileOptions::default()
+                .compression_method(CompressionMethod::Stored)
+                .large_file(true);
+
+            for entry in fs::read_dir(images_dir)? {
+                let entry = entry?;
+                let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                if entry.file_type()?.is_file() && is_plain_file_name(&name) {
+                    zip.start_file(format!("{}{}", IMAGES_ENTRY, name), stored)?;
+                    io::copy(&mut File::open(entry.path())?, &mut zip)?;
+                }
+            }
+        }
+
+        zip.finish()?;
+        fs::rename(&partial, path)?;
+
+        Ok(())
+    })();
+
+    if written.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+
+    written
+}
+
+/// Copies the database out of a backup into `database`.
+fn extract_database(backup: &Path, database: &Path) -> Result<(), BackupError> {
+    if backup.extension().is_some_and(|ext| ext == "db") {
+        fs::copy(backup, database)?;
+        return Ok(());
+    }
+
+    let mut zip = ZipArchive::new(File::open(backup)?)?;
+    let mut entry = zip.by_name(DATABASE_ENTRY)?;
+    io::copy(&mut entry, &mut File::create(database)?)?;
+
+    Ok(())
+}
+
+/// Copies the images out of a backup into `images_dir`, replacing any with the same name.
+/// Returns how many were copied.
+fn extract_images(backup: &Path, images_dir: &Path) -> Result<usize, BackupError> {
+    if backup.extension().is_some_and(|ext| ext == "db") {
+        return Ok(0);
+    }
+
+    let mut zip = ZipArchive::new(File::open(backup)?)?;
+    let mut count = 0;
+
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let Some(name) = entry.name().strip_prefix(IMAGES_ENTRY).map(str::to_string) else {
+            continue;
+        };
+        if entry.is_dir() || !is_plain_file_name(&name) {
+            continue;
+        }
+
+        fs::create_dir_all(images_dir)?;
+        io::copy(&mut entry, &mut File::create(images_dir.join(name))?)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Replaces everything in the database with the contents of the backup called `name`, and
+/// copies back its images if it has any.
+///
+/// The backup is integrity-checked and brought up to the current schema first, and the
+/// current data is backed up before anything is replaced. The tables are replaced in one
+/// transaction, so a failed restore leaves the database as it was. Older backups are only
+/// pruned once the restore has finished. Returns the backup taken of the data that was
+/// replaced.
+pub async fn restore_backup(
+    pool: &DbPool,
+    settings: &BackupSettings,
+    name: &str,
+    images_dir: &Path,
+) -> Result<BackupInfo, BackupError> {
+    let backup = find_backup(&settings.dir, name).ok_or(BackupError::NotFound)?;
+    let database = settings.dir.join(format!(
+        "restore_{}.db.partial",
+        Utc::now().timestamp_millis()
+    ));
+
+    let restored = restore_from(pool, settings, &backup, &database, images_dir).await;
+    if let Err(e) = fs::remove_file(&database) {
+        warn!("Failed to remove {}: {}", database.display(), e);
+    }
+
+    restored
+}
+
+async fn restore_from(
+    pool: &DbPool,
+    settings: &BackupSettings,
+    backup: &Path,
+    database: &Path,
+    images_dir: &Path,
+) -> Result<BackupInfo, BackupError> {
+    let (from, to) = (backup.to_path_buf(), database.to_path_buf());
+    task::spawn_blocking(move || extract_database(&from, &to))
+        .await
+        .map_err(io::Error::other)??;
+
+    check_integrity(database).await?;
+
+    // Older backups are migrated the same way an old database is at startup
+    let backup_pool =
+        SqlitePool::connect_with(SqliteConnectOptions::new().filename(database)).await?;
+    let migrated = setup_database(&backup_pool).await;
+    backup_pool.close().await;
+    migrated?;
+
+    // Pruning now could delete the backup being restored before its images are copied
+    let previous = create_unpruned_backup(pool, settings, images_dir).await?;
+
+    let mut conn = pool.acquire().await?;
+    let event = ChangeEvent::new(Entity::Data, Action::Replaced, None);
+    let copied = copy_from_backup(&mut conn, database, &event).await;
+
+    // The connection goes back to the pool, so it mustn't keep the backup attached or
+    // foreign keys off, whether or not the copy worked
+    if let Err(e) = reset_connection(&mut conn).await {
+        warn!(
+            "Closing a connection that couldn't be reset after a restore: {}",
+            e
+        );
+        drop(conn.detach());
+    }
+    copied?;
+    events::publish(event);
+
+    let (from, to) = (backup.to_path_buf(), images_dir.to_path_buf());
+    let images = task::spawn_blocking(move || extract_images(&from, &to))
+        .await
+        .map_err(io::Error::other)??;
+    info!(
+        "Restored {} with {} images; the replaced data was backed up to {}",
+        backup.display(),
+        images,
+        previous.name
+    );
+    prune_backups(&settings.dir, settings.keep)?;
+
+    Ok(previous)
+}
+
+/// Turns foreign keys off and attaches `database` as `backup`, then copies it over `main`.
+async fn copy_from_backup(
+    conn: &mut SqliteConnection,
+    database: &Path,
+    event: &ChangeEvent,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("ATTACH DATABASE ?1 AS backup")
+        .bind(database.to_string_lossy().as_ref())
+        .execute(&mut *conn)
+        .await?;
+
+    copy_tables(conn, event).await
+}
+
+/// Undoes `copy_from_backup`'s changes to the connection, however far it got.
+async fn reset_connection(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    let attached: Option<i64> =
+        sqlx::query_scalar("SELECT 1 FROM pragma_database_list WHERE name = 'backup'")
+            .fetch_optional(&mut *conn)
+            .await?;
+    if attached.is_some() {
+        sqlx::query("DETACH DATABASE backup")
+            .execute(&mut *conn)
+            .await?;
+    }
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Tables a restore leaves alone. The outbox holds changes that happened here and haven't
+/// been sent yet; sending the backup's again would replay old news.
+const NOT_RESTORED: [&str; 1] = ["webhook_outbox"];
+
+/// Replaces every table in `main` with its contents in the attached `backup` database.
+///
+/// Foreign keys are off while this runs, so the tables can be emptied and refilled in any
+/// order. Columns are copied by name. Rows keep their IDs, and SQLite never moves an
+/// AUTOINCREMENT counter backwards, so IDs handed out since the backup aren't reused.
+/// Webhook deliveries still pending in the backup are marked failed rather than sent late.
+/// `event` goes into the outbox along with the copied data.
+async fn copy_tables(conn: &mut SqliteConnection, event: &ChangeEvent) -> Result<(), sqlx::Error> {
+    let mut tx = conn.begin().await?;
+
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM main.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let tables: Vec<String> = tables
+        .into_iter()
+        .filter(|table| !NOT_RESTORED.contains(&table.as_str()))
+        .collect();
+
+    for table in &tables {
+        sqlx::query(&format!("DELETE FROM main.\"{}\"", table))
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    for table in &tables {
+        let columns: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM pragma_table_info(?1, 'main')
+             WHERE name IN (SELECT name FROM pragma_table_info(?1, 'backup'))",
+        )
+        .bind(table)
+        .fetch_all(&mut *tx)
+        .await?;
+        if columns.is_empty() {
+            continue;
+        }
+
+        let columns = columns
+            .iter()
+            .map(|column| format!("\"{}\"", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sqlx::query(&format!(
+            "INSERT INTO main.\"{table}\" ({columns}) SELECT {columns} FROM backup.\"{table}\""
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query(
+        "UPDATE main.webhook_deliveries
+         SET status = 'failed', next_attempt_at = NULL,
+             last_error = 'Abandoned when a backup was restored'
+         WHERE status = 'pending'",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    events::record(event, &mut *tx).await?;
+    tx.commit().await
+}
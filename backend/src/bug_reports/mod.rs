@@ -0,0 +1,204 @@
+#[cfg(test)]
+mod tests;
+
+use crate::config::GitHubCredentials;
+use crate::db::DbPool;
+use crate::events::{self, Action, Entity};
+use crate::logging;
+use crate::models::StoredBugReport;
+use crate::repo::bug_report_repo;
+use crate::retry::{self, Backoff};
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{error, info, warn};
+use rocket::serde::json::{json, Value};
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::tokio::time::sleep;
+use rocket::tokio::{pin, select};
+use serde::Deserialize;
+use std::fmt::Write;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Sent with every request, as GitHub requires.
+const USER_AGENT: &str = concat!("exhibit-manager/", env!("CARGO_PKG_VERSION"));
+
+/// The label every filed issue gets.
+const LABEL: &str = "bug report";
+
+/// How long to wait between attempts at filing a report, and when to give up on it.
+pub const BACKOFF: Backoff = Backoff {
+    max_attempts: 12,
+    first_delay: TimeDelta::minutes(1),
+    max_delay: TimeDelta::hours(6),
+};
+
+/// How often to check for retries that have come due.
+const RETRY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long GitHub gets to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many reports are filed per batch.
+const BATCH_SIZE: i64 = 20;
+
+#[derive(Debug, Error)]
+pub enum ForwardError {
+    #[error("GitHub request error: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("GitHub API error: {0} {1}")]
+    Api(u16, String),
+}
+
+/// The parts of a created issue that are kept.
+#[derive(Debug, Deserialize)]
+pub struct Issue {
+    pub number: i64,
+    pub html_url: String,
+}
+
+/// Files bug reports as issues in a GitHub repository.
+pub struct GitHubClient {
+    client: reqwest::Client,
+    github: GitHubCredentials,
+}
+
+impl GitHubClient {
+    pub fn new(github: GitHubCredentials) -> reqwest::Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+
+        Ok(GitHubClient { client, github })
+    }
+
+    /// Opens an issue for a bug report.
+    pub async fn file_issue(&self, report: &StoredBugReport) -> Result<Issue, ForwardError> {
+        let url = format!(
+            "{}/repos/{}/{}/issues",
+            self.github.api_base_url, self.github.repo_owner, self.github.repo_name
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.github.token)
+            .header("Accept", "application/vnd.github+json")
+            .json(&issue_payload(report))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(response.json().await?)
+        } else {
+            let text = response.text().await.unwrap_or_default();
+            Err(ForwardError::Api(status.as_u16(), text))
+        }
+    }
+}
+
+/// The issue a bug report is filed as.
+pub fn issue_payload(report: &StoredBugReport) -> Value {
+    let mut body = format!("{}\n\n---\n", report.description);
+    let _ = writeln!(body, "Reported by: {}", report.name);
+    let _ = writeln!(
+        body,
+        "App version: {}",
+        report.app_version.as_deref().unwrap_or("unknown")
+    );
+    let _ = writeln!(body, "OS: {}", report.os.as_deref().unwrap_or("unknown"));
+    let _ = writeln!(
+        body,
+        "Reported at: {}",
+        report.created_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    if report.has_screenshot {
+        let _ = writeln!(
+            body,
+            "Screenshot: kept with bug report #{} in Exhibit Manager",
+            report.id
+        );
+    }
+
+    json!({
+        "title": format!("[bug-report] {}", report.title),
+        "body": body,
+        "labels": [LABEL],
+    })
+}
+
+/// Files every bug report that's due by `now`, returning how many were attempted.
+///
+/// Reports that fail to file are retried with backoff, or marked failed once they've been
+/// tried `BACKOFF.max_attempts` times.
+pub async fn forward_due(
+    github: &GitHubClient,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> sqlx::Result<usize> {
+    retry::work_through_due(
+        BATCH_SIZE,
+        |limit| bug_report_repo::get_due_bug_reports(now, limit, pool),
+        |report| forward(github, report, now, pool),
+    )
+    .await
+}
+
+/// Files one bug report and records how it went.
+async fn forward(
+    github: &GitHubClient,
+    report: StoredBugReport,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> sqlx::Result<()> {
+    match github.file_issue(&report).await {
+        Ok(issue) => {
+            info!(bug_report_id = report.id, issue_number = issue.number; "Filed a bug report on GitHub");
+            bug_report_repo::record_filed(report.id, issue.number, &issue.html_url, now, pool).await
+        }
+        Err(e) => {
+            let retry_at = BACKOFF.delay(report.attempts + 1).map(|delay| now + delay);
+            match retry_at {
+                Some(_) => {
+                    warn!(bug_report_id = report.id; "Failed to file a bug report, will retry: {}", e)
+                }
+                None => {
+                    error!(bug_report_id = report.id; "Failed to file a bug report, giving up: {}", e)
+                }
+            }
+            bug_report_repo::record_failed_attempt(report.id, &e.to_string(), retry_at, pool).await
+        }
+    }
+}
+
+/// Files bug reports on GitHub as they come in, and retries ones that failed as they come
+/// due. Reports stored before a restart, or while GitHub was down, are picked up again.
+pub async fn run(pool: DbPool, github: GitHubClient) {
+    let mut changes = events::subscribe();
+
+    loop {
+        let filed = logging::in_span(
+            "bug_report_forwarding",
+            forward_due(&github, Utc::now(), &pool),
+        );
+        if let Err(e) = filed.await {
+            error!("Failed to file bug reports: {}", e);
+        }
+
+        // Wait for a new report, or for retries to come due
+        let poll = sleep(RETRY_POLL_INTERVAL);
+        pin!(poll);
+        loop {
+            select! {
+                change = changes.recv() => match change {
+                    Ok(event) if event.entity == Entity::BugReport && event.action == Action::Created => break,
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                },
+                _ = &mut poll => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use super::*;
+use crate::db::setup_database;
+use crate::models::{BugReport, BugReportStatus};
+use crate::test_server;
+use rocket::serde::json::serde_json;
+use rocket::tokio;
+use sqlx::SqlitePool;
+
+async fn setup_test_db() -> DbPool {
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+    setup_database(&pool).await.unwrap();
+
+    pool
+}
+
+fn github_client(api_base_url: String) -> GitHubClient {
+    GitHubClient {
+        client: reqwest::Client::builder()
+            .no_proxy()
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap(),
+        github: GitHubCredentials {
+            token: "test-token".to_string(),
+            repo_owner: "museum".to_string(),
+            repo_name: "exhibit_manager".to_string(),
+            api_base_url,
+        },
+    }
+}
+
+fn bug_report() -> BugReport {
+    BugReport {
+        name: "Kenneth".to_string(),
+        title: "Exhibit list is empty".to_string(),
+        description: "Nothing shows up after signing in.".to_string(),
+        app_version: Some("0.1.5".to_string()),
+        os: Some("Windows 11".to_string()),
+        screenshot: None,
+    }
+}
+
+#[test]
+fn test_retry_delay_backs_off_then_gives_up() {
+    assert_eq!(BACKOFF.delay(1), Some(TimeDelta::minutes(1)));
+    assert_eq!(BACKOFF.delay(2), Some(TimeDelta::minutes(2)));
+    assert_eq!(BACKOFF.delay(9), Some(TimeDelta::minutes(256)));
+    assert_eq!(BACKOFF.delay(10), Some(BACKOFF.max_delay));
+    assert_eq!(BACKOFF.delay(BACKOFF.max_attempts), None);
+}
+
+#[tokio::test]
+async fn test_reports_are_stored_then_filed_once_github_answers() {
+    let pool = setup_test_db().await;
+    let (base_url, received) = test_server::start(vec![
+        (502, r#"{"message":"Server Error"}"#),
+        (
+            201,
+            r#"{"number":42,"html_url":"https://github.com/museum/exhibit_manager/issues/42"}"#,
+        ),
+    ])
+    .await;
+    let github = github_client(base_url);
+
+    let now = Utc::now();
+    let id = bug_report_repo::create_bug_report(
+        &bug_report(),
+        Some(("image/png", b"\x89PNG\r\n\x1a\n")),
+        now,
+        &pool,
+    )
+    .await
+    .unwrap();
+
+    // GitHub is down, so the report waits to be retried
+    assert_eq!(forward_due(&github, now, &pool).await.unwrap(), 1);
+    let report = bug_report_repo::get_bug_report(id, &pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(report.status, BugReportStatus::Pending);
+    assert_eq!(report.attempts, 1);
+    assert!(report.last_error.unwrap().contains("502"));
+    assert_eq!(report.next_attempt_at, Some(now + BACKOFF.first_delay));
+    assert!(report.has_screenshot);
+
+    assert_eq!(forward_due(&github, now, &pool).await.unwrap(), 0);
+    let later = now + TimeDelta::minutes(2);
+    assert_eq!(forward_due(&github, later, &pool).await.unwrap(), 1);
+
+    let filed = bug_report_repo::get_bug_reports(Some(BugReportStatus::Filed), &pool)
+        .await
+        .unwrap();
+    assert_eq!(filed.len(), 1);
+    assert_eq!(filed[0].issue_number, Some(42));
+    assert_eq!(
+        filed[0].issue_url.as_deref(),
+        Some("https://github.com/museum/exhibit_manager/issues/42")
+    );
+    assert_eq!(filed[0].filed_at, Some(later));
+    assert_eq!(filed[0].last_error, None);
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+    let request = &received[1];
+    assert_eq!(
+        request.request_line,
+        "POST /repos/museum/exhibit_manager/issues HTTP/1.1"
+    );
+    assert_eq!(request.headers["authorization"], "Bearer test-token");
+    assert!(request.headers["user-agent"].starts_with("exhibit-manager/"));
+
+    // Only the one fixed label, not one per reporter
+    let issue: Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(issue["title"], "[bug-report] Exhibit list is empty");
+    assert_eq!(issue["labels"], json!(["bug report"]));
+    let body = issue["body"].as_str().unwrap();
+    assert!(body.contains("App version: 0.1.5"));
+    assert!(body.contains("OS: Windows 11"));
+    assert!(body.contains(&format!("bug report #{}", id)));
+}
+
+#[tokio::test]
+async fn test_reports_are_given_up_on_after_the_last_attempt() {
+    let pool = setup_test_db().await;
+    let failures = vec![(500, r#"{"message":"Server Error"}"#); BACKOFF.max_attempts as usize];
+    let (base_url, received) = test_server::start(failures).await;
+    let github = github_client(base_url);
+
+    let mut now = Utc::now();
+    let id = bug_report_repo::create_bug_report(&bug_report(), None, now, &pool)
+        .await
+        .unwrap();
+
+    for attempt in 1..=BACKOFF.max_attempts {
+        assert_eq!(forward_due(&github, now, &pool).await.unwrap(), 1);
+        let report = bug_report_repo::get_bug_report(id, &pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.attempts, attempt);
+        assert!(report.last_error.as_deref().unwrap().contains("500"));
+        match report.next_attempt_at {
+            Some(next) => {
+                assert_eq!(report.status, BugReportStatus::Pending);
+                now = next;
+            }
+            None => {
+                assert_eq!(attempt, BACKOFF.max_attempts);
+                assert_eq!(report.status, BugReportStatus::Failed);
+            }
+        }
+    }
+
+    // Nothing is due once it's failed, however long it's been
+    let much_later = now + TimeDelta::days(365);
+    assert_eq!(forward_due(&github, much_later, &pool).await.unwrap(), 0);
+    assert_eq!(
+        received.lock().unwrap().len(),
+        BACKOFF.max_attempts as usize
+    );
+    let failed = bug_report_repo::get_bug_reports(Some(BugReportStatus::Failed), &pool)
+        .await
+        .unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].issue_number, None);
+}
@@ -0,0 +1,455 @@
+use crate::api;
+use crate::api::deprecation::{Deprecation, Deprecations};
+use crate::api::health_handlers::ReadinessSettings;
+use crate::api::trash_handlers::TrashSettings;
+use crate::backup::BackupSettings;
+use crate::notifications::{EmailSettings, SmtpSecurity, DEPARTMENTS};
+use chrono::{NaiveDate, NaiveTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use lettre::message::Mailbox;
+use rocket::figment::providers::Env;
+use rocket::figment::Figment;
+use rocket_cors::{AllowedOrigins, CorsOptions};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Environment variables read without the `ROCKET_` prefix, for deployments configured
+/// before there was a config file. They win over `Rocket.toml`.
+const UNPREFIXED_ENV_VARS: [&str; 12] = [
+    "JOTFORM_API_KEY",
+    "JOTFORM_FORM_ID",
+    "GITHUB_TOKEN",
+    "GITHUB_REPO_OWNER",
+    "GITHUB_REPO_NAME",
+    "LOG_FILTER",
+    "SITE_TIMEZONE",
+    "TRASH_RETENTION_DAYS",
+    "BACKUP_DIR",
+    "BACKUP_INTERVAL_HOURS",
+    "BACKUPS_TO_KEEP",
+    "BACKUP_INCLUDE_IMAGES",
+];
+
+/// Where Jotform tickets are synced from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JotformCredentials {
+    pub api_key: String,
+    pub form_id: String,
+}
+
+/// The repository bug reports are filed in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubCredentials {
+    pub token: String,
+    pub repo_owner: String,
+    pub repo_name: String,
+    /// The GitHub API, or a stand-in for it.
+    pub api_base_url: String,
+}
+
+/// The app's settings.
+///
+/// Read from the active profile in `Rocket.toml`, then `ROCKET_<NAME>` environment
+/// variables (e.g. `ROCKET_IMAGES_DIR`), then the unprefixed variables listed in
+/// `UNPREFIXED_ENV_VARS`. Anything left unset takes its default.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Log levels, in `env_logger` syntax, e.g. `info,sqlx=warn`. `RUST_LOG` is applied on top.
+    pub log_filter: String,
+    pub database_path: PathBuf,
+    /// Where uploaded images are stored and served from at `/images`.
+    pub images_dir: PathBuf,
+    /// The IANA time zone the museum operates in.
+    pub site_timezone: String,
+    pub trash_retention_days: i64,
+    pub backup_dir: PathBuf,
+    pub backup_interval_hours: u64,
+    pub backups_to_keep: usize,
+    pub backup_include_images: bool,
+    /// Origins allowed to call the API from a browser. `*` allows any origin.
+    pub cors_allowed_origins: Vec<String>,
+    #[serde(deserialize_with = "optional_text")]
+    pub jotform_api_key: Option<String>,
+    #[serde(deserialize_with = "optional_text")]
+    pub jotform_form_id: Option<String>,
+    pub jotform_base_url: String,
+    pub jotform_sync_interval_secs: u64,
+    /// GET /readyz fails once the last successful Jotform sync is older than this.
+    pub readiness_max_sync_age_mins: u64,
+    /// GET /readyz fails once the newest backup is older than this.
+    pub readiness_max_backup_age_hours: u64,
+    #[serde(deserialize_with = "optional_text")]
+    pub github_token: Option<String>,
+    #[serde(deserialize_with = "optional_text")]
+    pub github_repo_owner: Option<String>,
+    #[serde(deserialize_with = "optional_text")]
+    pub github_repo_name: Option<String>,
+    pub github_api_base_url: String,
+    /// The SMTP server emails are sent through. Emails are turned off while it's unset.
+    #[serde(deserialize_with = "optional_text")]
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_security: SmtpSecurity,
+    #[serde(deserialize_with = "optional_text")]
+    pub smtp_username: Option<String>,
+    #[serde(deserialize_with = "optional_text")]
+    pub smtp_password: Option<String>,
+    /// Who emails are from, e.g. `Exhibit Manager <exhibits@example.org>`.
+    #[serde(deserialize_with = "optional_text")]
+    pub email_from: Option<String>,
+    /// Who's emailed about each department's tickets, by department.
+    pub email_recipients: BTreeMap<String, Vec<String>>,
+    /// The hour of the day, at the site, the daily digest of open work is sent.
+    pub email_digest_hour: u32,
+    /// When the unversioned routes at `/` are expected to be removed, sent as their `Sunset`.
+    pub legacy_routes_sunset: Option<NaiveDate>,
+    /// Sent in `X-Confirm-Token` to confirm a request to a development utility. They're
+    /// refused while it's unset, and only exist in builds with the `dev-tools` feature.
+    #[serde(deserialize_with = "optional_text")]
+    pub dev_tools_token: Option<String>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            // Each request gets one line from the request tracing fairing, so Rocket's own
+            // per-request lines are left out
+            log_filter: "info,rocket::server=warn".to_string(),
+            database_path: PathBuf::from("exhibits.db"),
+            images_dir: PathBuf::from("images"),
+            site_timezone: "America/Chicago".to_string(),
+            trash_retention_days: 30,
+            backup_dir: PathBuf::from(".backups"),
+            backup_interval_hours: 48,
+            backups_to_keep: 10,
+            backup_include_images: true,
+            // The desktop app, in development and as installed on macOS/Linux and Windows
+            cors_allowed_origins: vec![
+                "http://localhost:1420".to_string(),
+                "tauri://localhost".to_string(),
+                "https://tauri.localhost".to_string(),
+            ],
+            jotform_api_key: None,
+            jotform_form_id: None,
+            jotform_base_url: "https://api.jotform.com".to_string(),
+            jotform_sync_interval_secs: 30 * 60,
+            // A few missed syncs or backups in a row, not just one running late
+            readiness_max_sync_age_mins: 2 * 60,
+            readiness_max_backup_age_hours: 3 * 24,
+            github_token: None,
+            github_repo_owner: None,
+            github_repo_name: None,
+            github_api_base_url: "https://api.github.com".to_string(),
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_security: SmtpSecurity::StartTls,
+            smtp_username: None,
+            smtp_password: None,
+            email_from: None,
+            email_recipients: BTreeMap::new(),
+            email_digest_hour: 7,
+            legacy_routes_sunset: None,
+            dev_tools_token: None,
+        }
+    }
+}
+
+/// Reads a setting that's always text, even when it looks like a number. Jotform form IDs
+/// are all digits, so `JOTFORM_FORM_ID=2412...` would otherwise be read as an integer.
+fn optional_text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Text {
+        String(String),
+        Unsigned(u64),
+        Signed(i64),
+    }
+
+    Ok(
+        Option::<Text>::deserialize(deserializer)?.map(|text| match text {
+            Text::String(text) => text,
+            Text::Unsigned(number) => number.to_string(),
+            Text::Signed(number) => number.to_string(),
+        }),
+    )
+}
+
+/// Treats blank settings as unset, so `JOTFORM_API_KEY=` doesn't count as a key.
+fn present(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+impl AppConfig {
+    /// Layers the config sources on top of `figment`, which should be Rocket's own.
+    pub fn figment(figment: Figment) -> Figment {
+        figment.merge(Env::raw().only(&UNPREFIXED_ENV_VARS).global())
+    }
+
+    /// Reads and checks the config, returning every problem found.
+    pub fn load(figment: &Figment) -> Result<Self, Vec<String>> {
+        let config: AppConfig = figment.extract().map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+        })?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Checks the settings make sense together, returning one message per problem.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.site_timezone.parse::<Tz>().is_err() {
+            errors.push(format!(
+                "site_timezone `{}` is not a known IANA time zone",
+                self.site_timezone
+            ));
+        }
+        if !(0..=3650).contains(&self.trash_retention_days) {
+            errors.push("trash_retention_days must be between 0 and 3650".to_string());
+        }
+        if !(1..=8760).contains(&self.backup_interval_hours) {
+            errors.push("backup_interval_hours must be between 1 and 8760".to_string());
+        }
+        if !(1..=1000).contains(&self.backups_to_keep) {
+            errors.push("backups_to_keep must be between 1 and 1000".to_string());
+        }
+        if self.cors_allowed_origins.is_empty()
+            || self
+                .cors_allowed_origins
+                .iter()
+                .any(|o| o.trim().is_empty())
+        {
+            errors.push("cors_allowed_origins must list at least one origin, or `*`".to_string());
+        } else if let Err(e) = CorsOptions::default()
+            .allowed_origins(self.cors_allowed_origins())
+            .to_cors()
+        {
+            errors.push(format!("cors_allowed_origins is invalid: {}", e));
+        }
+        if !self.jotform_base_url.starts_with("http://")
+            && !self.jotform_base_url.starts_with("https://")
+        {
+            errors.push("jotform_base_url must be an http or https URL".to_string());
+        }
+        if !self.github_api_base_url.starts_with("http://")
+            && !self.github_api_base_url.starts_with("https://")
+        {
+            errors.push("github_api_base_url must be an http or https URL".to_string());
+        }
+        if self.jotform_sync_interval_secs < 60 {
+            errors.push("jotform_sync_interval_secs must be at least 60".to_string());
+        }
+        if !(1..=7 * 24 * 60).contains(&self.readiness_max_sync_age_mins) {
+            errors.push("readiness_max_sync_age_mins must be between 1 and 10080".to_string());
+        } else if self.readiness_max_sync_age_mins * 60 <= self.jotform_sync_interval_secs {
+            errors.push(
+                "readiness_max_sync_age_mins must be longer than jotform_sync_interval_secs"
+                    .to_string(),
+            );
+        }
+        if !(1..=2 * 8760).contains(&self.readiness_max_backup_age_hours) {
+            errors.push("readiness_max_backup_age_hours must be between 1 and 17520".to_string());
+        } else if self.readiness_max_backup_age_hours <= self.backup_interval_hours {
+            errors.push(
+                "readiness_max_backup_age_hours must be longer than backup_interval_hours"
+                    .to_string(),
+            );
+        }
+
+        let jotform = [&self.jotform_api_key, &self.jotform_form_id];
+        if jotform.iter().any(|v| present(v).is_some()) && self.jotform().is_none() {
+            errors.push(
+                "jotform_api_key and jotform_form_id must be set together, or both left unset"
+                    .to_string(),
+            );
+        }
+
+        let github = [
+            &self.github_token,
+            &self.github_repo_owner,
+            &self.github_repo_name,
+        ];
+        if github.iter().any(|v| present(v).is_some()) && self.github().is_none() {
+            errors.push(
+                "github_token, github_repo_owner and github_repo_name must be set together, \
+                 or all left unset"
+                    .to_string(),
+            );
+        }
+
+        if present(&self.smtp_host).is_some() {
+            match present(&self.email_from) {
+                None => errors.push("email_from must be set when smtp_host is".to_string()),
+                Some(from) if from.parse::<Mailbox>().is_err() => {
+                    errors.push(format!("email_from `{}` is not an email address", from))
+                }
+                Some(_) => {}
+            }
+        }
+        if present(&self.smtp_username).is_some() != present(&self.smtp_password).is_some() {
+            errors.push(
+                "smtp_username and smtp_password must be set together, or both left unset"
+                    .to_string(),
+            );
+        }
+        for (department, addresses) in &self.email_recipients {
+            if !DEPARTMENTS.contains(&department.as_str()) {
+                errors.push(format!(
+                    "email_recipients has unknown department `{}`, expected one of {}",
+                    department,
+                    DEPARTMENTS.join(", ")
+                ));
+            }
+            for address in addresses {
+                if address.parse::<Mailbox>().is_err() {
+                    errors.push(format!(
+                        "email_recipients.{} has `{}`, which is not an email address",
+                        department, address
+                    ));
+                }
+            }
+        }
+        if self.email_digest_hour > 23 {
+            errors.push("email_digest_hour must be between 0 and 23".to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The site time zone. Falls back to the default if the config wasn't validated.
+    pub fn site_time_zone(&self) -> Tz {
+        self.site_timezone
+            .parse()
+            .unwrap_or(crate::time_zone::DEFAULT_SITE_TIME_ZONE)
+    }
+
+    /// The Jotform credentials, or `None` if syncing is turned off.
+    pub fn jotform(&self) -> Option<JotformCredentials> {
+        Some(JotformCredentials {
+            api_key: present(&self.jotform_api_key)?,
+            form_id: present(&self.jotform_form_id)?,
+        })
+    }
+
+    /// The GitHub credentials, or `None` if bug reports are only stored, not filed.
+    pub fn github(&self) -> Option<GitHubCredentials> {
+        Some(GitHubCredentials {
+            token: present(&self.github_token)?,
+            repo_owner: present(&self.github_repo_owner)?,
+            repo_name: present(&self.github_repo_name)?,
+            api_base_url: self.github_api_base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// The email settings, or `None` if emails are turned off.
+    pub fn email(&self) -> Option<EmailSettings> {
+        let credentials = match (present(&self.smtp_username), present(&self.smtp_password)) {
+            (Some(username), Some(password)) => Some((username, password)),
+            _ => None,
+        };
+
+        Some(EmailSettings {
+            smtp_host: present(&self.smtp_host)?,
+            smtp_port: self.smtp_port,
+            smtp_security: self.smtp_security,
+            credentials,
+            from: present(&self.email_from)?.parse().ok()?,
+            recipients: self
+                .email_recipients
+                .iter()
+                .map(|(department, addresses)| {
+                    let mailboxes = addresses.iter().filter_map(|a| a.parse().ok()).collect();
+                    (department.clone(), mailboxes)
+                })
+                .collect(),
+            digest_hour: self.email_digest_hour,
+        })
+    }
+
+    /// The origins CORS lets through.
+    ///
+    /// `http` and `https` origins are matched exactly. Others, like the desktop app's
+    /// `tauri://localhost`, are opaque origins that CORS can only match by pattern.
+    pub fn cors_allowed_origins(&self) -> AllowedOrigins {
+        if self.cors_allowed_origins.iter().any(|origin| origin == "*") {
+            return AllowedOrigins::all();
+        }
+
+        let (exact, opaque): (Vec<&String>, Vec<&String>) = self
+            .cors_allowed_origins
+            .iter()
+            .partition(|origin| origin.starts_with("http://") || origin.starts_with("https://"));
+
+        let patterns: Vec<String> = opaque
+            .iter()
+            .map(|origin| {
+                let escaped: String = origin
+                    .chars()
+                    .flat_map(|c| match c.is_ascii_punctuation() {
+                        true => vec!['\\', c],
+                        false => vec![c],
+                    })
+                    .collect();
+                format!("^{}$", escaped)
+            })
+            .collect();
+
+        AllowedOrigins::some(&exact, &patterns)
+    }
+
+    pub fn jotform_sync_interval(&self) -> Duration {
+        Duration::from_secs(self.jotform_sync_interval_secs)
+    }
+
+    /// The deprecated routes: for now, the unversioned copies of the API at `/`.
+    pub fn deprecations(&self) -> Deprecations {
+        let midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc();
+
+        Deprecations(vec![Deprecation {
+            base: "/",
+            route: None,
+            since: midnight(NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()),
+            sunset: self.legacy_routes_sunset.map(midnight),
+            successor_base: Some(api::API_BASE),
+        }])
+    }
+
+    pub fn trash_settings(&self) -> TrashSettings {
+        TrashSettings {
+            retention: TimeDelta::days(self.trash_retention_days),
+        }
+    }
+
+    pub fn readiness_settings(&self) -> ReadinessSettings {
+        ReadinessSettings {
+            max_sync_age: TimeDelta::minutes(self.readiness_max_sync_age_mins as i64),
+            max_backup_age: TimeDelta::hours(self.readiness_max_backup_age_hours as i64),
+            started_at: Utc::now(),
+        }
+    }
+
+    pub fn backup_settings(&self) -> BackupSettings {
+        BackupSettings {
+            dir: self.backup_dir.clone(),
+            interval: Duration::from_secs(self.backup_interval_hours * 60 * 60),
+            keep: self.backups_to_keep,
+            include_images: self.backup_include_images,
+        }
+    }
+}
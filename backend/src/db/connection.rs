@@ -1,6 +1,9 @@
-use crate::repo::{exhibit_repo, jotform_repo, part_repo};
+use crate::repo::{
+    bug_report_repo, exhibit_repo, jotform_repo, maintenance_repo, note_repo, part_repo,
+    sponsorship_repo, webhook_repo,
+};
 use sqlx::Result as SqlxResult;
-use sqlx::SqlitePool;
+use sqlx::{SqliteConnection, SqlitePool};
 
 /// Type alias for the connection pool.
 pub type DbPool = SqlitePool;
@@ -15,11 +18,73 @@ pub async fn create_pool(database_url: &str) -> Result<DbPool, sqlx::Error> {
     SqlitePool::connect(database_url).await
 }
 
+/// Returns whether a table named `table` exists.
+pub async fn table_exists(conn: &mut SqliteConnection, table: &str) -> SqlxResult<bool> {
+    let found = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1")
+        .bind(table)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(found.is_some())
+}
+
+/// Returns whether `table` has a column named `column`.
+pub async fn column_exists(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+) -> SqlxResult<bool> {
+    let columns = sqlx::query_scalar::<_, String>(&format!(
+        "SELECT name FROM pragma_table_info('{}')",
+        table
+    ))
+    .fetch_all(conn)
+    .await?;
+
+    Ok(columns.iter().any(|name| name == column))
+}
+
+/// Adds `column` to `table` unless it already exists.
+///
+/// `CREATE TABLE IF NOT EXISTS` won't touch tables created by older versions of the app,
+/// so new columns are added here. `definition` is everything after the column name,
+/// e.g. `"INTEGER NOT NULL DEFAULT 1"`.
+pub async fn add_column_if_missing(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> SqlxResult<()> {
+    if !column_exists(&mut *conn, table, column).await? {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
 /// Sets up the database schema using a connection from the pool.
 pub async fn setup_database(pool: &DbPool) -> SqlxResult<()> {
-    exhibit_repo::create_exhibit_tables(pool).await?;
-    jotform_repo::create_jotform_tables(pool).await?;
-    part_repo::create_part_tables(pool).await?;
+    create_schema(&mut *pool.acquire().await?).await
+}
+
+/// Creates any missing tables and brings older ones up to date.
+///
+/// Takes a connection rather than the pool so it can run inside a transaction.
+pub async fn create_schema(conn: &mut SqliteConnection) -> SqlxResult<()> {
+    exhibit_repo::create_exhibit_tables(&mut *conn).await?;
+    jotform_repo::create_jotform_tables(&mut *conn).await?;
+    part_repo::create_part_tables(&mut *conn).await?;
+    sponsorship_repo::create_sponsorship_tables(&mut *conn).await?;
+    maintenance_repo::create_maintenance_tables(&mut *conn).await?;
+    webhook_repo::create_webhook_tables(&mut *conn).await?;
+    bug_report_repo::create_bug_report_tables(&mut *conn).await?;
+    // Notes go last: their cleanup triggers reference every table notes can belong to
+    note_repo::create_note_tables(&mut *conn).await?;
 
     Ok(())
 }
@@ -1,3 +1,7 @@
 mod connection;
 
-pub use connection::{create_pool, setup_database, DbPool};
+#[cfg(feature = "dev-tools")]
+pub use connection::create_schema;
+pub use connection::{
+    add_column_if_missing, column_exists, create_pool, setup_database, table_exists, DbPool,
+};
@@ -1,8 +1,10 @@
 use crate::api::exhibit_handlers::NewExhibit;
+use crate::api::note_handlers::NewNote;
+use crate::exhibit_import::IMPORTED_NOTE_SUBMITTER;
 use rand::prelude::SliceRandom;
 use rocket::serde;
 
-const EXHIBIT_DATA: &'static str = include_str!("../exhibit_dev_data/exhibits.json");
+const EXHIBIT_DATA: &str = include_str!("../exhibit_dev_data/exhibits.json");
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -12,7 +14,7 @@ struct DummyExhibitData {
     building_location: String,
     current_status: String,
     cluster: String,
-    _notes: String,
+    notes: String,
 }
 
 pub fn get_random_dummy_exhibit() -> NewExhibit {
@@ -31,7 +33,14 @@ pub fn get_random_dummy_exhibit() -> NewExhibit {
         )),
         sponsor: None,
         part_ids: vec![],
-        notes: vec![],
+        notes: Some(dummy_exhibit_data.notes.trim())
+            .filter(|notes| !notes.is_empty())
+            .map(|notes| NewNote {
+                submitter: IMPORTED_NOTE_SUBMITTER.to_string(),
+                message: notes.to_string(),
+            })
+            .into_iter()
+            .collect(),
     };
 
     exhibit
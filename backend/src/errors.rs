@@ -1,27 +1,24 @@
 // src/errors.rs
 
+use crate::logging;
 use rocket::http::Status;
-use rocket::response::{Responder, Response};
+use rocket::response::{self, Responder};
 use rocket::serde::json::Json;
+use rocket::Request;
 use serde::Serialize;
-use std::io::Cursor;
 use thiserror::Error;
+use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
 
-#[allow(dead_code)]
+#[allow(dead_code, clippy::enum_variant_names)]
 /// Unified API error type
 #[derive(Debug, Error)]
 pub enum ApiError {
     #[error("Database error: {0}")]
     DatabaseError(String),
 
-    #[error("Missing environment variable: {0}")]
-    MissingEnvVar(String),
-
-    #[error("GitHub request error: {0}")]
-    GitHubRequestError(String),
-
-    #[error("GitHub API error: {0}")]
-    GitHubApiError(String),
+    #[error("Not configured: {0}")]
+    NotConfigured(String),
 
     #[error("Internal server error")]
     InternalServerError,
@@ -32,16 +29,29 @@ pub enum ApiError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    /// Fields that failed their `validator` checks, reported one by one in `details`.
+    #[error("Invalid input: {0}")]
+    Validation(ValidationErrors),
+
     #[error("Not Found")]
     NotFound,
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
     #[error("Unauthorized access")]
     Unauthorized,
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
 }
 
 impl From<validator::ValidationErrors> for ApiError {
     fn from(errors: validator::ValidationErrors) -> Self {
-        ApiError::InvalidInput(errors.to_string())
+        ApiError::Validation(errors)
     }
 }
 
@@ -54,68 +64,214 @@ impl From<sqlx::Error> for ApiError {
     }
 }
 
-/// Structure for API error responses
-#[derive(Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-}
-
-/// Implement Responder for ApiError to convert it into HTTP responses
-impl<'r> Responder<'r, 'static> for ApiError {
-    fn respond_to(self, _: &'r rocket::Request<'_>) -> Result<Response<'static>, Status> {
-        let error_response = ErrorResponse {
-            error: self.to_string(),
-        };
-
-        let status = match self {
+impl ApiError {
+    fn status(&self) -> Status {
+        match self {
             ApiError::DatabaseError(_) => Status::InternalServerError,
-            ApiError::MissingEnvVar(_) => Status::BadRequest,
-            ApiError::GitHubRequestError(_) => Status::BadGateway,
-            ApiError::GitHubApiError(_) => Status::BadGateway,
+            ApiError::NotConfigured(_) => Status::ServiceUnavailable,
             ApiError::InternalServerError => Status::InternalServerError,
             ApiError::InvalidRequestBody => Status::BadRequest,
             ApiError::InvalidInput(_) => Status::BadRequest,
+            ApiError::Validation(_) => Status::BadRequest,
+            ApiError::PayloadTooLarge(_) => Status::PayloadTooLarge,
             ApiError::NotFound => Status::NotFound,
+            ApiError::Conflict(_) => Status::Conflict,
             ApiError::Unauthorized => Status::Unauthorized,
-        };
-
-        Response::build()
-            .status(status)
-            .header(rocket::http::ContentType::JSON)
-            .sized_body(
-                error_response.error.len(),
-                Cursor::new(rocket::serde::json::serde_json::to_string(&error_response).unwrap()),
-            )
-            .ok()
+            ApiError::Forbidden(_) => Status::Forbidden,
+        }
+    }
+
+    /// The `code` sent with the error, for clients to tell errors apart without parsing
+    /// the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::DatabaseError(_) => "database_error",
+            ApiError::NotConfigured(_) => "not_configured",
+            ApiError::InternalServerError => "internal_error",
+            ApiError::InvalidRequestBody => "invalid_request_body",
+            ApiError::InvalidInput(_) => "invalid_input",
+            ApiError::Validation(_) => "validation_failed",
+            ApiError::PayloadTooLarge(_) => "payload_too_large",
+            ApiError::NotFound => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+        }
+    }
+}
+
+/// The body of every error response.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    /// What went wrong, e.g. `not_found` or `validation_failed`, for code to check.
+    pub code: String,
+    /// What went wrong, for people.
+    pub message: String,
+    /// Each field that failed validation, for `validation_failed`. Empty otherwise.
+    pub details: Vec<FieldError>,
+    /// The request's `X-Request-Id`, to find it in the logs.
+    pub request_id: String,
+}
+
+/// A field that failed validation.
+#[derive(Debug, Serialize, PartialEq, Eq, ToSchema)]
+pub struct FieldError {
+    /// The field's path in the request body, e.g. `name` or `sponsor.end_date`. `null` when
+    /// the check was on the body as a whole.
+    pub field: Option<String>,
+    /// The check that failed, e.g. `length` or `url`.
+    pub code: String,
+    pub message: String,
+}
+
+impl ErrorResponse {
+    pub fn new(code: &str, message: String, request: &Request<'_>) -> Self {
+        ErrorResponse {
+            code: code.to_string(),
+            message,
+            details: Vec::new(),
+            request_id: logging::request_id(request).0.clone(),
+        }
     }
 }
 
+/// Flattens `errors` into one entry per failed check, ordered by field.
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    fn collect(errors: &ValidationErrors, prefix: Option<&str>, out: &mut Vec<FieldError>) {
+        for (field, kind) in &errors.0 {
+            let path = match (prefix, *field) {
+                // Checks on a whole struct are filed under `__all__`
+                (_, "__all__") => prefix.map(str::to_string),
+                (Some(prefix), field) => Some(format!("{}.{}", prefix, field)),
+                (None, field) => Some(field.to_string()),
+            };
+            match kind {
+                ValidationErrorsKind::Field(failures) => {
+                    for failure in failures {
+                        out.push(FieldError {
+                            field: path.clone(),
+                            code: failure.code.to_string(),
+                            message: match &failure.message {
+                                Some(message) => message.to_string(),
+                                None => describe_check(&failure.code),
+                            },
+                        });
+                    }
+                }
+                ValidationErrorsKind::Struct(nested) => collect(nested, path.as_deref(), out),
+                ValidationErrorsKind::List(items) => {
+                    for (index, nested) in items {
+                        let path = format!("{}[{}]", path.as_deref().unwrap_or(""), index);
+                        collect(nested, Some(&path), out);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    collect(errors, None, &mut out);
+    out.sort_by(|a, b| a.field.cmp(&b.field).then_with(|| a.code.cmp(&b.code)));
+    out
+}
+
+/// A message for a failed check that didn't come with one.
+fn describe_check(code: &str) -> String {
+    match code {
+        "url" => "Must be a valid URL".to_string(),
+        "email" => "Must be a valid email address".to_string(),
+        "length" => "Has the wrong length".to_string(),
+        "range" => "Is out of range".to_string(),
+        "required" => "Is required".to_string(),
+        // Custom checks here use the message as their code
+        other => other.to_string(),
+    }
+}
+
+/// Implement Responder for ApiError to convert it into HTTP responses
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let mut error_response = ErrorResponse::new(self.code(), self.to_string(), request);
+        if let ApiError::Validation(errors) = &self {
+            error_response.details = field_errors(errors);
+        }
+
+        (self.status(), Json(error_response)).respond_to(request)
+    }
+}
+
+/// Why a request body couldn't be read, kept by `JsonBody` for the catchers.
+pub struct BodyError(pub Option<String>);
+
 /// Error catchers
 
 #[rocket::catch(404)]
-pub fn not_found() -> Json<ErrorResponse> {
-    Json(ErrorResponse {
-        error: "Not Found".into(),
-    })
+pub fn not_found(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(ErrorResponse::new(
+        "not_found",
+        format!(
+            "Nothing is at {} {}",
+            request.method(),
+            request.uri().path()
+        ),
+        request,
+    ))
 }
 
 #[rocket::catch(400)]
-pub fn handle_invalid_request_body() -> Json<ErrorResponse> {
-    Json(ErrorResponse {
-        error: "Invalid request body".into(),
+pub fn handle_invalid_request_body(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(match &request.local_cache(|| BodyError(None)).0 {
+        Some(reason) => ErrorResponse::new(
+            "invalid_request_body",
+            format!("Invalid request body: {}", reason),
+            request,
+        ),
+        None => ErrorResponse::new("bad_request", "The request is malformed".into(), request),
     })
 }
 
-#[rocket::catch(405)]
-pub fn handle_method_not_allowed() -> Json<ErrorResponse> {
-    Json(ErrorResponse {
-        error: "Method Not Allowed".into(),
+/// Rocket also answers 422 when a query parameter has the wrong type, so only a body that
+/// `JsonBody` couldn't read is reported as one.
+#[rocket::catch(422)]
+pub fn handle_unprocessable_request_body(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(match &request.local_cache(|| BodyError(None)).0 {
+        Some(reason) => ErrorResponse::new(
+            "invalid_request_body",
+            format!("Invalid request body: {}", reason),
+            request,
+        ),
+        None => ErrorResponse::new(
+            "unprocessable_entity",
+            "A query parameter is missing or has the wrong type".into(),
+            request,
+        ),
     })
 }
 
+#[rocket::catch(405)]
+pub fn handle_method_not_allowed(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(ErrorResponse::new(
+        "method_not_allowed",
+        "Method Not Allowed".into(),
+        request,
+    ))
+}
+
 #[rocket::catch(500)]
-pub fn internal_server_error() -> Json<ErrorResponse> {
-    Json(ErrorResponse {
-        error: "Internal Server Error".into(),
-    })
+pub fn internal_server_error(request: &Request<'_>) -> Json<ErrorResponse> {
+    Json(ErrorResponse::new(
+        "internal_error",
+        "Internal Server Error".into(),
+        request,
+    ))
+}
+
+#[rocket::catch(default)]
+pub fn other_error(status: Status, request: &Request<'_>) -> (Status, Json<ErrorResponse>) {
+    let reason = status.reason().unwrap_or("Error");
+    let code = reason.to_lowercase().replace([' ', '-'], "_");
+    (
+        status,
+        Json(ErrorResponse::new(&code, reason.to_string(), request)),
+    )
 }
@@ -0,0 +1,218 @@
+use crate::repo::webhook_repo;
+use chrono::{DateTime, Utc};
+use rocket::serde::json::Value;
+use rocket::tokio::sync::broadcast;
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteExecutor;
+use std::sync::OnceLock;
+
+/// How many events a slow listener can fall behind by before it misses some.
+const CAPACITY: usize = 256;
+
+static BUS: OnceLock<broadcast::Sender<ChangeEvent>> = OnceLock::new();
+
+fn bus() -> &'static broadcast::Sender<ChangeEvent> {
+    BUS.get_or_init(|| broadcast::channel(CAPACITY).0)
+}
+
+/// The kind of record a change was made to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Entity {
+    Exhibit,
+    Part,
+    Note,
+    Attachment,
+    /// A Jotform ticket.
+    Jotform,
+    Sponsor,
+    Sponsorship,
+    Maintenance,
+    BugReport,
+    /// Everything at once, as when a backup or snapshot is restored.
+    Data,
+}
+
+/// What happened to the record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Created,
+    /// A note or attachment was added to something.
+    Added,
+    Updated,
+    Deleted,
+    /// Brought back from the trash.
+    Restored,
+    Completed,
+    Synced,
+    Replaced,
+}
+
+const ENTITIES: [Entity; 10] = [
+    Entity::Exhibit,
+    Entity::Part,
+    Entity::Note,
+    Entity::Attachment,
+    Entity::Jotform,
+    Entity::Sponsor,
+    Entity::Sponsorship,
+    Entity::Maintenance,
+    Entity::BugReport,
+    Entity::Data,
+];
+
+const ACTIONS: [Action; 8] = [
+    Action::Created,
+    Action::Added,
+    Action::Updated,
+    Action::Deleted,
+    Action::Restored,
+    Action::Completed,
+    Action::Synced,
+    Action::Replaced,
+];
+
+impl Entity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Entity::Exhibit => "exhibit",
+            Entity::Part => "part",
+            Entity::Note => "note",
+            Entity::Attachment => "attachment",
+            Entity::Jotform => "jotform",
+            Entity::Sponsor => "sponsor",
+            Entity::Sponsorship => "sponsorship",
+            Entity::Maintenance => "maintenance",
+            Entity::BugReport => "bug_report",
+            Entity::Data => "data",
+        }
+    }
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Created => "created",
+            Action::Added => "added",
+            Action::Updated => "updated",
+            Action::Deleted => "deleted",
+            Action::Restored => "restored",
+            Action::Completed => "completed",
+            Action::Synced => "synced",
+            Action::Replaced => "replaced",
+        }
+    }
+}
+
+/// A change to the data, sent to everyone listening on GET /events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// `<entity>.<action>`, e.g. `exhibit.updated`. Also the SSE event name.
+    pub topic: String,
+    pub entity: Entity,
+    pub action: Action,
+    /// The changed record's ID, if the change was to a single record.
+    pub id: Option<String>,
+    pub at: DateTime<Utc>,
+    /// Anything else listeners might want without re-fetching, such as a new status.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub data: Value,
+}
+
+impl ChangeEvent {
+    pub fn new(entity: Entity, action: Action, id: Option<String>) -> Self {
+        ChangeEvent {
+            topic: format!("{}.{}", entity.as_str(), action.as_str()),
+            entity,
+            action,
+            id,
+            at: Utc::now(),
+            data: Value::Null,
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Whether the event is one a listener asked for.
+    ///
+    /// No filters means everything. `data.replaced` always matches, since it invalidates
+    /// everything. See `filter_matches` for what a filter looks like.
+    pub fn matches(&self, filters: &[String]) -> bool {
+        filters.is_empty()
+            || self.entity == Entity::Data
+            || filters.iter().any(|filter| self.filter_matches(filter))
+    }
+
+    /// Whether the event matches one filter: an entity (`exhibit`) or a topic
+    /// (`exhibit.updated`), optionally followed by a condition on its data, e.g.
+    /// `jotform.created:priority_level=High` or `exhibit:status=out of service`. Conditions
+    /// ignore case.
+    pub fn filter_matches(&self, filter: &str) -> bool {
+        let (scope, condition) = match filter.split_once(':') {
+            Some((scope, condition)) => (scope, Some(condition)),
+            None => (filter, None),
+        };
+        if scope != self.entity.as_str() && scope != self.topic {
+            return false;
+        }
+
+        match condition.map(|condition| condition.split_once('=')) {
+            None => true,
+            Some(Some((field, value))) => self
+                .data
+                .get(field.trim())
+                .and_then(Value::as_str)
+                .is_some_and(|actual| actual.eq_ignore_ascii_case(value.trim())),
+            Some(None) => false,
+        }
+    }
+}
+
+/// Whether `filter` could ever match: its entity and action exist and any condition is
+/// `field=value`.
+pub fn is_valid_filter(filter: &str) -> bool {
+    let (scope, condition) = match filter.split_once(':') {
+        Some((scope, condition)) => (scope, Some(condition)),
+        None => (filter, None),
+    };
+    let (entity, action) = match scope.split_once('.') {
+        Some((entity, action)) => (entity, Some(action)),
+        None => (scope, None),
+    };
+
+    ENTITIES.iter().any(|e| e.as_str() == entity)
+        && action.is_none_or(|action| ACTIONS.iter().any(|a| a.as_str() == action))
+        && condition.is_none_or(|condition| {
+            condition
+                .split_once('=')
+                .is_some_and(|(field, _)| !field.trim().is_empty())
+        })
+}
+
+/// Tells every listener about a change.
+///
+/// Only publish once the change is committed, so listeners never hear about writes that
+/// were rolled back. Listeners that fall behind miss events, so anything that mustn't miss
+/// one, like webhooks, reads them from the outbox instead; see `record`.
+pub fn publish(event: ChangeEvent) {
+    // Sending only fails when nobody is listening, which is fine
+    let _ = bus().send(event);
+}
+
+/// Adds a change to the webhook outbox. Call it in the transaction making the change, so
+/// the event is kept if and only if the change is, then `publish` it once it's committed.
+pub async fn record<'e>(
+    event: &ChangeEvent,
+    executor: impl SqliteExecutor<'e>,
+) -> sqlx::Result<()> {
+    webhook_repo::record_event(event, executor).await
+}
+
+/// Starts listening for changes made from now on.
+pub fn subscribe() -> broadcast::Receiver<ChangeEvent> {
+    bus().subscribe()
+}
@@ -0,0 +1,282 @@
+use crate::models::Exhibit;
+use calamine::{Reader, Xlsx};
+use rocket::http::ContentType;
+use serde::Serialize;
+use std::io::Cursor;
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// The submitter recorded on notes brought in from a spreadsheet's `Notes` column.
+pub const IMPORTED_NOTE_SUBMITTER: &str = "Spreadsheet import";
+
+/// A spreadsheet format exhibits can be imported from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ImportFormat {
+    Csv,
+    Xlsx,
+}
+
+impl ImportFormat {
+    /// Picks the format from a `format` query value such as `csv` or `xlsx`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "csv" => Some(ImportFormat::Csv),
+            "xlsx" => Some(ImportFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    /// Picks the format from a request's `Content-Type`.
+    pub fn from_content_type(content_type: &ContentType) -> Option<Self> {
+        match (content_type.top().as_str(), content_type.sub().as_str()) {
+            ("text", "csv") => Some(ImportFormat::Csv),
+            ("application", "vnd.openxmlformats-officedocument.spreadsheetml.sheet") => {
+                Some(ImportFormat::Xlsx)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One spreadsheet row, with its columns mapped to exhibit fields and trimmed.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ImportRow {
+    /// The row's line number in the sheet; the header is line 1 in a typical sheet.
+    pub line: usize,
+    pub name: String,
+    pub cluster: String,
+    pub location: String,
+    pub description: String,
+    /// The status, lowercased to match how statuses are stored.
+    pub status: String,
+    pub image_url: Option<String>,
+    /// The legacy free-text `Notes` column, imported as a note on the exhibit.
+    pub notes: Option<String>,
+}
+
+impl ImportRow {
+    /// Checks the row with the same rules as `Exhibit`, returning one message per problem.
+    ///
+    /// `default_image_url` stands in for a missing image, as it would on create.
+    pub fn validate(&self, default_image_url: &str) -> Vec<String> {
+        let exhibit = Exhibit {
+            id: 0,
+            name: self.name.clone(),
+            cluster: self.cluster.clone(),
+            location: self.location.clone(),
+            description: self.description.clone(),
+            status: self.status.clone(),
+            part_ids: Vec::new(),
+            notes: Vec::new(),
+            image_url: self
+                .image_url
+                .clone()
+                .unwrap_or_else(|| default_image_url.to_string()),
+            sponsor: None,
+            version: 1,
+        };
+
+        match exhibit.validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => {
+                let mut messages: Vec<String> = errors
+                    .field_errors()
+                    .into_iter()
+                    .flat_map(|(field, errors)| {
+                        errors.iter().map(move |error| match &error.message {
+                            Some(message) => message.to_string(),
+                            None => format!("{}: {}", field, error.code),
+                        })
+                    })
+                    .collect();
+                messages.sort();
+                messages
+            }
+        }
+    }
+}
+
+/// What happened (or, in a dry run, would happen) to an imported row.
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Created,
+    Updated,
+    Invalid,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, ToSchema)]
+pub struct ImportRowResult {
+    pub line: usize,
+    pub name: String,
+    pub outcome: ImportOutcome,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, ToSchema)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub invalid: usize,
+    /// Header names that didn't match any exhibit field and were skipped.
+    pub ignored_columns: Vec<String>,
+    pub rows: Vec<ImportRowResult>,
+}
+
+/// A parsed sheet: the data rows plus the headers that weren't used.
+#[derive(Debug)]
+pub struct ParsedSheet {
+    pub rows: Vec<ImportRow>,
+    pub ignored_columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Name,
+    Cluster,
+    Location,
+    Description,
+    Status,
+    ImageUrl,
+    Notes,
+}
+
+const REQUIRED_COLUMNS: [(Column, &str); 4] = [
+    (Column::Name, "Name"),
+    (Column::Cluster, "Cluster"),
+    (Column::Location, "Location"),
+    (Column::Status, "Status"),
+];
+
+/// Maps a header to a field, ignoring case, spaces and punctuation. The floor team's old
+/// spreadsheet headers (`ExhibitName`, `BuildingLocation`, `CurrentStatus`, ...) are accepted
+/// alongside the plain field names.
+fn column_for(header: &str) -> Option<Column> {
+    let key: String = header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+
+    match key.as_str() {
+        "name" | "exhibit" | "exhibitname" => Some(Column::Name),
+        "cluster" => Some(Column::Cluster),
+        "location" | "buildinglocation" => Some(Column::Location),
+        "description" | "exhibitdescription" => Some(Column::Description),
+        "status" | "currentstatus" => Some(Column::Status),
+        "image" | "imageurl" => Some(Column::ImageUrl),
+        "note" | "notes" => Some(Column::Notes),
+        _ => None,
+    }
+}
+
+/// Reads the first sheet of a CSV or XLSX file into import rows.
+///
+/// The first non-blank row is the header. Blank rows are skipped. Returns an error message
+/// if the file can't be read or a required column is missing.
+pub fn parse_sheet(format: ImportFormat, data: &[u8]) -> Result<ParsedSheet, String> {
+    let table = match format {
+        ImportFormat::Csv => read_csv(data)?,
+        ImportFormat::Xlsx => read_xlsx(data)?,
+    };
+
+    let mut lines = table
+        .into_iter()
+        .filter(|(_, cells)| cells.iter().any(|cell| !cell.trim().is_empty()));
+
+    let Some((_, headers)) = lines.next() else {
+        return Err("The file is empty".to_string());
+    };
+
+    let columns: Vec<Option<Column>> = headers.iter().map(|header| column_for(header)).collect();
+
+    let missing: Vec<&str> = REQUIRED_COLUMNS
+        .iter()
+        .filter(|(column, _)| !columns.contains(&Some(*column)))
+        .map(|(_, name)| *name)
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing required columns: {}", missing.join(", ")));
+    }
+
+    let ignored_columns = headers
+        .iter()
+        .zip(&columns)
+        .filter(|(header, column)| column.is_none() && !header.trim().is_empty())
+        .map(|(header, _)| header.trim().to_string())
+        .collect();
+
+    let rows = lines
+        .map(|(line, cells)| {
+            let mut row = ImportRow {
+                line,
+                ..ImportRow::default()
+            };
+
+            for (column, cell) in columns.iter().zip(cells) {
+                let value = cell.trim().to_string();
+                match column {
+                    Some(Column::Name) => row.name = value,
+                    Some(Column::Cluster) => row.cluster = value,
+                    Some(Column::Location) => row.location = value,
+                    Some(Column::Description) => row.description = value,
+                    Some(Column::Status) => row.status = value.to_lowercase(),
+                    Some(Column::ImageUrl) => row.image_url = Some(value).filter(|v| !v.is_empty()),
+                    Some(Column::Notes) => row.notes = Some(value).filter(|v| !v.is_empty()),
+                    None => {}
+                }
+            }
+
+            row
+        })
+        .collect();
+
+    Ok(ParsedSheet {
+        rows,
+        ignored_columns,
+    })
+}
+
+fn read_csv(data: &[u8]) -> Result<Vec<(usize, Vec<String>)>, String> {
+    // Spreadsheet programs often save CSVs with a byte order mark
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(data);
+
+    let mut table = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Invalid CSV: {}", e))?;
+        let line = record
+            .position()
+            .map_or(0, |position| position.line() as usize);
+        table.push((line, record.iter().map(str::to_string).collect()));
+    }
+
+    Ok(table)
+}
+
+fn read_xlsx(data: &[u8]) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut workbook = Xlsx::new(Cursor::new(data)).map_err(|e| format!("Invalid XLSX: {}", e))?;
+
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| "The workbook has no sheets".to_string())?
+        .map_err(|e| format!("Invalid XLSX: {}", e))?;
+
+    let first_row = range.start().map_or(0, |(row, _)| row as usize);
+
+    Ok(range
+        .rows()
+        .enumerate()
+        .map(|(index, cells)| {
+            (
+                first_row + index + 1,
+                cells.iter().map(|cell| cell.to_string()).collect(),
+            )
+        })
+        .collect())
+}
@@ -0,0 +1,262 @@
+use crate::models::{Exhibit, Jotform, Part};
+use crate::time_zone::site_time_zone;
+use log::error;
+use rocket::futures::stream::{self, BoxStream, StreamExt};
+use rocket::futures::{future, Future};
+use rocket::http::ContentType;
+use rocket::serde::json::serde_json;
+use rust_xlsxwriter::Workbook;
+use serde::Serialize;
+
+/// How many records are loaded from the database at a time while exporting.
+pub const EXPORT_PAGE_SIZE: i64 = 200;
+
+/// A file format records can be exported to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+    Json,
+}
+
+impl ExportFormat {
+    /// Picks the format from a `format` query value such as `csv`, `xlsx` or `json`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn content_type(self) -> ContentType {
+        match self {
+            ExportFormat::Csv => ContentType::CSV,
+            ExportFormat::Xlsx => ContentType::new(
+                "application",
+                "vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ),
+            ExportFormat::Json => ContentType::JSON,
+        }
+    }
+}
+
+/// A record that can be written as a spreadsheet row.
+pub trait Exportable: Serialize + Send + 'static {
+    /// The header row for CSV and XLSX exports.
+    const HEADERS: &'static [&'static str];
+
+    /// The record's cells, in `HEADERS` order.
+    fn cells(&self) -> Vec<String>;
+}
+
+fn join_ids(ids: &[i64]) -> String {
+    ids.iter()
+        .map(i64::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// The headers line up with what the exhibit import understands, so an export can be edited
+// and imported again. Images are left out; they're usually embedded data URLs.
+impl Exportable for Exhibit {
+    const HEADERS: &'static [&'static str] = &[
+        "Id",
+        "Name",
+        "Cluster",
+        "Location",
+        "Description",
+        "Status",
+        "Sponsor",
+        "Sponsor Start",
+        "Sponsor End",
+        "Part Ids",
+        "Note Count",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        let sponsor = self.sponsor.as_ref();
+
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.cluster.clone(),
+            self.location.clone(),
+            self.description.clone(),
+            self.status.clone(),
+            sponsor.map(|s| s.name.clone()).unwrap_or_default(),
+            sponsor
+                .map(|s| s.start_date.to_string())
+                .unwrap_or_default(),
+            sponsor.map(|s| s.end_date.to_string()).unwrap_or_default(),
+            join_ids(&self.part_ids),
+            self.notes.len().to_string(),
+        ]
+    }
+}
+
+impl Exportable for Part {
+    const HEADERS: &'static [&'static str] = &["Id", "Name", "Link", "Exhibit Ids", "Note Count"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.map(|id| id.to_string()).unwrap_or_default(),
+            self.name.clone(),
+            self.link.clone(),
+            join_ids(&self.exhibit_ids),
+            self.notes.len().to_string(),
+        ]
+    }
+}
+
+impl Exportable for Jotform {
+    const HEADERS: &'static [&'static str] = &[
+        "Id",
+        "Submitted At",
+        "Submitter",
+        "Location",
+        "Exhibit",
+        "Description",
+        "Priority",
+        "Department",
+        "Status",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            // Spreadsheets are read by people, so times are shown at the site
+            self.created_at
+                .with_timezone(&site_time_zone())
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+            format!("{} {}", self.submitter_name.first, self.submitter_name.last),
+            self.location.clone(),
+            self.exhibit_name.clone(),
+            self.description.clone(),
+            self.priority_level.clone(),
+            self.department.clone(),
+            self.status.clone(),
+        ]
+    }
+}
+
+/// Walks a table one page at a time.
+///
+/// `fetch` loads the page after a cursor and `key` gives the cursor of a page's last record.
+/// The stream ends at the first empty page. A database error ends the stream after it has
+/// been logged; by then the response has started, so it can't become an error status.
+pub fn paged<T, K, F, Fut>(first: K, key: fn(&T) -> K, fetch: F) -> BoxStream<'static, Vec<T>>
+where
+    T: Send + 'static,
+    K: Send + 'static,
+    F: FnMut(K) -> Fut + Send + 'static,
+    Fut: Future<Output = sqlx::Result<Vec<T>>> + Send,
+{
+    stream::unfold(Some((first, fetch)), move |state| async move {
+        let (cursor, mut fetch) = state?;
+
+        match fetch(cursor).await {
+            Ok(page) => {
+                let next = key(page.last()?);
+                Some((page, Some((next, fetch))))
+            }
+            Err(e) => {
+                error!("Export stopped early, failed to load records: {}", e);
+                None
+            }
+        }
+    })
+    .boxed()
+}
+
+fn csv_chunk(rows: impl IntoIterator<Item = Vec<String>>) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        if let Err(e) = writer.write_record(&row) {
+            error!("Failed to write CSV row: {}", e);
+        }
+    }
+
+    writer.into_inner().unwrap_or_default()
+}
+
+/// Streams records as CSV: the header row, then one chunk per page.
+pub fn csv_stream<T: Exportable>(pages: BoxStream<'static, Vec<T>>) -> BoxStream<'static, Vec<u8>> {
+    let header = csv_chunk([T::HEADERS.iter().map(|h| h.to_string()).collect()]);
+
+    stream::once(future::ready(header))
+        .chain(pages.map(|page| csv_chunk(page.iter().map(Exportable::cells))))
+        .boxed()
+}
+
+/// Streams records as a JSON array, one chunk per page.
+pub fn json_stream<T: Serialize + Send + 'static>(
+    pages: BoxStream<'static, Vec<T>>,
+) -> BoxStream<'static, Vec<u8>> {
+    // A `None` after the last page closes the array
+    pages
+        .map(Some)
+        .chain(stream::once(future::ready(None)))
+        .scan(true, |first, page| {
+            let mut chunk = Vec::new();
+
+            match page {
+                Some(page) => {
+                    for record in &page {
+                        chunk.push(if *first { b'[' } else { b',' });
+                        *first = false;
+                        if let Err(e) = serde_json::to_writer(&mut chunk, record) {
+                            error!("Failed to write JSON record: {}", e);
+                        }
+                    }
+                }
+                None => {
+                    if *first {
+                        chunk.push(b'[');
+                    }
+                    chunk.push(b']');
+                }
+            }
+
+            future::ready(Some(chunk))
+        })
+        .boxed()
+}
+
+/// Writes records to an XLSX workbook with a single sheet.
+///
+/// XLSX is a zip archive, so unlike CSV and JSON it can't be sent until it's finished. Rows
+/// are written to a constant-memory worksheet, which keeps them in a temporary file rather
+/// than in memory, and only the compressed workbook is held at the end.
+pub async fn xlsx_workbook<T: Exportable>(
+    mut pages: BoxStream<'static, Vec<T>>,
+) -> Result<Vec<u8>, rust_xlsxwriter::XlsxError> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet_with_constant_memory();
+
+    for (col, header) in T::HEADERS.iter().enumerate() {
+        worksheet.write_string(0, col as u16, *header)?;
+    }
+
+    let mut row = 1;
+    while let Some(page) = pages.next().await {
+        for record in &page {
+            for (col, cell) in record.cells().iter().enumerate() {
+                worksheet.write_string(row, col as u16, cell)?;
+            }
+            row += 1;
+        }
+    }
+
+    workbook.save_to_buffer()
+}
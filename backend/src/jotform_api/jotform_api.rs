@@ -1,4 +1,5 @@
 use super::raw_submission::RawSubmission;
+use crate::metrics;
 use crate::models::Jotform;
 use log::info;
 use rocket::async_trait;
@@ -52,6 +53,7 @@ impl JotformApiTrait for JotformApi {
 
         let limit_left = response_body.limit_left;
         info!("JotForm API rate limit left: {}", limit_left);
+        metrics::record_jotform_limit_left(limit_left);
 
         Ok(response_body
             .content
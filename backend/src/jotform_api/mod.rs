@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod jotform_api;
 mod raw_submission;
 #[cfg(test)]
@@ -6,66 +7,126 @@ mod tests;
 pub use jotform_api::JotformApi;
 use jotform_api::JotformApiTrait;
 
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::logging;
+use crate::metrics;
+use crate::notifications;
 use crate::repo::jotform_repo;
 
-use crate::models::Jotform;
-use log::info;
+use crate::models::{Jotform, UNKNOWN_CREATED_AT};
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use rocket::serde::json::json;
 use sqlx::SqlitePool;
 use std::collections::HashSet;
+use std::time::Instant;
 
+/// Fetches every submission from Jotform and saves it, inserting new tickets and updating
+/// the rest. Its logs are tagged with a `jotform_sync` span, so one run's lines can be told
+/// apart from the next's.
 pub async fn sync_jotforms_once(
     pool: &SqlitePool,
     jotform_api_client: &impl JotformApiTrait,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    info!("! Syncing jotforms !");
+    let started = Instant::now();
+    let result = logging::in_span("jotform_sync", sync(pool, jotform_api_client)).await;
+    metrics::record_jotform_sync(started.elapsed(), result.is_ok());
 
-    info!("Fetching new submissions from JotForm");
-    // 2) Fetch new submissions from JotForm
-    let new_submissions: Vec<Jotform> = jotform_api_client.get_submissions().await?; // returns Vec<Jotform>, or an error
-    info!("Fetched {} new submissions", new_submissions.len());
+    result
+}
 
-    for submission in &new_submissions {
-        info!("Submission: {:?}", submission);
-    }
+async fn sync(
+    pool: &SqlitePool,
+    jotform_api_client: &impl JotformApiTrait,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    info!("Syncing jotforms");
+
+    // 1) Fetch new submissions from JotForm
+    let new_submissions: Vec<Jotform> = jotform_api_client.get_submissions().await?; // returns Vec<Jotform>, or an error
+    info!(submissions = new_submissions.len(); "Fetched submissions from Jotform");
 
-    info!("Getting existing IDs from local DB");
-    // 3) Collect existing IDs from local DB
+    // 2) Collect existing IDs from local DB
     let existing_ids = get_existing_ids(pool).await?;
-    info!("Found {} existing IDs", existing_ids.len());
-    info!("Existing IDs: {:?}", existing_ids);
+    debug!(existing = existing_ids.len(); "Found existing ticket IDs");
 
-    info!("Inserting or updating jotforms");
-    // 4) Insert or update
-    insert_or_update_jotforms(pool, &new_submissions, &existing_ids).await?;
+    // 3) Insert or update, alerting departments to tickets submitted since the last sync
+    let alert_after = jotform_repo::get_newest_created_at(pool).await?;
+    let (inserted, updated) =
+        insert_or_update_jotforms(pool, &new_submissions, &existing_ids, alert_after).await?;
 
-    info!("! Syncing jotforms complete !");
+    info!(
+        submissions = new_submissions.len(),
+        inserted = inserted,
+        updated = updated,
+        elapsed_ms = started.elapsed().as_millis() as u64;
+        "Synced jotforms"
+    );
     Ok(())
 }
 
 async fn get_existing_ids(
     pool: &SqlitePool,
 ) -> Result<HashSet<String>, Box<dyn std::error::Error>> {
-    let existing_ids = sqlx::query_scalar::<_, String>("SELECT id FROM jotforms")
-        .fetch_all(pool)
-        .await?;
+    // Trashed and purged tickets count as existing so they aren't inserted again
+    let existing_ids = sqlx::query_scalar::<_, String>(
+        "SELECT id FROM jotforms UNION SELECT id FROM purged_jotforms",
+    )
+    .fetch_all(pool)
+    .await?;
     Ok(existing_ids.into_iter().collect())
 }
 
+/// Stores the submissions in one transaction, along with a `jotform.synced` event, and
+/// returns how many were inserted and how many updated.
+///
+/// New tickets that need an email are queued one with it, but only if they were submitted
+/// no earlier than `alert_after`, the newest ticket already stored. With nothing stored,
+/// as on the first sync or after a reset, every ticket is old news and none are alerted.
+/// Nor are tickets whose submission time couldn't be read.
 async fn insert_or_update_jotforms(
     pool: &SqlitePool,
     new_submissions: &[Jotform],
     existing_ids: &HashSet<String>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    for submission in new_submissions {
-        info!("Processing submission: {:?}", submission.id);
+    alert_after: Option<DateTime<Utc>>,
+) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+    let mut created = Vec::new();
+    let mut updated = 0;
 
+    for submission in new_submissions {
         if existing_ids.contains(&submission.id) {
-            info!("Jotform already existed in the DB, updating");
-            jotform_repo::update_jotform(submission, pool).await?;
-        } else {
-            info!("Jotform didn't exist in the DB, inserting");
-            jotform_repo::insert_jotform(submission, pool).await?;
+            debug!(ticket_id = submission.id.as_str(); "Jotform already existed in the DB, updating");
+            jotform_repo::update_jotform(submission, &mut *tx).await?;
+            updated += 1;
+            continue;
         }
+
+        debug!(ticket_id = submission.id.as_str(); "Jotform didn't exist in the DB, inserting");
+        let Some(event) = jotform_repo::insert_new_jotform(submission, &mut tx).await? else {
+            continue;
+        };
+        // A ticket whose submission time couldn't be read can't be known to be new
+        if notifications::needs_alert(submission)
+            && submission.created_at != UNKNOWN_CREATED_AT
+            && alert_after.is_some_and(|after| submission.created_at >= after)
+        {
+            debug!(ticket_id = submission.id.as_str(); "Jotform needs an alert, queueing one");
+            jotform_repo::queue_ticket_alert(&submission.id, now, &mut tx).await?;
+        }
+        created.push(event);
     }
-    Ok(())
+
+    let inserted = created.len();
+    let synced = ChangeEvent::new(Entity::Jotform, Action::Synced, None)
+        .with_data(json!({ "inserted": inserted, "updated": updated }));
+    events::record(&synced, &mut *tx).await?;
+    tx.commit().await?;
+
+    for event in created.into_iter().chain([synced]) {
+        events::publish(event);
+    }
+
+    Ok((inserted, updated))
 }
@@ -1,4 +1,7 @@
-use crate::models::{FullName, Jotform, SubmissionDate};
+use crate::models::{FullName, Jotform, UNKNOWN_CREATED_AT};
+use crate::time_zone::site_local_to_utc;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::warn;
 use rocket::serde::json::serde_json::Value;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -73,8 +76,14 @@ impl RawSubmission {
         let priority_level = parse_priority_level(&raw_priority);
         let department = parse_department(&raw_department);
 
-        // Extract the date and time from the "created_at" field using `parse_submission_date`.
-        let submission_date = parse_submission_date(&self.created_at);
+        // Convert the "created_at" field to UTC, keeping the submission even if it's malformed.
+        let submission_date = parse_submission_date(&self.created_at).unwrap_or_else(|| {
+            warn!(
+                "Submission {} has an unreadable created_at `{}`, storing it as submitted at {}",
+                self.id, self.created_at, UNKNOWN_CREATED_AT
+            );
+            UNKNOWN_CREATED_AT
+        });
 
         // Build the final Jotform struct, defaulting `status` to "Open".
         Jotform {
@@ -95,7 +104,7 @@ impl RawSubmission {
 fn get_str(answers: &HashMap<String, Answer>, q_id: &str) -> String {
     answers
         .get(q_id)
-        .expect(&format!("Question #{} missing; did the form change?", q_id))
+        .unwrap_or_else(|| panic!("Question #{} missing; did the form change?", q_id))
         .answer
         .as_ref()
         .expect("No answer value for question")
@@ -165,16 +174,13 @@ fn parse_priority_level(raw_priority: &str) -> String {
     }
 }
 
-/// Helper function to parse the raw submission date into a `SubmissionDate` struct.
-/// This is just to split the date and time into seperate fields.
+/// Helper function to parse the raw submission date into a UTC instant.
 ///
-/// This way either the date or time can be accessed independently
-/// and without any string manipulation.
-///
-/// This will usually only be used to access the date but still useful.
-fn parse_submission_date(raw_date: &str) -> SubmissionDate {
-    let date = raw_date.split(' ').next().unwrap().to_string();
-    let time = raw_date.split(' ').last().unwrap().to_string();
+/// Jotform sends `created_at` as a wall-clock time like `2024-12-04 13:39:24` with no
+/// offset, in the account's time zone, which is expected to be the site time zone.
+/// Returns `None` if the date can't be read.
+pub(super) fn parse_submission_date(raw_date: &str) -> Option<DateTime<Utc>> {
+    let local = NaiveDateTime::parse_from_str(raw_date.trim(), "%Y-%m-%d %H:%M:%S").ok()?;
 
-    SubmissionDate { date, time }
+    site_local_to_utc(local)
 }
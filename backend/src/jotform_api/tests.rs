@@ -1,8 +1,11 @@
 use super::*;
-use crate::models::{FullName, Jotform, SubmissionDate};
+use crate::api::jotform_handlers::JotformFilter;
+use crate::models::{FullName, Jotform, UNKNOWN_CREATED_AT};
 use crate::repo::jotform_repo;
 use crate::repo::jotform_repo::JotformRow;
+use chrono::{DateTime, Utc};
 use rocket::async_trait;
+use rocket::serde::json::serde_json;
 use rocket::tokio;
 use sqlx::SqlitePool;
 
@@ -26,11 +29,15 @@ impl JotformApiTrait for MockJotformApi {
 async fn setup_test_db() -> SqlitePool {
     let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
 
-    jotform_repo::create_jotform_tables(&pool).await.unwrap();
+    crate::db::setup_database(&pool).await.unwrap();
 
     pool
 }
 
+fn utc(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
 fn get_fake_jotforms() -> Vec<Jotform> {
     vec![
         Jotform {
@@ -39,10 +46,7 @@ fn get_fake_jotforms() -> Vec<Jotform> {
                 first: "Kenneth".to_string(),
                 last: "Smith".to_string(),
             },
-            created_at: SubmissionDate {
-                date: "2024-12-04".to_string(),
-                time: "13:39:24".to_string(),
-            },
+            created_at: utc("2024-12-04T13:39:24Z"),
             location: "Deep Space".to_string(),
             exhibit_name: "Electromagnetic Spectrum".to_string(),
             description: "It's peeling off the wall.".to_string(),
@@ -56,10 +60,7 @@ fn get_fake_jotforms() -> Vec<Jotform> {
                 first: "Kenneth".to_string(),
                 last: "Smith".to_string(),
             },
-            created_at: SubmissionDate {
-                date: "2024-11-22".to_string(),
-                time: "14:09:13".to_string(),
-            },
+            created_at: utc("2024-11-22T14:09:13Z"),
             location: "Space".to_string(),
             exhibit_name: "Moon Chair".to_string(),
             description: "One of the moons is cracked".to_string(),
@@ -81,7 +82,7 @@ async fn test_insert_jotform() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Verify that all jotforms were inserted
-    let results = jotform_repo::get_all_jotforms(&pool).await?.unwrap();
+    let results = jotform_repo::find_jotforms(&JotformFilter::default(), "", None, &pool).await?;
 
     assert_eq!(results.len(), jotforms.len());
     for jotform in &jotforms {
@@ -120,6 +121,7 @@ async fn test_update_jotform() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[tokio::test]
+#[allow(clippy::if_same_then_else)]
 async fn test_sync_jotforms_once() -> Result<(), Box<dyn std::error::Error>> {
     // Setup test database and insert initial jotforms
     let pool = setup_test_db().await;
@@ -136,10 +138,7 @@ async fn test_sync_jotforms_once() -> Result<(), Box<dyn std::error::Error>> {
                 first: "Lou".to_string(),
                 last: "Papai".to_string(),
             },
-            created_at: SubmissionDate {
-                date: "2024-12-27".to_string(),
-                time: "16:46:03".to_string(),
-            },
+            created_at: utc("2024-12-27T16:46:03Z"),
             location: "Solarium".to_string(),
             exhibit_name: "Solarium Signage".to_string(),
             description: "The sign for the Solarium needs re-mounted on the wall - maybe above the bridge? It explains the 3 parts of the Solarium.".to_string(),
@@ -153,10 +152,7 @@ async fn test_sync_jotforms_once() -> Result<(), Box<dyn std::error::Error>> {
                 first: "Lou".to_string(),
                 last: "Papai".to_string(),
             },
-            created_at: SubmissionDate {
-                date: "2024-12-27".to_string(),
-                time: "16:11:03".to_string(),
-            },
+            created_at: utc("2024-12-27T16:11:03Z"),
             location: "PoP Children's Museum".to_string(),
             exhibit_name: "Water Table".to_string(),
             description: "The PoP Water Table’s water is low at the end in the large circle. I think maybe the filter might need cleaned.".to_string(),
@@ -173,7 +169,7 @@ async fn test_sync_jotforms_once() -> Result<(), Box<dyn std::error::Error>> {
     sync_jotforms_once(&pool, &mock_api).await?;
 
     // Verify that all jotforms (initial + new) are in the database
-    let results = jotform_repo::get_all_jotforms(&pool).await?.unwrap();
+    let results = jotform_repo::find_jotforms(&JotformFilter::default(), "", None, &pool).await?;
 
     // Check that all initial and new jotforms are present
     let all_jotforms: Vec<Jotform> = initial_jotforms
@@ -199,3 +195,176 @@ async fn test_sync_jotforms_once() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_parse_submission_date_follows_daylight_saving() {
+    // Central time is UTC-5 in summer and UTC-6 in winter
+    assert_eq!(
+        raw_submission::parse_submission_date("2024-07-01 10:30:00"),
+        Some(utc("2024-07-01T15:30:00Z"))
+    );
+    assert_eq!(
+        raw_submission::parse_submission_date("2024-12-04 13:39:24"),
+        Some(utc("2024-12-04T19:39:24Z"))
+    );
+    assert_eq!(raw_submission::parse_submission_date("yesterday"), None);
+
+    // A submission with an unreadable date is kept, dated so it's plainly unknown
+    let answer = |value: &str| serde_json::json!({ "answer": value });
+    let raw: raw_submission::RawSubmission = serde_json::from_value(serde_json::json!({
+        "id": "1",
+        "created_at": "yesterday",
+        "answers": {
+            "4": { "answer": { "first": "Kenneth", "last": "Smith" } },
+            "5": answer("Space"),
+            "6": answer("Moon Chair"),
+            "7": answer("Cracked"),
+            "8": answer("High"),
+            "9": answer("Exhibits"),
+        },
+    }))
+    .unwrap();
+    assert_eq!(raw.to_jotform().created_at, UNKNOWN_CREATED_AT);
+}
+
+#[tokio::test]
+async fn test_split_created_at_is_migrated() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = SqlitePool::connect("sqlite::memory:").await?;
+
+    // The jotforms table as it looked when dates and times were stored apart
+    sqlx::query(
+        "CREATE TABLE jotforms (
+            id TEXT PRIMARY KEY,
+            submitter_first_name TEXT NOT NULL,
+            submitter_last_name TEXT NOT NULL,
+            created_at_date TEXT NOT NULL,
+            created_at_time TEXT NOT NULL,
+            location TEXT NOT NULL,
+            exhibit_name TEXT NOT NULL,
+            description TEXT NOT NULL,
+            priority_level TEXT NOT NULL,
+            department TEXT NOT NULL,
+            status TEXT NOT NULL
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO jotforms VALUES ('1', 'Kenneth', 'Smith', '2024-07-01', '10:30:00',
+                                      'Space', 'Moon Chair', 'Cracked', 'High', 'Exhibits',
+                                      'Open'),
+                                     ('2', 'Kenneth', 'Smith', '07/01/2024', '10:30:00',
+                                      'Space', 'Moon Chair', 'Cracked', 'High', 'Exhibits',
+                                      'Open')",
+    )
+    .execute(&pool)
+    .await?;
+
+    jotform_repo::create_jotform_tables(&mut *pool.acquire().await?).await?;
+    // Running it again must be harmless
+    jotform_repo::create_jotform_tables(&mut *pool.acquire().await?).await?;
+
+    let jotform = jotform_repo::get_jotform("1".to_string(), &pool)
+        .await?
+        .unwrap();
+    assert_eq!(jotform.created_at, utc("2024-07-01T15:30:00Z"));
+    assert!(
+        !crate::db::column_exists(&mut *pool.acquire().await?, "jotforms", "created_at_date")
+            .await?
+    );
+
+    // A date that doesn't parse is kept as unknown rather than made up
+    let jotform = jotform_repo::get_jotform("2".to_string(), &pool)
+        .await?
+        .unwrap();
+    assert_eq!(jotform.created_at, UNKNOWN_CREATED_AT);
+
+    // With every row filled in, the new column can't be left empty
+    let not_null: bool = sqlx::query_scalar(
+        "SELECT \"notnull\" FROM pragma_table_info('jotforms') WHERE name = 'created_at'",
+    )
+    .fetch_one(&pool)
+    .await?;
+    assert!(not_null);
+    assert!(crate::db::column_exists(&mut *pool.acquire().await?, "jotforms", "deleted_at").await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_skips_trashed_and_purged_tickets() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = SqlitePool::connect("sqlite::memory:").await?;
+    crate::db::setup_database(&pool).await?;
+
+    let jotforms = get_fake_jotforms();
+    for jotform in &jotforms {
+        jotform_repo::insert_jotform(jotform, &pool).await?;
+    }
+
+    // One ticket is purged from the trash, the other is still in it
+    jotform_repo::delete_jotform(jotforms[0].id.clone(), &pool).await?;
+    crate::repo::trash_repo::purge_expired(Utc::now(), &pool).await?;
+    jotform_repo::delete_jotform(jotforms[1].id.clone(), &pool).await?;
+    assert!(matches!(
+        jotform_repo::delete_jotform(jotforms[1].id.clone(), &pool).await,
+        Err(sqlx::Error::RowNotFound)
+    ));
+
+    sync_jotforms_once(&pool, &MockJotformApi::new(jotforms.clone())).await?;
+
+    assert!(
+        jotform_repo::find_jotforms(&JotformFilter::default(), "", None, &pool)
+            .await?
+            .is_empty()
+    );
+
+    let stored = sqlx::query_scalar::<_, String>("SELECT id FROM jotforms")
+        .fetch_all(&pool)
+        .await?;
+    assert_eq!(stored, vec![jotforms[1].id.clone()]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sync_only_alerts_tickets_newer_than_any_stored(
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let alerted = || {
+        sqlx::query_scalar::<_, String>("SELECT ticket_id FROM ticket_alerts ORDER BY ticket_id")
+            .fetch_all(&pool)
+    };
+
+    // Everything brought in by the first sync is history, however urgent
+    let history = get_fake_jotforms();
+    sync_jotforms_once(&pool, &MockJotformApi::new(history.clone())).await?;
+    assert!(alerted().await?.is_empty());
+
+    let ticket = |id: &str, created_at: &str, priority_level: &str| Jotform {
+        id: id.to_string(),
+        created_at: utc(created_at),
+        priority_level: priority_level.to_string(),
+        ..history[0].clone()
+    };
+    let mut submissions = history.clone();
+    submissions.extend([
+        ticket("7000000000000000001", "2024-12-20T09:00:00Z", "High"),
+        ticket("7000000000000000002", "2024-12-20T09:05:00Z", "Medium"),
+        // Newly seen, but older than what's stored, as when tickets are backfilled
+        ticket("7000000000000000003", "2024-11-01T09:00:00Z", "High"),
+        // When it was submitted isn't known, so neither is whether it's new
+        Jotform {
+            created_at: UNKNOWN_CREATED_AT,
+            ..ticket("7000000000000000004", "2024-12-20T09:00:00Z", "High")
+        },
+    ]);
+    sync_jotforms_once(&pool, &MockJotformApi::new(submissions.clone())).await?;
+    assert_eq!(alerted().await?, vec!["7000000000000000001"]);
+
+    // Syncing the same tickets again doesn't alert them twice
+    sync_jotforms_once(&pool, &MockJotformApi::new(submissions)).await?;
+    assert_eq!(alerted().await?, vec!["7000000000000000001"]);
+
+    Ok(())
+}
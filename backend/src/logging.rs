@@ -0,0 +1,208 @@
+use crate::metrics;
+use chrono::{SecondsFormat, Utc};
+use log::kv::{self, Key, Value, VisitSource};
+use log::{log, Level, Record};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::json::serde_json::{self, Map, Value as Json};
+use rocket::{Data, Request, Response};
+use std::future::Future;
+use std::io::Write;
+use std::time::Instant;
+
+/// The header a request ID is read from, and echoed back in.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Log fields that can hold personal details. Their values are never written out.
+const REDACTED_FIELDS: [&str; 7] = [
+    "name",
+    "submitter_name",
+    "contact_name",
+    "email",
+    "contact_email",
+    "phone",
+    "contact_phone",
+];
+
+rocket::tokio::task_local! {
+    static SPAN: LogSpan;
+}
+
+/// A unit of background work, such as one Jotform sync, that its logs are tagged with.
+#[derive(Debug, Clone)]
+pub struct LogSpan {
+    pub name: &'static str,
+    pub id: String,
+}
+
+/// A random ID for a request or span, as 16 hex digits.
+pub fn new_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// Sets up logging, with one JSON object per line.
+///
+/// `filter` uses `env_logger`'s syntax, e.g. `info,sqlx=warn`, and is followed by anything
+/// in `RUST_LOG`, so a module's level can be changed for one run without editing config.
+pub fn init(filter: &str) {
+    env_logger::Builder::new()
+        .parse_filters(filter)
+        .parse_env("RUST_LOG")
+        .format(|buf, record| {
+            let span = SPAN.try_with(LogSpan::clone).ok();
+            writeln!(buf, "{}", json_line(record, span.as_ref()))
+        })
+        .init();
+}
+
+/// Runs `future` with its logs tagged with a new span called `name`.
+pub async fn in_span<F: Future>(name: &'static str, future: F) -> F::Output {
+    SPAN.scope(LogSpan { name, id: new_id() }, future).await
+}
+
+/// Formats a log record as a JSON object.
+pub fn json_line(record: &Record, span: Option<&LogSpan>) -> Json {
+    let mut line = Map::new();
+    line.insert(
+        "ts".into(),
+        Utc::now()
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+            .into(),
+    );
+    line.insert("level".into(), record.level().as_str().into());
+    line.insert("target".into(), record.target().into());
+    line.insert("msg".into(), record.args().to_string().into());
+    if let Some(span) = span {
+        line.insert("span".into(), span.name.into());
+        line.insert("span_id".into(), span.id.clone().into());
+    }
+
+    // A field that fails to format is left out rather than losing the whole line
+    let _ = record.key_values().visit(&mut Fields(&mut line));
+
+    Json::Object(line)
+}
+
+/// Copies a record's key-value pairs into a JSON object, redacting personal fields.
+struct Fields<'a>(&'a mut Map<String, Json>);
+
+impl<'kvs> VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if REDACTED_FIELDS.contains(&key.as_str()) {
+            Json::from("[redacted]")
+        } else if let Some(v) = value.to_bool() {
+            Json::from(v)
+        } else if let Some(v) = value.to_u64() {
+            Json::from(v)
+        } else if let Some(v) = value.to_i64() {
+            Json::from(v)
+        } else if let Some(v) = value.to_f64() {
+            serde_json::Number::from_f64(v).map_or(Json::Null, Json::Number)
+        } else {
+            Json::from(value.to_string())
+        };
+        self.0.insert(key.as_str().to_string(), value);
+        Ok(())
+    }
+}
+
+/// The ID of the request being handled.
+///
+/// Taken from the request's `X-Request-Id` header when it has a usable one, so a request
+/// can be followed from the app through to the logs, and generated otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Uses `header` as the ID if it's short and safe to log, otherwise makes a new one.
+    pub fn from_header(header: Option<&str>) -> Self {
+        match header {
+            Some(id)
+                if (1..=64).contains(&id.len())
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) =>
+            {
+                RequestId(id.to_string())
+            }
+            _ => RequestId(new_id()),
+        }
+    }
+}
+
+/// When a request came in, for timing it.
+struct RequestStart(Instant);
+
+/// The ID of `request`, read from its header or generated the first time it's asked for.
+pub fn request_id<'r>(request: &'r Request<'_>) -> &'r RequestId {
+    request.local_cache(|| RequestId::from_header(request.headers().get_one(REQUEST_ID_HEADER)))
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for &'r RequestId {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(request_id(request))
+    }
+}
+
+/// Gives every request an ID, returns it in the `X-Request-Id` response header, and logs
+/// one line per request with its method, path, matched route, status and latency.
+///
+/// Query strings aren't logged, since searches can include names.
+pub struct RequestTracing;
+
+#[rocket::async_trait]
+impl Fairing for RequestTracing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request Tracing",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| RequestStart(Instant::now()));
+        request_id(request);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let id = request_id(request);
+        let latency = request
+            .local_cache(|| RequestStart(Instant::now()))
+            .0
+            .elapsed();
+        response.set_header(Header::new(REQUEST_ID_HEADER, id.0.clone()));
+
+        let status = response.status().code;
+        let method = request.method().as_str();
+        let path = request.uri().path().as_str();
+        let route = request.route().map(|r| r.uri.to_string());
+        metrics::record_request(
+            method,
+            route.as_deref().unwrap_or("unmatched"),
+            status,
+            latency,
+        );
+
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        let level = match status {
+            500.. => Level::Error,
+            400.. => Level::Warn,
+            _ => Level::Info,
+        };
+
+        log!(
+            level,
+            request_id = id.0.as_str(),
+            method = method,
+            path = path,
+            route = route.as_deref().unwrap_or(""),
+            status = status,
+            latency_ms = latency_ms;
+            "{} {} {}", method, path, status
+        );
+    }
+}
@@ -1,35 +1,69 @@
 mod api;
+mod backup;
+mod bug_reports;
+mod config;
 mod db;
+#[cfg(feature = "dev-tools")]
 mod dev;
 mod errors;
+mod events;
+mod exhibit_import;
+mod export;
 mod jotform_api;
+mod logging;
+mod metrics;
 mod models;
+mod notifications;
 mod repo;
-
+mod retry;
+mod snapshot;
+#[cfg(test)]
+mod test_server;
+mod time_zone;
+mod webhooks;
+
+use config::AppConfig;
 use db::{create_pool, setup_database, DbPool};
 use dotenv::dotenv;
 use log::{error, info};
 use rocket::tokio::time::{sleep, Duration};
 use rocket::{catchers, launch, routes};
 use rocket::{Orbit, Rocket};
-use rocket_cors::{AllowedHeaders, AllowedMethods, AllowedOrigins, CorsOptions};
-use std::path::Path;
+use rocket_cors::{AllowedHeaders, AllowedMethods, CorsOptions};
 
 #[launch]
 async fn rocket() -> _ {
     dotenv().ok();
 
-    // Initialize the logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let figment = AppConfig::figment(rocket::Config::figment());
+    let config = AppConfig::load(&figment);
+
+    // Initialize the logger, with the default levels if the config can't be used
+    let log_filter = match &config {
+        Ok(config) => config.log_filter.clone(),
+        Err(_) => AppConfig::default().log_filter,
+    };
+    logging::init(&log_filter);
     info!("Starting Rocket server...");
 
-    // Check if images directory exists; if not, create it
-    if !Path::new("images").exists() {
-        std::fs::create_dir("images").expect("Failed to create images directory");
-    }
+    let config = match config {
+        Ok(config) => config,
+        Err(errors) => {
+            for e in errors {
+                error!("Invalid configuration: {}", e);
+            }
+            std::process::exit(1);
+        }
+    };
+
+    time_zone::set_site_time_zone(config.site_time_zone());
+    info!("Site time zone is {}", time_zone::site_time_zone());
+
+    // Create the images directory if it doesn't exist
+    std::fs::create_dir_all(&config.images_dir).expect("Failed to create images directory");
 
     // Initialize the database connection pool
-    let db_pool = create_pool("exhibits.db")
+    let db_pool = create_pool(&config.database_path.to_string_lossy())
         .await
         .expect("Failed to create pool");
 
@@ -45,7 +79,7 @@ async fn rocket() -> _ {
         .collect();
 
     let cors = CorsOptions::default()
-        .allowed_origins(AllowedOrigins::all())
+        .allowed_origins(config.cors_allowed_origins())
         .allowed_methods(allowed_methods)
         .allowed_headers(AllowedHeaders::some(&[
             "Authorization",
@@ -54,60 +88,72 @@ async fn rocket() -> _ {
             "Origin",
             "X-Requested-With",
             "Access-Control-Allow-Origin",
+            "If-Match",
+            "X-Confirm-Token",
+            logging::REQUEST_ID_HEADER,
         ]))
+        .expose_headers(
+            [
+                "ETag",
+                logging::REQUEST_ID_HEADER,
+                "Deprecation",
+                "Sunset",
+                "Link",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        )
         .allow_credentials(false)
         .to_cors()
         .expect("Error creating CORS fairing");
 
-    rocket::build()
+    let images = rocket::fs::FileServer::from(&config.images_dir);
+    let deprecations = config.deprecations();
+
+    let rocket = rocket::custom(figment)
         .manage(db_pool) // Inject the connection pool into Rocket's state
+        .manage(config.trash_settings())
+        .manage(config.backup_settings())
+        .manage(config.readiness_settings())
+        .manage(config)
+        .attach(logging::RequestTracing)
         .attach(cors) // Attach the CORS fairing
+        .attach(deprecations)
         .attach(JotformFairing)
         .attach(BackupFairing)
+        .attach(MaintenanceFairing)
+        .attach(TrashPurgeFairing)
+        .attach(WebhookFairing)
+        .attach(EmailFairing)
+        .attach(BugReportFairing)
+        .mount(api::API_BASE, api::routes())
         .mount(
-            "/",
+            api::API_BASE,
             routes![
-                api::github_handlers::report_bug_handler,
-                api::exhibit_handlers::get_exhibit_handler,
-                api::exhibit_handlers::list_exhibits_handler,
-                api::exhibit_handlers::handle_random_exhibit,
-                api::exhibit_handlers::get_exhibit_note_handler,
-                api::exhibit_handlers::list_exhibit_notes_handler,
-                api::exhibit_handlers::create_exhibit_handler,
-                api::exhibit_handlers::create_exhibit_note_handler,
-                api::exhibit_handlers::update_exhibit_handler,
-                api::exhibit_handlers::add_existing_part_handler,
-                api::exhibit_handlers::delete_exhibit_handler,
-                api::exhibit_handlers::change_exhibit_status_handler,
-                api::exhibit_handlers::delete_exhibit_note_handler,
-                api::part_handlers::get_part_handler,
-                api::part_handlers::list_parts_handler,
-                api::part_handlers::get_part_note_handler,
-                api::part_handlers::list_part_notes_handler,
-                api::part_handlers::get_parts_by_ids_handler,
-                api::part_handlers::update_part_handler,
-                api::part_handlers::create_part_handler,
-                api::part_handlers::create_part_note_handler,
-                api::part_handlers::delete_part_handler,
-                api::part_handlers::delete_part_note_handler,
-                api::jotform_handlers::list_jotforms_handler,
-                api::jotform_handlers::get_jotform_handler,
-                api::jotform_handlers::change_status_handler,
-                api::jotform_handlers::delete_jotform_handler,
-                api::development_util_handlers::handle_reset_db,
-                api::development_util_handlers::create_dummy_exhibits_handler,
+                api::openapi_handlers::openapi_handler,
+                api::openapi_handlers::docs_handler
             ],
         )
-        .mount("/images", rocket::fs::FileServer::from("images"))
+        // The desktop app's older releases call the API here
+        .mount("/", api::routes())
+        .mount("/images", images)
         .register(
             "/",
             catchers![
                 errors::not_found,
                 errors::handle_invalid_request_body,
+                errors::handle_unprocessable_request_body,
                 errors::handle_method_not_allowed,
-                errors::internal_server_error
+                errors::internal_server_error,
+                errors::other_error
             ],
-        )
+        );
+
+    #[cfg(feature = "dev-tools")]
+    let rocket = rocket.mount(api::DEV_BASE, api::dev_routes());
+
+    rocket
 }
 
 struct JotformFairing;
@@ -121,6 +167,55 @@ impl rocket::fairing::Fairing for JotformFairing {
         }
     }
 
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let (db_pool, config) = match (rocket.state::<DbPool>(), rocket.state::<AppConfig>()) {
+            (Some(pool), Some(config)) => (pool.clone(), config),
+            _ => {
+                error!("Database pool or config not found in Rocket state");
+                return;
+            }
+        };
+
+        let Some(credentials) = config.jotform() else {
+            info!("Jotform sync is turned off, jotform_api_key and jotform_form_id aren't set");
+            return;
+        };
+
+        // Create the api client
+        let jotform_api_client = jotform_api::JotformApi::new(
+            credentials.api_key,
+            credentials.form_id,
+            config.jotform_base_url.clone(),
+        );
+        let interval = config.jotform_sync_interval();
+
+        // Spawn the synchronization task that syncs Jotform data on the configured interval
+        rocket::tokio::spawn(async move {
+            loop {
+                match jotform_api::sync_jotforms_once(&db_pool, &jotform_api_client).await {
+                    Ok(_) => info!("Successfully synced Jotform data"),
+                    Err(e) => error!("Failed to sync Jotform data: {:?}", e),
+                }
+
+                sleep(interval).await;
+            }
+        });
+
+        info!("Jotform synchronization task started");
+    }
+}
+
+struct MaintenanceFairing;
+
+#[rocket::async_trait]
+impl rocket::fairing::Fairing for MaintenanceFairing {
+    fn info(&self) -> rocket::fairing::Info {
+        rocket::fairing::Info {
+            name: "Maintenance Tickets",
+            kind: rocket::fairing::Kind::Liftoff,
+        }
+    }
+
     async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
         let db_pool = match rocket.state::<DbPool>() {
             Some(pool) => pool.clone(),
@@ -130,45 +225,68 @@ impl rocket::fairing::Fairing for JotformFairing {
             }
         };
 
-        // setup variables for Jotform API
-        let jotform_api_key =
-            std::env::var("JOTFORM_API_KEY").expect("JOTFORM_API_KEY env not set");
+        // Open tickets for maintenance tasks as they come due, checking once an hour
+        rocket::tokio::spawn(async move {
+            loop {
+                let today = time_zone::site_today();
+                match repo::maintenance_repo::create_due_tickets(today, &db_pool).await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Created {} maintenance tickets", count),
+                    Err(e) => error!("Failed to create maintenance tickets: {:?}", e),
+                }
 
-        let jotform_form_id =
-            std::env::var("JOTFORM_FORM_ID").expect("JOTFORM_FORM_ID env not set");
+                sleep(Duration::from_secs(60 * 60)).await;
+            }
+        });
 
-        let jotform_base_url = "https://api.jotform.com".to_string();
+        info!("Maintenance ticket task started");
+    }
+}
 
-        // Create the api client
-        let jotform_api_client =
-            jotform_api::JotformApi::new(jotform_api_key, jotform_form_id, jotform_base_url);
+struct TrashPurgeFairing;
 
-        let pool_clone = db_pool.clone();
-        let api_clone = jotform_api_client;
+#[rocket::async_trait]
+impl rocket::fairing::Fairing for TrashPurgeFairing {
+    fn info(&self) -> rocket::fairing::Info {
+        rocket::fairing::Info {
+            name: "Trash Purge",
+            kind: rocket::fairing::Kind::Liftoff,
+        }
+    }
 
-        // Spawn the synchronization task that runs every 30 minutes and syncs Jotform data
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let (db_pool, retention) = match (
+            rocket.state::<DbPool>(),
+            rocket.state::<api::trash_handlers::TrashSettings>(),
+        ) {
+            (Some(pool), Some(settings)) => (pool.clone(), settings.retention),
+            _ => {
+                error!("Database pool or trash settings not found in Rocket state");
+                return;
+            }
+        };
+
+        // Permanently delete records that have been in the trash too long, once a day
         rocket::tokio::spawn(async move {
             loop {
-                match jotform_api::sync_jotforms_once(&pool_clone, &api_clone).await {
-                    Ok(_) => info!("Successfully synced Jotform data"),
-                    Err(e) => error!("Failed to sync Jotform data: {:?}", e),
+                let before = chrono::Utc::now() - retention;
+                match repo::trash_repo::purge_expired(before, &db_pool).await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Purged {} records from the trash", count),
+                    Err(e) => error!("Failed to purge the trash: {:?}", e),
                 }
 
-                // Sleep for 30 minutes which is 1800 seconds
-                sleep(Duration::from_secs(1800)).await;
+                sleep(Duration::from_secs(24 * 60 * 60)).await;
             }
         });
 
-        info!("Jotform synchronization task started");
+        info!(
+            "Trash purge task started, keeping deleted records for {} days",
+            retention.num_days()
+        );
     }
 }
 
-use chrono::Local;
-use std::fs;
-
-const BACKUP_INTERVAL_IN_HOURS: u64 = 48;
-const NUMBER_OF_BACKUPS_TO_KEEP: usize = 10;
-
 struct BackupFairing;
 
 #[rocket::async_trait]
@@ -180,62 +298,143 @@ impl rocket::fairing::Fairing for BackupFairing {
         }
     }
 
-    async fn on_liftoff(&self, _rocket: &Rocket<Orbit>) {
-        let db_path = "exhibits.db".to_string();
-        let backups_dir = ".backups".to_string();
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let (db_pool, settings, images_dir) = match (
+            rocket.state::<DbPool>(),
+            rocket.state::<backup::BackupSettings>(),
+            rocket.state::<AppConfig>(),
+        ) {
+            (Some(pool), Some(settings), Some(config)) => {
+                (pool.clone(), settings.clone(), config.images_dir.clone())
+            }
+            _ => {
+                error!("Database pool, backup settings or config not found in Rocket state");
+                return;
+            }
+        };
 
+        let interval = settings.interval;
         rocket::tokio::spawn(async move {
             loop {
-                // Perform the backup
-                if let Err(e) = backup_database(&db_path, &backups_dir) {
-                    error!("Failed to back up database: {:?}", e);
+                match backup::create_backup(&db_pool, &settings, &images_dir).await {
+                    Ok(backup) => info!("Database backed up to {}", backup.name),
+                    Err(e) => error!("Failed to back up database: {}", e),
                 }
 
-                // Sleep for 48 hours (every other day)
-                sleep(Duration::from_secs(BACKUP_INTERVAL_IN_HOURS * 60 * 60)).await;
+                sleep(interval).await;
             }
         });
 
-        info!("Database backup task started");
+        info!(
+            "Database backup task started, backing up every {} hours",
+            interval.as_secs() / 3600
+        );
     }
 }
 
-fn backup_database(db_path: &str, backups_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
-    // Ensure the backups directory exists
-    if !Path::new(backups_dir).exists() {
-        fs::create_dir(backups_dir)?;
+struct WebhookFairing;
+
+#[rocket::async_trait]
+impl rocket::fairing::Fairing for WebhookFairing {
+    fn info(&self) -> rocket::fairing::Info {
+        rocket::fairing::Info {
+            name: "Webhook Delivery",
+            kind: rocket::fairing::Kind::Liftoff,
+        }
     }
 
-    // Create a timestamp for the backup file
-    let timestamp = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-    let backup_file_name = format!("{}/backup_{}.db", backups_dir, timestamp);
-
-    // Copy the database file to the backup location
-    fs::copy(db_path, &backup_file_name)?;
-    info!("Database backed up to: {}", backup_file_name);
-
-    // Clean up old backups (keep only the last 10)
-    let mut backups: Vec<_> = fs::read_dir(backups_dir)?
-        .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let path = entry.path();
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "db") {
-                Some((entry.metadata().ok()?.modified().ok()?, path))
-            } else {
-                None
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let Some(db_pool) = rocket.state::<DbPool>().cloned() else {
+            error!("Database pool not found in Rocket state");
+            return;
+        };
+
+        // Send change events to webhooks as they happen, retrying failed deliveries
+        rocket::tokio::spawn(webhooks::run(db_pool));
+
+        info!("Webhook delivery task started");
+    }
+}
+
+struct EmailFairing;
+
+#[rocket::async_trait]
+impl rocket::fairing::Fairing for EmailFairing {
+    fn info(&self) -> rocket::fairing::Info {
+        rocket::fairing::Info {
+            name: "Email Notifications",
+            kind: rocket::fairing::Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let (db_pool, config) = match (rocket.state::<DbPool>(), rocket.state::<AppConfig>()) {
+            (Some(pool), Some(config)) => (pool.clone(), config),
+            _ => {
+                error!("Database pool or config not found in Rocket state");
+                return;
+            }
+        };
+
+        let Some(settings) = config.email() else {
+            info!("Email notifications are turned off, smtp_host isn't set");
+            return;
+        };
+
+        let notifier = match notifications::Notifier::new(&settings) {
+            Ok(notifier) => std::sync::Arc::new(notifier),
+            Err(e) => {
+                error!("Failed to set up email notifications: {}", e);
+                return;
             }
-        })
-        .collect();
+        };
 
-    // Sort backups by modification time (oldest first)
-    backups.sort_by(|a, b| a.0.cmp(&b.0));
+        // Email new high-priority tickets as they come in, and a digest of open work daily
+        rocket::tokio::spawn(notifications::run(db_pool, notifier, settings.digest_hour));
 
-    // Delete backups beyond the last NUMBER_OF_BACKUPS_TO_KEEP
-    while backups.len() > NUMBER_OF_BACKUPS_TO_KEEP {
-        let (_, oldest_backup) = backups.remove(0);
-        fs::remove_file(&oldest_backup)?;
-        info!("Deleted old backup: {:?}", oldest_backup);
+        info!(
+            "Email notification task started, sending digests at {}:00",
+            settings.digest_hour
+        );
     }
+}
 
-    Ok(())
+struct BugReportFairing;
+
+#[rocket::async_trait]
+impl rocket::fairing::Fairing for BugReportFairing {
+    fn info(&self) -> rocket::fairing::Info {
+        rocket::fairing::Info {
+            name: "Bug Report Forwarding",
+            kind: rocket::fairing::Kind::Liftoff,
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let (db_pool, config) = match (rocket.state::<DbPool>(), rocket.state::<AppConfig>()) {
+            (Some(pool), Some(config)) => (pool.clone(), config),
+            _ => {
+                error!("Database pool or config not found in Rocket state");
+                return;
+            }
+        };
+
+        let Some(github) = config.github() else {
+            info!("Bug reports will be stored but not filed, GitHub isn't configured");
+            return;
+        };
+
+        let github = match bug_reports::GitHubClient::new(github) {
+            Ok(github) => github,
+            Err(e) => {
+                error!("Failed to create the GitHub client: {}", e);
+                return;
+            }
+        };
+
+        // File bug reports on GitHub as they come in, retrying ones that fail
+        rocket::tokio::spawn(bug_reports::run(db_pool, github));
+
+        info!("Bug report forwarding task started");
+    }
 }
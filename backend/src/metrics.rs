@@ -0,0 +1,274 @@
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Every metric name starts with this.
+const PREFIX: &str = "exhibit_manager";
+
+/// Upper bounds, in seconds, of the buckets request and sync durations are counted in.
+const BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// The app's metrics, recorded as things happen and read by GET /metrics.
+static METRICS: Metrics = Metrics {
+    requests: Mutex::new(BTreeMap::new()),
+    jotform_syncs: Mutex::new(Histogram::new()),
+    jotform_sync_failures: AtomicU64::new(0),
+    jotform_last_success: AtomicU64::new(0),
+    jotform_limit_left: AtomicI64::new(-1),
+    backups: AtomicU64::new(0),
+    backup_failures: AtomicU64::new(0),
+    backup_last_success: AtomicU64::new(0),
+    backup_last_size: AtomicU64::new(0),
+};
+
+struct Metrics {
+    /// Request durations by method, route and status.
+    requests: Mutex<BTreeMap<(String, String, u16), Histogram>>,
+    jotform_syncs: Mutex<Histogram>,
+    jotform_sync_failures: AtomicU64,
+    /// Unix time of the last successful sync, or 0 if there hasn't been one.
+    jotform_last_success: AtomicU64,
+    /// Jotform API calls left today, or -1 until the first sync.
+    jotform_limit_left: AtomicI64,
+    backups: AtomicU64,
+    backup_failures: AtomicU64,
+    /// Unix time of the last successful backup, or 0 if there hasn't been one.
+    backup_last_success: AtomicU64,
+    backup_last_size: AtomicU64,
+}
+
+/// Counts of observations falling in each of `BUCKETS`.
+#[derive(Debug, Clone, PartialEq)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Histogram {
+            buckets: [0; BUCKETS.len()],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        if let Some(bucket) = BUCKETS.iter().position(|&le| seconds <= le) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+/// Records a handled request. `route` should be the matched route's template, such as
+/// `/exhibits/<id>`, so each exhibit doesn't get its own series.
+pub fn record_request(method: &str, route: &str, status: u16, elapsed: Duration) {
+    let mut requests = METRICS.requests.lock().unwrap_or_else(|e| e.into_inner());
+    requests
+        .entry((method.to_string(), route.to_string(), status))
+        .or_insert_with(Histogram::new)
+        .observe(elapsed);
+}
+
+/// Records how a Jotform sync went.
+pub fn record_jotform_sync(elapsed: Duration, succeeded: bool) {
+    let mut syncs = METRICS
+        .jotform_syncs
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    syncs.observe(elapsed);
+
+    if succeeded {
+        METRICS
+            .jotform_last_success
+            .store(unix_now(), Ordering::Relaxed);
+    } else {
+        METRICS
+            .jotform_sync_failures
+            .fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// When a Jotform sync last succeeded, if one has since the app started.
+pub fn jotform_last_success() -> Option<DateTime<Utc>> {
+    match METRICS.jotform_last_success.load(Ordering::Relaxed) {
+        0 => None,
+        secs => DateTime::from_timestamp(secs as i64, 0),
+    }
+}
+
+/// Records the Jotform API calls left, as reported with each response.
+pub fn record_jotform_limit_left(limit_left: u32) {
+    METRICS
+        .jotform_limit_left
+        .store(limit_left.into(), Ordering::Relaxed);
+}
+
+/// Records a backup that was taken and verified.
+pub fn record_backup_succeeded(size_bytes: u64) {
+    METRICS.backups.fetch_add(1, Ordering::Relaxed);
+    METRICS
+        .backup_last_success
+        .store(unix_now(), Ordering::Relaxed);
+    METRICS
+        .backup_last_size
+        .store(size_bytes, Ordering::Relaxed);
+}
+
+pub fn record_backup_failed() {
+    METRICS.backups.fetch_add(1, Ordering::Relaxed);
+    METRICS.backup_failures.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Metrics in the Prometheus text format.
+#[derive(Debug, Default)]
+pub struct Exposition(String);
+
+impl Exposition {
+    /// Starts a metric, which its samples must follow. `kind` is `counter`, `gauge` or
+    /// `histogram`.
+    pub fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.0, "# HELP {}_{} {}", PREFIX, name, help);
+        let _ = writeln!(self.0, "# TYPE {}_{} {}", PREFIX, name, kind);
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let _ = write!(self.0, "{}_{}", PREFIX, name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+                .collect();
+            let _ = write!(self.0, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.0, " {}", value);
+    }
+
+    fn histogram(&mut self, name: &str, labels: &[(&str, &str)], histogram: &Histogram) {
+        let mut cumulative = 0;
+        for (le, count) in BUCKETS.iter().zip(histogram.buckets) {
+            cumulative += count;
+            let le = le.to_string();
+            let labels = [labels, &[("le", le.as_str())]].concat();
+            self.sample(&format!("{}_bucket", name), &labels, cumulative as f64);
+        }
+        let labels_inf = [labels, &[("le", "+Inf")]].concat();
+        self.sample(
+            &format!("{}_bucket", name),
+            &labels_inf,
+            histogram.count as f64,
+        );
+        self.sample(&format!("{}_sum", name), labels, histogram.sum);
+        self.sample(&format!("{}_count", name), labels, histogram.count as f64);
+    }
+
+    /// Adds everything recorded since the app started.
+    pub fn recorded(&mut self) {
+        let requests = METRICS
+            .requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        self.family(
+            "http_request_duration_seconds",
+            "histogram",
+            "How long requests took to handle, by method, route and status.",
+        );
+        for ((method, route, status), histogram) in &requests {
+            let status = status.to_string();
+            self.histogram(
+                "http_request_duration_seconds",
+                &[("method", method), ("route", route), ("status", &status)],
+                histogram,
+            );
+        }
+
+        let syncs = METRICS
+            .jotform_syncs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        self.family(
+            "jotform_sync_duration_seconds",
+            "histogram",
+            "How long Jotform syncs took, whether or not they succeeded.",
+        );
+        self.histogram("jotform_sync_duration_seconds", &[], &syncs);
+        self.counter(
+            "jotform_sync_failures_total",
+            "Jotform syncs that failed.",
+            METRICS.jotform_sync_failures.load(Ordering::Relaxed),
+        );
+        self.gauge(
+            "jotform_last_success_timestamp_seconds",
+            "Unix time of the last successful Jotform sync, or 0 if there hasn't been one.",
+            METRICS.jotform_last_success.load(Ordering::Relaxed) as f64,
+        );
+        let limit_left = METRICS.jotform_limit_left.load(Ordering::Relaxed);
+        if limit_left >= 0 {
+            self.gauge(
+                "jotform_api_limit_left",
+                "Jotform API calls left today, as of the last sync.",
+                limit_left as f64,
+            );
+        }
+
+        self.counter(
+            "backups_total",
+            "Backups attempted, scheduled or on demand.",
+            METRICS.backups.load(Ordering::Relaxed),
+        );
+        self.counter(
+            "backup_failures_total",
+            "Backups that failed or didn't pass their integrity check.",
+            METRICS.backup_failures.load(Ordering::Relaxed),
+        );
+        self.gauge(
+            "backup_last_success_timestamp_seconds",
+            "Unix time of the last successful backup, or 0 if there hasn't been one.",
+            METRICS.backup_last_success.load(Ordering::Relaxed) as f64,
+        );
+        self.gauge(
+            "backup_last_size_bytes",
+            "Size of the last successful backup.",
+            METRICS.backup_last_size.load(Ordering::Relaxed) as f64,
+        );
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, "gauge", help);
+        self.sample(name, &[], value);
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: u64) {
+        self.family(name, "counter", help);
+        self.sample(name, &[], value as f64);
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// Escapes a label value, as the text format requires.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
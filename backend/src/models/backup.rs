@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+/// A database backup in the backups directory.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema, Type)]
+pub struct BackupInfo {
+    /// The backup's file name, used to download or restore it.
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+    /// Whether the backup has a copy of the images directory as well as the database.
+    pub includes_images: bool,
+}
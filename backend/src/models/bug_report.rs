@@ -1,7 +1,10 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
 use validator::Validate;
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Validate)]
+#[derive(Debug, Deserialize, PartialEq, Eq, Validate, ToSchema, Type)]
 pub struct BugReport {
     #[validate(length(
         min = 1,
@@ -23,4 +26,60 @@ pub struct BugReport {
         message = "Description must be between 1 and 250 characters"
     ))]
     pub description: String,
+
+    /// The version of the app the bug was seen in.
+    #[validate(length(max = 50, message = "App version must be at most 50 characters"))]
+    pub app_version: Option<String>,
+
+    /// The operating system the app was running on.
+    #[validate(length(max = 100, message = "OS must be at most 100 characters"))]
+    pub os: Option<String>,
+
+    /// A PNG or JPEG screenshot, base64-encoded, optionally as a `data:` URL.
+    pub screenshot: Option<String>,
+}
+
+/// Where a bug report is up to.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, sqlx::Type, ToSchema, Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum BugReportStatus {
+    /// Waiting to be filed on GitHub, for the first time or after a failed attempt.
+    Pending,
+    /// Filed as a GitHub issue.
+    Filed,
+    /// Every attempt to file it failed, so it won't be retried.
+    Failed,
+}
+
+/// A bug report as it's stored, along with how filing it on GitHub is going.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, sqlx::FromRow, ToSchema, Type)]
+pub struct StoredBugReport {
+    pub id: i64,
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub app_version: Option<String>,
+    pub os: Option<String>,
+    /// Whether a screenshot came with it, served at GET /bug-reports/<id>/screenshot.
+    pub has_screenshot: bool,
+    pub status: BugReportStatus,
+    /// Attempts made to file it on GitHub.
+    pub attempts: i64,
+    /// When it'll next be tried, while it's pending.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// Why the last attempt failed.
+    pub last_error: Option<String>,
+    pub issue_number: Option<i64>,
+    /// The GitHub issue it was filed as.
+    pub issue_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub filed_at: Option<DateTime<Utc>>,
+}
+
+/// A bug report's screenshot.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct BugReportScreenshot {
+    pub content_type: String,
+    pub data: Vec<u8>,
 }
@@ -1,27 +1,47 @@
 use crate::models::note::Note;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
 use validator::{Validate, ValidationError};
 
-#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone)]
+/// The exhibit's current (or next upcoming) sponsorship, in the shape the app displays.
+///
+/// The full history lives in the `exhibit_sponsorships` table; see `Sponsorship`.
+#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone, ToSchema, Type)]
+#[validate(schema(function = "Sponsor::validate_date_range"))]
 pub struct Sponsor {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Sponsor name must be between 1 and 100 characters"
+    ))]
     pub name: String,
 
-    #[validate(custom(function = "Sponsor::validate_date_format"))]
-    pub start_date: String,
+    pub start_date: NaiveDate,
 
-    #[validate(custom(function = "Sponsor::validate_date_format"))]
-    pub end_date: String,
+    pub end_date: NaiveDate,
 }
 
 impl Sponsor {
-    fn validate_date_format(date: &str) -> Result<(), ValidationError> {
-        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
-            .map_err(|_| ValidationError::new("Date must be in YYYY-MM-DD format"))?;
+    fn validate_date_range(&self) -> Result<(), ValidationError> {
+        validate_date_range(self.start_date, self.end_date)
+    }
+}
+
+/// Checks that a sponsorship ends after it starts.
+pub fn validate_date_range(
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<(), ValidationError> {
+    if end_date > start_date {
         Ok(())
+    } else {
+        Err(ValidationError::new("End date must be after start date"))
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone)]
+#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone, ToSchema, Type)]
 pub struct Exhibit {
     pub id: i64,
 
@@ -61,15 +81,27 @@ pub struct Exhibit {
     pub image_url: String,
 
     pub sponsor: Option<Sponsor>,
+
+    /// Incremented on every write; exposed as the exhibit's ETag.
+    pub version: i64,
+}
+
+/// The statuses an exhibit can be in, as stored (lowercase) in the database.
+pub const EXHIBIT_STATUSES: [&str; 3] = ["operational", "needs repair", "out of service"];
+
+/// Checks a status against `EXHIBIT_STATUSES`, ignoring case and surrounding whitespace.
+pub fn validate_exhibit_status(status: &str) -> Result<(), ValidationError> {
+    if EXHIBIT_STATUSES.contains(&status.trim().to_lowercase().as_str()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new(
+            "Invalid status. Must be 'operational', 'needs repair', or 'out of service'",
+        ))
+    }
 }
 
 impl Exhibit {
     fn validate_status(status: &str) -> Result<(), ValidationError> {
-        match status.to_lowercase().as_str() {
-            "active" | "inactive" | "maintenance" => Ok(()),
-            _ => Err(ValidationError::new(
-                "Invalid status. Must be 'active', 'inactive', or 'maintenance'",
-            )),
-        }
+        validate_exhibit_status(status)
     }
 }
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+/// The outcome of one of GET /readyz's checks.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema, Type)]
+pub struct ReadinessCheck {
+    /// `database`, `images_dir`, `backup_dir`, `jotform_sync` or `backup`.
+    pub name: String,
+    pub ok: bool,
+    /// What was found, or what went wrong.
+    pub detail: String,
+    /// For the sync and backup checks, how long ago the last success was, in seconds.
+    pub age_secs: Option<i64>,
+}
+
+/// Whether the app is ready to serve, with the checks that decided it.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema, Type)]
+pub struct Readiness {
+    /// Whether every check passed.
+    pub ready: bool,
+    pub checks: Vec<ReadinessCheck>,
+}
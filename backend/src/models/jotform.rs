@@ -1,23 +1,30 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::fmt;
+use utoipa::ToSchema;
 use validator::{Validate, ValidationError};
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
-pub struct SubmissionDate {
-    pub date: String,
-    pub time: String,
-}
-
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema, Type)]
 pub struct FullName {
     pub first: String,
     pub last: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Validate, Clone)]
+// Submitters' names are personal, so they're kept out of anything that's debug-printed,
+// such as logs
+impl fmt::Debug for FullName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FullName([redacted])")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Validate, Clone, ToSchema, Type)]
 pub struct Jotform {
     pub id: String,
     pub submitter_name: FullName,
-    pub created_at: SubmissionDate,
+    /// When it was submitted, or the Unix epoch if that couldn't be read.
+    pub created_at: DateTime<Utc>,
     pub location: String,
     pub exhibit_name: String,
     pub description: String,
@@ -32,6 +39,10 @@ pub struct Jotform {
     pub status: String,
 }
 
+/// Stands in for a submission time that couldn't be read. It sorts before every real
+/// ticket, and tickets with it are never treated as new.
+pub const UNKNOWN_CREATED_AT: DateTime<Utc> = DateTime::UNIX_EPOCH;
+
 fn validate_status(status: &str) -> Result<(), ValidationError> {
     match status {
         "Open" => Ok(()),
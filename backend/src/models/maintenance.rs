@@ -0,0 +1,65 @@
+use chrono::{DateTime, Days, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// The unit of a maintenance task's repeat interval.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, sqlx::Type, ToSchema, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum IntervalUnit {
+    Days,
+    Weeks,
+    Months,
+}
+
+impl IntervalUnit {
+    /// Returns the date `every` units after `from`, or `None` if it would overflow.
+    pub fn advance(self, from: NaiveDate, every: u32) -> Option<NaiveDate> {
+        match self {
+            IntervalUnit::Days => from.checked_add_days(Days::new(every.into())),
+            IntervalUnit::Weeks => from.checked_add_days(Days::new(u64::from(every) * 7)),
+            IntervalUnit::Months => from.checked_add_months(Months::new(every)),
+        }
+    }
+}
+
+/// A recurring maintenance task on an exhibit, e.g. "clean lenses every 14 days".
+///
+/// `next_due_date` is recalculated from the completion date each time the task is done.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow, ToSchema, Type)]
+pub struct MaintenanceTask {
+    pub id: i64,
+    pub exhibit_id: i64,
+    pub title: String,
+    pub description: String,
+    pub interval_every: u32,
+    pub interval_unit: IntervalUnit,
+    pub next_due_date: NaiveDate,
+    /// Whether a ticket should be opened automatically when the task comes due.
+    pub auto_create_ticket: bool,
+}
+
+/// A record of someone completing a maintenance task.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow, ToSchema, Type)]
+pub struct MaintenanceCompletion {
+    pub id: i64,
+    pub task_id: i64,
+    pub completed_by: String,
+    pub completed_at: DateTime<Utc>,
+    /// The due date this completion satisfied.
+    pub due_date: NaiveDate,
+    pub notes: Option<String>,
+}
+
+/// A maintenance task that is past its due date.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow, ToSchema, Type)]
+pub struct OverdueMaintenanceTask {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub task: MaintenanceTask,
+    pub exhibit_name: String,
+    pub exhibit_location: String,
+    pub days_overdue: i64,
+}
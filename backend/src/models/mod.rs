@@ -1,15 +1,40 @@
+mod backup;
 mod bug_report;
 mod exhibit;
+mod health;
 mod jotform;
+mod maintenance;
 mod note;
 mod part;
+mod snapshot;
+mod sponsorship;
+mod trash;
 mod update_exhibit;
 mod update_part;
+mod webhook;
 
-pub use bug_report::BugReport;
-pub use exhibit::{Exhibit, Sponsor};
-pub use jotform::{FullName, Jotform, SubmissionDate};
-pub use note::{Note, Timestamp};
+pub use backup::BackupInfo;
+pub use bug_report::{BugReport, BugReportScreenshot, BugReportStatus, StoredBugReport};
+pub use exhibit::{
+    validate_date_range, validate_exhibit_status, Exhibit, Sponsor, EXHIBIT_STATUSES,
+};
+pub use health::{Readiness, ReadinessCheck};
+pub use jotform::{FullName, Jotform, UNKNOWN_CREATED_AT};
+pub use maintenance::{
+    IntervalUnit, MaintenanceCompletion, MaintenanceTask, OverdueMaintenanceTask,
+};
+pub use note::{Note, NoteAttachment, NoteRevision, NoteTarget};
 pub use part::Part;
+pub use snapshot::{
+    RestoreSummary, Snapshot, SnapshotExhibit, SnapshotExhibitPart, SnapshotMaintenanceCompletion,
+    SnapshotMaintenanceTask, SnapshotNote, SnapshotNoteAttachment, SnapshotNoteRevision,
+    SnapshotPart, SnapshotSponsor, SnapshotSponsorship, SnapshotTicket, SNAPSHOT_FORMAT_VERSION,
+};
+pub use sponsorship::{ExpiringSponsorship, SponsorProfile, Sponsorship};
+pub use trash::{TrashEntity, TrashItem};
 pub use update_exhibit::UpdateExhibit;
 pub use update_part::UpdatePart;
+pub use webhook::{DeliveryStatus, Webhook, WebhookDelivery};
+
+#[cfg(test)]
+mod tests;
@@ -1,25 +1,24 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use specta::Type;
 use sqlx::FromRow;
-use validator::{Validate, ValidationError};
-
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Validate, Clone, FromRow)]
-pub struct Timestamp {
-    #[validate(custom(function = "Timestamp::validate_date_format"))]
-    pub date: String,
-    #[validate(custom(function = "Timestamp::validate_date_format"))]
-    pub time: String,
-}
+use utoipa::ToSchema;
+use validator::Validate;
 
-impl Timestamp {
-    fn validate_date_format(date: &str) -> Result<(), ValidationError> {
-        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
-            .map_err(|_| ValidationError::new("Date must be in YYYY-MM-DD format"))?;
-        Ok(())
-    }
+/// The kind of record a note is attached to.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, sqlx::Type, ToSchema, Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum NoteTarget {
+    Exhibit,
+    Part,
+    /// A Jotform ticket, including tickets opened by maintenance schedules.
+    Ticket,
+    /// A maintenance task.
+    WorkOrder,
 }
 
-// Also need to validate the Note struct
-#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone, FromRow)]
+#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone, ToSchema, Type)]
 pub struct Note {
     pub id: i64,
 
@@ -28,6 +27,32 @@ pub struct Note {
     #[validate(length(min = 1, message = "Note cannot be empty"))]
     pub message: String,
 
-    #[sqlx(flatten)]
-    pub timestamp: Timestamp,
+    pub created_at: DateTime<Utc>,
+
+    /// When the message was last edited, if ever.
+    pub edited_at: Option<DateTime<Utc>>,
+
+    pub attachments: Vec<NoteAttachment>,
+}
+
+/// A file or photo attached to a note. The contents are downloaded separately.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow, ToSchema, Type)]
+pub struct NoteAttachment {
+    pub id: i64,
+    pub note_id: i64,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A previous version of a note's message, recorded when the note is edited.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow, ToSchema, Type)]
+pub struct NoteRevision {
+    pub id: i64,
+    pub note_id: i64,
+    /// The message as it was before the edit.
+    pub message: String,
+    pub edited_by: String,
+    pub edited_at: DateTime<Utc>,
 }
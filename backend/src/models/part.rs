@@ -1,8 +1,10 @@
 use crate::models::note::Note;
 use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Validate)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Validate, ToSchema, Type)]
 pub struct Part {
     pub id: Option<i64>,
 
@@ -20,4 +22,7 @@ pub struct Part {
 
     #[validate(nested)]
     pub notes: Vec<Note>,
+
+    /// Incremented on every write; exposed as the part's ETag.
+    pub version: i64,
 }
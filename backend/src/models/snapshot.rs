@@ -0,0 +1,169 @@
+use super::{IntervalUnit, NoteTarget};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// The snapshot format written by this version of the app. Bump it when the layout changes
+/// in a way older versions can't restore.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Everything in the database, as stored in `snapshot.json` inside a snapshot archive.
+///
+/// Rows keep the IDs they had at the site they came from. They're only used to connect
+/// rows to each other; a restore gives every row a new ID. Trashed records are included
+/// with their `deleted_at`, so they stay restorable after the move.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Snapshot {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub exhibits: Vec<SnapshotExhibit>,
+    pub parts: Vec<SnapshotPart>,
+    pub exhibit_parts: Vec<SnapshotExhibitPart>,
+    pub sponsors: Vec<SnapshotSponsor>,
+    pub sponsorships: Vec<SnapshotSponsorship>,
+    pub maintenance_tasks: Vec<SnapshotMaintenanceTask>,
+    pub maintenance_completions: Vec<SnapshotMaintenanceCompletion>,
+    pub tickets: Vec<SnapshotTicket>,
+    /// IDs of purged tickets, so the Jotform sync doesn't bring them back at the new site.
+    pub purged_ticket_ids: Vec<String>,
+    pub notes: Vec<SnapshotNote>,
+    pub note_revisions: Vec<SnapshotNoteRevision>,
+    /// Attachment metadata. The contents are stored in the archive under `attachments/<id>`.
+    pub note_attachments: Vec<SnapshotNoteAttachment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotExhibit {
+    pub id: i64,
+    pub name: String,
+    pub cluster: String,
+    pub location: String,
+    pub description: String,
+    pub status: String,
+    pub image_url: String,
+    pub version: i64,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotPart {
+    pub id: i64,
+    pub name: String,
+    pub link: String,
+    pub version: i64,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotExhibitPart {
+    pub exhibit_id: i64,
+    pub part_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotSponsor {
+    pub id: i64,
+    pub name: String,
+    pub contact_name: Option<String>,
+    pub contact_email: Option<String>,
+    pub contact_phone: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotSponsorship {
+    pub id: i64,
+    pub exhibit_id: i64,
+    pub sponsor_id: i64,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub amount_cents: Option<i64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotMaintenanceTask {
+    pub id: i64,
+    pub exhibit_id: i64,
+    pub title: String,
+    pub description: String,
+    pub interval_every: u32,
+    pub interval_unit: IntervalUnit,
+    pub next_due_date: NaiveDate,
+    pub auto_create_ticket: bool,
+    pub last_ticket_due_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotMaintenanceCompletion {
+    pub id: i64,
+    pub task_id: i64,
+    pub completed_by: String,
+    pub completed_at: DateTime<Utc>,
+    pub due_date: NaiveDate,
+    pub notes: Option<String>,
+}
+
+/// A ticket. Jotform submission IDs are kept as they are; tickets opened by maintenance
+/// tasks get new IDs to match their task's new ID.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotTicket {
+    pub id: String,
+    pub submitter_first_name: String,
+    pub submitter_last_name: String,
+    pub created_at: DateTime<Utc>,
+    pub location: String,
+    pub exhibit_name: String,
+    pub description: String,
+    pub priority_level: String,
+    pub department: String,
+    pub status: String,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotNote {
+    pub id: i64,
+    pub target_type: NoteTarget,
+    pub target_id: String,
+    pub submitter: String,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotNoteRevision {
+    pub id: i64,
+    pub note_id: i64,
+    pub message: String,
+    pub edited_by: String,
+    pub edited_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow)]
+pub struct SnapshotNoteAttachment {
+    pub id: i64,
+    pub note_id: i64,
+    pub file_name: String,
+    pub content_type: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// How many records a restore brought in.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default, ToSchema, Type)]
+pub struct RestoreSummary {
+    pub exhibits: usize,
+    pub parts: usize,
+    pub sponsors: usize,
+    pub maintenance_tasks: usize,
+    pub tickets: usize,
+    pub notes: usize,
+    pub attachments: usize,
+    pub images: usize,
+    /// Images that couldn't be moved into the images directory. The records were restored
+    /// regardless, so these need copying over by hand.
+    pub images_not_restored: Vec<String>,
+}
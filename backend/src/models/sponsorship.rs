@@ -0,0 +1,61 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// An organization or person that sponsors exhibits, with contact details for the
+/// development office.
+#[derive(
+    Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone, FromRow, ToSchema, Type,
+)]
+pub struct SponsorProfile {
+    pub id: i64,
+
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Name must be between 1 and 100 characters"
+    ))]
+    pub name: String,
+
+    pub contact_name: Option<String>,
+
+    #[validate(email)]
+    pub contact_email: Option<String>,
+
+    pub contact_phone: Option<String>,
+
+    pub notes: Option<String>,
+}
+
+/// One sponsor's support of one exhibit over a period of time.
+///
+/// Sponsorships are never overwritten when a sponsor changes, so an exhibit's rows form
+/// its sponsorship history.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow, ToSchema, Type)]
+pub struct Sponsorship {
+    pub id: i64,
+    pub exhibit_id: i64,
+    pub sponsor_id: i64,
+    pub sponsor_name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// The pledged amount in cents, if known.
+    pub amount_cents: Option<i64>,
+    pub notes: Option<String>,
+}
+
+/// A sponsorship that ends soon, with what's needed to follow up on it.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, FromRow, ToSchema, Type)]
+pub struct ExpiringSponsorship {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub sponsorship: Sponsorship,
+    pub exhibit_name: String,
+    pub contact_name: Option<String>,
+    pub contact_email: Option<String>,
+    pub contact_phone: Option<String>,
+    pub days_remaining: i64,
+}
@@ -0,0 +1,123 @@
+use super::*;
+use std::fs;
+use std::path::Path;
+
+/// The models as TypeScript, for the desktop app's `src/types/models.ts`.
+fn typescript() -> String {
+    let mut types = specta::TypeCollection::default();
+    types
+        .register::<BackupInfo>()
+        .register::<BugReport>()
+        .register::<BugReportStatus>()
+        .register::<StoredBugReport>()
+        .register::<Exhibit>()
+        .register::<Sponsor>()
+        .register::<Readiness>()
+        .register::<ReadinessCheck>()
+        .register::<FullName>()
+        .register::<Jotform>()
+        .register::<IntervalUnit>()
+        .register::<MaintenanceCompletion>()
+        .register::<MaintenanceTask>()
+        .register::<OverdueMaintenanceTask>()
+        .register::<Note>()
+        .register::<NoteAttachment>()
+        .register::<NoteRevision>()
+        .register::<NoteTarget>()
+        .register::<Part>()
+        .register::<RestoreSummary>()
+        .register::<ExpiringSponsorship>()
+        .register::<SponsorProfile>()
+        .register::<Sponsorship>()
+        .register::<TrashEntity>()
+        .register::<TrashItem>()
+        .register::<UpdateExhibit>()
+        .register::<UpdatePart>()
+        .register::<DeliveryStatus>()
+        .register::<Webhook>()
+        .register::<WebhookDelivery>();
+
+    specta_typescript::Typescript::default()
+        .header("// Generated from the backend's models. Run `UPDATE_TYPES=1 cargo test` in backend/ after changing them.")
+        // IDs and counts are i64 in Rust but well within what a JavaScript number holds
+        .bigint(specta_typescript::BigIntExportBehavior::Number)
+        .export(&types)
+        .expect("the models can be written as TypeScript")
+}
+
+#[test]
+fn test_typescript_models_are_up_to_date() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../application/src/types/models.ts");
+    let generated = typescript();
+
+    if std::env::var_os("UPDATE_TYPES").is_some() {
+        fs::write(&path, &generated).unwrap();
+    }
+
+    let checked_in = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        checked_in == generated,
+        "{} is out of date with the models; run `UPDATE_TYPES=1 cargo test` in backend/",
+        path.display()
+    );
+}
+
+/// The name declared by a line of TypeScript, if it starts a type, interface, enum or class.
+fn declared_type(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let line = line.strip_prefix("declare ").unwrap_or(line);
+    let rest = ["type ", "interface ", "enum ", "class "]
+        .iter()
+        .find_map(|keyword| line.strip_prefix(keyword))?;
+    let end = rest
+        .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '$')
+        .unwrap_or(rest.len());
+
+    Some(&rest[..end]).filter(|name| !name.is_empty())
+}
+
+#[test]
+fn test_app_does_not_redefine_generated_types() {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("../application/src");
+    let generated = typescript();
+    let models: Vec<&str> = generated.lines().filter_map(declared_type).collect();
+    assert!(models.contains(&"Exhibit"));
+
+    // A copy of a model would drift from the backend without the check above noticing
+    let mut dirs = vec![src.clone()];
+    let mut redefined = Vec::new();
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let is_typescript = path
+                .extension()
+                .is_some_and(|ext| ext == "ts" || ext == "tsx");
+            if !is_typescript || path.ends_with("types/models.ts") {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            for (number, line) in source.lines().enumerate() {
+                if let Some(name) = declared_type(line).filter(|name| models.contains(name)) {
+                    redefined.push(format!("{}:{} {}", path.display(), number + 1, name));
+                }
+            }
+        }
+    }
+
+    assert!(
+        redefined.is_empty(),
+        "these redefine types from models.ts; import them from \"@/types/models\" instead:\n{}",
+        redefined.join("\n")
+    );
+    assert_eq!(
+        declared_type("export interface UserProfile {"),
+        Some("UserProfile")
+    );
+    assert_eq!(declared_type("  type Props = {"), Some("Props"));
+    assert_eq!(declared_type("const type = 'a';"), None);
+}
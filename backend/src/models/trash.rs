@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+/// The kinds of records that go to the trash when deleted.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, sqlx::Type, ToSchema, Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum TrashEntity {
+    Exhibit,
+    Part,
+    /// A Jotform ticket.
+    Ticket,
+}
+
+impl TrashEntity {
+    /// The table the entity's rows live in.
+    pub fn table(self) -> &'static str {
+        match self {
+            TrashEntity::Exhibit => "exhibits",
+            TrashEntity::Part => "parts",
+            TrashEntity::Ticket => "jotforms",
+        }
+    }
+}
+
+/// A deleted record waiting in the trash.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema, Type)]
+pub struct TrashItem {
+    pub entity: TrashEntity,
+    /// The record's ID. Ticket IDs aren't numeric, so every ID is a string here.
+    pub id: String,
+    /// The exhibit or part name, or the exhibit named on a ticket.
+    pub name: String,
+    pub deleted_at: DateTime<Utc>,
+    /// When the purge job will permanently delete the record.
+    pub purge_after: DateTime<Utc>,
+}
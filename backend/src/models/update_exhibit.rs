@@ -1,43 +1,90 @@
-use crate::models::{Note, Sponsor};
-use serde::{Deserialize, Serialize};
+use crate::models::exhibit::validate_exhibit_status;
+use crate::models::Sponsor;
+use serde::{Deserialize, Deserializer, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
 use validator::{Validate, ValidationError};
 
-#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone)]
+/// Distinguishes a field that was left out of the request (`None`) from one that was
+/// explicitly set to `null` (`Some(None)`), as JSON Merge Patch requires.
+fn patch_field<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// A partial update for an exhibit, following JSON Merge Patch (RFC 7396) semantics.
+///
+/// Omitted fields are left alone. `null` clears a field: `sponsor` is removed, `description`
+/// becomes empty and `image_url` falls back to the default image. `name`, `cluster`,
+/// `location` and `status` are required on an exhibit and can't be set to `null`.
+/// `part_ids`, when present, replaces the exhibit's whole part list.
+#[derive(
+    Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone, Default, ToSchema, Type,
+)]
+#[validate(schema(function = "UpdateExhibit::validate_required_fields"))]
 pub struct UpdateExhibit {
+    #[serde(default, deserialize_with = "patch_field")]
     #[validate(length(
         min = 1,
         max = 100,
         message = "Name must be between 1 and 100 characters"
     ))]
-    pub name: Option<String>,
+    pub name: Option<Option<String>>,
 
+    #[serde(default, deserialize_with = "patch_field")]
     #[validate(length(
         min = 1,
         max = 100,
         message = "Cluster must be between 1 and 100 characters"
     ))]
-    pub cluster: Option<String>,
+    pub cluster: Option<Option<String>>,
 
+    #[serde(default, deserialize_with = "patch_field")]
     #[validate(length(
         min = 1,
         max = 100,
         message = "Location must be between 1 and 100 characters"
     ))]
-    pub location: Option<String>,
+    pub location: Option<Option<String>>,
+
+    #[serde(default, deserialize_with = "patch_field")]
+    pub description: Option<Option<String>>,
 
-    pub description: Option<String>,
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(custom(function = "UpdateExhibit::validate_status"))]
+    pub status: Option<Option<String>>,
 
+    #[serde(default, deserialize_with = "patch_field")]
     #[validate(url)]
-    pub image_url: Option<String>,
+    pub image_url: Option<Option<String>>,
+
+    #[serde(default, deserialize_with = "patch_field")]
+    #[validate(nested)]
+    pub sponsor: Option<Option<Sponsor>>,
+
+    #[serde(default, deserialize_with = "patch_field")]
+    pub part_ids: Option<Option<Vec<i64>>>,
 }
 
 impl UpdateExhibit {
     fn validate_status(status: &str) -> Result<(), ValidationError> {
-        match status.to_lowercase().as_str() {
-            "active" | "inactive" | "maintenance" => Ok(()),
-            _ => Err(ValidationError::new(
-                "Invalid status. Must be 'active', 'inactive', or 'maintenance'",
-            )),
+        validate_exhibit_status(status)
+    }
+
+    fn validate_required_fields(&self) -> Result<(), ValidationError> {
+        let cleared_required_field = [&self.name, &self.cluster, &self.location, &self.status]
+            .iter()
+            .any(|field| matches!(field, Some(None)));
+
+        if cleared_required_field || matches!(self.part_ids, Some(None)) {
+            return Err(ValidationError::new(
+                "name, cluster, location, status and part_ids cannot be null",
+            ));
         }
+
+        Ok(())
     }
 }
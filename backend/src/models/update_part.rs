@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Validate)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Validate, ToSchema, Type)]
 pub struct UpdatePart {
     #[validate(length(
         min = 1,
@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use utoipa::ToSchema;
+
+/// An outside system that's sent change events as they happen.
+///
+/// The secret the payloads are signed with is never sent back out.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, ToSchema, Type)]
+pub struct Webhook {
+    pub id: i64,
+    /// Where events are POSTed.
+    pub url: String,
+    /// The events sent, as filters like those GET /events takes, e.g. `exhibit.updated` or
+    /// `jotform.created:priority_level=High`.
+    pub events: Vec<String>,
+    /// Inactive webhooks keep their settings and delivery log but aren't sent anything.
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where a delivery is up to.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, sqlx::Type, ToSchema, Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Waiting for its first attempt or a retry.
+    Pending,
+    Delivered,
+    /// Every attempt failed, so it won't be retried.
+    Failed,
+}
+
+/// One event sent, or to be sent, to a webhook.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, sqlx::FromRow, ToSchema, Type)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    /// The event's topic, e.g. `exhibit.updated`.
+    pub topic: String,
+    /// The JSON body that's POSTed.
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i64,
+    /// When it'll next be tried, while it's pending.
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// The HTTP status of the last attempt, if the receiver answered.
+    pub last_status_code: Option<i64>,
+    /// Why the last attempt failed.
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,433 @@
+#[cfg(test)]
+mod tests;
+
+use crate::api::jotform_handlers::JotformFilter;
+use crate::db::DbPool;
+use crate::events::{self, Action, Entity};
+use crate::logging;
+use crate::models::{Jotform, OverdueMaintenanceTask};
+use crate::repo::jotform_repo::{self, DueTicketAlert};
+use crate::repo::maintenance_repo;
+use crate::retry::{self, Backoff};
+use crate::time_zone::{site_local_to_utc, to_site_date};
+use chrono::{DateTime, Days, NaiveDate, NaiveTime, TimeDelta, Utc};
+use lettre::message::{Mailbox, Message};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use log::{error, info, warn};
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::tokio::time::sleep;
+use rocket::tokio::{self, pin, select};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+/// The departments tickets are assigned to, in the order digests list them.
+pub const DEPARTMENTS: [&str; 2] = ["Exhibits", "Operations"];
+
+/// Ticket statuses that count as open work.
+const OPEN_STATUSES: [&str; 2] = ["Open", "InProgress"];
+
+/// Maintenance tickets are opened for the Exhibits department, so its digest lists overdue
+/// maintenance too.
+const MAINTENANCE_DEPARTMENT: &str = "Exhibits";
+
+/// New tickets at this priority are emailed to their department straight away.
+const ALERT_PRIORITY: &str = "High";
+
+/// How long to wait between attempts at emailing about a new ticket, and when to give up.
+pub const BACKOFF: Backoff = Backoff {
+    max_attempts: 10,
+    first_delay: TimeDelta::minutes(1),
+    max_delay: TimeDelta::hours(1),
+};
+
+/// How often to check for ticket alerts whose retry has come due.
+const RETRY_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many ticket alerts are sent per batch.
+const BATCH_SIZE: i64 = 20;
+
+/// How the connection to the SMTP server is secured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Connect in plain text, then upgrade with STARTTLS. Usually port 587.
+    StartTls,
+    /// TLS from the start. Usually port 465.
+    Tls,
+    /// Plain text throughout. Only for a relay on the same machine or network.
+    None,
+}
+
+/// Where emails are sent from and who gets them.
+#[derive(Debug, Clone)]
+pub struct EmailSettings {
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_security: SmtpSecurity,
+    /// The SMTP username and password, if the server needs them.
+    pub credentials: Option<(String, String)>,
+    pub from: Mailbox,
+    /// Who's emailed about each department's tickets.
+    pub recipients: BTreeMap<String, Vec<Mailbox>>,
+    /// The hour of the day, at the site, the digest of open work is sent.
+    pub digest_hour: u32,
+}
+
+#[derive(Debug, Error)]
+pub enum NotifyError {
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+
+    #[error("Email error: {0}")]
+    Email(#[from] lettre::error::Error),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// An email, before it's addressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub subject: String,
+    pub body: String,
+}
+
+/// Emails departments about their tickets.
+pub struct Notifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    recipients: BTreeMap<String, Vec<Mailbox>>,
+}
+
+impl Notifier {
+    pub fn new(settings: &EmailSettings) -> Result<Self, NotifyError> {
+        let host = settings.smtp_host.as_str();
+        let mut transport = match settings.smtp_security {
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        }
+        .port(settings.smtp_port);
+        if let Some((username, password)) = &settings.credentials {
+            transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Notifier {
+            transport: transport.build(),
+            from: settings.from.clone(),
+            recipients: settings.recipients.clone(),
+        })
+    }
+
+    /// Who's emailed about `department`'s tickets.
+    pub fn recipients(&self, department: &str) -> &[Mailbox] {
+        self.recipients
+            .get(department)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Sends `email` to everyone responsible for `department`, returning whether anyone was.
+    pub async fn send(&self, department: &str, email: &Email) -> Result<bool, NotifyError> {
+        let recipients = self.recipients(department);
+        if recipients.is_empty() {
+            return Ok(false);
+        }
+
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(email.subject.as_str());
+        for recipient in recipients {
+            message = message.to(recipient.clone());
+        }
+        self.transport
+            .send(message.body(email.body.clone())?)
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Emails each department a digest of its open tickets and overdue work, returning how
+    /// many digests were sent. Departments with nothing open aren't emailed.
+    pub async fn send_digests(
+        &self,
+        today: NaiveDate,
+        pool: &DbPool,
+    ) -> Result<usize, NotifyError> {
+        let overdue = maintenance_repo::get_overdue_tasks(today, pool).await?;
+        let mut sent = 0;
+
+        for department in DEPARTMENTS {
+            if self.recipients(department).is_empty() {
+                continue;
+            }
+
+            let tickets = open_tickets(department, pool).await?;
+            let overdue: &[OverdueMaintenanceTask] = match department {
+                MAINTENANCE_DEPARTMENT => &overdue,
+                _ => &[],
+            };
+            if let Some(email) = digest_email(department, &tickets, overdue, today) {
+                self.send(department, &email).await?;
+                sent += 1;
+            }
+        }
+
+        Ok(sent)
+    }
+}
+
+/// A department's open tickets, most urgent first, then oldest first.
+async fn open_tickets(department: &str, pool: &DbPool) -> sqlx::Result<Vec<Jotform>> {
+    let mut tickets = Vec::new();
+    for status in OPEN_STATUSES {
+        let filter = JotformFilter {
+            search: None,
+            status: Some(status.to_string()),
+            department: Some(department.to_string()),
+            priority_level: None,
+        };
+        tickets.extend(jotform_repo::find_jotforms(&filter, "", None, pool).await?);
+    }
+
+    tickets.sort_by_key(|ticket| (priority_rank(&ticket.priority_level), ticket.created_at));
+    Ok(tickets)
+}
+
+fn priority_rank(priority_level: &str) -> u8 {
+    match priority_level {
+        "High" => 0,
+        "Medium" => 1,
+        _ => 2,
+    }
+}
+
+/// The email sent when a high-priority ticket comes in.
+pub fn ticket_email(ticket: &Jotform) -> Email {
+    let mut body = String::new();
+    let _ = writeln!(body, "A high-priority ticket was just submitted.\n");
+    let _ = writeln!(body, "Exhibit:      {}", ticket.exhibit_name);
+    let _ = writeln!(body, "Location:     {}", ticket.location);
+    let _ = writeln!(
+        body,
+        "Submitted by: {} {}",
+        ticket.submitter_name.first, ticket.submitter_name.last
+    );
+    let _ = writeln!(
+        body,
+        "Submitted:    {}",
+        ticket.created_at.format("%Y-%m-%d %H:%M UTC")
+    );
+    let _ = writeln!(body, "Ticket:       {}\n", ticket.id);
+    let _ = writeln!(body, "{}", ticket.description);
+
+    Email {
+        subject: format!(
+            "High-priority ticket: {} ({})",
+            ticket.exhibit_name, ticket.location
+        ),
+        body,
+    }
+}
+
+/// The daily digest for a department, or `None` if it has nothing open.
+pub fn digest_email(
+    department: &str,
+    tickets: &[Jotform],
+    overdue: &[OverdueMaintenanceTask],
+    today: NaiveDate,
+) -> Option<Email> {
+    if tickets.is_empty() && overdue.is_empty() {
+        return None;
+    }
+
+    let mut body = String::new();
+    if !tickets.is_empty() {
+        let _ = writeln!(body, "Open tickets ({}):\n", tickets.len());
+        for ticket in tickets {
+            let _ = writeln!(
+                body,
+                "- [{}] {} ({}), {} days old, {}",
+                ticket.priority_level,
+                ticket.exhibit_name,
+                ticket.location,
+                (today - to_site_date(ticket.created_at)).num_days(),
+                ticket.status
+            );
+        }
+        let _ = writeln!(body);
+    }
+    if !overdue.is_empty() {
+        let _ = writeln!(body, "Overdue maintenance ({}):\n", overdue.len());
+        for task in overdue {
+            let _ = writeln!(
+                body,
+                "- {} on {} ({}), {} days overdue",
+                task.task.title, task.exhibit_name, task.exhibit_location, task.days_overdue
+            );
+        }
+    }
+
+    Some(Email {
+        subject: format!(
+            "{} digest for {}: {} open tickets, {} overdue tasks",
+            department,
+            today,
+            tickets.len(),
+            overdue.len()
+        ),
+        body,
+    })
+}
+
+/// When the next digest is due: `hour` o'clock at the site, today if that's still to come,
+/// otherwise tomorrow.
+pub fn next_digest_at(now: DateTime<Utc>, hour: u32) -> DateTime<Utc> {
+    let time = NaiveTime::from_hms_opt(hour, 0, 0).unwrap_or(NaiveTime::MIN);
+    let today = to_site_date(now);
+
+    // The hour can be skipped when clocks spring forward, so look a few days out
+    (0..3)
+        .filter_map(|days| today.checked_add_days(Days::new(days)))
+        .filter_map(|date| site_local_to_utc(date.and_time(time)))
+        .find(|at| *at > now)
+        .unwrap_or(now + TimeDelta::days(1))
+}
+
+/// Whether a new ticket's department is emailed about it as soon as it comes in.
+pub fn needs_alert(ticket: &Jotform) -> bool {
+    ticket.priority_level == ALERT_PRIORITY
+}
+
+/// Emails the department about a new ticket, if it still needs it. Tickets deleted or
+/// downgraded since they came in aren't emailed about.
+async fn notify_new_ticket(
+    notifier: &Notifier,
+    id: &str,
+    pool: &DbPool,
+) -> Result<(), NotifyError> {
+    let Some(ticket) = jotform_repo::get_jotform(id.to_string(), pool).await? else {
+        return Ok(());
+    };
+    if !needs_alert(&ticket) {
+        return Ok(());
+    }
+
+    if notifier
+        .send(&ticket.department, &ticket_email(&ticket))
+        .await?
+    {
+        info!(ticket_id = id, department = ticket.department.as_str(); "Emailed the department about a high-priority ticket");
+    } else {
+        warn!(ticket_id = id, department = ticket.department.as_str(); "No one to email about a high-priority ticket");
+    }
+
+    Ok(())
+}
+
+/// Sends every ticket alert that's due by `now`, returning how many were attempted.
+///
+/// Alerts that fail to send are retried with backoff, or given up on once they've been
+/// tried `BACKOFF.max_attempts` times.
+pub async fn send_due_alerts(
+    notifier: &Notifier,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> sqlx::Result<usize> {
+    retry::work_through_due(
+        BATCH_SIZE,
+        |limit| jotform_repo::get_due_ticket_alerts(now, limit, pool),
+        |alert| send_alert(notifier, alert, now, pool),
+    )
+    .await
+}
+
+/// Sends one ticket alert and records how it went.
+async fn send_alert(
+    notifier: &Notifier,
+    alert: DueTicketAlert,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> sqlx::Result<()> {
+    let id = alert.ticket_id.as_str();
+    let failure = match notify_new_ticket(notifier, id, pool).await {
+        Ok(()) => None,
+        // The alert can't be recorded either, so leave it be to retry
+        Err(NotifyError::Database(e)) => return Err(e),
+        Err(e) => Some(e.to_string()),
+    };
+
+    let retry_at = match &failure {
+        Some(_) => BACKOFF.delay(alert.attempts + 1).map(|delay| now + delay),
+        None => None,
+    };
+    match (&failure, retry_at) {
+        (None, _) => {}
+        (Some(e), Some(_)) => {
+            warn!(ticket_id = id; "Failed to email about a new ticket, will retry: {}", e)
+        }
+        (Some(e), None) => {
+            error!(ticket_id = id; "Failed to email about a new ticket, giving up: {}", e)
+        }
+    }
+
+    jotform_repo::record_ticket_alert_attempt(id, failure.as_deref(), retry_at, pool).await
+}
+
+/// Emails departments about new high-priority tickets as they come in, and sends each one
+/// a digest of its open work every day at `digest_hour`.
+///
+/// Alerts are queued in the database when a sync stores a new ticket, so ones queued before
+/// a restart, or while the mail server was down, are picked up again.
+pub async fn run(pool: DbPool, notifier: Arc<Notifier>, digest_hour: u32) {
+    let digests = {
+        let pool = pool.clone();
+        let notifier = notifier.clone();
+        async move {
+            loop {
+                let at = next_digest_at(Utc::now(), digest_hour);
+                sleep((at - Utc::now()).to_std().unwrap_or_default()).await;
+
+                let sent = logging::in_span(
+                    "email_digest",
+                    notifier.send_digests(to_site_date(at), &pool),
+                );
+                match sent.await {
+                    Ok(count) => info!(digests = count; "Sent the daily digests"),
+                    Err(e) => error!("Failed to send the daily digests: {}", e),
+                }
+            }
+        }
+    };
+    tokio::spawn(digests);
+
+    let mut changes = events::subscribe();
+    loop {
+        let sent = logging::in_span(
+            "ticket_alerts",
+            send_due_alerts(&notifier, Utc::now(), &pool),
+        );
+        if let Err(e) = sent.await {
+            error!("Failed to email about new tickets: {}", e);
+        }
+
+        // Wait for a sync to bring in tickets, or for retries to come due
+        let poll = sleep(RETRY_POLL_INTERVAL);
+        pin!(poll);
+        loop {
+            select! {
+                change = changes.recv() => match change {
+                    Ok(event) if event.entity == Entity::Jotform && event.action == Action::Synced => break,
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                },
+                _ = &mut poll => break,
+            }
+        }
+    }
+}
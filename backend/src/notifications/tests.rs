@@ -0,0 +1,241 @@
+use super::*;
+use crate::db::setup_database;
+use crate::models::FullName;
+use rocket::tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use rocket::tokio::net::TcpListener;
+use sqlx::SqlitePool;
+use std::sync::Mutex;
+
+/// A message the fake SMTP server accepted.
+#[derive(Debug, Default)]
+struct Received {
+    recipients: Vec<String>,
+    data: String,
+}
+
+/// A local fake SMTP server that accepts everything it's sent and keeps it.
+async fn start_smtp_server() -> (u16, Arc<Mutex<Vec<Received>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let received = Arc::new(Mutex::new(Vec::new()));
+
+    let log = received.clone();
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let log = log.clone();
+            tokio::spawn(async move {
+                let (reader, mut writer) = socket.into_split();
+                let mut lines = BufReader::new(reader).lines();
+                let mut message = Received::default();
+                writer.write_all(b"220 fake ESMTP\r\n").await.unwrap();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let command = line.to_ascii_uppercase();
+                    let reply: &[u8] = if command.starts_with("RCPT TO:") {
+                        message.recipients.push(line[8..].trim().to_string());
+                        b"250 OK\r\n"
+                    } else if command == "DATA" {
+                        writer.write_all(b"354 Go ahead\r\n").await.unwrap();
+                        while let Ok(Some(line)) = lines.next_line().await {
+                            if line == "." {
+                                break;
+                            }
+                            message.data.push_str(&line);
+                            message.data.push('\n');
+                        }
+                        log.lock().unwrap().push(std::mem::take(&mut message));
+                        b"250 Queued\r\n"
+                    } else if command == "QUIT" {
+                        writer.write_all(b"221 Bye\r\n").await.unwrap();
+                        break;
+                    } else {
+                        b"250 OK\r\n"
+                    };
+                    writer.write_all(reply).await.unwrap();
+                }
+            });
+        }
+    });
+
+    (port, received)
+}
+
+async fn setup_test_db() -> DbPool {
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+    setup_database(&pool).await.unwrap();
+
+    pool
+}
+
+fn notifier(port: u16) -> Notifier {
+    let recipients = BTreeMap::from([
+        (
+            "Operations".to_string(),
+            vec!["facilities@example.org".parse().unwrap()],
+        ),
+        (
+            "Exhibits".to_string(),
+            vec![
+                "exhibits@example.org".parse().unwrap(),
+                "curator@example.org".parse().unwrap(),
+            ],
+        ),
+    ]);
+
+    Notifier::new(&EmailSettings {
+        smtp_host: "127.0.0.1".to_string(),
+        smtp_port: port,
+        smtp_security: SmtpSecurity::None,
+        credentials: None,
+        from: "Exhibit Manager <manager@example.org>".parse().unwrap(),
+        recipients,
+        digest_hour: 7,
+    })
+    .unwrap()
+}
+
+fn ticket(id: &str, priority_level: &str, department: &str, status: &str) -> Jotform {
+    Jotform {
+        id: id.to_string(),
+        submitter_name: FullName {
+            first: "Kenneth".to_string(),
+            last: "Smith".to_string(),
+        },
+        created_at: "2024-12-04T13:39:24Z".parse().unwrap(),
+        location: "Deep Space".to_string(),
+        exhibit_name: format!("Exhibit {}", id),
+        description: "It's peeling off the wall.".to_string(),
+        priority_level: priority_level.to_string(),
+        department: department.to_string(),
+        status: status.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_high_priority_tickets_email_their_department() {
+    let pool = setup_test_db().await;
+    let (port, received) = start_smtp_server().await;
+    let notifier = notifier(port);
+
+    jotform_repo::insert_jotform(&ticket("1", "High", "Operations", "Open"), &pool)
+        .await
+        .unwrap();
+    jotform_repo::insert_jotform(&ticket("2", "Medium", "Operations", "Open"), &pool)
+        .await
+        .unwrap();
+
+    notify_new_ticket(&notifier, "1", &pool).await.unwrap();
+    notify_new_ticket(&notifier, "2", &pool).await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].recipients, vec!["<facilities@example.org>"]);
+    assert!(received[0]
+        .data
+        .contains("Subject: High-priority ticket: Exhibit 1 (Deep Space)"));
+    assert!(received[0].data.contains("It's peeling off the wall."));
+}
+
+#[tokio::test]
+async fn test_ticket_alerts_are_kept_until_sent() {
+    let pool = setup_test_db().await;
+    let now: DateTime<Utc> = "2024-12-04T14:00:00Z".parse().unwrap();
+
+    jotform_repo::insert_jotform_with_alert(&ticket("1", "High", "Exhibits", "Open"), now, &pool)
+        .await
+        .unwrap();
+
+    // The mail server is down, so the alert waits to be retried
+    let closed_port = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let down = notifier(closed_port);
+    assert_eq!(send_due_alerts(&down, now, &pool).await.unwrap(), 1);
+    let (attempts, next_attempt_at, last_error): (i64, DateTime<Utc>, Option<String>) =
+        sqlx::query_as("SELECT attempts, next_attempt_at, last_error FROM ticket_alerts")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(attempts, 1);
+    assert_eq!(next_attempt_at, now + BACKOFF.first_delay);
+    assert!(last_error.is_some());
+
+    // Once it's back, the alert is sent and cleared
+    let (port, received) = start_smtp_server().await;
+    let up = notifier(port);
+    assert_eq!(send_due_alerts(&up, now, &pool).await.unwrap(), 0);
+    let later = now + BACKOFF.first_delay;
+    assert_eq!(send_due_alerts(&up, later, &pool).await.unwrap(), 1);
+    assert_eq!(received.lock().unwrap().len(), 1);
+    assert!(jotform_repo::get_due_ticket_alerts(later, 10, &pool)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_digests_list_each_departments_open_tickets() {
+    let pool = setup_test_db().await;
+    let (port, received) = start_smtp_server().await;
+    let notifier = notifier(port);
+
+    for ticket in [
+        ticket("1", "Low", "Exhibits", "Open"),
+        ticket("2", "High", "Exhibits", "InProgress"),
+        ticket("3", "High", "Exhibits", "Closed"),
+        ticket("4", "Medium", "Operations", "Closed"),
+    ] {
+        jotform_repo::insert_jotform(&ticket, &pool).await.unwrap();
+    }
+
+    let today = NaiveDate::from_ymd_opt(2024, 12, 14).unwrap();
+    assert_eq!(notifier.send_digests(today, &pool).await.unwrap(), 1);
+
+    // Operations has nothing open, so only Exhibits gets a digest
+    {
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(
+            received[0].recipients,
+            vec!["<exhibits@example.org>", "<curator@example.org>"]
+        );
+    }
+
+    let tickets = open_tickets("Exhibits", &pool).await.unwrap();
+    let ids: Vec<&str> = tickets.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, vec!["2", "1"]);
+
+    let email = digest_email("Exhibits", &tickets, &[], today).unwrap();
+    assert_eq!(
+        email.subject,
+        "Exhibits digest for 2024-12-14: 2 open tickets, 0 overdue tasks"
+    );
+    assert!(email
+        .body
+        .contains("- [High] Exhibit 2 (Deep Space), 10 days old, InProgress"));
+    assert!(!email.body.contains("Exhibit 3"));
+    assert!(digest_email("Operations", &[], &[], today).is_none());
+}
+
+#[test]
+fn test_next_digest_at_is_the_next_time_the_hour_comes_round_at_the_site() {
+    let utc = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+
+    // 7am in Chicago is 13:00 UTC in winter
+    assert_eq!(
+        next_digest_at(utc("2025-01-15T12:00:00Z"), 7),
+        utc("2025-01-15T13:00:00Z")
+    );
+    assert_eq!(
+        next_digest_at(utc("2025-01-15T13:00:00Z"), 7),
+        utc("2025-01-16T13:00:00Z")
+    );
+    // and 12:00 UTC in summer
+    assert_eq!(
+        next_digest_at(utc("2025-07-15T20:00:00Z"), 7),
+        utc("2025-07-16T12:00:00Z")
+    );
+}
@@ -0,0 +1,205 @@
+use crate::db::DbPool;
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::models::{BugReport, BugReportScreenshot, BugReportStatus, StoredBugReport};
+use chrono::{DateTime, Utc};
+use sqlx::{Result, SqliteConnection};
+
+/// Everything but the screenshot itself, which can be large.
+const BUG_REPORT_COLUMNS: &str = "id, name, title, description, app_version, os,
+    screenshot IS NOT NULL AS has_screenshot, status, attempts, next_attempt_at, last_error,
+    issue_number, issue_url, created_at, filed_at";
+
+pub async fn create_bug_report_tables(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS bug_reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL,
+            app_version TEXT,
+            os TEXT,
+            screenshot BLOB,
+            screenshot_content_type TEXT,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at TEXT,
+            last_error TEXT,
+            issue_number INTEGER,
+            issue_url TEXT,
+            created_at TEXT NOT NULL,
+            filed_at TEXT
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Stores a bug report, to be filed on GitHub straight away. `screenshot` is its content
+/// type and contents.
+pub async fn create_bug_report(
+    report: &BugReport,
+    screenshot: Option<(&str, &[u8])>,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO bug_reports (
+            name, title, description, app_version, os, screenshot, screenshot_content_type,
+            next_attempt_at, created_at
+         )
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)",
+    )
+    .bind(&report.name)
+    .bind(&report.title)
+    .bind(&report.description)
+    .bind(&report.app_version)
+    .bind(&report.os)
+    .bind(screenshot.map(|(_, data)| data))
+    .bind(screenshot.map(|(content_type, _)| content_type))
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+
+    let id = result.last_insert_rowid();
+    let event = ChangeEvent::new(Entity::BugReport, Action::Created, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(id)
+}
+
+pub async fn get_bug_report(id: i64, pool: &DbPool) -> Result<Option<StoredBugReport>> {
+    sqlx::query_as::<_, StoredBugReport>(&format!(
+        "SELECT {} FROM bug_reports WHERE id = ?1",
+        BUG_REPORT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Lists bug reports, newest first.
+pub async fn get_bug_reports(
+    status: Option<BugReportStatus>,
+    pool: &DbPool,
+) -> Result<Vec<StoredBugReport>> {
+    sqlx::query_as::<_, StoredBugReport>(&format!(
+        "SELECT {} FROM bug_reports WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC",
+        BUG_REPORT_COLUMNS
+    ))
+    .bind(status)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_screenshot(id: i64, pool: &DbPool) -> Result<Option<BugReportScreenshot>> {
+    sqlx::query_as::<_, BugReportScreenshot>(
+        "SELECT screenshot_content_type AS content_type, screenshot AS data
+         FROM bug_reports
+         WHERE id = ?1 AND screenshot IS NOT NULL",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Lists pending bug reports due to be filed by `now`, oldest first.
+pub async fn get_due_bug_reports(
+    now: DateTime<Utc>,
+    limit: i64,
+    pool: &DbPool,
+) -> Result<Vec<StoredBugReport>> {
+    sqlx::query_as::<_, StoredBugReport>(&format!(
+        "SELECT {} FROM bug_reports
+         WHERE status = 'pending' AND next_attempt_at <= ?1
+         ORDER BY next_attempt_at, id
+         LIMIT ?2",
+        BUG_REPORT_COLUMNS
+    ))
+    .bind(now)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Records that a bug report was filed as a GitHub issue.
+pub async fn record_filed(
+    id: i64,
+    issue_number: i64,
+    issue_url: &str,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "UPDATE bug_reports
+         SET status = 'filed', attempts = attempts + 1, next_attempt_at = NULL,
+             last_error = NULL, issue_number = ?1, issue_url = ?2, filed_at = ?3
+         WHERE id = ?4",
+    )
+    .bind(issue_number)
+    .bind(issue_url)
+    .bind(now)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    let event = ChangeEvent::new(Entity::BugReport, Action::Updated, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(())
+}
+
+/// Records a failed attempt to file a bug report. It's retried at `retry_at`, or given up
+/// on if that's `None`.
+pub async fn record_failed_attempt(
+    id: i64,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>,
+    pool: &DbPool,
+) -> Result<()> {
+    let status = match retry_at {
+        Some(_) => BugReportStatus::Pending,
+        None => BugReportStatus::Failed,
+    };
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "UPDATE bug_reports
+         SET status = ?1, attempts = attempts + 1, next_attempt_at = ?2, last_error = ?3
+         WHERE id = ?4",
+    )
+    .bind(status)
+    .bind(retry_at)
+    .bind(error)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    // Only giving up is news; retries happen quietly
+    let event = (status == BugReportStatus::Failed)
+        .then(|| ChangeEvent::new(Entity::BugReport, Action::Updated, Some(id.to_string())));
+    if let Some(event) = &event {
+        events::record(event, &mut *tx).await?;
+    }
+    tx.commit().await?;
+
+    if let Some(event) = event {
+        events::publish(event);
+    }
+
+    Ok(())
+}
@@ -1,9 +1,17 @@
-use crate::api::exhibit_handlers::NewExhibit;
-use crate::db::DbPool;
-use crate::models::{Exhibit, Note, Sponsor, Timestamp, UpdateExhibit};
-use chrono::{DateTime, FixedOffset, Utc};
-use sqlx::Result;
+use crate::api::exhibit_handlers::{
+    ExhibitBatchAction, ExhibitBatchItemResult, ExhibitBatchOutcome, ExhibitBatchRequest,
+    ExhibitFieldChange, ExhibitFilter, NewExhibit,
+};
+use crate::db::{add_column_if_missing, DbPool};
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::exhibit_import::{ImportOutcome, ImportRow, IMPORTED_NOTE_SUBMITTER};
+use crate::models::{Exhibit, NoteTarget, UpdateExhibit};
+use crate::repo::{note_repo, sponsorship_repo};
+use crate::time_zone::site_today;
+use chrono::Utc;
+use rocket::serde::json::json;
 use sqlx::Sqlite;
+use sqlx::{Result, SqliteConnection};
 
 #[derive(sqlx::FromRow)]
 struct ExhibitRow {
@@ -14,26 +22,22 @@ struct ExhibitRow {
     description: String,
     status: String,
     image_url: String,
-    sponsor_name: Option<String>,
-    sponsor_start_date: Option<String>,
-    sponsor_end_date: Option<String>,
+    version: i64,
 }
 
 #[derive(sqlx::FromRow)]
-struct ExhibitPartRow {
-    part_id: i64,
+struct ExhibitBatchRow {
+    status: String,
+    cluster: String,
+    location: String,
 }
 
 #[derive(sqlx::FromRow)]
-struct ExhibitNoteRow {
-    id: i64,
-    submitter: String,
-    date: String,
-    time: String,
-    message: String,
+struct ExhibitPartRow {
+    part_id: i64,
 }
 
-pub async fn create_exhibit_tables(pool: &DbPool) -> Result<()> {
+pub async fn create_exhibit_tables(conn: &mut SqliteConnection) -> Result<()> {
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS exhibits (
@@ -44,14 +48,22 @@ pub async fn create_exhibit_tables(pool: &DbPool) -> Result<()> {
             description TEXT NOT NULL,
             status TEXT NOT NULL,
             image_url TEXT NOT NULL,
-            sponsor_name TEXT,
-            sponsor_start_date TEXT,
-            sponsor_end_date TEXT
+            version INTEGER NOT NULL DEFAULT 1,
+            deleted_at TEXT
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
+    .await?;
+
+    add_column_if_missing(
+        &mut *conn,
+        "exhibits",
+        "version",
+        "INTEGER NOT NULL DEFAULT 1",
+    )
     .await?;
+    add_column_if_missing(&mut *conn, "exhibits", "deleted_at", "TEXT").await?;
 
     sqlx::query(
         r#"
@@ -63,111 +75,67 @@ pub async fn create_exhibit_tables(pool: &DbPool) -> Result<()> {
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS exhibit_notes (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            exhibit_id INTEGER NOT NULL,
-            submitter TEXT NOT NULL,
-            date TEXT NOT NULL,
-            time TEXT NOT NULL,
-            message TEXT NOT NULL,
-            FOREIGN KEY (exhibit_id) REFERENCES exhibits(id) ON DELETE CASCADE
-        )
-        "#,
+    Ok(())
+}
+
+/// Loads the sponsor, parts and notes that go with an exhibit row.
+async fn into_exhibit(exhibit_row: ExhibitRow, pool: &DbPool) -> Result<Exhibit> {
+    let sponsor = sponsorship_repo::get_current_sponsor(exhibit_row.id, site_today(), pool).await?;
+
+    let part_rows = sqlx::query_as::<_, ExhibitPartRow>(
+        "SELECT ep.part_id FROM exhibit_parts ep
+         JOIN parts p ON p.id = ep.part_id
+         WHERE ep.exhibit_id = ?1 AND p.deleted_at IS NULL",
     )
-    .execute(pool)
+    .bind(exhibit_row.id)
+    .fetch_all(pool)
     .await?;
 
-    Ok(())
+    let part_ids = part_rows.iter().map(|row| row.part_id).collect();
+
+    let notes =
+        note_repo::get_notes(NoteTarget::Exhibit, &exhibit_row.id.to_string(), pool).await?;
+
+    Ok(Exhibit {
+        id: exhibit_row.id,
+        name: exhibit_row.name,
+        cluster: exhibit_row.cluster,
+        location: exhibit_row.location,
+        description: exhibit_row.description,
+        status: exhibit_row.status,
+        image_url: exhibit_row.image_url,
+        sponsor,
+        part_ids,
+        notes,
+        version: exhibit_row.version,
+    })
 }
 
 pub async fn get_exhibit(id: i64, pool: &DbPool) -> Result<Option<Exhibit>> {
     let exhibit_row = sqlx::query_as::<_, ExhibitRow>(
-        "SELECT id, name, cluster, location, description, status, image_url,
-                sponsor_name, sponsor_start_date, sponsor_end_date
+        "SELECT id, name, cluster, location, description, status, image_url, version
          FROM exhibits
-         WHERE id = ?1",
+         WHERE id = ?1 AND deleted_at IS NULL",
     )
     .bind(id)
     .fetch_optional(pool)
     .await?;
 
-    if let Some(exhibit_row) = exhibit_row {
-        let sponsor = match (
-            exhibit_row.sponsor_name,
-            exhibit_row.sponsor_start_date,
-            exhibit_row.sponsor_end_date,
-        ) {
-            (Some(name), Some(start_date), Some(end_date)) => Some(Sponsor {
-                name,
-                start_date,
-                end_date,
-            }),
-            _ => None,
-        };
-
-        let part_rows = sqlx::query_as::<_, ExhibitPartRow>(
-            "SELECT part_id FROM exhibit_parts WHERE exhibit_id = ?1",
-        )
-        .bind(id)
-        .fetch_all(pool)
-        .await?;
-
-        let part_ids = part_rows.iter().map(|row| row.part_id).collect();
-
-        // Include submitter in the SELECT query
-        let note_rows = sqlx::query_as::<_, ExhibitNoteRow>(
-            "SELECT id, submitter, date, time, message
-             FROM exhibit_notes
-             WHERE exhibit_id = ?1",
-        )
-        .bind(id)
-        .fetch_all(pool)
-        .await?;
-
-        let notes = note_rows
-            .iter()
-            .map(|row| Note {
-                id: row.id,
-                submitter: row.submitter.clone(),
-                timestamp: Timestamp {
-                    date: row.date.clone(),
-                    time: row.time.clone(),
-                },
-                message: row.message.clone(),
-            })
-            .collect();
-
-        Ok(Some(Exhibit {
-            id: exhibit_row.id,
-            name: exhibit_row.name,
-            cluster: exhibit_row.cluster,
-            location: exhibit_row.location,
-            description: exhibit_row.description,
-            status: exhibit_row.status,
-            image_url: exhibit_row.image_url,
-            sponsor,
-            part_ids,
-            notes,
-        }))
-    } else {
-        Ok(None)
+    match exhibit_row {
+        Some(exhibit_row) => Ok(Some(into_exhibit(exhibit_row, pool).await?)),
+        None => Ok(None),
     }
 }
 
 pub async fn create_exhibit(exhibit: &NewExhibit, pool: &DbPool) -> Result<()> {
-    let sponsor_name = exhibit.sponsor.as_ref().map(|s| &s.name);
-    let sponsor_start = exhibit.sponsor.as_ref().map(|s| &s.start_date);
-    let sponsor_end = exhibit.sponsor.as_ref().map(|s| &s.end_date);
+    let mut tx = pool.begin().await?;
 
     let result = sqlx::query(
-        "INSERT INTO exhibits (name, cluster, location, description, status, image_url,
-                              sponsor_name, sponsor_start_date, sponsor_end_date)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        "INSERT INTO exhibits (name, cluster, location, description, status, image_url)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
     )
     .bind(&exhibit.name)
     .bind(&exhibit.cluster)
@@ -175,288 +143,237 @@ pub async fn create_exhibit(exhibit: &NewExhibit, pool: &DbPool) -> Result<()> {
     .bind(&exhibit.description)
     .bind(&exhibit.status)
     .bind(&exhibit.image_url)
-    .bind(sponsor_name)
-    .bind(sponsor_start)
-    .bind(sponsor_end)
-    .execute(pool)
+    .execute(&mut *tx)
     .await?;
 
     let exhibit_id = result.last_insert_rowid();
 
+    if let Some(sponsor) = &exhibit.sponsor {
+        sponsorship_repo::set_current_sponsor(exhibit_id, Some(sponsor), site_today(), &mut tx)
+            .await?;
+    }
+
     for part_id in &exhibit.part_ids {
         sqlx::query("INSERT INTO exhibit_parts (exhibit_id, part_id) VALUES (?1, ?2)")
             .bind(exhibit_id)
             .bind(part_id)
-            .execute(pool)
+            .execute(&mut *tx)
             .await?;
     }
 
-    // Make sure we insert the submitter column too:
     for note in &exhibit.notes {
-        sqlx::query(
-            "INSERT INTO exhibit_notes (exhibit_id, submitter, date, time, message)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+        note_repo::create_note(
+            NoteTarget::Exhibit,
+            &exhibit_id.to_string(),
+            &note.submitter,
+            &note.message,
+            Utc::now(),
+            &mut *tx,
         )
-        .bind(exhibit_id)
-        .bind(&note.submitter)
-        .bind(&note.timestamp.date)
-        .bind(&note.timestamp.time)
-        .bind(&note.message)
-        .execute(pool)
         .await?;
     }
 
+    let event = ChangeEvent::new(
+        Entity::Exhibit,
+        Action::Created,
+        Some(exhibit_id.to_string()),
+    );
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
     Ok(())
 }
 
+/// Applies a merge-patch style update to an exhibit and bumps its version.
+///
+/// Scalar fields, the current sponsorship and (when given) the part list are written in one
+/// transaction. When `expected_version` is given the update only applies if the stored
+/// version still matches. Returns the new version, or `None` if the exhibit doesn't exist
+/// or is stale.
 pub async fn update_exhibit(
     id: &i64,
     exhibit: &UpdateExhibit,
+    expected_version: Option<i64>,
     pool: &DbPool,
-) -> Result<(), sqlx::Error> {
-    // Update only fields that are provided
-    let mut query = "UPDATE exhibits SET ".to_string();
-    let mut params: Vec<String> = Vec::new();
+) -> Result<Option<i64>, sqlx::Error> {
+    // Collect the columns that are part of the patch, with the value to write
+    let mut columns: Vec<(&str, Option<&str>)> = Vec::new();
 
-    if let Some(_name) = &exhibit.name {
-        params.push("name = ?".to_string());
+    if let Some(Some(name)) = &exhibit.name {
+        columns.push(("name", Some(name)));
     }
-    if let Some(_cluster) = &exhibit.cluster {
-        params.push("cluster = ?".to_string());
+    if let Some(Some(cluster)) = &exhibit.cluster {
+        columns.push(("cluster", Some(cluster)));
     }
-    if let Some(_location) = &exhibit.location {
-        params.push("location = ?".to_string());
+    if let Some(Some(location)) = &exhibit.location {
+        columns.push(("location", Some(location)));
     }
-    if let Some(_description) = &exhibit.description {
-        params.push("description = ?".to_string());
+    if let Some(description) = &exhibit.description {
+        // A cleared description is stored as an empty string; the column is NOT NULL
+        columns.push(("description", Some(description.as_deref().unwrap_or(""))));
     }
-    if let Some(_image_url) = &exhibit.image_url {
-        params.push("image_url = ?".to_string());
+    if let Some(Some(status)) = &exhibit.status {
+        columns.push(("status", Some(status)));
     }
-
-    if params.is_empty() {
-        // No fields to update
-        return Ok(());
+    if let Some(Some(image_url)) = &exhibit.image_url {
+        columns.push(("image_url", Some(image_url)));
     }
 
-    query.push_str(&params.join(", "));
-    query.push_str(" WHERE id = ?");
+    let mut params: Vec<String> = columns
+        .iter()
+        .map(|(column, _)| format!("{} = ?", column))
+        .collect();
 
-    // Build the query dynamically
-    let mut query_builder = sqlx::query::<Sqlite>(&query);
+    // Always bump the version, even if no fields were provided
+    params.push("version = version + 1".to_string());
 
-    // Bind values in the correct order
-    if let Some(name) = &exhibit.name {
-        query_builder = query_builder.bind(name);
-    }
-    if let Some(cluster) = &exhibit.cluster {
-        query_builder = query_builder.bind(cluster);
+    let mut query = format!(
+        "UPDATE exhibits SET {} WHERE id = ? AND deleted_at IS NULL",
+        params.join(", ")
+    );
+    if expected_version.is_some() {
+        query.push_str(" AND version = ?");
     }
-    if let Some(location) = &exhibit.location {
-        query_builder = query_builder.bind(location);
-    }
-    if let Some(description) = &exhibit.description {
-        query_builder = query_builder.bind(description);
-    }
-    if let Some(image_url) = &exhibit.image_url {
-        query_builder = query_builder.bind(image_url);
+    query.push_str(" RETURNING version");
+
+    // Build the query dynamically, binding values in the same order as the columns
+    let mut query_builder = sqlx::query_scalar::<Sqlite, i64>(&query);
+    for (_, value) in &columns {
+        query_builder = query_builder.bind(*value);
     }
 
-    // Bind the ID last (for the WHERE clause)
+    // Bind the ID and expected version last (for the WHERE clause)
     query_builder = query_builder.bind(id);
+    if let Some(expected_version) = expected_version {
+        query_builder = query_builder.bind(expected_version);
+    }
 
-    // Execute the query
-    query_builder.execute(pool).await?;
-
-    Ok(())
-}
-
-pub async fn delete_exhibit(id: i64, pool: &DbPool) -> Result<()> {
-    sqlx::query("DELETE FROM exhibits WHERE id = ?1")
-        .bind(id)
-        .execute(pool)
-        .await?;
-
-    Ok(())
-}
-
-pub async fn get_all_exhibits(pool: &DbPool) -> Result<Option<Vec<Exhibit>>> {
-    let exhibit_rows = sqlx::query_as::<_, ExhibitRow>(
-        "SELECT id, name, cluster, location, description, status, image_url,
-                sponsor_name, sponsor_start_date, sponsor_end_date
-         FROM exhibits",
-    )
-    .fetch_all(pool)
-    .await?;
-
-    let mut exhibits = Vec::new();
-
-    for exhibit_row in exhibit_rows {
-        let sponsor = match (
-            exhibit_row.sponsor_name,
-            exhibit_row.sponsor_start_date,
-            exhibit_row.sponsor_end_date,
-        ) {
-            (Some(name), Some(start_date), Some(end_date)) => Some(Sponsor {
-                name,
-                start_date,
-                end_date,
-            }),
-            _ => None,
-        };
+    let mut tx = pool.begin().await?;
 
-        let part_rows = sqlx::query_as::<_, ExhibitPartRow>(
-            "SELECT part_id FROM exhibit_parts WHERE exhibit_id = ?1",
-        )
-        .bind(exhibit_row.id)
-        .fetch_all(pool)
-        .await?;
+    let Some(new_version) = query_builder.fetch_optional(&mut *tx).await? else {
+        tx.rollback().await?;
+        return Ok(None);
+    };
 
-        let part_ids: Vec<i64> = part_rows.iter().map(|row| row.part_id).collect();
+    if let Some(sponsor) = &exhibit.sponsor {
+        sponsorship_repo::set_current_sponsor(*id, sponsor.as_ref(), site_today(), &mut tx).await?;
+    }
 
-        // Again, include 'submitter' in the SELECT
-        let note_rows = sqlx::query_as::<_, ExhibitNoteRow>(
-            "SELECT id, submitter, date, time, message
-             FROM exhibit_notes
-             WHERE exhibit_id = ?1",
-        )
-        .bind(exhibit_row.id)
-        .fetch_all(pool)
-        .await?;
+    // Replace the part list if one was provided
+    if let Some(Some(part_ids)) = &exhibit.part_ids {
+        sqlx::query("DELETE FROM exhibit_parts WHERE exhibit_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
 
-        let notes = note_rows
-            .iter()
-            .map(|row| Note {
-                id: row.id,
-                submitter: row.submitter.clone(),
-                timestamp: Timestamp {
-                    date: row.date.clone(),
-                    time: row.time.clone(),
-                },
-                message: row.message.clone(),
-            })
-            .collect();
-
-        exhibits.push(Exhibit {
-            id: exhibit_row.id,
-            name: exhibit_row.name,
-            cluster: exhibit_row.cluster,
-            location: exhibit_row.location,
-            description: exhibit_row.description,
-            status: exhibit_row.status,
-            image_url: exhibit_row.image_url,
-            sponsor,
-            part_ids,
-            notes,
-        });
+        for part_id in part_ids {
+            sqlx::query(
+                "INSERT OR IGNORE INTO exhibit_parts (exhibit_id, part_id) VALUES (?1, ?2)",
+            )
+            .bind(id)
+            .bind(part_id)
+            .execute(&mut *tx)
+            .await?;
+        }
     }
 
-    let response = match exhibits.is_empty() {
-        true => None,
-        false => Some(exhibits),
+    let change = ChangeEvent::new(Entity::Exhibit, Action::Updated, Some(id.to_string()));
+    let event = match &exhibit.status {
+        Some(Some(status)) => change.with_data(json!({ "status": status })),
+        _ => change,
     };
+    events::record(&event, &mut *tx).await?;
 
-    Ok(response)
-}
+    tx.commit().await?;
 
-pub async fn get_exhibit_note(id: i64, note_id: i64, pool: &DbPool) -> Result<Option<Note>> {
-    // Include submitter
-    let note_row = sqlx::query_as::<_, ExhibitNoteRow>(
-        "SELECT id, submitter, date, time, message
-         FROM exhibit_notes
-         WHERE exhibit_id = ?1 AND id = ?2",
-    )
-    .bind(id)
-    .bind(note_id)
-    .fetch_optional(pool)
-    .await?;
+    events::publish(event);
 
-    Ok(note_row.map(|row| Note {
-        id: row.id,
-        submitter: row.submitter,
-        timestamp: Timestamp {
-            date: row.date,
-            time: row.time,
-        },
-        message: row.message,
-    }))
+    Ok(Some(new_version))
 }
 
-pub async fn create_exhibit_note(
-    id: i64,
-    submitter: String,
-    message: String,
-    pool: &DbPool,
-) -> Result<()> {
-    // Get the current time in UTC
-    let now_utc: DateTime<Utc> = Utc::now();
+/// Moves an exhibit to the trash. Its notes, parts and schedules are kept until it's purged.
+pub async fn delete_exhibit(id: i64, pool: &DbPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
 
-    // Convert the time to Central Time (UTC-6)
-    let now_central = now_utc.with_timezone(&FixedOffset::west_opt(6 * 3600).unwrap());
+    let result =
+        sqlx::query("UPDATE exhibits SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
 
-    // Extract the date and time components
-    let date = now_central.date_naive().to_string();
-    let time = now_central.time().to_string();
+    // Already in the trash, or never existed
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
 
-    sqlx::query(
-        "INSERT INTO exhibit_notes (exhibit_id, submitter, date, time, message)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
-    )
-    .bind(id)
-    .bind(submitter)
-    .bind(date)
-    .bind(time)
-    .bind(message)
-    .execute(pool)
-    .await?;
+    let event = ChangeEvent::new(Entity::Exhibit, Action::Deleted, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
 
     Ok(())
 }
 
-pub async fn delete_exhibit_note(id: i64, note_id: i64, pool: &DbPool) -> Result<()> {
-    sqlx::query("DELETE FROM exhibit_notes WHERE exhibit_id = ?1 AND id = ?2")
-        .bind(id)
-        .bind(note_id)
-        .execute(pool)
-        .await?;
+pub async fn get_all_exhibits(pool: &DbPool) -> Result<Option<Vec<Exhibit>>> {
+    let exhibits = find_exhibits(&ExhibitFilter::default(), 0, None, pool).await?;
 
-    Ok(())
+    let response = match exhibits.is_empty() {
+        true => None,
+        false => Some(exhibits),
+    };
+
+    Ok(response)
 }
 
-pub async fn get_all_exhibit_notes(id: i64, pool: &DbPool) -> Result<Option<Vec<Note>>> {
-    // Include submitter in the SELECT
-    let note_rows = sqlx::query_as::<_, ExhibitNoteRow>(
-        "SELECT id, submitter, date, time, message
-         FROM exhibit_notes
-         WHERE exhibit_id = ?1",
+/// Lists exhibits matching `filter` in ID order, starting after `after_id`.
+///
+/// With a `limit` this returns one page at a time, so callers can walk every exhibit
+/// without holding them all in memory.
+pub async fn find_exhibits(
+    filter: &ExhibitFilter,
+    after_id: i64,
+    limit: Option<i64>,
+    pool: &DbPool,
+) -> Result<Vec<Exhibit>> {
+    let exhibit_rows = sqlx::query_as::<_, ExhibitRow>(
+        "SELECT id, name, cluster, location, description, status, image_url, version
+         FROM exhibits
+         WHERE deleted_at IS NULL AND id > ?1
+           AND (?2 IS NULL OR instr(lower(name), lower(?2)) > 0)
+           AND (?3 IS NULL OR cluster = ?3)
+           AND (?4 IS NULL OR location = ?4)
+           AND (?5 IS NULL OR lower(status) = lower(?5))
+         ORDER BY id
+         LIMIT ?6",
     )
-    .bind(id)
+    .bind(after_id)
+    .bind(&filter.search)
+    .bind(&filter.cluster)
+    .bind(&filter.location)
+    .bind(&filter.status)
+    .bind(limit.unwrap_or(-1))
     .fetch_all(pool)
     .await?;
 
-    let notes: Vec<Note> = note_rows
-        .iter()
-        .map(|row| Note {
-            id: row.id,
-            submitter: row.submitter.clone(),
-            timestamp: Timestamp {
-                date: row.date.clone(),
-                time: row.time.clone(),
-            },
-            message: row.message.clone(),
-        })
-        .collect();
-
-    let response = if notes.is_empty() { None } else { Some(notes) };
+    let mut exhibits = Vec::with_capacity(exhibit_rows.len());
+    for exhibit_row in exhibit_rows {
+        exhibits.push(into_exhibit(exhibit_row, pool).await?);
+    }
 
-    Ok(response)
+    Ok(exhibits)
 }
 
 pub async fn add_part_to_exhibit(exhibit_id: i64, part_id: i64, pool: &DbPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
     // Check if the exhibit exists
-    let exhibit_exists = sqlx::query("SELECT 1 FROM exhibits WHERE id = ?1")
+    let exhibit_exists = sqlx::query("SELECT 1 FROM exhibits WHERE id = ?1 AND deleted_at IS NULL")
         .bind(exhibit_id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *tx)
         .await?
         .is_some();
 
@@ -465,9 +382,9 @@ pub async fn add_part_to_exhibit(exhibit_id: i64, part_id: i64, pool: &DbPool) -
     }
 
     // Check if the part exists (assuming you have a parts table)
-    let part_exists = sqlx::query("SELECT 1 FROM parts WHERE id = ?1")
+    let part_exists = sqlx::query("SELECT 1 FROM parts WHERE id = ?1 AND deleted_at IS NULL")
         .bind(part_id)
-        .fetch_optional(pool)
+        .fetch_optional(&mut *tx)
         .await?
         .is_some();
 
@@ -480,7 +397,7 @@ pub async fn add_part_to_exhibit(exhibit_id: i64, part_id: i64, pool: &DbPool) -
         sqlx::query("SELECT 1 FROM exhibit_parts WHERE exhibit_id = ?1 AND part_id = ?2")
             .bind(exhibit_id)
             .bind(part_id)
-            .fetch_optional(pool)
+            .fetch_optional(&mut *tx)
             .await?
             .is_some();
 
@@ -493,22 +410,304 @@ pub async fn add_part_to_exhibit(exhibit_id: i64, part_id: i64, pool: &DbPool) -
     sqlx::query("INSERT INTO exhibit_parts (exhibit_id, part_id) VALUES (?1, ?2)")
         .bind(exhibit_id)
         .bind(part_id)
-        .execute(pool)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE exhibits SET version = version + 1 WHERE id = ?1")
+        .bind(exhibit_id)
+        .execute(&mut *tx)
         .await?;
 
+    let event = ChangeEvent::new(
+        Entity::Exhibit,
+        Action::Updated,
+        Some(exhibit_id.to_string()),
+    );
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
     Ok(())
 }
 
 pub async fn change_exhibit_status(id: i64, new_status: String, pool: &DbPool) -> Result<()> {
-    let result = sqlx::query("UPDATE exhibits SET status = ?1 WHERE id = ?2")
-        .bind(new_status)
-        .bind(id)
-        .execute(pool)
-        .await?;
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE exhibits SET status = ?1, version = version + 1
+             WHERE id = ?2 AND deleted_at IS NULL",
+    )
+    .bind(&new_status)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
 
     if result.rows_affected() == 0 {
         return Err(sqlx::Error::RowNotFound);
     }
 
+    let event = ChangeEvent::new(Entity::Exhibit, Action::Updated, Some(id.to_string()))
+        .with_data(json!({ "status": new_status }));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
     Ok(())
 }
+
+/// Applies a batch action to every exhibit in `request.ids` inside a single transaction.
+///
+/// IDs that don't exist are reported as `NotFound` and skipped; any database error rolls
+/// back the whole batch. In dry-run mode the changes are computed but never written.
+pub async fn apply_exhibit_batch(
+    request: &ExhibitBatchRequest,
+    pool: &DbPool,
+) -> Result<Vec<ExhibitBatchItemResult>> {
+    let mut tx = pool.begin().await?;
+    let mut results = Vec::with_capacity(request.ids.len());
+
+    for id in &request.ids {
+        let current = sqlx::query_as::<_, ExhibitBatchRow>(
+            "SELECT status, cluster, location FROM exhibits WHERE id = ?1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(current) = current else {
+            results.push(ExhibitBatchItemResult {
+                id: *id,
+                outcome: ExhibitBatchOutcome::NotFound,
+                changes: Vec::new(),
+            });
+            continue;
+        };
+
+        if let ExhibitBatchAction::Delete = request.action {
+            if !request.dry_run {
+                sqlx::query("UPDATE exhibits SET deleted_at = ?1 WHERE id = ?2")
+                    .bind(Utc::now())
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            results.push(ExhibitBatchItemResult {
+                id: *id,
+                outcome: ExhibitBatchOutcome::Deleted,
+                changes: Vec::new(),
+            });
+            continue;
+        }
+
+        let (status, cluster, location) = match &request.action {
+            ExhibitBatchAction::ChangeStatus { status } => (
+                status.clone(),
+                current.cluster.clone(),
+                current.location.clone(),
+            ),
+            ExhibitBatchAction::Move { cluster, location } => (
+                current.status.clone(),
+                cluster.clone().unwrap_or_else(|| current.cluster.clone()),
+                location.clone().unwrap_or_else(|| current.location.clone()),
+            ),
+            ExhibitBatchAction::Delete => unreachable!("handled above"),
+        };
+
+        let changes: Vec<ExhibitFieldChange> = [
+            ("status", &current.status, &status),
+            ("cluster", &current.cluster, &cluster),
+            ("location", &current.location, &location),
+        ]
+        .into_iter()
+        .filter(|(_, from, to)| from != to)
+        .map(|(field, from, to)| ExhibitFieldChange {
+            field: field.to_string(),
+            from: from.clone(),
+            to: to.clone(),
+        })
+        .collect();
+
+        if changes.is_empty() {
+            results.push(ExhibitBatchItemResult {
+                id: *id,
+                outcome: ExhibitBatchOutcome::Unchanged,
+                changes,
+            });
+            continue;
+        }
+
+        if !request.dry_run {
+            sqlx::query(
+                "UPDATE exhibits SET status = ?1, cluster = ?2, location = ?3, version = version + 1
+                 WHERE id = ?4",
+            )
+            .bind(&status)
+            .bind(&cluster)
+            .bind(&location)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        results.push(ExhibitBatchItemResult {
+            id: *id,
+            outcome: ExhibitBatchOutcome::Updated,
+            changes,
+        });
+    }
+
+    if request.dry_run {
+        tx.rollback().await?;
+        return Ok(results);
+    }
+
+    let mut changes = Vec::new();
+    for result in &results {
+        let action = match result.outcome {
+            ExhibitBatchOutcome::Updated => Action::Updated,
+            ExhibitBatchOutcome::Deleted => Action::Deleted,
+            _ => continue,
+        };
+        let change = ChangeEvent::new(Entity::Exhibit, action, Some(result.id.to_string()));
+        let change = match &request.action {
+            ExhibitBatchAction::ChangeStatus { status } if action == Action::Updated => {
+                change.with_data(json!({ "status": status }))
+            }
+            _ => change,
+        };
+        events::record(&change, &mut *tx).await?;
+        changes.push(change);
+    }
+    tx.commit().await?;
+
+    for change in changes {
+        events::publish(change);
+    }
+
+    Ok(results)
+}
+
+/// Creates or updates one exhibit per imported row, matching existing exhibits by name
+/// (ignoring case), inside a single transaction.
+///
+/// Rows must already be validated. A row's legacy notes become a note on the exhibit unless
+/// it already has a note with the same text, so importing the same sheet twice is harmless.
+/// A blank image keeps the current image on update and uses `default_image_url` on create.
+/// In dry-run mode the outcomes are worked out but nothing is written.
+pub async fn import_exhibits(
+    rows: &[ImportRow],
+    default_image_url: &str,
+    dry_run: bool,
+    pool: &DbPool,
+) -> Result<Vec<ImportOutcome>> {
+    let mut tx = pool.begin().await?;
+    let mut outcomes = Vec::with_capacity(rows.len());
+    let mut changes = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let existing_id = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM exhibits
+             WHERE name = ?1 COLLATE NOCASE AND deleted_at IS NULL
+             ORDER BY id
+             LIMIT 1",
+        )
+        .bind(&row.name)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let outcome = match existing_id {
+            Some(_) => ImportOutcome::Updated,
+            None => ImportOutcome::Created,
+        };
+        outcomes.push(outcome);
+
+        if dry_run {
+            continue;
+        }
+
+        let exhibit_id = match existing_id {
+            Some(id) => {
+                sqlx::query(
+                    "UPDATE exhibits
+                     SET name = ?1, cluster = ?2, location = ?3, description = ?4, status = ?5,
+                         image_url = COALESCE(?6, image_url), version = version + 1
+                     WHERE id = ?7",
+                )
+                .bind(&row.name)
+                .bind(&row.cluster)
+                .bind(&row.location)
+                .bind(&row.description)
+                .bind(&row.status)
+                .bind(&row.image_url)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+
+                id
+            }
+            None => sqlx::query(
+                "INSERT INTO exhibits (name, cluster, location, description, status, image_url)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .bind(&row.name)
+            .bind(&row.cluster)
+            .bind(&row.location)
+            .bind(&row.description)
+            .bind(&row.status)
+            .bind(row.image_url.as_deref().unwrap_or(default_image_url))
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid(),
+        };
+
+        // One event per exhibit, with its status, so webhooks can filter imports like any
+        // other change
+        let action = match existing_id {
+            Some(_) => Action::Updated,
+            None => Action::Created,
+        };
+        let change = ChangeEvent::new(Entity::Exhibit, action, Some(exhibit_id.to_string()))
+            .with_data(json!({ "status": row.status }));
+        events::record(&change, &mut *tx).await?;
+        changes.push(change);
+
+        if let Some(message) = &row.notes {
+            let target_id = exhibit_id.to_string();
+            let already_noted = sqlx::query(
+                "SELECT 1 FROM notes WHERE target_type = ?1 AND target_id = ?2 AND message = ?3",
+            )
+            .bind(NoteTarget::Exhibit)
+            .bind(&target_id)
+            .bind(message)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+
+            if !already_noted {
+                note_repo::create_note(
+                    NoteTarget::Exhibit,
+                    &target_id,
+                    IMPORTED_NOTE_SUBMITTER,
+                    message,
+                    Utc::now(),
+                    &mut *tx,
+                )
+                .await?;
+            }
+        }
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+        for change in changes {
+            events::publish(change);
+        }
+    }
+
+    Ok(outcomes)
+}
@@ -1,16 +1,28 @@
-use crate::db::DbPool;
-use crate::models::{FullName, Jotform, SubmissionDate};
-use log::error;
+use crate::api::jotform_handlers::JotformFilter;
+use crate::db::{add_column_if_missing, column_exists, DbPool};
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::models::{FullName, Jotform, UNKNOWN_CREATED_AT};
+use crate::time_zone::site_local_to_utc;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use log::{error, warn};
+use rocket::serde::json::json;
+use sqlx::sqlite::{SqliteConnection, SqliteExecutor};
 use sqlx::FromRow;
-use sqlx::Result;
+use sqlx::{Connection, Result};
+
+#[derive(FromRow)]
+struct LegacyCreatedAtRow {
+    id: String,
+    created_at_date: String,
+    created_at_time: String,
+}
 
 #[derive(FromRow)]
 pub struct JotformRow {
     pub id: String,
     pub submitter_first_name: String,
     pub submitter_last_name: String,
-    pub created_at_date: String,
-    pub created_at_time: String,
+    pub created_at: DateTime<Utc>,
     pub location: String,
     pub exhibit_name: String,
     pub description: String,
@@ -19,39 +31,221 @@ pub struct JotformRow {
     pub status: String,
 }
 
-pub async fn create_jotform_tables(pool: &DbPool) -> Result<()> {
-    sqlx::query(
+/// The statement creating the tickets table under `name`, if it doesn't exist yet.
+fn create_jotforms_table(name: &str) -> String {
+    format!(
         r#"
-        CREATE TABLE IF NOT EXISTS jotforms (
+        CREATE TABLE IF NOT EXISTS {name} (
             id TEXT PRIMARY KEY,
             submitter_first_name TEXT NOT NULL,
             submitter_last_name TEXT NOT NULL,
-            created_at_date TEXT NOT NULL,
-            created_at_time TEXT NOT NULL,
+            created_at TEXT NOT NULL,
             location TEXT NOT NULL,
             exhibit_name TEXT NOT NULL,
             description TEXT NOT NULL,
             priority_level TEXT NOT NULL,
             department TEXT NOT NULL,
-            status TEXT NOT NULL
+            status TEXT NOT NULL,
+            deleted_at TEXT
+        )
+        "#
+    )
+}
+
+pub async fn create_jotform_tables(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query(&create_jotforms_table("jotforms"))
+        .execute(&mut *conn)
+        .await?;
+
+    // IDs of purged tickets, so syncing doesn't bring them back from Jotform
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS purged_jotforms (
+            id TEXT PRIMARY KEY
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Emails still to be sent about new tickets, kept until they're sent so none are lost
+    // to a restart or a failed send
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ticket_alerts (
+            ticket_id TEXT PRIMARY KEY,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at TEXT,
+            last_error TEXT
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
+    migrate_split_created_at(&mut *conn).await?;
+    add_column_if_missing(&mut *conn, "jotforms", "deleted_at", "TEXT").await?;
+
     Ok(())
 }
 
+/// Replaces the old `created_at_date`/`created_at_time` columns, which held wall-clock
+/// strings in the site time zone, with a single UTC `created_at`. Does nothing once the
+/// old columns are gone.
+///
+/// SQLite can't make a column NOT NULL once it's added, so the table is rebuilt with the
+/// current schema once every row has its `created_at`.
+async fn migrate_split_created_at(conn: &mut SqliteConnection) -> Result<()> {
+    if !column_exists(&mut *conn, "jotforms", "created_at_date").await? {
+        return Ok(());
+    }
+
+    add_column_if_missing(&mut *conn, "jotforms", "created_at", "TEXT").await?;
+
+    let rows = sqlx::query_as::<_, LegacyCreatedAtRow>(
+        "SELECT id, created_at_date, created_at_time FROM jotforms",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut tx = conn.begin().await?;
+
+    for row in &rows {
+        sqlx::query("UPDATE jotforms SET created_at = ?1 WHERE id = ?2")
+            .bind(legacy_created_at_to_utc(
+                &row.id,
+                &row.created_at_date,
+                &row.created_at_time,
+            ))
+            .bind(&row.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query("DROP TABLE IF EXISTS jotforms_migrated")
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(&create_jotforms_table("jotforms_migrated"))
+        .execute(&mut *tx)
+        .await?;
+
+    // Only the columns both tables have are copied, which leaves the old ones behind
+    let columns: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM pragma_table_info('jotforms_migrated')
+         WHERE name IN (SELECT name FROM pragma_table_info('jotforms'))",
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|column: String| format!("\"{}\"", column))
+    .collect();
+    let columns = columns.join(", ");
+    sqlx::query(&format!(
+        "INSERT INTO jotforms_migrated ({columns}) SELECT {columns} FROM jotforms"
+    ))
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DROP TABLE jotforms").execute(&mut *tx).await?;
+    sqlx::query("ALTER TABLE jotforms_migrated RENAME TO jotforms")
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Converts a stored date and time in the site time zone to UTC.
+///
+/// Unparseable times fall back to midnight on the date, and times skipped by a daylight
+/// saving change move forward an hour. Unparseable dates become `UNKNOWN_CREATED_AT`.
+fn legacy_created_at_to_utc(id: &str, date: &str, time: &str) -> DateTime<Utc> {
+    let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+        warn!(
+            "Jotform {} has an invalid date `{}`, storing it as submitted at {}",
+            id, date, UNKNOWN_CREATED_AT
+        );
+        return UNKNOWN_CREATED_AT;
+    };
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S%.f").unwrap_or(NaiveTime::MIN);
+    let local = NaiveDateTime::new(date, time);
+
+    site_local_to_utc(local)
+        .or_else(|| site_local_to_utc(local + TimeDelta::hours(1)))
+        .unwrap_or(UNKNOWN_CREATED_AT)
+}
+
+// Syncs and maintenance schedules insert tickets in their own transactions with
+// `insert_new_jotform`; these insert one on its own, which only tests need to.
+
+#[cfg(test)]
 pub async fn insert_jotform(jotform: &Jotform, pool: &DbPool) -> Result<()> {
-    sqlx::query(
+    insert_jotform_and_alert(jotform, None, pool).await
+}
+
+/// Inserts a new ticket along with an alert to email its department about it, so the
+/// alert is queued if and only if the ticket is.
+#[cfg(test)]
+pub async fn insert_jotform_with_alert(
+    jotform: &Jotform,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> Result<()> {
+    insert_jotform_and_alert(jotform, Some(now), pool).await
+}
+
+#[cfg(test)]
+async fn insert_jotform_and_alert(
+    jotform: &Jotform,
+    alert_at: Option<DateTime<Utc>>,
+    pool: &DbPool,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let Some(event) = insert_new_jotform(jotform, &mut tx).await? else {
+        return Ok(());
+    };
+    if let Some(alert_at) = alert_at {
+        queue_ticket_alert(&jotform.id, alert_at, &mut tx).await?;
+    }
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(())
+}
+
+/// Queues an email to a ticket's department about it, first tried at `at`. Call it in the
+/// transaction inserting the ticket.
+pub async fn queue_ticket_alert(
+    ticket_id: &str,
+    at: DateTime<Utc>,
+    conn: &mut SqliteConnection,
+) -> Result<()> {
+    sqlx::query("INSERT INTO ticket_alerts (ticket_id, next_attempt_at) VALUES (?1, ?2)")
+        .bind(ticket_id)
+        .bind(at)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Inserts a ticket as part of a transaction, unless one with its ID is already stored.
+///
+/// Returns the ticket's `created` event, already in the outbox, to publish once the
+/// transaction is committed, or `None` if the ticket was already there.
+pub async fn insert_new_jotform(
+    jotform: &Jotform,
+    conn: &mut SqliteConnection,
+) -> Result<Option<ChangeEvent>> {
+    let result = sqlx::query(
         r#"
         INSERT INTO jotforms (
             id,
             submitter_first_name,
             submitter_last_name,
-            created_at_date,
-            created_at_time,
+            created_at,
             location,
             exhibit_name,
             description,
@@ -59,131 +253,215 @@ pub async fn insert_jotform(jotform: &Jotform, pool: &DbPool) -> Result<()> {
             department,
             status
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (id) DO NOTHING
         "#,
     )
     .bind(&jotform.id)
     .bind(&jotform.submitter_name.first)
     .bind(&jotform.submitter_name.last)
-    .bind(&jotform.created_at.date)
-    .bind(&jotform.created_at.time)
+    .bind(jotform.created_at)
     .bind(&jotform.location)
     .bind(&jotform.exhibit_name)
     .bind(&jotform.description)
     .bind(&jotform.priority_level)
     .bind(&jotform.department)
     .bind(&jotform.status)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
-    Ok(())
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let event = ChangeEvent::new(Entity::Jotform, Action::Created, Some(jotform.id.clone()))
+        .with_data(
+            json!({ "priority_level": jotform.priority_level, "department": jotform.department }),
+        );
+    events::record(&event, &mut *conn).await?;
+
+    Ok(Some(event))
 }
 
-pub async fn get_jotform(id: String, pool: &DbPool) -> Result<Option<Jotform>> {
-    let jotform = sqlx::query_as::<_, JotformRow>("SELECT * FROM jotforms WHERE id = $1")
-        .bind(&id)
-        .fetch_optional(pool)
-        .await?;
+/// When the newest ticket stored was submitted, trashed ones included, or `None` if there
+/// are none.
+pub async fn get_newest_created_at(pool: &DbPool) -> Result<Option<DateTime<Utc>>> {
+    sqlx::query_scalar("SELECT MAX(created_at) FROM jotforms")
+        .fetch_one(pool)
+        .await
+}
 
-    match jotform {
-        Some(jotform) => Ok(Some(Jotform {
-            id: jotform.id,
-            submitter_name: {
-                FullName {
-                    first: jotform.submitter_first_name,
-                    last: jotform.submitter_last_name,
-                }
-            },
-            created_at: {
-                SubmissionDate {
-                    date: jotform.created_at_date,
-                    time: jotform.created_at_time,
-                }
-            },
-            location: jotform.location,
-            exhibit_name: jotform.exhibit_name,
-            description: jotform.description,
-            priority_level: jotform.priority_level,
-            department: jotform.department,
-            status: jotform.status,
-        })),
-        None => Ok(None),
-    }
+/// A queued email about a new ticket.
+#[derive(Debug, FromRow)]
+pub struct DueTicketAlert {
+    pub ticket_id: String,
+    pub attempts: i64,
 }
 
-pub async fn get_all_jotforms(pool: &DbPool) -> Result<Option<Vec<Jotform>>> {
-    let jotforms = sqlx::query_as::<_, JotformRow>("SELECT * FROM jotforms")
-        .fetch_all(pool)
-        .await?;
+/// Lists the ticket alerts due by `now`, oldest first.
+pub async fn get_due_ticket_alerts(
+    now: DateTime<Utc>,
+    limit: i64,
+    pool: &DbPool,
+) -> Result<Vec<DueTicketAlert>> {
+    sqlx::query_as::<_, DueTicketAlert>(
+        "SELECT ticket_id, attempts FROM ticket_alerts
+         WHERE next_attempt_at <= ?1
+         ORDER BY next_attempt_at, ticket_id
+         LIMIT ?2",
+    )
+    .bind(now)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
 
-    if jotforms.is_empty() {
-        return Ok(None);
-    }
+/// Records an attempt at sending a ticket alert.
+///
+/// A sent alert is removed. A failed one is retried at `retry_at`, or kept with its error
+/// and given up on if that's `None`.
+pub async fn record_ticket_alert_attempt(
+    ticket_id: &str,
+    error: Option<&str>,
+    retry_at: Option<DateTime<Utc>>,
+    pool: &DbPool,
+) -> Result<()> {
+    let query = match error {
+        None => sqlx::query("DELETE FROM ticket_alerts WHERE ticket_id = ?1").bind(ticket_id),
+        Some(error) => sqlx::query(
+            "UPDATE ticket_alerts
+             SET attempts = attempts + 1, next_attempt_at = ?2, last_error = ?3
+             WHERE ticket_id = ?1",
+        )
+        .bind(ticket_id)
+        .bind(retry_at)
+        .bind(error),
+    };
+    query.execute(pool).await?;
+
+    Ok(())
+}
 
-    let jotforms = jotforms
-        .iter()
-        .map(|jotform| Jotform {
-            id: jotform.id.clone(),
+impl From<JotformRow> for Jotform {
+    fn from(row: JotformRow) -> Self {
+        Jotform {
+            id: row.id,
             submitter_name: FullName {
-                first: jotform.submitter_first_name.clone(),
-                last: jotform.submitter_last_name.clone(),
+                first: row.submitter_first_name,
+                last: row.submitter_last_name,
             },
-            created_at: {
-                SubmissionDate {
-                    date: jotform.created_at_date.clone(),
-                    time: jotform.created_at_time.clone(),
-                }
-            },
-            location: jotform.location.clone(),
-            exhibit_name: jotform.exhibit_name.clone(),
-            description: jotform.description.clone(),
-            priority_level: jotform.priority_level.clone(),
-            department: jotform.department.clone(),
-            status: jotform.status.clone(),
-        })
-        .collect();
+            created_at: row.created_at,
+            location: row.location,
+            exhibit_name: row.exhibit_name,
+            description: row.description,
+            priority_level: row.priority_level,
+            department: row.department,
+            status: row.status,
+        }
+    }
+}
 
-    Ok(Some(jotforms))
+pub async fn get_jotform(id: String, pool: &DbPool) -> Result<Option<Jotform>> {
+    let jotform = sqlx::query_as::<_, JotformRow>(
+        "SELECT * FROM jotforms WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(&id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(jotform.map(Jotform::from))
+}
+
+/// Lists tickets matching `filter` in ID order, starting after `after_id`.
+///
+/// With a `limit` this returns one page at a time, so callers can walk every ticket without
+/// holding them all in memory.
+pub async fn find_jotforms(
+    filter: &JotformFilter,
+    after_id: &str,
+    limit: Option<i64>,
+    pool: &DbPool,
+) -> Result<Vec<Jotform>> {
+    let jotforms = sqlx::query_as::<_, JotformRow>(
+        "SELECT * FROM jotforms
+         WHERE deleted_at IS NULL AND id > $1
+           AND ($2 IS NULL OR instr(lower(
+               submitter_first_name || ' ' || submitter_last_name || ' ' || location || ' ' ||
+               exhibit_name || ' ' || description
+           ), lower($2)) > 0)
+           AND ($3 IS NULL OR status = $3)
+           AND ($4 IS NULL OR department = $4)
+           AND ($5 IS NULL OR priority_level = $5)
+         ORDER BY id
+         LIMIT $6",
+    )
+    .bind(after_id)
+    .bind(&filter.search)
+    .bind(&filter.status)
+    .bind(&filter.department)
+    .bind(&filter.priority_level)
+    .bind(limit.unwrap_or(-1))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(jotforms.into_iter().map(Jotform::from).collect())
 }
 
-pub async fn update_jotform(jotform: &Jotform, pool: &DbPool) -> Result<()> {
+pub async fn update_jotform<'e>(
+    jotform: &Jotform,
+    executor: impl SqliteExecutor<'e>,
+) -> Result<()> {
     sqlx::query(
         r#"
         UPDATE jotforms
         SET
             submitter_first_name = $1,
             submitter_last_name = $2,
-            created_at_date = $3,
-            created_at_time = $4,
-            location = $5,
-            exhibit_name = $6,
-            description = $7,
-            priority_level = $8,
-            department = $9
-        WHERE id = $10
+            created_at = $3,
+            location = $4,
+            exhibit_name = $5,
+            description = $6,
+            priority_level = $7,
+            department = $8
+        WHERE id = $9
         "#,
     )
     .bind(&jotform.submitter_name.first)
     .bind(&jotform.submitter_name.last)
-    .bind(&jotform.created_at.date)
-    .bind(&jotform.created_at.time)
+    .bind(jotform.created_at)
     .bind(&jotform.location)
     .bind(&jotform.exhibit_name)
     .bind(&jotform.description)
     .bind(&jotform.priority_level)
     .bind(&jotform.department)
     .bind(&jotform.id)
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     Ok(())
 }
 
+/// Moves a ticket to the trash. Its notes are kept until it's purged.
 pub async fn delete_jotform(id: String, pool: &DbPool) -> Result<()> {
-    sqlx::query("DELETE FROM jotforms WHERE id = $1")
-        .bind(&id)
-        .execute(pool)
-        .await?;
+    let mut tx = pool.begin().await?;
+
+    let result =
+        sqlx::query("UPDATE jotforms SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL")
+            .bind(Utc::now())
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+
+    // Already in the trash, or never existed
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let event = ChangeEvent::new(Entity::Jotform, Action::Deleted, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
 
     Ok(())
 }
@@ -193,38 +471,23 @@ const STATUS_OPTIONS: [&str; 4] = ["Closed", "InProgress", "Open", "Unplanned"];
 pub async fn change_jotform_status(id: String, status: String, pool: &DbPool) -> Result<()> {
     if !STATUS_OPTIONS.contains(&status.as_str()) {
         error!("Invalid status: {}", status);
-        return Err(sqlx::Error::RowNotFound.into());
+        return Err(sqlx::Error::RowNotFound);
     }
 
+    let mut tx = pool.begin().await?;
+
     sqlx::query("UPDATE jotforms SET status = $1 WHERE id = $2")
         .bind(&status)
         .bind(&id)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
-    Ok(())
-}
+    let event = ChangeEvent::new(Entity::Jotform, Action::Updated, Some(id))
+        .with_data(json!({ "status": status }));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
 
-pub async fn change_jotform_department(
-    id: String,
-    department: String,
-    pool: &DbPool,
-) -> Result<()> {
-    sqlx::query("UPDATE jotforms SET department = $1 WHERE id = $2")
-        .bind(&department)
-        .bind(&id)
-        .execute(pool)
-        .await?;
-
-    Ok(())
-}
-
-pub async fn change_jotform_priority(id: String, priority: String, pool: &DbPool) -> Result<()> {
-    sqlx::query("UPDATE jotforms SET priority_level = $1 WHERE id = $2")
-        .bind(&priority)
-        .bind(&id)
-        .execute(pool)
-        .await?;
+    events::publish(event);
 
     Ok(())
 }
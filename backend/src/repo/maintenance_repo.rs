@@ -0,0 +1,330 @@
+use crate::api::maintenance_handlers::NewMaintenanceTask;
+use crate::db::DbPool;
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::models::{
+    FullName, Jotform, MaintenanceCompletion, MaintenanceTask, OverdueMaintenanceTask,
+};
+use crate::repo::jotform_repo;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Result, SqliteConnection};
+
+const TASK_SELECT: &str = "
+    SELECT id, exhibit_id, title, description, interval_every, interval_unit,
+           next_due_date, auto_create_ticket
+    FROM maintenance_tasks";
+
+#[derive(sqlx::FromRow)]
+struct DueTaskRow {
+    id: i64,
+    title: String,
+    description: String,
+    next_due_date: NaiveDate,
+    exhibit_name: String,
+    exhibit_location: String,
+}
+
+pub async fn create_maintenance_tables(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS maintenance_tasks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            exhibit_id INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL,
+            interval_every INTEGER NOT NULL,
+            interval_unit TEXT NOT NULL,
+            next_due_date TEXT NOT NULL,
+            auto_create_ticket INTEGER NOT NULL DEFAULT 0,
+            last_ticket_due_date TEXT,
+            FOREIGN KEY (exhibit_id) REFERENCES exhibits(id) ON DELETE CASCADE
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS maintenance_completions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id INTEGER NOT NULL,
+            completed_by TEXT NOT NULL,
+            completed_at TEXT NOT NULL,
+            due_date TEXT NOT NULL,
+            notes TEXT,
+            FOREIGN KEY (task_id) REFERENCES maintenance_tasks(id) ON DELETE CASCADE
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// The ID of the ticket auto-created for a task's due date.
+///
+/// It's derived from the task and due date so the ticket can be found again (and closed)
+/// when the task is completed, and so it can never collide with a Jotform submission ID.
+pub fn maintenance_ticket_id(task_id: i64, due_date: NaiveDate) -> String {
+    format!("maintenance-{}-{}", task_id, due_date)
+}
+
+/// Splits a ticket ID made by `maintenance_ticket_id` back into its task ID and due date.
+///
+/// Returns `None` for any other ticket, such as a Jotform submission.
+pub fn parse_maintenance_ticket_id(ticket_id: &str) -> Option<(i64, NaiveDate)> {
+    let (task_id, due_date) = ticket_id.strip_prefix("maintenance-")?.split_once('-')?;
+
+    Some((task_id.parse().ok()?, due_date.parse().ok()?))
+}
+
+pub async fn get_exhibit_tasks(exhibit_id: i64, pool: &DbPool) -> Result<Vec<MaintenanceTask>> {
+    sqlx::query_as::<_, MaintenanceTask>(&format!(
+        "{} WHERE exhibit_id = ?1 ORDER BY next_due_date",
+        TASK_SELECT
+    ))
+    .bind(exhibit_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_task(id: i64, pool: &DbPool) -> Result<Option<MaintenanceTask>> {
+    sqlx::query_as::<_, MaintenanceTask>(&format!("{} WHERE id = ?1", TASK_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn create_task(
+    exhibit_id: i64,
+    task: &NewMaintenanceTask,
+    next_due_date: NaiveDate,
+    pool: &DbPool,
+) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO maintenance_tasks
+             (exhibit_id, title, description, interval_every, interval_unit,
+              next_due_date, auto_create_ticket)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )
+    .bind(exhibit_id)
+    .bind(&task.title)
+    .bind(&task.description)
+    .bind(task.interval_every)
+    .bind(task.interval_unit)
+    .bind(next_due_date)
+    .bind(task.auto_create_ticket)
+    .execute(&mut *tx)
+    .await?;
+
+    let id = result.last_insert_rowid();
+    let event = ChangeEvent::new(Entity::Maintenance, Action::Created, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(id)
+}
+
+pub async fn update_task(
+    id: i64,
+    task: &NewMaintenanceTask,
+    next_due_date: NaiveDate,
+    pool: &DbPool,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE maintenance_tasks
+         SET title = ?1, description = ?2, interval_every = ?3, interval_unit = ?4,
+             next_due_date = ?5, auto_create_ticket = ?6
+         WHERE id = ?7",
+    )
+    .bind(&task.title)
+    .bind(&task.description)
+    .bind(task.interval_every)
+    .bind(task.interval_unit)
+    .bind(next_due_date)
+    .bind(task.auto_create_ticket)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let event = ChangeEvent::new(Entity::Maintenance, Action::Updated, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(())
+}
+
+pub async fn delete_task(id: i64, pool: &DbPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM maintenance_tasks WHERE id = ?1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    let event = ChangeEvent::new(Entity::Maintenance, Action::Deleted, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(())
+}
+
+/// Logs a completion of `task` and moves its due date forward to `next_due_date`.
+///
+/// If a ticket was auto-created for the due date being completed, it is closed.
+pub async fn complete_task(
+    task: &MaintenanceTask,
+    completed_by: &str,
+    completed_at: DateTime<Utc>,
+    notes: Option<&str>,
+    next_due_date: NaiveDate,
+    pool: &DbPool,
+) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO maintenance_completions (task_id, completed_by, completed_at, due_date, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(task.id)
+    .bind(completed_by)
+    .bind(completed_at)
+    .bind(task.next_due_date)
+    .bind(notes)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE maintenance_tasks SET next_due_date = ?1 WHERE id = ?2")
+        .bind(next_due_date)
+        .bind(task.id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE jotforms SET status = 'Closed' WHERE id = ?1")
+        .bind(maintenance_ticket_id(task.id, task.next_due_date))
+        .execute(&mut *tx)
+        .await?;
+
+    let event = ChangeEvent::new(
+        Entity::Maintenance,
+        Action::Completed,
+        Some(task.id.to_string()),
+    );
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_completions(task_id: i64, pool: &DbPool) -> Result<Vec<MaintenanceCompletion>> {
+    sqlx::query_as::<_, MaintenanceCompletion>(
+        "SELECT id, task_id, completed_by, completed_at, due_date, notes
+         FROM maintenance_completions
+         WHERE task_id = ?1
+         ORDER BY completed_at DESC",
+    )
+    .bind(task_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Lists tasks whose due date is before `today`, most overdue first.
+pub async fn get_overdue_tasks(
+    today: NaiveDate,
+    pool: &DbPool,
+) -> Result<Vec<OverdueMaintenanceTask>> {
+    sqlx::query_as::<_, OverdueMaintenanceTask>(
+        "SELECT t.id, t.exhibit_id, t.title, t.description, t.interval_every, t.interval_unit,
+                t.next_due_date, t.auto_create_ticket,
+                e.name AS exhibit_name, e.location AS exhibit_location,
+                CAST(julianday(?1) - julianday(t.next_due_date) AS INTEGER) AS days_overdue
+         FROM maintenance_tasks t
+         JOIN exhibits e ON e.id = t.exhibit_id
+         WHERE t.next_due_date < ?1 AND e.deleted_at IS NULL
+         ORDER BY t.next_due_date",
+    )
+    .bind(today)
+    .fetch_all(pool)
+    .await
+}
+
+/// Opens a ticket for every task that is due by `today`, has auto-creation turned on and
+/// doesn't have a ticket for its current due date yet.
+///
+/// Each ticket is opened in the same transaction that records it on its task, and a ticket
+/// that's somehow already there is just recorded, so a failed pass can safely be run
+/// again. Returns the number of tickets created.
+pub async fn create_due_tickets(today: NaiveDate, pool: &DbPool) -> Result<usize> {
+    let due_tasks = sqlx::query_as::<_, DueTaskRow>(
+        "SELECT t.id, t.title, t.description, t.next_due_date,
+                e.name AS exhibit_name, e.location AS exhibit_location
+         FROM maintenance_tasks t
+         JOIN exhibits e ON e.id = t.exhibit_id
+         WHERE t.auto_create_ticket = 1
+           AND e.deleted_at IS NULL
+           AND t.next_due_date <= ?1
+           AND (t.last_ticket_due_date IS NULL OR t.last_ticket_due_date <> t.next_due_date)",
+    )
+    .bind(today)
+    .fetch_all(pool)
+    .await?;
+
+    let mut opened = 0;
+    for task in &due_tasks {
+        let ticket = Jotform {
+            id: maintenance_ticket_id(task.id, task.next_due_date),
+            submitter_name: FullName {
+                first: "Maintenance".to_string(),
+                last: "Schedule".to_string(),
+            },
+            created_at: Utc::now(),
+            location: task.exhibit_location.clone(),
+            exhibit_name: task.exhibit_name.clone(),
+            description: format!(
+                "Scheduled maintenance due {}: {}\n{}",
+                task.next_due_date, task.title, task.description
+            )
+            .trim_end()
+            .to_string(),
+            priority_level: "Medium".to_string(),
+            department: "Exhibits".to_string(),
+            status: "Open".to_string(),
+        };
+
+        // The ticket and the record of it go in together, so a failure between them can't
+        // leave a ticket the task doesn't know it has
+        let mut tx = pool.begin().await?;
+        let created = jotform_repo::insert_new_jotform(&ticket, &mut tx).await?;
+
+        sqlx::query("UPDATE maintenance_tasks SET last_ticket_due_date = ?1 WHERE id = ?2")
+            .bind(task.next_due_date)
+            .bind(task.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        if let Some(event) = created {
+            events::publish(event);
+            opened += 1;
+        }
+    }
+
+    Ok(opened)
+}
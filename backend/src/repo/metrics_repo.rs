@@ -0,0 +1,21 @@
+use crate::db::DbPool;
+use sqlx::Result;
+
+/// Counts exhibits by status, leaving out the trash.
+pub async fn count_exhibits_by_status(pool: &DbPool) -> Result<Vec<(String, i64)>> {
+    sqlx::query_as("SELECT status, COUNT(*) FROM exhibits WHERE deleted_at IS NULL GROUP BY status")
+        .fetch_all(pool)
+        .await
+}
+
+/// Counts tickets that still need work (`Open` or `InProgress`) by priority, leaving out
+/// the trash.
+pub async fn count_open_tickets_by_priority(pool: &DbPool) -> Result<Vec<(String, i64)>> {
+    sqlx::query_as(
+        "SELECT priority_level, COUNT(*) FROM jotforms
+         WHERE deleted_at IS NULL AND status IN ('Open', 'InProgress')
+         GROUP BY priority_level",
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -1,3 +1,13 @@
+pub mod bug_report_repo;
 pub mod exhibit_repo;
 pub mod jotform_repo;
+pub mod maintenance_repo;
+pub mod metrics_repo;
+pub mod note_repo;
 pub mod part_repo;
+pub mod snapshot_repo;
+pub mod sponsorship_repo;
+#[cfg(test)]
+mod tests;
+pub mod trash_repo;
+pub mod webhook_repo;
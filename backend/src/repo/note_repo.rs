@@ -0,0 +1,515 @@
+use crate::db::{table_exists, DbPool};
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::models::{Note, NoteAttachment, NoteRevision, NoteTarget};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use log::warn;
+use rocket::serde::json::json;
+use sqlx::{Connection, Result, SqliteConnection, SqliteExecutor};
+
+#[derive(sqlx::FromRow)]
+struct NoteRow {
+    id: i64,
+    submitter: String,
+    message: String,
+    created_at: DateTime<Utc>,
+    edited_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct LegacyNoteRow {
+    owner_id: i64,
+    submitter: String,
+    date: String,
+    time: String,
+    message: String,
+}
+
+/// An attachment's metadata together with its contents.
+#[derive(sqlx::FromRow)]
+pub struct AttachmentContent {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// The tables notes can belong to, with the trigger that cleans up their notes.
+const NOTE_OWNERS: [(NoteTarget, &str, &str); 4] = [
+    (NoteTarget::Exhibit, "exhibit", "exhibits"),
+    (NoteTarget::Part, "part", "parts"),
+    (NoteTarget::Ticket, "ticket", "jotforms"),
+    (NoteTarget::WorkOrder, "work_order", "maintenance_tasks"),
+];
+
+pub async fn create_note_tables(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            target_type TEXT NOT NULL,
+            target_id TEXT NOT NULL,
+            submitter TEXT NOT NULL,
+            message TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            edited_at TEXT
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS notes_target ON notes (target_type, target_id)")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS note_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            note_id INTEGER NOT NULL,
+            message TEXT NOT NULL,
+            edited_by TEXT NOT NULL,
+            edited_at TEXT NOT NULL,
+            FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS note_attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            note_id INTEGER NOT NULL,
+            file_name TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            data BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Notes can't have a foreign key to every table they belong to, so triggers remove
+    // them when their exhibit, part, ticket or work order is deleted
+    for (_, target_type, table) in NOTE_OWNERS {
+        sqlx::query(&format!(
+            "CREATE TRIGGER IF NOT EXISTS delete_{table}_notes AFTER DELETE ON {table}
+             BEGIN
+                 DELETE FROM notes
+                 WHERE target_type = '{target_type}' AND target_id = CAST(OLD.id AS TEXT);
+             END"
+        ))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    migrate_legacy_note_table(
+        &mut *conn,
+        "exhibit_notes",
+        "exhibit_id",
+        NoteTarget::Exhibit,
+    )
+    .await?;
+    migrate_legacy_note_table(&mut *conn, "part_notes", "part_id", NoteTarget::Part).await?;
+
+    Ok(())
+}
+
+/// Moves notes from one of the old per-entity note tables into `notes` and drops it.
+/// Does nothing once the table is gone.
+async fn migrate_legacy_note_table(
+    conn: &mut SqliteConnection,
+    table: &str,
+    owner_column: &str,
+    target: NoteTarget,
+) -> Result<()> {
+    if !table_exists(&mut *conn, table).await? {
+        return Ok(());
+    }
+
+    let rows = sqlx::query_as::<_, LegacyNoteRow>(&format!(
+        "SELECT {} AS owner_id, submitter, date, time, message FROM {} ORDER BY id",
+        owner_column, table
+    ))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut tx = conn.begin().await?;
+
+    for row in &rows {
+        sqlx::query(
+            "INSERT INTO notes (target_type, target_id, submitter, message, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(target)
+        .bind(row.owner_id.to_string())
+        .bind(&row.submitter)
+        .bind(&row.message)
+        .bind(legacy_timestamp_to_utc(&row.date, &row.time))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query(&format!("DROP TABLE {}", table))
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Converts a date and time written by the old note code back to UTC.
+///
+/// The old code always wrote them at a fixed UTC-6, regardless of daylight saving time,
+/// so undoing that offset gives the exact original instant. Unparseable times fall back
+/// to midnight on the date; unparseable dates fall back to now.
+fn legacy_timestamp_to_utc(date: &str, time: &str) -> DateTime<Utc> {
+    let legacy_offset = FixedOffset::west_opt(6 * 3600).unwrap();
+
+    let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") else {
+        warn!("Legacy note has an invalid date `{}`, using now", date);
+        return Utc::now();
+    };
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S%.f").unwrap_or(NaiveTime::MIN);
+
+    NaiveDateTime::new(date, time)
+        .and_local_timezone(legacy_offset)
+        .single()
+        .map(|at| at.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+/// Returns whether the exhibit, part, ticket or work order a note would belong to exists.
+pub async fn target_exists(target: NoteTarget, target_id: &str, pool: &DbPool) -> Result<bool> {
+    let (_, _, table) = NOTE_OWNERS
+        .iter()
+        .find(|(owner, _, _)| *owner == target)
+        .expect("every note target has an owning table");
+
+    // Maintenance tasks are deleted outright; everything else can be in the trash
+    let live = match target {
+        NoteTarget::WorkOrder => "",
+        _ => " AND deleted_at IS NULL",
+    };
+
+    let found = sqlx::query(&format!("SELECT 1 FROM {} WHERE id = ?1{}", table, live))
+        .bind(target_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(found.is_some())
+}
+
+async fn get_note_attachments(note_id: i64, pool: &DbPool) -> Result<Vec<NoteAttachment>> {
+    sqlx::query_as::<_, NoteAttachment>(
+        "SELECT id, note_id, file_name, content_type, size_bytes, created_at
+         FROM note_attachments
+         WHERE note_id = ?1
+         ORDER BY id",
+    )
+    .bind(note_id)
+    .fetch_all(pool)
+    .await
+}
+
+async fn into_note(row: NoteRow, pool: &DbPool) -> Result<Note> {
+    let attachments = get_note_attachments(row.id, pool).await?;
+
+    Ok(Note {
+        id: row.id,
+        submitter: row.submitter,
+        message: row.message,
+        created_at: row.created_at,
+        edited_at: row.edited_at,
+        attachments,
+    })
+}
+
+/// Lists the notes on an exhibit, part, ticket or work order, oldest first.
+pub async fn get_notes(target: NoteTarget, target_id: &str, pool: &DbPool) -> Result<Vec<Note>> {
+    let rows = sqlx::query_as::<_, NoteRow>(
+        "SELECT id, submitter, message, created_at, edited_at
+         FROM notes
+         WHERE target_type = ?1 AND target_id = ?2
+         ORDER BY created_at, id",
+    )
+    .bind(target)
+    .bind(target_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut notes = Vec::with_capacity(rows.len());
+    for row in rows {
+        notes.push(into_note(row, pool).await?);
+    }
+
+    Ok(notes)
+}
+
+pub async fn get_note(id: i64, pool: &DbPool) -> Result<Option<Note>> {
+    let row = sqlx::query_as::<_, NoteRow>(
+        "SELECT id, submitter, message, created_at, edited_at FROM notes WHERE id = ?1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    match row {
+        Some(row) => Ok(Some(into_note(row, pool).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Gets a note only if it belongs to the given exhibit, part, ticket or work order.
+pub async fn get_target_note(
+    target: NoteTarget,
+    target_id: &str,
+    note_id: i64,
+    pool: &DbPool,
+) -> Result<Option<Note>> {
+    let row = sqlx::query_as::<_, NoteRow>(
+        "SELECT id, submitter, message, created_at, edited_at
+         FROM notes
+         WHERE target_type = ?1 AND target_id = ?2 AND id = ?3",
+    )
+    .bind(target)
+    .bind(target_id)
+    .bind(note_id)
+    .fetch_optional(pool)
+    .await?;
+
+    match row {
+        Some(row) => Ok(Some(into_note(row, pool).await?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn create_note<'e>(
+    target: NoteTarget,
+    target_id: &str,
+    submitter: &str,
+    message: &str,
+    created_at: DateTime<Utc>,
+    executor: impl SqliteExecutor<'e>,
+) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO notes (target_type, target_id, submitter, message, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(target)
+    .bind(target_id)
+    .bind(submitter)
+    .bind(message)
+    .bind(created_at)
+    .execute(executor)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Replaces a note's message, keeping the previous message in its history.
+///
+/// Returns `false` if the note doesn't exist.
+pub async fn edit_note(
+    id: i64,
+    edited_by: &str,
+    message: &str,
+    edited_at: DateTime<Utc>,
+    pool: &DbPool,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let inserted = sqlx::query(
+        "INSERT INTO note_revisions (note_id, message, edited_by, edited_at)
+         SELECT id, message, ?2, ?3 FROM notes WHERE id = ?1",
+    )
+    .bind(id)
+    .bind(edited_by)
+    .bind(edited_at)
+    .execute(&mut *tx)
+    .await?;
+
+    if inserted.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+
+    sqlx::query("UPDATE notes SET message = ?1, edited_at = ?2 WHERE id = ?3")
+        .bind(message)
+        .bind(edited_at)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    let event = ChangeEvent::new(Entity::Note, Action::Updated, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(true)
+}
+
+/// Deletes a note. Returns `false` if the note doesn't exist.
+pub async fn delete_note(id: i64, pool: &DbPool) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query("DELETE FROM notes WHERE id = ?1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let event = ChangeEvent::new(Entity::Note, Action::Deleted, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(true)
+}
+
+/// Deletes a note only if it belongs to the given exhibit, part, ticket or work order.
+///
+/// Returns `false` if there's no such note.
+pub async fn delete_target_note(
+    target: NoteTarget,
+    target_id: &str,
+    note_id: i64,
+    pool: &DbPool,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let result =
+        sqlx::query("DELETE FROM notes WHERE target_type = ?1 AND target_id = ?2 AND id = ?3")
+            .bind(target)
+            .bind(target_id)
+            .bind(note_id)
+            .execute(&mut *tx)
+            .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let event = ChangeEvent::new(Entity::Note, Action::Deleted, Some(note_id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(true)
+}
+
+/// Lists a note's previous messages, newest edit first.
+pub async fn get_note_history(note_id: i64, pool: &DbPool) -> Result<Vec<NoteRevision>> {
+    sqlx::query_as::<_, NoteRevision>(
+        "SELECT id, note_id, message, edited_by, edited_at
+         FROM note_revisions
+         WHERE note_id = ?1
+         ORDER BY edited_at DESC, id DESC",
+    )
+    .bind(note_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn create_attachment(
+    note_id: i64,
+    file_name: &str,
+    content_type: &str,
+    data: &[u8],
+    pool: &DbPool,
+) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO note_attachments
+             (note_id, file_name, content_type, size_bytes, data, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )
+    .bind(note_id)
+    .bind(file_name)
+    .bind(content_type)
+    .bind(data.len() as i64)
+    .bind(data)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await?;
+
+    let id = result.last_insert_rowid();
+    let event = ChangeEvent::new(Entity::Attachment, Action::Added, Some(id.to_string()))
+        .with_data(json!({ "note_id": note_id }));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(id)
+}
+
+pub async fn get_attachment(
+    note_id: i64,
+    attachment_id: i64,
+    pool: &DbPool,
+) -> Result<Option<NoteAttachment>> {
+    sqlx::query_as::<_, NoteAttachment>(
+        "SELECT id, note_id, file_name, content_type, size_bytes, created_at
+         FROM note_attachments
+         WHERE note_id = ?1 AND id = ?2",
+    )
+    .bind(note_id)
+    .bind(attachment_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_attachment_content(
+    note_id: i64,
+    attachment_id: i64,
+    pool: &DbPool,
+) -> Result<Option<AttachmentContent>> {
+    sqlx::query_as::<_, AttachmentContent>(
+        "SELECT file_name, content_type, data
+         FROM note_attachments
+         WHERE note_id = ?1 AND id = ?2",
+    )
+    .bind(note_id)
+    .bind(attachment_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Deletes one of a note's attachments. Returns `false` if there's no such attachment.
+pub async fn delete_attachment(note_id: i64, attachment_id: i64, pool: &DbPool) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query("DELETE FROM note_attachments WHERE note_id = ?1 AND id = ?2")
+        .bind(note_id)
+        .bind(attachment_id)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let event = ChangeEvent::new(
+        Entity::Attachment,
+        Action::Deleted,
+        Some(attachment_id.to_string()),
+    )
+    .with_data(json!({ "note_id": note_id }));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+    events::publish(event);
+
+    Ok(true)
+}
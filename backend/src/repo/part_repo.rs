@@ -1,14 +1,17 @@
-use crate::api::part_handlers::NewPart;
-use crate::db::DbPool;
-use crate::models::{Note, Part, Timestamp, UpdatePart};
-use chrono::{DateTime, FixedOffset, Utc};
-use sqlx::{Error, Result, Sqlite};
+use crate::api::part_handlers::{NewPart, PartFilter};
+use crate::db::{add_column_if_missing, DbPool};
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::models::{NoteTarget, Part, UpdatePart};
+use crate::repo::note_repo;
+use chrono::Utc;
+use sqlx::{Result, SqliteConnection};
 
 #[derive(sqlx::FromRow)]
 struct PartRow {
     id: i64,
     name: String,
     link: String,
+    version: i64,
 }
 
 #[derive(sqlx::FromRow)]
@@ -16,45 +19,24 @@ struct PartExhibitRow {
     exhibit_id: i64,
 }
 
-#[derive(sqlx::FromRow)]
-struct PartNoteRow {
-    id: i64,
-    submitter: String,
-    date: String,
-    time: String,
-    message: String,
-}
-
-pub async fn create_part_tables(pool: &DbPool) -> Result<()> {
+pub async fn create_part_tables(conn: &mut SqliteConnection) -> Result<()> {
     // Create 'parts' table
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS parts (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL,
-            link TEXT NOT NULL
+            link TEXT NOT NULL,
+            version INTEGER NOT NULL DEFAULT 1,
+            deleted_at TEXT
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
-    // Create 'part_notes' table
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS part_notes (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            part_id INTEGER NOT NULL,
-            submitter TEXT NOT NULL,
-            date TEXT NOT NULL,
-            time TEXT NOT NULL,
-            message TEXT NOT NULL,
-            FOREIGN KEY (part_id) REFERENCES parts(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
+    add_column_if_missing(&mut *conn, "parts", "version", "INTEGER NOT NULL DEFAULT 1").await?;
+    add_column_if_missing(&mut *conn, "parts", "deleted_at", "TEXT").await?;
 
     // Create 'exhibit_parts' table if it doesn't exist
     sqlx::query(
@@ -67,67 +49,61 @@ pub async fn create_part_tables(pool: &DbPool) -> Result<()> {
         )
         "#,
     )
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
     Ok(())
 }
 
+/// Loads the exhibit links and notes that go with a part row.
+async fn into_part(part: PartRow, pool: &DbPool) -> Result<Part> {
+    let exhibit_ids = sqlx::query_as::<_, PartExhibitRow>(
+        "SELECT ep.exhibit_id FROM exhibit_parts ep
+         JOIN exhibits e ON e.id = ep.exhibit_id
+         WHERE ep.part_id = ?1 AND e.deleted_at IS NULL",
+    )
+    .bind(part.id)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|row| row.exhibit_id)
+    .collect();
+
+    let notes = note_repo::get_notes(NoteTarget::Part, &part.id.to_string(), pool).await?;
+
+    Ok(Part {
+        id: Some(part.id),
+        name: part.name,
+        link: part.link,
+        exhibit_ids,
+        notes,
+        version: part.version,
+    })
+}
+
 pub async fn get_part(id: i64, pool: &DbPool) -> Result<Option<Part>> {
     // Use ? placeholders for SQLite
-    let part = sqlx::query_as::<_, PartRow>("SELECT id, name, link FROM parts WHERE id = ?1")
-        .bind(id)
-        .fetch_optional(pool)
-        .await?;
+    let part = sqlx::query_as::<_, PartRow>(
+        "SELECT id, name, link, version FROM parts WHERE id = ?1 AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
 
     match part {
-        Some(part) => {
-            let exhibit_ids = sqlx::query_as::<_, PartExhibitRow>(
-                "SELECT exhibit_id FROM exhibit_parts WHERE part_id = ?1",
-            )
-            .bind(id)
-            .fetch_all(pool)
-            .await?
-            .iter()
-            .map(|row| row.exhibit_id)
-            .collect();
-
-            let notes = sqlx::query_as::<_, PartNoteRow>(
-                "SELECT id, submitter, date, time, message FROM part_notes WHERE part_id = ?1",
-            )
-            .bind(id)
-            .fetch_all(pool)
-            .await?
-            .iter()
-            .map(|row| Note {
-                id: row.id,
-                submitter: row.submitter.clone(),
-                timestamp: Timestamp {
-                    date: row.date.clone(),
-                    time: row.time.clone(),
-                },
-                message: row.message.clone(),
-            })
-            .collect();
-
-            Ok(Some(Part {
-                id: Some(part.id),
-                name: part.name,
-                link: part.link,
-                exhibit_ids,
-                notes,
-            }))
-        }
+        Some(part) => Ok(Some(into_part(part, pool).await?)),
         None => Ok(None),
     }
 }
 
 pub async fn create_part(part: &NewPart, pool: &DbPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
     // Insert into 'parts' table
     let result = sqlx::query("INSERT INTO parts (name, link) VALUES (?1, ?2)")
         .bind(&part.name)
         .bind(&part.link)
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
     let part_id = result.last_insert_rowid();
@@ -137,38 +113,61 @@ pub async fn create_part(part: &NewPart, pool: &DbPool) -> Result<()> {
         sqlx::query("INSERT INTO exhibit_parts (exhibit_id, part_id) VALUES (?1, ?2)")
             .bind(exhibit_id) // exhibit_id first
             .bind(part_id)
-            .execute(pool)
+            .execute(&mut *tx)
             .await?;
     }
 
-    // Insert notes with 'submitter'
     for note in &part.notes {
-        sqlx::query(
-            "INSERT INTO part_notes (part_id, submitter, date, time, message) VALUES (?1, ?2, ?3, ?4, ?5)",
+        note_repo::create_note(
+            NoteTarget::Part,
+            &part_id.to_string(),
+            &note.submitter,
+            &note.message,
+            Utc::now(),
+            &mut *tx,
         )
-        .bind(part_id)
-        .bind(&note.submitter) // Bind 'submitter'
-        .bind(&note.timestamp.date)
-        .bind(&note.timestamp.time)
-        .bind(&note.message)
-        .execute(pool)
         .await?;
     }
 
+    let event = ChangeEvent::new(Entity::Part, Action::Created, Some(part_id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
     Ok(())
 }
 
-pub async fn update_part(id: &i64, part: &UpdatePart, pool: &DbPool) -> Result<()> {
+/// Updates a part and replaces its exhibit associations.
+///
+/// When `expected_version` is given the update only applies if the stored version still
+/// matches. Returns the new version, or `None` if the part doesn't exist or is stale.
+pub async fn update_part(
+    id: &i64,
+    part: &UpdatePart,
+    expected_version: Option<i64>,
+    pool: &DbPool,
+) -> Result<Option<i64>> {
     // Start a transaction since we're making multiple related changes
     let mut tx = pool.begin().await?;
 
     // Update the main part record
-    sqlx::query("UPDATE parts SET name = ?1, link = ?2 WHERE id = ?3")
-        .bind(&part.name)
-        .bind(&part.link)
-        .bind(id)
-        .execute(&mut *tx)
-        .await?;
+    let new_version = sqlx::query_scalar::<_, i64>(
+        "UPDATE parts SET name = ?1, link = ?2, version = version + 1
+         WHERE id = ?3 AND deleted_at IS NULL AND (?4 IS NULL OR version = ?4)
+         RETURNING version",
+    )
+    .bind(&part.name)
+    .bind(&part.link)
+    .bind(id)
+    .bind(expected_version)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(new_version) = new_version else {
+        tx.rollback().await?;
+        return Ok(None);
+    };
 
     // Delete all existing exhibit associations for this part
     sqlx::query("DELETE FROM exhibit_parts WHERE part_id = ?1")
@@ -185,71 +184,75 @@ pub async fn update_part(id: &i64, part: &UpdatePart, pool: &DbPool) -> Result<(
             .await?;
     }
 
+    let event = ChangeEvent::new(Entity::Part, Action::Updated, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+
     // Commit the transaction
     tx.commit().await?;
 
-    Ok(())
+    events::publish(event);
+
+    Ok(Some(new_version))
 }
 
+/// Moves a part to the trash. Its notes and exhibit links are kept until it's purged.
 pub async fn delete_part(id: i64, pool: &DbPool) -> Result<()> {
-    sqlx::query("DELETE FROM parts WHERE id = ?1")
-        .bind(id)
-        .execute(pool)
-        .await?;
-
-    Ok(())
-}
+    let mut tx = pool.begin().await?;
 
-pub async fn get_all_parts(pool: &DbPool) -> Result<Option<Vec<Part>>> {
-    let parts = sqlx::query_as::<_, PartRow>("SELECT id, name, link FROM parts")
-        .fetch_all(pool)
-        .await?;
+    let result =
+        sqlx::query("UPDATE parts SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
 
-    if parts.is_empty() {
-        return Ok(None);
+    // Already in the trash, or never existed
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
     }
 
-    let mut part_vec = Vec::new();
+    let event = ChangeEvent::new(Entity::Part, Action::Deleted, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
 
-    for part in parts {
-        let exhibit_ids = sqlx::query_as::<_, PartExhibitRow>(
-            "SELECT exhibit_id FROM exhibit_parts WHERE part_id = ?1",
-        )
-        .bind(part.id)
-        .fetch_all(pool)
-        .await?
-        .iter()
-        .map(|row| row.exhibit_id)
-        .collect();
-
-        let notes = sqlx::query_as::<_, PartNoteRow>(
-            "SELECT id, submitter, date, time, message FROM part_notes WHERE part_id = ?1",
-        )
-        .bind(part.id)
-        .fetch_all(pool)
-        .await?
-        .iter()
-        .map(|row| Note {
-            id: row.id,
-            submitter: row.submitter.clone(),
-            timestamp: Timestamp {
-                date: row.date.clone(),
-                time: row.time.clone(),
-            },
-            message: row.message.clone(),
-        })
-        .collect();
-
-        part_vec.push(Part {
-            id: Some(part.id),
-            name: part.name,
-            link: part.link,
-            exhibit_ids,
-            notes,
-        });
+    events::publish(event);
+
+    Ok(())
+}
+
+/// Lists parts matching `filter` in ID order, starting after `after_id`.
+///
+/// With a `limit` this returns one page at a time, so callers can walk every part without
+/// holding them all in memory.
+pub async fn find_parts(
+    filter: &PartFilter,
+    after_id: i64,
+    limit: Option<i64>,
+    pool: &DbPool,
+) -> Result<Vec<Part>> {
+    let rows = sqlx::query_as::<_, PartRow>(
+        "SELECT id, name, link, version FROM parts p
+         WHERE deleted_at IS NULL AND id > ?1
+           AND (?2 IS NULL OR instr(lower(name), lower(?2)) > 0)
+           AND (?3 IS NULL OR EXISTS (
+               SELECT 1 FROM exhibit_parts ep WHERE ep.part_id = p.id AND ep.exhibit_id = ?3
+           ))
+         ORDER BY id
+         LIMIT ?4",
+    )
+    .bind(after_id)
+    .bind(&filter.search)
+    .bind(filter.exhibit_id)
+    .bind(limit.unwrap_or(-1))
+    .fetch_all(pool)
+    .await?;
+
+    let mut parts = Vec::with_capacity(rows.len());
+    for row in rows {
+        parts.push(into_part(row, pool).await?);
     }
 
-    Ok(Some(part_vec))
+    Ok(parts)
 }
 
 pub async fn get_parts_by_ids(ids: Vec<i64>, pool: &DbPool) -> Result<Option<Vec<Part>>> {
@@ -260,15 +263,19 @@ pub async fn get_parts_by_ids(ids: Vec<i64>, pool: &DbPool) -> Result<Option<Vec
     let mut part_vec = Vec::new();
 
     for id in ids {
-        let part = sqlx::query_as::<_, PartRow>("SELECT id, name, link FROM parts WHERE id = ?1")
-            .bind(id)
-            .fetch_optional(pool)
-            .await?;
+        let part = sqlx::query_as::<_, PartRow>(
+            "SELECT id, name, link, version FROM parts WHERE id = ?1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
 
         match part {
             Some(part) => {
                 let exhibit_ids = sqlx::query_as::<_, PartExhibitRow>(
-                    "SELECT exhibit_id FROM exhibit_parts WHERE part_id = ?1",
+                    "SELECT ep.exhibit_id FROM exhibit_parts ep
+             JOIN exhibits e ON e.id = ep.exhibit_id
+             WHERE ep.part_id = ?1 AND e.deleted_at IS NULL",
                 )
                 .bind(id)
                 .fetch_all(pool)
@@ -277,23 +284,7 @@ pub async fn get_parts_by_ids(ids: Vec<i64>, pool: &DbPool) -> Result<Option<Vec
                 .map(|row| row.exhibit_id)
                 .collect();
 
-                let notes = sqlx::query_as::<_, PartNoteRow>(
-                    "SELECT id, submitter, date, time, message FROM part_notes WHERE part_id = ?1",
-                )
-                .bind(id)
-                .fetch_all(pool)
-                .await?
-                .iter()
-                .map(|row| Note {
-                    id: row.id,
-                    submitter: row.submitter.clone(),
-                    timestamp: Timestamp {
-                        date: row.date.clone(),
-                        time: row.time.clone(),
-                    },
-                    message: row.message.clone(),
-                })
-                .collect();
+                let notes = note_repo::get_notes(NoteTarget::Part, &id.to_string(), pool).await?;
 
                 part_vec.push(Part {
                     id: Some(part.id),
@@ -301,6 +292,7 @@ pub async fn get_parts_by_ids(ids: Vec<i64>, pool: &DbPool) -> Result<Option<Vec
                     link: part.link,
                     exhibit_ids,
                     notes,
+                    version: part.version,
                 });
             }
             None => return Ok(None),
@@ -310,93 +302,21 @@ pub async fn get_parts_by_ids(ids: Vec<i64>, pool: &DbPool) -> Result<Option<Vec
     Ok(Some(part_vec))
 }
 
-pub async fn get_part_note(id: i64, note_id: i64, pool: &DbPool) -> Result<Option<Note>> {
-    let note = sqlx::query_as::<_, PartNoteRow>(
-        "SELECT id, submitter, date, time, message FROM part_notes WHERE part_id = ?1 AND id = ?2",
-    )
-    .bind(id)
-    .bind(note_id)
-    .fetch_optional(pool)
-    .await?;
-
-    match note {
-        Some(note) => Ok(Some(Note {
-            id: note.id,
-            submitter: note.submitter,
-            timestamp: Timestamp {
-                date: note.date,
-                time: note.time,
-            },
-            message: note.message,
-        })),
-        None => Ok(None),
-    }
-}
-
-pub async fn create_part_note(
-    id: i64,
-    submitter: String,
-    message: String,
-    pool: &DbPool,
-) -> Result<()> {
-    // Get the current time in UTC
-    let now_utc: DateTime<Utc> = Utc::now();
-
-    // Convert the time to Central Time (UTC-6)
-    let now_central = now_utc.with_timezone(&FixedOffset::west_opt(6 * 3600).unwrap());
-
-    // Extract the date and time components
-    let date = now_central.date_naive().to_string();
-    let time = now_central.time().to_string();
-
-    sqlx::query(
-        "INSERT INTO part_notes (part_id, submitter, date, time, message) VALUES (?1, ?2, ?3, ?4, ?5)",
-    )
-    .bind(id)
-    .bind(submitter)
-    .bind(date)
-    .bind(time)
-    .bind(message)
-    .execute(pool)
-    .await?;
+/// Returns the IDs from `ids` that don't match any part.
+pub async fn find_missing_part_ids(ids: &[i64], pool: &DbPool) -> Result<Vec<i64>> {
+    let mut missing = Vec::new();
 
-    Ok(())
-}
-
-pub async fn delete_part_note(id: i64, note_id: i64, pool: &DbPool) -> Result<()> {
-    sqlx::query("DELETE FROM part_notes WHERE part_id = ?1 AND id = ?2")
-        .bind(id)
-        .bind(note_id)
-        .execute(pool)
-        .await?;
-
-    Ok(())
-}
-
-pub async fn get_all_part_notes(id: i64, pool: &DbPool) -> Result<Option<Vec<Note>>> {
-    let notes = sqlx::query_as::<_, PartNoteRow>(
-        "SELECT id, submitter, date, time, message FROM part_notes WHERE part_id = ?1",
-    )
-    .bind(id)
-    .fetch_all(pool)
-    .await?;
+    for id in ids {
+        let exists = sqlx::query("SELECT 1 FROM parts WHERE id = ?1 AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .is_some();
 
-    if notes.is_empty() {
-        return Ok(None);
+        if !exists {
+            missing.push(*id);
+        }
     }
 
-    let note_vec = notes
-        .iter()
-        .map(|note| Note {
-            id: note.id,
-            submitter: note.submitter.clone(),
-            timestamp: Timestamp {
-                date: note.date.clone(),
-                time: note.time.clone(),
-            },
-            message: note.message.clone(),
-        })
-        .collect();
-
-    Ok(Some(note_vec))
+    Ok(missing)
 }
@@ -0,0 +1,450 @@
+use crate::db::DbPool;
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::models::{
+    NoteTarget, RestoreSummary, Snapshot, SnapshotExhibit, SnapshotExhibitPart,
+    SnapshotMaintenanceCompletion, SnapshotMaintenanceTask, SnapshotNote, SnapshotNoteAttachment,
+    SnapshotNoteRevision, SnapshotPart, SnapshotSponsor, SnapshotSponsorship, SnapshotTicket,
+    SNAPSHOT_FORMAT_VERSION,
+};
+use crate::repo::maintenance_repo::{maintenance_ticket_id, parse_maintenance_ticket_id};
+use chrono::Utc;
+use log::warn;
+use sqlx::{Result, Sqlite, Transaction};
+use std::collections::HashMap;
+
+/// The tables that must all be empty before a snapshot can be restored.
+const RESTORE_TABLES: [&str; 7] = [
+    "exhibits",
+    "parts",
+    "sponsors",
+    "maintenance_tasks",
+    "jotforms",
+    "purged_jotforms",
+    "notes",
+];
+
+#[derive(sqlx::FromRow)]
+struct AttachmentDataRow {
+    id: i64,
+    data: Vec<u8>,
+}
+
+/// Reads every table into a snapshot, along with the contents of each note attachment
+/// keyed by attachment ID.
+///
+/// Everything is read in one transaction, so the snapshot is consistent even if records
+/// change while it's being taken.
+pub async fn read_snapshot(pool: &DbPool) -> Result<(Snapshot, HashMap<i64, Vec<u8>>)> {
+    let mut tx = pool.begin().await?;
+
+    let snapshot = Snapshot {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        created_at: Utc::now(),
+        exhibits: sqlx::query_as::<_, SnapshotExhibit>(
+            "SELECT id, name, cluster, location, description, status, image_url, version,
+                    deleted_at
+             FROM exhibits ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        parts: sqlx::query_as::<_, SnapshotPart>(
+            "SELECT id, name, link, version, deleted_at FROM parts ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        exhibit_parts: sqlx::query_as::<_, SnapshotExhibitPart>(
+            "SELECT exhibit_id, part_id FROM exhibit_parts ORDER BY exhibit_id, part_id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        sponsors: sqlx::query_as::<_, SnapshotSponsor>(
+            "SELECT id, name, contact_name, contact_email, contact_phone, notes
+             FROM sponsors ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        sponsorships: sqlx::query_as::<_, SnapshotSponsorship>(
+            "SELECT id, exhibit_id, sponsor_id, start_date, end_date, amount_cents, notes
+             FROM exhibit_sponsorships ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        maintenance_tasks: sqlx::query_as::<_, SnapshotMaintenanceTask>(
+            "SELECT id, exhibit_id, title, description, interval_every, interval_unit,
+                    next_due_date, auto_create_ticket, last_ticket_due_date
+             FROM maintenance_tasks ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        maintenance_completions: sqlx::query_as::<_, SnapshotMaintenanceCompletion>(
+            "SELECT id, task_id, completed_by, completed_at, due_date, notes
+             FROM maintenance_completions ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        tickets: sqlx::query_as::<_, SnapshotTicket>(
+            "SELECT id, submitter_first_name, submitter_last_name, created_at, location,
+                    exhibit_name, description, priority_level, department, status, deleted_at
+             FROM jotforms ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        purged_ticket_ids: sqlx::query_scalar("SELECT id FROM purged_jotforms ORDER BY id")
+            .fetch_all(&mut *tx)
+            .await?,
+        notes: sqlx::query_as::<_, SnapshotNote>(
+            "SELECT id, target_type, target_id, submitter, message, created_at, edited_at
+             FROM notes ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        note_revisions: sqlx::query_as::<_, SnapshotNoteRevision>(
+            "SELECT id, note_id, message, edited_by, edited_at FROM note_revisions ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+        note_attachments: sqlx::query_as::<_, SnapshotNoteAttachment>(
+            "SELECT id, note_id, file_name, content_type, created_at
+             FROM note_attachments ORDER BY id",
+        )
+        .fetch_all(&mut *tx)
+        .await?,
+    };
+
+    let attachments =
+        sqlx::query_as::<_, AttachmentDataRow>("SELECT id, data FROM note_attachments")
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|row| (row.id, row.data))
+            .collect();
+
+    tx.commit().await?;
+
+    Ok((snapshot, attachments))
+}
+
+async fn is_empty(tx: &mut Transaction<'_, Sqlite>) -> Result<bool> {
+    for table in RESTORE_TABLES {
+        let has_rows: bool =
+            sqlx::query_scalar(&format!("SELECT EXISTS (SELECT 1 FROM {})", table))
+                .fetch_one(&mut **tx)
+                .await?;
+        if has_rows {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Restores a snapshot into an empty database.
+///
+/// Every record gets a new ID and the references between them are rewritten to match:
+/// part links, sponsorships, maintenance tasks and their tickets, and notes. Rows that refer
+/// to a record missing from the snapshot are skipped with a warning. `attachments` holds the
+/// contents of each note attachment, keyed by its ID in the snapshot.
+///
+/// Everything is restored in one transaction. Returns `None`, without changing anything, if
+/// the database already has records in it.
+pub async fn restore_snapshot(
+    snapshot: &Snapshot,
+    attachments: &HashMap<i64, Vec<u8>>,
+    pool: &DbPool,
+) -> Result<Option<RestoreSummary>> {
+    let mut tx = pool.begin().await?;
+
+    if !is_empty(&mut tx).await? {
+        return Ok(None);
+    }
+
+    let mut summary = RestoreSummary::default();
+
+    let mut sponsor_ids = HashMap::new();
+    for sponsor in &snapshot.sponsors {
+        let result = sqlx::query(
+            "INSERT INTO sponsors (name, contact_name, contact_email, contact_phone, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(&sponsor.name)
+        .bind(&sponsor.contact_name)
+        .bind(&sponsor.contact_email)
+        .bind(&sponsor.contact_phone)
+        .bind(&sponsor.notes)
+        .execute(&mut *tx)
+        .await?;
+        sponsor_ids.insert(sponsor.id, result.last_insert_rowid());
+    }
+    summary.sponsors = sponsor_ids.len();
+
+    let mut exhibit_ids = HashMap::new();
+    for exhibit in &snapshot.exhibits {
+        let result = sqlx::query(
+            "INSERT INTO exhibits
+                 (name, cluster, location, description, status, image_url, version, deleted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )
+        .bind(&exhibit.name)
+        .bind(&exhibit.cluster)
+        .bind(&exhibit.location)
+        .bind(&exhibit.description)
+        .bind(&exhibit.status)
+        .bind(&exhibit.image_url)
+        .bind(exhibit.version)
+        .bind(exhibit.deleted_at)
+        .execute(&mut *tx)
+        .await?;
+        exhibit_ids.insert(exhibit.id, result.last_insert_rowid());
+    }
+    summary.exhibits = exhibit_ids.len();
+
+    let mut part_ids = HashMap::new();
+    for part in &snapshot.parts {
+        let result = sqlx::query(
+            "INSERT INTO parts (name, link, version, deleted_at) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(&part.name)
+        .bind(&part.link)
+        .bind(part.version)
+        .bind(part.deleted_at)
+        .execute(&mut *tx)
+        .await?;
+        part_ids.insert(part.id, result.last_insert_rowid());
+    }
+    summary.parts = part_ids.len();
+
+    for link in &snapshot.exhibit_parts {
+        let (Some(exhibit_id), Some(part_id)) = (
+            exhibit_ids.get(&link.exhibit_id),
+            part_ids.get(&link.part_id),
+        ) else {
+            warn!(
+                "Skipping link from exhibit {} to part {}, one of them isn't in the snapshot",
+                link.exhibit_id, link.part_id
+            );
+            continue;
+        };
+
+        sqlx::query("INSERT OR IGNORE INTO exhibit_parts (exhibit_id, part_id) VALUES (?1, ?2)")
+            .bind(exhibit_id)
+            .bind(part_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    for sponsorship in &snapshot.sponsorships {
+        let (Some(exhibit_id), Some(sponsor_id)) = (
+            exhibit_ids.get(&sponsorship.exhibit_id),
+            sponsor_ids.get(&sponsorship.sponsor_id),
+        ) else {
+            warn!(
+                "Skipping sponsorship {}, its exhibit or sponsor isn't in the snapshot",
+                sponsorship.id
+            );
+            continue;
+        };
+
+        sqlx::query(
+            "INSERT INTO exhibit_sponsorships
+                 (exhibit_id, sponsor_id, start_date, end_date, amount_cents, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(exhibit_id)
+        .bind(sponsor_id)
+        .bind(sponsorship.start_date)
+        .bind(sponsorship.end_date)
+        .bind(sponsorship.amount_cents)
+        .bind(&sponsorship.notes)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let mut task_ids = HashMap::new();
+    for task in &snapshot.maintenance_tasks {
+        let Some(exhibit_id) = exhibit_ids.get(&task.exhibit_id) else {
+            warn!(
+                "Skipping maintenance task {}, its exhibit isn't in the snapshot",
+                task.id
+            );
+            continue;
+        };
+
+        let result = sqlx::query(
+            "INSERT INTO maintenance_tasks
+                 (exhibit_id, title, description, interval_every, interval_unit, next_due_date,
+                  auto_create_ticket, last_ticket_due_date)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )
+        .bind(exhibit_id)
+        .bind(&task.title)
+        .bind(&task.description)
+        .bind(task.interval_every)
+        .bind(task.interval_unit)
+        .bind(task.next_due_date)
+        .bind(task.auto_create_ticket)
+        .bind(task.last_ticket_due_date)
+        .execute(&mut *tx)
+        .await?;
+        task_ids.insert(task.id, result.last_insert_rowid());
+    }
+    summary.maintenance_tasks = task_ids.len();
+
+    for completion in &snapshot.maintenance_completions {
+        let Some(task_id) = task_ids.get(&completion.task_id) else {
+            warn!(
+                "Skipping maintenance completion {}, its task isn't in the snapshot",
+                completion.id
+            );
+            continue;
+        };
+
+        sqlx::query(
+            "INSERT INTO maintenance_completions
+                 (task_id, completed_by, completed_at, due_date, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(task_id)
+        .bind(&completion.completed_by)
+        .bind(completion.completed_at)
+        .bind(completion.due_date)
+        .bind(&completion.notes)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // Tickets opened by maintenance tasks are named after the task, so they follow its new ID
+    let ticket_id = |id: &str| match parse_maintenance_ticket_id(id) {
+        Some((task_id, due_date)) => task_ids
+            .get(&task_id)
+            .map(|task_id| maintenance_ticket_id(*task_id, due_date)),
+        None => Some(id.to_string()),
+    };
+
+    let mut ticket_ids = HashMap::new();
+    for ticket in &snapshot.tickets {
+        let Some(id) = ticket_id(&ticket.id) else {
+            warn!(
+                "Skipping ticket {}, its task isn't in the snapshot",
+                ticket.id
+            );
+            continue;
+        };
+
+        sqlx::query(
+            "INSERT INTO jotforms
+                 (id, submitter_first_name, submitter_last_name, created_at, location,
+                  exhibit_name, description, priority_level, department, status, deleted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        )
+        .bind(&id)
+        .bind(&ticket.submitter_first_name)
+        .bind(&ticket.submitter_last_name)
+        .bind(ticket.created_at)
+        .bind(&ticket.location)
+        .bind(&ticket.exhibit_name)
+        .bind(&ticket.description)
+        .bind(&ticket.priority_level)
+        .bind(&ticket.department)
+        .bind(&ticket.status)
+        .bind(ticket.deleted_at)
+        .execute(&mut *tx)
+        .await?;
+        ticket_ids.insert(ticket.id.clone(), id);
+    }
+    summary.tickets = ticket_ids.len();
+
+    for id in &snapshot.purged_ticket_ids {
+        sqlx::query("INSERT OR IGNORE INTO purged_jotforms (id) VALUES (?1)")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    let mut note_ids = HashMap::new();
+    for note in &snapshot.notes {
+        let remap = |ids: &HashMap<i64, i64>| {
+            let id = note.target_id.parse::<i64>().ok()?;
+            ids.get(&id).map(i64::to_string)
+        };
+        let target_id = match note.target_type {
+            NoteTarget::Exhibit => remap(&exhibit_ids),
+            NoteTarget::Part => remap(&part_ids),
+            NoteTarget::WorkOrder => remap(&task_ids),
+            NoteTarget::Ticket => ticket_ids.get(&note.target_id).cloned(),
+        };
+        let Some(target_id) = target_id else {
+            warn!(
+                "Skipping note {}, the record it belongs to isn't in the snapshot",
+                note.id
+            );
+            continue;
+        };
+
+        let result = sqlx::query(
+            "INSERT INTO notes (target_type, target_id, submitter, message, created_at, edited_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(note.target_type)
+        .bind(target_id)
+        .bind(&note.submitter)
+        .bind(&note.message)
+        .bind(note.created_at)
+        .bind(note.edited_at)
+        .execute(&mut *tx)
+        .await?;
+        note_ids.insert(note.id, result.last_insert_rowid());
+    }
+    summary.notes = note_ids.len();
+
+    for revision in &snapshot.note_revisions {
+        let Some(note_id) = note_ids.get(&revision.note_id) else {
+            continue;
+        };
+
+        sqlx::query(
+            "INSERT INTO note_revisions (note_id, message, edited_by, edited_at)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(note_id)
+        .bind(&revision.message)
+        .bind(&revision.edited_by)
+        .bind(revision.edited_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    for attachment in &snapshot.note_attachments {
+        let (Some(note_id), Some(data)) = (
+            note_ids.get(&attachment.note_id),
+            attachments.get(&attachment.id),
+        ) else {
+            warn!(
+                "Skipping attachment {}, its note or contents aren't in the snapshot",
+                attachment.id
+            );
+            continue;
+        };
+
+        sqlx::query(
+            "INSERT INTO note_attachments
+                 (note_id, file_name, content_type, size_bytes, data, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(note_id)
+        .bind(&attachment.file_name)
+        .bind(&attachment.content_type)
+        .bind(data.len() as i64)
+        .bind(data)
+        .bind(attachment.created_at)
+        .execute(&mut *tx)
+        .await?;
+        summary.attachments += 1;
+    }
+
+    let event = ChangeEvent::new(Entity::Data, Action::Replaced, None);
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+    events::publish(event);
+
+    Ok(Some(summary))
+}
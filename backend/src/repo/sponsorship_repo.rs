@@ -0,0 +1,504 @@
+use crate::api::sponsorship_handlers::{NewSponsor, NewSponsorship};
+use crate::db::{column_exists, DbPool};
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::models::{ExpiringSponsorship, Sponsor, SponsorProfile, Sponsorship};
+use chrono::{Days, NaiveDate};
+use log::warn;
+use sqlx::{Connection, Result, SqliteConnection, SqliteExecutor};
+
+const SPONSORSHIP_SELECT: &str = "
+    SELECT es.id, es.exhibit_id, es.sponsor_id, s.name AS sponsor_name,
+           es.start_date, es.end_date, es.amount_cents, es.notes
+    FROM exhibit_sponsorships es
+    JOIN sponsors s ON s.id = es.sponsor_id";
+
+#[derive(sqlx::FromRow)]
+struct CurrentSponsorRow {
+    name: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+}
+
+pub async fn create_sponsorship_tables(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS sponsors (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            contact_name TEXT,
+            contact_email TEXT,
+            contact_phone TEXT,
+            notes TEXT
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS exhibit_sponsorships (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            exhibit_id INTEGER NOT NULL,
+            sponsor_id INTEGER NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            amount_cents INTEGER,
+            notes TEXT,
+            FOREIGN KEY (exhibit_id) REFERENCES exhibits(id) ON DELETE CASCADE,
+            FOREIGN KEY (sponsor_id) REFERENCES sponsors(id) ON DELETE RESTRICT
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    migrate_legacy_sponsor_columns(&mut *conn).await?;
+
+    Ok(())
+}
+
+/// Moves sponsors from the old `exhibits.sponsor_*` columns into the sponsorship tables
+/// and drops those columns. Does nothing once the columns are gone.
+///
+/// Sponsorships whose dates never parsed as YYYY-MM-DD can't become sponsorships, so they're
+/// copied as they were into `unmigrated_sponsorships` for someone to fix up by hand.
+async fn migrate_legacy_sponsor_columns(conn: &mut SqliteConnection) -> Result<()> {
+    if !column_exists(&mut *conn, "exhibits", "sponsor_name").await? {
+        return Ok(());
+    }
+
+    let mut tx = conn.begin().await?;
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO sponsors (name)
+         SELECT DISTINCT sponsor_name FROM exhibits WHERE sponsor_name IS NOT NULL",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // SQLite's date() returns NULL for anything that isn't a valid date
+    sqlx::query(
+        "INSERT INTO exhibit_sponsorships (exhibit_id, sponsor_id, start_date, end_date)
+         SELECT e.id, s.id, date(e.sponsor_start_date), date(e.sponsor_end_date)
+         FROM exhibits e
+         JOIN sponsors s ON s.name = e.sponsor_name
+         WHERE date(e.sponsor_start_date) IS NOT NULL AND date(e.sponsor_end_date) IS NOT NULL",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let unmigrated: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM exhibits
+         WHERE sponsor_name IS NOT NULL
+           AND (date(sponsor_start_date) IS NULL OR date(sponsor_end_date) IS NULL)
+         ORDER BY id",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if !unmigrated.is_empty() {
+        warn!(
+            "{} legacy sponsorships have dates that don't parse; keeping them in \
+             unmigrated_sponsorships. Exhibit IDs: {:?}",
+            unmigrated.len(),
+            unmigrated
+        );
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS unmigrated_sponsorships (
+                exhibit_id INTEGER NOT NULL,
+                sponsor_name TEXT NOT NULL,
+                start_date TEXT,
+                end_date TEXT
+            )",
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO unmigrated_sponsorships (exhibit_id, sponsor_name, start_date, end_date)
+             SELECT id, sponsor_name, sponsor_start_date, sponsor_end_date
+             FROM exhibits
+             WHERE sponsor_name IS NOT NULL
+               AND (date(sponsor_start_date) IS NULL OR date(sponsor_end_date) IS NULL)",
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    for column in ["sponsor_name", "sponsor_start_date", "sponsor_end_date"] {
+        sqlx::query(&format!("ALTER TABLE exhibits DROP COLUMN {}", column))
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn get_all_sponsors(pool: &DbPool) -> Result<Vec<SponsorProfile>> {
+    sqlx::query_as::<_, SponsorProfile>(
+        "SELECT id, name, contact_name, contact_email, contact_phone, notes
+         FROM sponsors
+         ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_sponsor(id: i64, pool: &DbPool) -> Result<Option<SponsorProfile>> {
+    sqlx::query_as::<_, SponsorProfile>(
+        "SELECT id, name, contact_name, contact_email, contact_phone, notes
+         FROM sponsors
+         WHERE id = ?1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn create_sponsor(sponsor: &NewSponsor, pool: &DbPool) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO sponsors (name, contact_name, contact_email, contact_phone, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(&sponsor.name)
+    .bind(&sponsor.contact_name)
+    .bind(&sponsor.contact_email)
+    .bind(&sponsor.contact_phone)
+    .bind(&sponsor.notes)
+    .execute(&mut *tx)
+    .await?;
+
+    let id = result.last_insert_rowid();
+    let event = ChangeEvent::new(Entity::Sponsor, Action::Created, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(id)
+}
+
+pub async fn update_sponsor(id: i64, sponsor: &NewSponsor, pool: &DbPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE sponsors
+         SET name = ?1, contact_name = ?2, contact_email = ?3, contact_phone = ?4, notes = ?5
+         WHERE id = ?6",
+    )
+    .bind(&sponsor.name)
+    .bind(&sponsor.contact_name)
+    .bind(&sponsor.contact_email)
+    .bind(&sponsor.contact_phone)
+    .bind(&sponsor.notes)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let event = ChangeEvent::new(Entity::Sponsor, Action::Updated, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(())
+}
+
+/// Deletes a sponsor. Fails with a foreign key error while it still has sponsorships.
+pub async fn delete_sponsor(id: i64, pool: &DbPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query("DELETE FROM sponsors WHERE id = ?1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let event = ChangeEvent::new(Entity::Sponsor, Action::Deleted, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(())
+}
+
+pub async fn get_exhibit_sponsorships(exhibit_id: i64, pool: &DbPool) -> Result<Vec<Sponsorship>> {
+    sqlx::query_as::<_, Sponsorship>(&format!(
+        "{} WHERE es.exhibit_id = ?1 ORDER BY es.start_date DESC",
+        SPONSORSHIP_SELECT
+    ))
+    .bind(exhibit_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_sponsorship(id: i64, pool: &DbPool) -> Result<Option<Sponsorship>> {
+    sqlx::query_as::<_, Sponsorship>(&format!("{} WHERE es.id = ?1", SPONSORSHIP_SELECT))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn create_sponsorship(
+    exhibit_id: i64,
+    sponsorship: &NewSponsorship,
+    pool: &DbPool,
+) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO exhibit_sponsorships
+             (exhibit_id, sponsor_id, start_date, end_date, amount_cents, notes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )
+    .bind(exhibit_id)
+    .bind(sponsorship.sponsor_id)
+    .bind(sponsorship.start_date)
+    .bind(sponsorship.end_date)
+    .bind(sponsorship.amount_cents)
+    .bind(&sponsorship.notes)
+    .execute(&mut *tx)
+    .await?;
+
+    let id = result.last_insert_rowid();
+    let event = ChangeEvent::new(Entity::Sponsorship, Action::Created, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(id)
+}
+
+pub async fn update_sponsorship(
+    id: i64,
+    sponsorship: &NewSponsorship,
+    pool: &DbPool,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE exhibit_sponsorships
+         SET sponsor_id = ?1, start_date = ?2, end_date = ?3, amount_cents = ?4, notes = ?5
+         WHERE id = ?6",
+    )
+    .bind(sponsorship.sponsor_id)
+    .bind(sponsorship.start_date)
+    .bind(sponsorship.end_date)
+    .bind(sponsorship.amount_cents)
+    .bind(&sponsorship.notes)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let event = ChangeEvent::new(Entity::Sponsorship, Action::Updated, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(())
+}
+
+pub async fn delete_sponsorship(id: i64, pool: &DbPool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query("DELETE FROM exhibit_sponsorships WHERE id = ?1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let event = ChangeEvent::new(Entity::Sponsorship, Action::Deleted, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(())
+}
+
+/// Lists sponsorships ending between `today` and `until` (inclusive), soonest first.
+///
+/// Sponsorships that have already been renewed, i.e. the exhibit has another sponsorship
+/// running past this one's end date, are left out.
+pub async fn get_expiring_sponsorships(
+    today: NaiveDate,
+    until: NaiveDate,
+    pool: &DbPool,
+) -> Result<Vec<ExpiringSponsorship>> {
+    sqlx::query_as::<_, ExpiringSponsorship>(
+        "SELECT es.id, es.exhibit_id, es.sponsor_id, s.name AS sponsor_name,
+                es.start_date, es.end_date, es.amount_cents, es.notes,
+                e.name AS exhibit_name, s.contact_name, s.contact_email, s.contact_phone,
+                CAST(julianday(es.end_date) - julianday(?1) AS INTEGER) AS days_remaining
+         FROM exhibit_sponsorships es
+         JOIN sponsors s ON s.id = es.sponsor_id
+         JOIN exhibits e ON e.id = es.exhibit_id
+         WHERE es.end_date >= ?1 AND es.end_date <= ?2
+           AND e.deleted_at IS NULL
+           AND NOT EXISTS (
+               SELECT 1 FROM exhibit_sponsorships renewal
+               WHERE renewal.exhibit_id = es.exhibit_id AND renewal.end_date > es.end_date
+           )
+         ORDER BY es.end_date",
+    )
+    .bind(today)
+    .bind(until)
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns the exhibit's current sponsorship, or the next upcoming one if none is running.
+pub async fn get_current_sponsor<'e>(
+    exhibit_id: i64,
+    today: NaiveDate,
+    executor: impl SqliteExecutor<'e>,
+) -> Result<Option<Sponsor>> {
+    let row = sqlx::query_as::<_, CurrentSponsorRow>(
+        "SELECT s.name, es.start_date, es.end_date
+         FROM exhibit_sponsorships es
+         JOIN sponsors s ON s.id = es.sponsor_id
+         WHERE es.exhibit_id = ?1 AND es.end_date >= ?2
+         ORDER BY es.start_date
+         LIMIT 1",
+    )
+    .bind(exhibit_id)
+    .bind(today)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(row.map(|row| Sponsor {
+        name: row.name,
+        start_date: row.start_date,
+        end_date: row.end_date,
+    }))
+}
+
+/// Sets or clears the sponsorship shown on the exhibit itself.
+///
+/// Setting the current sponsor again edits its sponsorship in place, while a different
+/// sponsor ends the current sponsorship and starts a new one, so the old one stays in the
+/// history. The sponsor is created by name if needed. Clearing ends running sponsorships
+/// yesterday and drops ones that haven't started, so past history is kept.
+pub async fn set_current_sponsor(
+    exhibit_id: i64,
+    sponsor: Option<&Sponsor>,
+    today: NaiveDate,
+    conn: &mut SqliteConnection,
+) -> Result<()> {
+    let Some(sponsor) = sponsor else {
+        let yesterday = today - Days::new(1);
+
+        sqlx::query(
+            "UPDATE exhibit_sponsorships SET end_date = ?2
+             WHERE exhibit_id = ?1 AND start_date < ?3 AND end_date >= ?3",
+        )
+        .bind(exhibit_id)
+        .bind(yesterday)
+        .bind(today)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query("DELETE FROM exhibit_sponsorships WHERE exhibit_id = ?1 AND start_date >= ?2")
+            .bind(exhibit_id)
+            .bind(today)
+            .execute(&mut *conn)
+            .await?;
+
+        return Ok(());
+    };
+
+    sqlx::query("INSERT INTO sponsors (name) VALUES (?1) ON CONFLICT(name) DO NOTHING")
+        .bind(&sponsor.name)
+        .execute(&mut *conn)
+        .await?;
+
+    let sponsor_id = sqlx::query_scalar::<_, i64>("SELECT id FROM sponsors WHERE name = ?1")
+        .bind(&sponsor.name)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let current = sqlx::query_as::<_, (i64, i64, NaiveDate)>(
+        "SELECT id, sponsor_id, start_date FROM exhibit_sponsorships
+         WHERE exhibit_id = ?1 AND end_date >= ?2
+         ORDER BY start_date
+         LIMIT 1",
+    )
+    .bind(exhibit_id)
+    .bind(today)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some((current_id, current_sponsor_id, current_start)) = current {
+        if current_sponsor_id == sponsor_id {
+            sqlx::query(
+                "UPDATE exhibit_sponsorships SET start_date = ?1, end_date = ?2 WHERE id = ?3",
+            )
+            .bind(sponsor.start_date)
+            .bind(sponsor.end_date)
+            .bind(current_id)
+            .execute(&mut *conn)
+            .await?;
+
+            return Ok(());
+        }
+
+        // A different sponsor takes over: the current one ends the day before, unless it
+        // would then end before it began, in which case one that hasn't started yet is
+        // dropped and one that has is ended yesterday
+        let day_before = sponsor.start_date - Days::new(1);
+        if day_before >= current_start {
+            end_sponsorship(current_id, day_before, conn).await?;
+        } else if current_start >= today {
+            sqlx::query("DELETE FROM exhibit_sponsorships WHERE id = ?1")
+                .bind(current_id)
+                .execute(&mut *conn)
+                .await?;
+        } else {
+            end_sponsorship(current_id, today - Days::new(1), conn).await?;
+        }
+    }
+
+    sqlx::query(
+        "INSERT INTO exhibit_sponsorships (exhibit_id, sponsor_id, start_date, end_date)
+         VALUES (?1, ?2, ?3, ?4)",
+    )
+    .bind(exhibit_id)
+    .bind(sponsor_id)
+    .bind(sponsor.start_date)
+    .bind(sponsor.end_date)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn end_sponsorship(id: i64, end_date: NaiveDate, conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query("UPDATE exhibit_sponsorships SET end_date = ?1 WHERE id = ?2")
+        .bind(end_date)
+        .bind(id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,1444 @@
+use super::{
+    exhibit_repo, jotform_repo, maintenance_repo, note_repo, part_repo, snapshot_repo,
+    sponsorship_repo, trash_repo,
+};
+use crate::api::exhibit_handlers::{
+    ExhibitBatchAction, ExhibitBatchOutcome, ExhibitBatchRequest, ExhibitFilter, NewExhibit,
+};
+use crate::api::maintenance_handlers::NewMaintenanceTask;
+use crate::backup::{self, BackupError, BackupSettings};
+use crate::db::{setup_database, DbPool};
+use crate::exhibit_import::{parse_sheet, ImportFormat, ImportOutcome, IMPORTED_NOTE_SUBMITTER};
+use crate::models::{Exhibit, IntervalUnit, NoteTarget, TrashEntity, UpdateExhibit};
+use rocket::serde::json::serde_json;
+use rocket::tokio;
+use sqlx::SqlitePool;
+use validator::Validate;
+
+async fn setup_test_db() -> DbPool {
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+    setup_database(&pool).await.unwrap();
+
+    pool
+}
+
+fn fake_exhibit(name: &str) -> NewExhibit {
+    NewExhibit {
+        name: name.to_string(),
+        cluster: "Physics".to_string(),
+        location: "Main Hall".to_string(),
+        description: "A test exhibit".to_string(),
+        status: "operational".to_string(),
+        image_url: Some("https://example.com/exhibit.jpg".to_string()),
+        sponsor: None,
+        part_ids: vec![],
+        notes: vec![],
+    }
+}
+
+async fn insert_fake_exhibits(pool: &DbPool, count: usize) -> Vec<i64> {
+    for i in 0..count {
+        exhibit_repo::create_exhibit(&fake_exhibit(&format!("Exhibit {}", i)), pool)
+            .await
+            .unwrap();
+    }
+
+    sqlx::query_scalar::<_, i64>("SELECT id FROM exhibits ORDER BY id")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_batch_change_status() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 3).await;
+
+    let request = ExhibitBatchRequest {
+        ids: vec![ids[0], ids[1], 9999],
+        action: ExhibitBatchAction::ChangeStatus {
+            status: "out of service".to_string(),
+        },
+        dry_run: false,
+    };
+
+    let results = exhibit_repo::apply_exhibit_batch(&request, &pool).await?;
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].outcome, ExhibitBatchOutcome::Updated);
+    assert_eq!(results[0].changes[0].field, "status");
+    assert_eq!(results[0].changes[0].from, "operational");
+    assert_eq!(results[1].outcome, ExhibitBatchOutcome::Updated);
+    assert_eq!(results[2].outcome, ExhibitBatchOutcome::NotFound);
+
+    let updated = exhibit_repo::get_exhibit(ids[0], &pool).await?.unwrap();
+    assert_eq!(updated.status, "out of service");
+    let untouched = exhibit_repo::get_exhibit(ids[2], &pool).await?.unwrap();
+    assert_eq!(untouched.status, "operational");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_dry_run_writes_nothing() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 2).await;
+
+    let request = ExhibitBatchRequest {
+        ids: ids.clone(),
+        action: ExhibitBatchAction::Delete,
+        dry_run: true,
+    };
+
+    let results = exhibit_repo::apply_exhibit_batch(&request, &pool).await?;
+
+    assert!(results
+        .iter()
+        .all(|r| r.outcome == ExhibitBatchOutcome::Deleted));
+    assert_eq!(
+        exhibit_repo::get_all_exhibits(&pool).await?.unwrap().len(),
+        2
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_batch_move_reports_unchanged() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 1).await;
+
+    let request = ExhibitBatchRequest {
+        ids: ids.clone(),
+        action: ExhibitBatchAction::Move {
+            cluster: Some("Physics".to_string()),
+            location: None,
+        },
+        dry_run: false,
+    };
+
+    let results = exhibit_repo::apply_exhibit_batch(&request, &pool).await?;
+
+    assert_eq!(results[0].outcome, ExhibitBatchOutcome::Unchanged);
+    assert!(results[0].changes.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_exhibit_checks_version() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 1).await;
+
+    let update = UpdateExhibit {
+        name: Some(Some("Renamed".to_string())),
+        ..Default::default()
+    };
+
+    // First writer holds version 1 and succeeds
+    let new_version = exhibit_repo::update_exhibit(&ids[0], &update, Some(1), &pool).await?;
+    assert_eq!(new_version, Some(2));
+
+    // Second writer still holds version 1 and is rejected
+    let stale = exhibit_repo::update_exhibit(&ids[0], &update, Some(1), &pool).await?;
+    assert_eq!(stale, None);
+
+    // Unconditional writes always apply
+    let forced = exhibit_repo::update_exhibit(&ids[0], &update, None, &pool).await?;
+    assert_eq!(forced, Some(3));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_exhibit_merge_patch() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 1).await;
+
+    let patch: UpdateExhibit = serde_json::from_str(
+        r#"{
+            "status": "needs repair",
+            "sponsor": { "name": "Acme", "start_date": "2090-01-01", "end_date": "2099-01-01" },
+            "part_ids": []
+        }"#,
+    )?;
+    exhibit_repo::update_exhibit(&ids[0], &patch, None, &pool).await?;
+
+    let exhibit = exhibit_repo::get_exhibit(ids[0], &pool).await?.unwrap();
+    assert_eq!(exhibit.status, "needs repair");
+    assert_eq!(exhibit.sponsor.unwrap().name, "Acme");
+    assert_eq!(exhibit.name, "Exhibit 0");
+
+    // `null` clears the sponsor; fields that are left out stay as they were
+    let patch: UpdateExhibit = serde_json::from_str(r#"{ "sponsor": null }"#)?;
+    exhibit_repo::update_exhibit(&ids[0], &patch, None, &pool).await?;
+
+    let exhibit = exhibit_repo::get_exhibit(ids[0], &pool).await?.unwrap();
+    assert_eq!(exhibit.sponsor, None);
+    assert_eq!(exhibit.status, "needs repair");
+
+    Ok(())
+}
+
+#[test]
+fn test_update_exhibit_validation() {
+    let patch: UpdateExhibit = serde_json::from_str(r#"{ "status": "broken" }"#).unwrap();
+    assert!(patch.validate().is_err());
+
+    let patch: UpdateExhibit = serde_json::from_str(r#"{ "name": null }"#).unwrap();
+    assert!(patch.validate().is_err());
+
+    let patch: UpdateExhibit =
+        serde_json::from_str(r#"{ "status": "Out of Service", "description": null }"#).unwrap();
+    assert!(patch.validate().is_ok());
+}
+
+#[tokio::test]
+async fn test_legacy_sponsor_columns_are_migrated() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = SqlitePool::connect("sqlite::memory:").await?;
+
+    // The exhibits table as it looked before sponsorships had their own tables
+    sqlx::query(
+        "CREATE TABLE exhibits (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            cluster TEXT NOT NULL,
+            location TEXT NOT NULL,
+            description TEXT NOT NULL,
+            status TEXT NOT NULL,
+            image_url TEXT NOT NULL,
+            sponsor_name TEXT,
+            sponsor_start_date TEXT,
+            sponsor_end_date TEXT
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO exhibits (name, cluster, location, description, status, image_url,
+                               sponsor_name, sponsor_start_date, sponsor_end_date)
+         VALUES ('Big Lever', 'Physics', 'Outside', '', 'operational', 'x',
+                 'Acme', '2020-01-01', '2021-01-01'),
+                ('Small Lever', 'Physics', 'Outside', '', 'operational', 'x',
+                 'Acme', '1/1/2020', NULL)",
+    )
+    .execute(&pool)
+    .await?;
+
+    setup_database(&pool).await?;
+
+    let history = sponsorship_repo::get_exhibit_sponsorships(1, &pool).await?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].sponsor_name, "Acme");
+    assert_eq!(history[0].end_date.to_string(), "2021-01-01");
+    assert!(
+        !crate::db::column_exists(&mut *pool.acquire().await?, "exhibits", "sponsor_name").await?
+    );
+
+    // Sponsorships with dates that don't parse are kept as they were
+    assert!(sponsorship_repo::get_exhibit_sponsorships(2, &pool)
+        .await?
+        .is_empty());
+    let unmigrated: Vec<(i64, String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT exhibit_id, sponsor_name, start_date, end_date FROM unmigrated_sponsorships",
+    )
+    .fetch_all(&pool)
+    .await?;
+    assert_eq!(
+        unmigrated,
+        vec![(2, "Acme".to_string(), Some("1/1/2020".to_string()), None)]
+    );
+
+    // Running setup again must not duplicate anything
+    setup_database(&pool).await?;
+    assert_eq!(
+        sponsorship_repo::get_exhibit_sponsorships(1, &pool)
+            .await?
+            .len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expiring_sponsorships_skip_renewed() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 2).await;
+    let date = |s: &str| s.parse::<chrono::NaiveDate>().unwrap();
+
+    let mut conn = pool.acquire().await?;
+    for id in &ids {
+        let sponsor = crate::models::Sponsor {
+            name: "Acme".to_string(),
+            start_date: date("2030-01-01"),
+            end_date: date("2030-03-01"),
+        };
+        sponsorship_repo::set_current_sponsor(*id, Some(&sponsor), date("2030-01-15"), &mut conn)
+            .await?;
+    }
+    drop(conn);
+
+    // The second exhibit has already been renewed for another year
+    sqlx::query(
+        "INSERT INTO exhibit_sponsorships (exhibit_id, sponsor_id, start_date, end_date)
+         VALUES (?1, 1, '2030-03-01', '2031-03-01')",
+    )
+    .bind(ids[1])
+    .execute(&pool)
+    .await?;
+
+    let expiring =
+        sponsorship_repo::get_expiring_sponsorships(date("2030-02-01"), date("2030-04-01"), &pool)
+            .await?;
+
+    assert_eq!(expiring.len(), 1);
+    assert_eq!(expiring[0].sponsorship.exhibit_id, ids[0]);
+    assert_eq!(expiring[0].days_remaining, 28);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_changing_sponsor_keeps_history() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let id = insert_fake_exhibits(&pool, 1).await[0];
+    let date = |s: &str| s.parse::<chrono::NaiveDate>().unwrap();
+    let sponsor = |name: &str, start: &str, end: &str| crate::models::Sponsor {
+        name: name.to_string(),
+        start_date: date(start),
+        end_date: date(end),
+    };
+
+    let mut conn = pool.acquire().await?;
+    let today = date("2030-06-01");
+    sponsorship_repo::set_current_sponsor(
+        id,
+        Some(&sponsor("Acme", "2030-01-01", "2030-12-31")),
+        today,
+        &mut conn,
+    )
+    .await?;
+
+    // The same sponsor is edited in place
+    sponsorship_repo::set_current_sponsor(
+        id,
+        Some(&sponsor("Acme", "2030-01-01", "2031-06-30")),
+        today,
+        &mut conn,
+    )
+    .await?;
+
+    // A new sponsor ends the old sponsorship the day before it starts
+    sponsorship_repo::set_current_sponsor(
+        id,
+        Some(&sponsor("Globex", "2030-07-01", "2031-06-30")),
+        today,
+        &mut conn,
+    )
+    .await?;
+    drop(conn);
+
+    let history = sponsorship_repo::get_exhibit_sponsorships(id, &pool).await?;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].sponsor_name, "Globex");
+    assert_eq!(history[0].start_date, date("2030-07-01"));
+    assert_eq!(history[1].sponsor_name, "Acme");
+    assert_eq!(history[1].start_date, date("2030-01-01"));
+    assert_eq!(history[1].end_date, date("2030-06-30"));
+
+    // Deleting one only works once
+    sponsorship_repo::delete_sponsorship(history[0].id, &pool).await?;
+    assert!(matches!(
+        sponsorship_repo::delete_sponsorship(history[0].id, &pool).await,
+        Err(sqlx::Error::RowNotFound)
+    ));
+    assert_eq!(
+        sponsorship_repo::get_exhibit_sponsorships(id, &pool)
+            .await?
+            .len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_interval_unit_advance() {
+    let date = |s: &str| s.parse::<chrono::NaiveDate>().unwrap();
+
+    assert_eq!(
+        IntervalUnit::Days.advance(date("2030-01-25"), 14),
+        Some(date("2030-02-08"))
+    );
+    assert_eq!(
+        IntervalUnit::Weeks.advance(date("2030-01-01"), 2),
+        Some(date("2030-01-15"))
+    );
+    // Month arithmetic clamps to the end of shorter months
+    assert_eq!(
+        IntervalUnit::Months.advance(date("2030-08-31"), 6),
+        Some(date("2031-02-28"))
+    );
+}
+
+#[tokio::test]
+async fn test_maintenance_tickets_and_completion() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 1).await;
+    let date = |s: &str| s.parse::<chrono::NaiveDate>().unwrap();
+
+    let new_task = NewMaintenanceTask {
+        title: "Clean lenses".to_string(),
+        description: String::new(),
+        interval_every: 14,
+        interval_unit: IntervalUnit::Days,
+        next_due_date: None,
+        auto_create_ticket: true,
+    };
+    let task_id =
+        maintenance_repo::create_task(ids[0], &new_task, date("2030-01-10"), &pool).await?;
+
+    // Not due yet
+    assert_eq!(
+        maintenance_repo::create_due_tickets(date("2030-01-09"), &pool).await?,
+        0
+    );
+    assert!(
+        maintenance_repo::get_overdue_tasks(date("2030-01-10"), &pool)
+            .await?
+            .is_empty()
+    );
+
+    // Due: exactly one ticket, even if the job runs again
+    assert_eq!(
+        maintenance_repo::create_due_tickets(date("2030-01-10"), &pool).await?,
+        1
+    );
+    assert_eq!(
+        maintenance_repo::create_due_tickets(date("2030-01-11"), &pool).await?,
+        0
+    );
+
+    let overdue = maintenance_repo::get_overdue_tasks(date("2030-01-12"), &pool).await?;
+    assert_eq!(overdue.len(), 1);
+    assert_eq!(overdue[0].days_overdue, 2);
+
+    let ticket_id = maintenance_repo::maintenance_ticket_id(task_id, date("2030-01-10"));
+    let ticket = jotform_repo::get_jotform(ticket_id.clone(), &pool)
+        .await?
+        .unwrap();
+    assert_eq!(ticket.status, "Open");
+
+    // Completing the task logs it, reschedules it and closes the ticket
+    let task = maintenance_repo::get_task(task_id, &pool).await?.unwrap();
+    maintenance_repo::complete_task(
+        &task,
+        "Kenneth",
+        chrono::Utc::now(),
+        None,
+        date("2030-01-26"),
+        &pool,
+    )
+    .await?;
+
+    let task = maintenance_repo::get_task(task_id, &pool).await?.unwrap();
+    assert_eq!(task.next_due_date, date("2030-01-26"));
+
+    let completions = maintenance_repo::get_completions(task_id, &pool).await?;
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].completed_by, "Kenneth");
+    assert_eq!(completions[0].due_date, date("2030-01-10"));
+
+    let ticket = jotform_repo::get_jotform(ticket_id, &pool).await?.unwrap();
+    assert_eq!(ticket.status, "Closed");
+
+    // A pass that stopped after opening the next ticket but before recording it is finished
+    // off by the next one, without a second ticket or a duplicate ID error
+    let next_ticket_id = maintenance_repo::maintenance_ticket_id(task_id, date("2030-01-26"));
+    let mut half_done = ticket.clone();
+    half_done.id = next_ticket_id.clone();
+    half_done.status = "Open".to_string();
+    jotform_repo::insert_jotform(&half_done, &pool).await?;
+    assert_eq!(
+        maintenance_repo::create_due_tickets(date("2030-01-26"), &pool).await?,
+        0
+    );
+    let recorded: Option<chrono::NaiveDate> =
+        sqlx::query_scalar("SELECT last_ticket_due_date FROM maintenance_tasks WHERE id = ?1")
+            .bind(task_id)
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(recorded, Some(date("2030-01-26")));
+    assert!(jotform_repo::get_jotform(next_ticket_id, &pool)
+        .await?
+        .is_some());
+
+    // Passes that overlap, like the scheduled one and one at startup, open it only once
+    let other_id =
+        maintenance_repo::create_task(ids[0], &new_task, date("2030-02-01"), &pool).await?;
+    let (first, second) = rocket::tokio::join!(
+        maintenance_repo::create_due_tickets(date("2030-02-01"), &pool),
+        maintenance_repo::create_due_tickets(date("2030-02-01"), &pool)
+    );
+    assert_eq!(first? + second?, 1);
+    let tickets: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jotforms WHERE id = ?1")
+        .bind(maintenance_repo::maintenance_ticket_id(
+            other_id,
+            date("2030-02-01"),
+        ))
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(tickets, 1);
+    assert_eq!(
+        maintenance_repo::create_due_tickets(date("2030-02-01"), &pool).await?,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_legacy_note_tables_migrate_to_utc() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = SqlitePool::connect("sqlite::memory:").await?;
+
+    setup_database(&pool).await?;
+    let ids = insert_fake_exhibits(&pool, 1).await;
+
+    // The per-exhibit notes table as it looked before notes were unified, with a time
+    // written at the old fixed UTC-6 offset
+    sqlx::query(
+        "CREATE TABLE exhibit_notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            exhibit_id INTEGER NOT NULL,
+            submitter TEXT NOT NULL,
+            date TEXT NOT NULL,
+            time TEXT NOT NULL,
+            message TEXT NOT NULL
+        )",
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO exhibit_notes (exhibit_id, submitter, date, time, message)
+         VALUES (?1, 'Kenneth', '2024-07-01', '09:30:00.123456789', 'Replaced bulb')",
+    )
+    .bind(ids[0])
+    .execute(&pool)
+    .await?;
+
+    setup_database(&pool).await?;
+
+    let notes = note_repo::get_notes(NoteTarget::Exhibit, &ids[0].to_string(), &pool).await?;
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].message, "Replaced bulb");
+    assert_eq!(
+        notes[0].created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        "2024-07-01 15:30:00"
+    );
+    assert!(!crate::db::table_exists(&mut *pool.acquire().await?, "exhibit_notes").await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_note_edit_history_and_cleanup() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 1).await;
+    let exhibit_id = ids[0].to_string();
+
+    let note_id = note_repo::create_note(
+        NoteTarget::Exhibit,
+        &exhibit_id,
+        "Kenneth",
+        "Lens is dusty",
+        chrono::Utc::now(),
+        &pool,
+    )
+    .await?;
+    note_repo::create_attachment(note_id, "lens.jpg", "image/jpeg", b"jpeg", &pool).await?;
+
+    assert!(
+        note_repo::edit_note(note_id, "Dana", "Lens cleaned", chrono::Utc::now(), &pool).await?
+    );
+    assert!(!note_repo::edit_note(9999, "Dana", "Nope", chrono::Utc::now(), &pool).await?);
+
+    let note = note_repo::get_note(note_id, &pool).await?.unwrap();
+    assert_eq!(note.message, "Lens cleaned");
+    assert!(note.edited_at.is_some());
+    assert_eq!(note.attachments.len(), 1);
+    assert_eq!(note.attachments[0].size_bytes, 4);
+
+    let history = note_repo::get_note_history(note_id, &pool).await?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].message, "Lens is dusty");
+    assert_eq!(history[0].edited_by, "Dana");
+
+    // Notes on other kinds of records don't leak across
+    assert!(note_repo::get_notes(NoteTarget::Part, &exhibit_id, &pool)
+        .await?
+        .is_empty());
+
+    // Purging the exhibit from the trash removes its notes, their history and attachments
+    exhibit_repo::delete_exhibit(ids[0], &pool).await?;
+    trash_repo::purge_expired(chrono::Utc::now(), &pool).await?;
+    assert!(note_repo::get_note(note_id, &pool).await?.is_none());
+    assert!(note_repo::get_note_history(note_id, &pool)
+        .await?
+        .is_empty());
+    assert!(note_repo::get_attachment(note_id, 1, &pool)
+        .await?
+        .is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deleting_missing_notes_reports_not_found() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 1).await;
+    let exhibit_id = ids[0].to_string();
+
+    let note_id = note_repo::create_note(
+        NoteTarget::Exhibit,
+        &exhibit_id,
+        "Kenneth",
+        "Lens is dusty",
+        chrono::Utc::now(),
+        &pool,
+    )
+    .await?;
+    let attachment_id =
+        note_repo::create_attachment(note_id, "lens.jpg", "image/jpeg", b"jpeg", &pool).await?;
+
+    let outbox_len = || async {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM webhook_outbox")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    let before = outbox_len().await;
+
+    // Nothing is deleted, so nothing is announced
+    assert!(!note_repo::delete_attachment(note_id, 9999, &pool).await?);
+    assert!(!note_repo::delete_note(9999, &pool).await?);
+    assert!(!note_repo::delete_target_note(NoteTarget::Part, &exhibit_id, note_id, &pool).await?);
+    assert_eq!(outbox_len().await, before);
+
+    assert!(note_repo::delete_attachment(note_id, attachment_id, &pool).await?);
+    assert!(note_repo::delete_target_note(NoteTarget::Exhibit, &exhibit_id, note_id, &pool).await?);
+    assert!(!note_repo::delete_note(note_id, &pool).await?);
+    assert_eq!(outbox_len().await, before + 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_writes_record_their_events_with_the_change() -> Result<(), Box<dyn std::error::Error>>
+{
+    let pool = setup_test_db().await;
+    let id = insert_fake_exhibits(&pool, 1).await[0];
+
+    let outbox = || async {
+        sqlx::query_scalar::<_, String>("SELECT event FROM webhook_outbox ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+    };
+    let before = outbox().await.len();
+
+    // Writes that change nothing leave nothing in the outbox
+    assert!(matches!(
+        exhibit_repo::change_exhibit_status(9999, "operational".to_string(), &pool).await,
+        Err(sqlx::Error::RowNotFound)
+    ));
+    assert!(!trash_repo::restore(TrashEntity::Exhibit, &id.to_string(), &pool).await?);
+    assert_eq!(outbox().await.len(), before);
+
+    // Each write that does lands in the outbox exactly once
+    exhibit_repo::change_exhibit_status(id, "needs repair".to_string(), &pool).await?;
+    exhibit_repo::delete_exhibit(id, &pool).await?;
+    assert!(trash_repo::restore(TrashEntity::Exhibit, &id.to_string(), &pool).await?);
+    let recorded = outbox().await;
+    assert_eq!(recorded.len(), before + 3);
+    for (event, topic) in
+        recorded[before..]
+            .iter()
+            .zip(["exhibit.updated", "exhibit.deleted", "exhibit.restored"])
+    {
+        assert!(event.contains(&format!("\"{}\"", topic)), "{}", event);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_trash_restore_and_purge() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 2).await;
+    let retention = chrono::TimeDelta::days(30);
+
+    part_repo::create_part(
+        &crate::api::part_handlers::NewPart {
+            name: "Bulb".to_string(),
+            link: "https://example.com/bulb".to_string(),
+            exhibit_ids: vec![ids[0]],
+            notes: vec![],
+        },
+        &pool,
+    )
+    .await?;
+    let part_id = sqlx::query_scalar::<_, i64>("SELECT id FROM parts")
+        .fetch_one(&pool)
+        .await?;
+
+    note_repo::create_note(
+        NoteTarget::Exhibit,
+        &ids[0].to_string(),
+        "Kenneth",
+        "Years of history",
+        chrono::Utc::now(),
+        &pool,
+    )
+    .await?;
+
+    // Deleted records disappear from normal reads but keep their notes
+    exhibit_repo::delete_exhibit(ids[0], &pool).await?;
+    part_repo::delete_part(part_id, &pool).await?;
+
+    // Deleting them again finds nothing and announces nothing
+    let outbox_len = || async {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM webhook_outbox")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    let recorded = outbox_len().await;
+    assert!(matches!(
+        exhibit_repo::delete_exhibit(ids[0], &pool).await,
+        Err(sqlx::Error::RowNotFound)
+    ));
+    assert!(matches!(
+        part_repo::delete_part(part_id, &pool).await,
+        Err(sqlx::Error::RowNotFound)
+    ));
+    assert!(matches!(
+        part_repo::delete_part(9999, &pool).await,
+        Err(sqlx::Error::RowNotFound)
+    ));
+    assert_eq!(outbox_len().await, recorded);
+
+    assert!(exhibit_repo::get_exhibit(ids[0], &pool).await?.is_none());
+    assert!(part_repo::get_part(part_id, &pool).await?.is_none());
+    assert_eq!(
+        exhibit_repo::get_all_exhibits(&pool).await?.unwrap().len(),
+        1
+    );
+    assert!(!note_repo::target_exists(NoteTarget::Exhibit, &ids[0].to_string(), &pool).await?);
+    assert_eq!(
+        note_repo::get_notes(NoteTarget::Exhibit, &ids[0].to_string(), &pool)
+            .await?
+            .len(),
+        1
+    );
+
+    let trash = trash_repo::get_trash(None, retention, &pool).await?;
+    assert_eq!(trash.len(), 2);
+    assert!(trash
+        .iter()
+        .all(|item| item.purge_after == item.deleted_at + retention));
+
+    let parts_only = trash_repo::get_trash(Some(TrashEntity::Part), retention, &pool).await?;
+    assert_eq!(parts_only.len(), 1);
+    assert_eq!(parts_only[0].name, "Bulb");
+
+    // Restoring brings the exhibit back with its notes; it can't be restored twice
+    assert!(trash_repo::restore(TrashEntity::Exhibit, &ids[0].to_string(), &pool).await?);
+    assert!(!trash_repo::restore(TrashEntity::Exhibit, &ids[0].to_string(), &pool).await?);
+
+    let exhibit = exhibit_repo::get_exhibit(ids[0], &pool).await?.unwrap();
+    assert_eq!(exhibit.notes.len(), 1);
+    assert!(exhibit.part_ids.is_empty());
+
+    // Nothing is purged before the retention period is up
+    let now = chrono::Utc::now();
+    assert_eq!(trash_repo::purge_expired(now - retention, &pool).await?, 0);
+    assert_eq!(trash_repo::purge_expired(now, &pool).await?, 1);
+    assert!(trash_repo::get_trash(None, retention, &pool)
+        .await?
+        .is_empty());
+    assert!(!trash_repo::restore(TrashEntity::Part, &part_id.to_string(), &pool).await?);
+
+    let links = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM exhibit_parts")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(links, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_exhibits_dry_run_then_upsert() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    insert_fake_exhibits(&pool, 1).await;
+
+    // The floor team's old headers, an unknown column and one row that fails validation
+    let sheet = "\u{feff}ExhibitName,ExhibitDescription,BuildingLocation,CurrentStatus,Cluster,Notes,Owner\n\
+                 Big Lever,Lift an engine,Outside,Needs Repair,Physics,\"Repainted, Feb. 2023\",Kim\n\
+                 Exhibit 0,Updated,Main Hall,Operational,Physics,,Kim\n\
+                 ,No name,Outside,Broken,Physics,,Kim\n";
+
+    let parsed = parse_sheet(ImportFormat::Csv, sheet.as_bytes())?;
+    assert_eq!(parsed.ignored_columns, vec!["Owner".to_string()]);
+    assert_eq!(parsed.rows.len(), 3);
+    assert_eq!(parsed.rows[0].line, 2);
+    assert_eq!(parsed.rows[0].status, "needs repair");
+    assert_eq!(
+        parsed.rows[0].notes.as_deref(),
+        Some("Repainted, Feb. 2023")
+    );
+    assert!(parsed.rows[0]
+        .validate("https://example.com/x.jpg")
+        .is_empty());
+    assert_eq!(
+        parsed.rows[2].validate("https://example.com/x.jpg").len(),
+        2
+    );
+
+    let valid = &parsed.rows[..2];
+    let image = "https://example.com/x.jpg";
+
+    let outcomes = exhibit_repo::import_exhibits(valid, image, true, &pool).await?;
+    assert_eq!(
+        outcomes,
+        vec![ImportOutcome::Created, ImportOutcome::Updated]
+    );
+    assert_eq!(
+        exhibit_repo::get_all_exhibits(&pool).await?.unwrap().len(),
+        1
+    );
+
+    // Importing twice updates by name and doesn't duplicate the legacy note
+    exhibit_repo::import_exhibits(valid, image, false, &pool).await?;
+    let outcomes = exhibit_repo::import_exhibits(valid, image, false, &pool).await?;
+    assert_eq!(
+        outcomes,
+        vec![ImportOutcome::Updated, ImportOutcome::Updated]
+    );
+
+    let exhibits = exhibit_repo::get_all_exhibits(&pool).await?.unwrap();
+    assert_eq!(exhibits.len(), 2);
+
+    let updated = exhibits.iter().find(|e| e.name == "Exhibit 0").unwrap();
+    assert_eq!(updated.description, "Updated");
+    assert_eq!(updated.image_url, "https://example.com/exhibit.jpg");
+
+    let lever = exhibits.iter().find(|e| e.name == "Big Lever").unwrap();
+    assert_eq!(lever.image_url, image);
+    assert_eq!(lever.notes.len(), 1);
+    assert_eq!(lever.notes[0].submitter, IMPORTED_NOTE_SUBMITTER);
+
+    assert!(parse_sheet(ImportFormat::Csv, b"Name,Cluster\nA,B\n").is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_pages_and_formats_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::export::{csv_stream, json_stream, paged, xlsx_workbook};
+    use rocket::futures::StreamExt;
+
+    let pool = setup_test_db().await;
+    insert_fake_exhibits(&pool, 5).await;
+
+    // Pages of two, filtered by name, the way the export handlers walk the table
+    let pages = |search: &str| {
+        let pool = pool.clone();
+        let filter = ExhibitFilter {
+            search: Some(search.to_string()),
+            ..ExhibitFilter::default()
+        };
+        paged(
+            0,
+            |exhibit: &Exhibit| exhibit.id,
+            move |after_id| {
+                let pool = pool.clone();
+                let filter = filter.clone();
+                async move { exhibit_repo::find_exhibits(&filter, after_id, Some(2), &pool).await }
+            },
+        )
+    };
+
+    assert_eq!(pages("exhibit").count().await, 3);
+    assert_eq!(pages("exhibit 3").count().await, 1);
+
+    let csv = csv_stream(pages("exhibit")).concat().await;
+    let sheet = parse_sheet(ImportFormat::Csv, &csv)?;
+    assert_eq!(sheet.rows.len(), 5);
+    assert_eq!(sheet.rows[4].name, "Exhibit 4");
+    assert!(sheet.ignored_columns.contains(&"Note Count".to_string()));
+
+    let xlsx = xlsx_workbook(pages("exhibit")).await?;
+    let sheet = parse_sheet(ImportFormat::Xlsx, &xlsx)?;
+    assert_eq!(sheet.rows.len(), 5);
+    assert_eq!(sheet.rows[0].line, 2);
+    assert_eq!(sheet.rows[0].status, "operational");
+
+    let json = json_stream(pages("exhibit")).concat().await;
+    let exhibits: Vec<Exhibit> = serde_json::from_slice(&json)?;
+    assert_eq!(exhibits.len(), 5);
+
+    let json = json_stream(pages("nothing matches")).concat().await;
+    assert_eq!(json, b"[]");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_stops_at_a_failed_page() {
+    use crate::export::{csv_stream, paged};
+    use rocket::futures::StreamExt;
+
+    // The second page fails to load, after the response would have started
+    let pages = paged(
+        0,
+        |id: &i64| *id,
+        |after_id| async move {
+            match after_id {
+                0 => Ok(vec![1, 2]),
+                _ => Err(sqlx::Error::PoolTimedOut),
+            }
+        },
+    );
+    assert_eq!(pages.collect::<Vec<_>>().await, vec![vec![1, 2]]);
+
+    let pages = paged(
+        0,
+        |exhibit: &Exhibit| exhibit.id,
+        |_| async { Err(sqlx::Error::PoolTimedOut) },
+    );
+    let csv = String::from_utf8(csv_stream(pages).concat().await).unwrap();
+    assert_eq!(csv.lines().count(), 1);
+}
+
+#[tokio::test]
+async fn test_snapshot_round_trip_remaps_ids() -> Result<(), Box<dyn std::error::Error>> {
+    let source = setup_test_db().await;
+    let ids = insert_fake_exhibits(&source, 2).await;
+    let date = |s: &str| s.parse::<chrono::NaiveDate>().unwrap();
+
+    part_repo::create_part(
+        &crate::api::part_handlers::NewPart {
+            name: "Bulb".to_string(),
+            link: "https://example.com/bulb".to_string(),
+            exhibit_ids: vec![ids[1]],
+            notes: vec![],
+        },
+        &source,
+    )
+    .await?;
+
+    let note_id = note_repo::create_note(
+        NoteTarget::Exhibit,
+        &ids[1].to_string(),
+        "Kenneth",
+        "Rewired",
+        chrono::Utc::now(),
+        &source,
+    )
+    .await?;
+    note_repo::create_attachment(note_id, "wiring.txt", "text/plain", b"red to red", &source)
+        .await?;
+
+    let task = NewMaintenanceTask {
+        title: "Clean lenses".to_string(),
+        description: String::new(),
+        interval_every: 14,
+        interval_unit: IntervalUnit::Days,
+        next_due_date: None,
+        auto_create_ticket: true,
+    };
+    let task_id = maintenance_repo::create_task(ids[1], &task, date("2030-01-10"), &source).await?;
+    maintenance_repo::create_due_tickets(date("2030-01-10"), &source).await?;
+    let ticket_id = maintenance_repo::maintenance_ticket_id(task_id, date("2030-01-10"));
+    note_repo::create_note(
+        NoteTarget::Ticket,
+        &ticket_id,
+        "Kenneth",
+        "On it",
+        chrono::Utc::now(),
+        &source,
+    )
+    .await?;
+
+    exhibit_repo::delete_exhibit(ids[0], &source).await?;
+
+    let root = std::env::temp_dir().join(format!(
+        "exhibit-snapshot-test-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let (images_dir, staging) = (root.join("images"), root.join("staging"));
+    std::fs::create_dir_all(&images_dir)?;
+    std::fs::create_dir_all(&staging)?;
+    std::fs::write(images_dir.join("photo.jpg"), b"jpeg")?;
+
+    let (snapshot, attachments) = snapshot_repo::read_snapshot(&source).await?;
+    let path = root.join("snapshot.zip");
+    crate::snapshot::write_archive(&snapshot, &attachments, &images_dir, &path)?;
+    let archive = crate::snapshot::read_archive(&path, &staging)?;
+    assert_eq!(archive.images, vec!["photo.jpg".to_string()]);
+    assert_eq!(std::fs::read(staging.join("photo.jpg"))?, b"jpeg");
+
+    // Images are moved into place, or listed if they can't be
+    let new_images = root.join("new-images");
+    assert!(crate::snapshot::move_images(&staging, &new_images, &archive.images).is_empty());
+    assert_eq!(std::fs::read(new_images.join("photo.jpg"))?, b"jpeg");
+    assert_eq!(
+        crate::snapshot::move_images(&staging, &new_images, &archive.images),
+        vec!["photo.jpg".to_string()]
+    );
+    std::fs::remove_dir_all(&root)?;
+
+    // The target has used IDs before, so restored records can't keep their old ones
+    let target = setup_test_db().await;
+    let used = insert_fake_exhibits(&target, 3).await;
+    maintenance_repo::create_task(used[0], &task, date("2030-01-10"), &target).await?;
+    for id in &used {
+        exhibit_repo::delete_exhibit(*id, &target).await?;
+    }
+    trash_repo::purge_expired(chrono::Utc::now(), &target).await?;
+
+    let summary = snapshot_repo::restore_snapshot(&archive.snapshot, &archive.attachments, &target)
+        .await?
+        .unwrap();
+    assert_eq!(summary.exhibits, 2);
+    assert_eq!(summary.parts, 1);
+    assert_eq!(summary.maintenance_tasks, 1);
+    assert_eq!(summary.tickets, 1);
+    assert_eq!(summary.notes, 2);
+    assert_eq!(summary.attachments, 1);
+
+    // The trashed exhibit comes along, still in the trash
+    let exhibits = exhibit_repo::get_all_exhibits(&target).await?.unwrap();
+    assert_eq!(exhibits.len(), 1);
+    let exhibit = &exhibits[0];
+    assert_eq!(exhibit.name, "Exhibit 1");
+    assert_ne!(exhibit.id, ids[1]);
+    assert_eq!(
+        trash_repo::get_trash(None, chrono::TimeDelta::days(30), &target)
+            .await?
+            .len(),
+        1
+    );
+
+    let part = part_repo::get_part(exhibit.part_ids[0], &target)
+        .await?
+        .unwrap();
+    assert_eq!(part.name, "Bulb");
+    assert_eq!(part.exhibit_ids, vec![exhibit.id]);
+
+    assert_eq!(exhibit.notes.len(), 1);
+    let note = &exhibit.notes[0];
+    assert_eq!(note.attachments.len(), 1);
+    let content = note_repo::get_attachment_content(note.id, note.attachments[0].id, &target)
+        .await?
+        .unwrap();
+    assert_eq!(content.data, b"red to red");
+
+    // The maintenance ticket and its note follow the task's new ID
+    let tasks = maintenance_repo::get_exhibit_tasks(exhibit.id, &target).await?;
+    assert_eq!(tasks.len(), 1);
+    let new_ticket_id = maintenance_repo::maintenance_ticket_id(tasks[0].id, date("2030-01-10"));
+    assert_ne!(new_ticket_id, ticket_id);
+    assert!(jotform_repo::get_jotform(new_ticket_id.clone(), &target)
+        .await?
+        .is_some());
+    assert_eq!(
+        note_repo::get_notes(NoteTarget::Ticket, &new_ticket_id, &target)
+            .await?
+            .len(),
+        1
+    );
+
+    // Only an empty database can be restored into
+    assert!(
+        snapshot_repo::restore_snapshot(&archive.snapshot, &archive.attachments, &target)
+            .await?
+            .is_none()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_snapshot_archive_size_limits() -> Result<(), Box<dyn std::error::Error>> {
+    let pool = setup_test_db().await;
+    let (snapshot, attachments) = snapshot_repo::read_snapshot(&pool).await?;
+    let snapshot_len = serde_json::to_vec(&snapshot)?.len() as u64;
+
+    let root = std::env::temp_dir().join(format!(
+        "exhibit-snapshot-limits-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let (images_dir, staging) = (root.join("images"), root.join("staging"));
+    std::fs::create_dir_all(&images_dir)?;
+    std::fs::create_dir_all(&staging)?;
+    std::fs::write(images_dir.join("a.jpg"), vec![0; 1000])?;
+    std::fs::write(images_dir.join("b.jpg"), vec![0; 1000])?;
+    let path = root.join("snapshot.zip");
+    crate::snapshot::write_archive(&snapshot, &attachments, &images_dir, &path)?;
+
+    let room = snapshot_len + 2000;
+    assert!(crate::snapshot::read_archive_within(&path, &staging, room, room).is_ok());
+
+    let err = crate::snapshot::read_archive_within(&path, &staging, room, room - 1).unwrap_err();
+    assert!(err.contains("unpacks to more than"), "{}", err);
+
+    // Compressed the images are tiny, but that's not what's checked
+    assert!(std::fs::metadata(&path)?.len() < 1000);
+    let err = crate::snapshot::read_archive_within(&path, &staging, 999, room).unwrap_err();
+    assert!(err.contains("`images/a.jpg`"), "{}", err);
+
+    // No images directory yet is the same as an empty one
+    let missing = root.join("no-images");
+    crate::snapshot::write_archive(&snapshot, &attachments, &missing, &path)?;
+    assert!(crate::snapshot::read_archive(&path, &staging)?
+        .images
+        .is_empty());
+
+    std::fs::remove_dir_all(&root)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_snapshot_archives_that_cant_be_restored() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    let pool = setup_test_db().await;
+    let ids = insert_fake_exhibits(&pool, 1).await;
+    let note_id = note_repo::create_note(
+        NoteTarget::Exhibit,
+        &ids[0].to_string(),
+        "Kenneth",
+        "Rewired",
+        chrono::Utc::now(),
+        &pool,
+    )
+    .await?;
+    note_repo::create_attachment(note_id, "wiring.txt", "text/plain", b"red to red", &pool).await?;
+    let (snapshot, _) = snapshot_repo::read_snapshot(&pool).await?;
+    let attachment_id = snapshot.note_attachments[0].id;
+
+    let root = std::env::temp_dir().join(format!(
+        "exhibit-snapshot-errors-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let staging = root.join("staging");
+    std::fs::create_dir_all(&staging)?;
+    let path = root.join("snapshot.zip");
+    let write_zip = |entries: &[(String, Vec<u8>)]| -> std::io::Result<()> {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path)?);
+        for (name, contents) in entries {
+            zip.start_file(name.as_str(), SimpleFileOptions::default())?;
+            zip.write_all(contents)?;
+        }
+        zip.finish()?;
+        Ok(())
+    };
+    let attachment = (
+        format!("attachments/{}", attachment_id),
+        b"red to red".to_vec(),
+    );
+    let newer = crate::models::Snapshot {
+        format_version: crate::models::SNAPSHOT_FORMAT_VERSION + 1,
+        ..snapshot.clone()
+    };
+
+    for (entries, expected) in [
+        (vec![], "has no snapshot.json"),
+        (
+            vec![("images/photo.jpg".to_string(), b"jpeg".to_vec())],
+            "has no snapshot.json",
+        ),
+        (
+            vec![("snapshot.json".to_string(), b"{".to_vec())],
+            "Invalid snapshot.json",
+        ),
+        (
+            vec![
+                ("snapshot.json".to_string(), serde_json::to_vec(&newer)?),
+                attachment.clone(),
+            ],
+            "is newer than this server understands",
+        ),
+        (
+            vec![("snapshot.json".to_string(), serde_json::to_vec(&snapshot)?)],
+            "missing the contents of attachment",
+        ),
+    ] {
+        write_zip(&entries)?;
+        let err = crate::snapshot::read_archive(&path, &staging).unwrap_err();
+        assert!(err.contains(expected), "{}", err);
+    }
+
+    std::fs::write(&path, b"not a zip")?;
+    let err = crate::snapshot::read_archive(&path, &staging).unwrap_err();
+    assert!(err.contains("Invalid snapshot archive"), "{}", err);
+
+    // With everything it needs the same archive reads fine
+    write_zip(&[
+        ("snapshot.json".to_string(), serde_json::to_vec(&snapshot)?),
+        attachment,
+    ])?;
+    assert!(crate::snapshot::read_archive(&path, &staging).is_ok());
+
+    std::fs::remove_dir_all(&root)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_backup_verify_and_restore() -> Result<(), Box<dyn std::error::Error>> {
+    // VACUUM INTO can't copy an in-memory database to a file, so this one is on disk
+    let root = std::env::temp_dir().join(format!(
+        "exhibit-backup-test-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let images_dir = root.join("images");
+    std::fs::create_dir_all(&images_dir)?;
+    std::fs::write(images_dir.join("photo.jpg"), b"jpeg")?;
+
+    let pool = crate::db::create_pool(&root.join("exhibits.db").to_string_lossy()).await?;
+    setup_database(&pool).await?;
+    let ids = insert_fake_exhibits(&pool, 2).await;
+
+    let settings = BackupSettings {
+        dir: root.join("backups"),
+        interval: std::time::Duration::from_secs(60),
+        keep: 2,
+        include_images: true,
+    };
+
+    sqlx::query(
+        "INSERT INTO webhooks (url, events, secret, created_at)
+         VALUES ('https://example.com/hook', '[]', 'secret', '2030-01-01T00:00:00Z')",
+    )
+    .execute(&pool)
+    .await?;
+    sqlx::query(
+        "INSERT INTO webhook_deliveries (webhook_id, topic, payload, next_attempt_at, created_at)
+         VALUES (1, 'exhibit.created', '{}', '2030-01-01T00:00:00Z', '2030-01-01T00:00:00Z')",
+    )
+    .execute(&pool)
+    .await?;
+
+    let first = backup::create_backup(&pool, &settings, &images_dir).await?;
+    assert!(first.includes_images);
+
+    exhibit_repo::delete_exhibit(ids[0], &pool).await?;
+    trash_repo::purge_expired(chrono::Utc::now(), &pool).await?;
+    exhibit_repo::create_exhibit(&fake_exhibit("Added later"), &pool).await?;
+    std::fs::remove_file(images_dir.join("photo.jpg"))?;
+    let outbox_len = || async {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM webhook_outbox")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+    let unsent = outbox_len().await;
+
+    // Restoring brings back the data and images, and keeps what it replaced
+    let previous = backup::restore_backup(&pool, &settings, &first.name, &images_dir).await?;
+    let names: Vec<String> = exhibit_repo::get_all_exhibits(&pool)
+        .await?
+        .unwrap()
+        .into_iter()
+        .map(|exhibit| exhibit.name)
+        .collect();
+    assert_eq!(names, vec!["Exhibit 0", "Exhibit 1"]);
+    assert_eq!(std::fs::read(images_dir.join("photo.jpg"))?, b"jpeg");
+
+    // Changes waiting to go out still go, and the backup's old deliveries aren't resent
+    assert_eq!(outbox_len().await, unsent + 1);
+    let delivery: (String, Option<String>) =
+        sqlx::query_as("SELECT status, next_attempt_at FROM webhook_deliveries")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(delivery, ("failed".to_string(), None));
+
+    // The connection used goes back to the pool as it came out
+    let mut conn = pool.acquire().await?;
+    let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+        .fetch_one(&mut *conn)
+        .await?;
+    assert_eq!(foreign_keys, 1);
+    let databases: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_database_list")
+        .fetch_all(&mut *conn)
+        .await?;
+    assert_eq!(databases, vec!["main"]);
+    drop(conn);
+
+    // The next exhibit doesn't reuse the ID of the one added after the backup
+    exhibit_repo::create_exhibit(&fake_exhibit("Newest"), &pool).await?;
+    let newest = sqlx::query_scalar::<_, i64>("SELECT MAX(id) FROM exhibits")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(newest, ids[1] + 2);
+
+    let backups = backup::list_backups(&settings.dir)?;
+    assert_eq!(backups.len(), 2);
+    assert_eq!(backups[0].name, previous.name);
+
+    // Only the newest backups are kept
+    backup::create_backup(&pool, &settings, &images_dir).await?;
+    let backups = backup::list_backups(&settings.dir)?;
+    assert_eq!(backups.len(), 2);
+    assert!(backups.iter().all(|backup| backup.name != first.name));
+
+    // Damaged and unknown backups are refused without touching the data
+    std::fs::write(settings.dir.join("backup_damaged.db"), b"not a database")?;
+    assert!(matches!(
+        backup::restore_backup(&pool, &settings, "backup_damaged.db", &images_dir).await,
+        Err(BackupError::Corrupt(_))
+    ));
+    assert!(matches!(
+        backup::restore_backup(&pool, &settings, "../exhibits.db", &images_dir).await,
+        Err(BackupError::NotFound)
+    ));
+    std::fs::write(settings.dir.join("backup_damaged.zip"), b"not an archive")?;
+    assert!(matches!(
+        backup::restore_backup(&pool, &settings, "backup_damaged.zip", &images_dir).await,
+        Err(BackupError::Zip(_))
+    ));
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(
+        settings.dir.join("backup_no_database.zip"),
+    )?);
+    zip.start_file("images/photo.jpg", zip::write::SimpleFileOptions::default())?;
+    std::io::Write::write_all(&mut zip, b"jpeg")?;
+    zip.finish()?;
+    assert!(matches!(
+        backup::restore_backup(&pool, &settings, "backup_no_database.zip", &images_dir).await,
+        Err(BackupError::Zip(_))
+    ));
+    // None of them took a safety backup or left a half-extracted database behind
+    let mut left: Vec<_> = std::fs::read_dir(&settings.dir)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<Result<_, _>>()?;
+    left.sort();
+    assert_eq!(left.len(), 5, "{:?}", left);
+    assert!(left
+        .iter()
+        .all(|name| name.to_string_lossy().starts_with("backup_")));
+    assert_eq!(
+        exhibit_repo::get_all_exhibits(&pool).await?.unwrap().len(),
+        3
+    );
+
+    pool.close().await;
+    std::fs::remove_dir_all(&root)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restore_the_only_backup_kept() -> Result<(), Box<dyn std::error::Error>> {
+    let root = std::env::temp_dir().join(format!(
+        "exhibit-backup-keep-test-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let images_dir = root.join("images");
+    std::fs::create_dir_all(&images_dir)?;
+    std::fs::write(images_dir.join("photo.jpg"), b"jpeg")?;
+
+    let pool = crate::db::create_pool(&root.join("exhibits.db").to_string_lossy()).await?;
+    setup_database(&pool).await?;
+    insert_fake_exhibits(&pool, 1).await;
+
+    let settings = BackupSettings {
+        dir: root.join("backups"),
+        interval: std::time::Duration::from_secs(60),
+        keep: 1,
+        include_images: true,
+    };
+    let only = backup::create_backup(&pool, &settings, &images_dir).await?;
+
+    exhibit_repo::create_exhibit(&fake_exhibit("Added later"), &pool).await?;
+    std::fs::remove_file(images_dir.join("photo.jpg"))?;
+
+    // The safety backup doesn't push out the one being restored before its images are read
+    let previous = backup::restore_backup(&pool, &settings, &only.name, &images_dir).await?;
+    assert_eq!(
+        exhibit_repo::get_all_exhibits(&pool).await?.unwrap().len(),
+        1
+    );
+    assert_eq!(std::fs::read(images_dir.join("photo.jpg"))?, b"jpeg");
+
+    // Pruning happens once the restore is done
+    let backups = backup::list_backups(&settings.dir)?;
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].name, previous.name);
+
+    pool.close().await;
+    std::fs::remove_dir_all(&root)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_writes_publish_change_events() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::events::{self, Action, Entity};
+    use rocket::tokio::sync::broadcast::error::TryRecvError;
+
+    let pool = setup_test_db().await;
+    let mut changes = events::subscribe();
+
+    let id = insert_fake_exhibits(&pool, 1).await[0];
+    exhibit_repo::change_exhibit_status(id, "needs repair".to_string(), &pool).await?;
+    exhibit_repo::delete_exhibit(id, &pool).await?;
+    trash_repo::restore(TrashEntity::Exhibit, &id.to_string(), &pool).await?;
+
+    // Other tests write at the same time, so look for this test's events among theirs
+    let mut received = Vec::new();
+    loop {
+        match changes.try_recv() {
+            Ok(change) if change.id == Some(id.to_string()) => received.push(change),
+            Ok(_) | Err(TryRecvError::Lagged(_)) => {}
+            Err(_) => break,
+        }
+    }
+    let created = received
+        .iter()
+        .find(|change| change.topic == "exhibit.created")
+        .expect("no exhibit.created event");
+    assert_eq!(created.entity, Entity::Exhibit);
+    let updated = received
+        .iter()
+        .find(|change| change.action == Action::Updated && change.data["status"] == "needs repair")
+        .expect("no exhibit.updated event");
+    assert_eq!(updated.topic, "exhibit.updated");
+    for topic in ["exhibit.deleted", "exhibit.restored"] {
+        assert!(
+            received.iter().any(|change| change.topic == topic),
+            "no {}",
+            topic
+        );
+    }
+
+    // Listeners can narrow down to an entity or a topic, but always hear about restores
+    let filters = |topics: &[&str]| topics.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    assert!(updated.matches(&[]));
+    assert!(updated.matches(&filters(&["part", "exhibit"])));
+    assert!(updated.matches(&filters(&["exhibit.updated"])));
+    assert!(!updated.matches(&filters(&["exhibit.deleted", "note"])));
+    assert!(!updated.matches(&filters(&["exhib"])));
+    let replaced = events::ChangeEvent::new(Entity::Data, Action::Replaced, None);
+    assert_eq!(replaced.topic, "data.replaced");
+    assert!(replaced.matches(&filters(&["part"])));
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use crate::db::DbPool;
+use crate::events::{self, Action, ChangeEvent, Entity};
+use crate::models::{TrashEntity, TrashItem};
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::Result;
+
+#[derive(sqlx::FromRow)]
+struct TrashRow {
+    entity: TrashEntity,
+    id: String,
+    name: String,
+    deleted_at: DateTime<Utc>,
+}
+
+/// Lists everything in the trash, most recently deleted first.
+///
+/// `retention` is how long items stay in the trash and is used to fill in `purge_after`.
+pub async fn get_trash(
+    entity: Option<TrashEntity>,
+    retention: TimeDelta,
+    pool: &DbPool,
+) -> Result<Vec<TrashItem>> {
+    let rows = sqlx::query_as::<_, TrashRow>(
+        "SELECT entity, id, name, deleted_at FROM (
+             SELECT 'exhibit' AS entity, CAST(id AS TEXT) AS id, name, deleted_at
+             FROM exhibits WHERE deleted_at IS NOT NULL
+             UNION ALL
+             SELECT 'part', CAST(id AS TEXT), name, deleted_at
+             FROM parts WHERE deleted_at IS NOT NULL
+             UNION ALL
+             SELECT 'ticket', id, exhibit_name, deleted_at
+             FROM jotforms WHERE deleted_at IS NOT NULL
+         )
+         WHERE ?1 IS NULL OR entity = ?1
+         ORDER BY deleted_at DESC",
+    )
+    .bind(entity)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TrashItem {
+            entity: row.entity,
+            id: row.id,
+            name: row.name,
+            deleted_at: row.deleted_at,
+            purge_after: row.deleted_at + retention,
+        })
+        .collect())
+}
+
+/// Takes a record back out of the trash.
+///
+/// Returns `false` if there's no such record in the trash.
+pub async fn restore(entity: TrashEntity, id: &str, pool: &DbPool) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(&format!(
+        "UPDATE {} SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+        entity.table()
+    ))
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let entity = match entity {
+        TrashEntity::Exhibit => Entity::Exhibit,
+        TrashEntity::Part => Entity::Part,
+        TrashEntity::Ticket => Entity::Jotform,
+    };
+    let event = ChangeEvent::new(entity, Action::Restored, Some(id.to_string()));
+    events::record(&event, &mut *tx).await?;
+    tx.commit().await?;
+
+    events::publish(event);
+
+    Ok(true)
+}
+
+/// Permanently deletes everything that went into the trash at or before `before`.
+///
+/// This is the only place exhibits, parts and tickets are really deleted, so their notes,
+/// sponsorships and maintenance tasks go with them here. Purged ticket IDs are remembered so
+/// the Jotform sync doesn't bring them back. Returns the number of records purged.
+pub async fn purge_expired(before: DateTime<Utc>, pool: &DbPool) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut purged = 0;
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO purged_jotforms (id)
+         SELECT id FROM jotforms WHERE deleted_at <= ?1",
+    )
+    .bind(before)
+    .execute(&mut *tx)
+    .await?;
+
+    // exhibit_parts only has a foreign key to exhibits, so a part's links are removed by hand
+    sqlx::query(
+        "DELETE FROM exhibit_parts
+         WHERE part_id IN (SELECT id FROM parts WHERE deleted_at <= ?1)",
+    )
+    .bind(before)
+    .execute(&mut *tx)
+    .await?;
+
+    for entity in [TrashEntity::Exhibit, TrashEntity::Part, TrashEntity::Ticket] {
+        purged += sqlx::query(&format!(
+            "DELETE FROM {} WHERE deleted_at <= ?1",
+            entity.table()
+        ))
+        .bind(before)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+
+    tx.commit().await?;
+
+    Ok(purged)
+}
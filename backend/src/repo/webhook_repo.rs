@@ -0,0 +1,337 @@
+use crate::api::webhook_handlers::NewWebhook;
+use crate::db::DbPool;
+use crate::events::ChangeEvent;
+use crate::models::{DeliveryStatus, Webhook, WebhookDelivery};
+use chrono::{DateTime, Utc};
+use rocket::serde::json::serde_json;
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{Result, SqliteExecutor};
+
+/// Webhook filters are stored one per line.
+const EVENT_SEPARATOR: &str = "\n";
+
+#[derive(sqlx::FromRow)]
+struct WebhookRow {
+    id: i64,
+    url: String,
+    events: String,
+    active: bool,
+    created_at: DateTime<Utc>,
+}
+
+impl From<WebhookRow> for Webhook {
+    fn from(row: WebhookRow) -> Self {
+        Webhook {
+            id: row.id,
+            url: row.url,
+            events: row
+                .events
+                .split(EVENT_SEPARATOR)
+                .map(str::to_string)
+                .collect(),
+            active: row.active,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// An active webhook and the events it's sent.
+#[derive(Debug, sqlx::FromRow)]
+pub struct WebhookTarget {
+    pub id: i64,
+    pub events: String,
+}
+
+impl WebhookTarget {
+    pub fn filters(&self) -> Vec<String> {
+        self.events
+            .split(EVENT_SEPARATOR)
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// A delivery that's due to be tried.
+#[derive(Debug, sqlx::FromRow)]
+pub struct DueDelivery {
+    pub id: i64,
+    pub url: String,
+    pub secret: String,
+    pub topic: String,
+    pub payload: String,
+    pub attempts: i64,
+}
+
+pub async fn create_webhook_tables(conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL,
+            events TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            active INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            webhook_id INTEGER NOT NULL,
+            topic TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at TEXT,
+            last_status_code INTEGER,
+            last_error TEXT,
+            created_at TEXT NOT NULL,
+            delivered_at TEXT,
+            FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due
+         ON webhook_deliveries (status, next_attempt_at)",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Change events written along with the changes, waiting to be turned into deliveries
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhook_outbox (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            event TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Adds a change event to the outbox.
+pub async fn record_event<'e>(
+    event: &ChangeEvent,
+    executor: impl SqliteExecutor<'e>,
+) -> Result<()> {
+    sqlx::query("INSERT INTO webhook_outbox (event) VALUES (?1)")
+        .bind(serde_json::to_string(event).expect("change events always serialize"))
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Lists the oldest events in the outbox, with their outbox IDs.
+pub async fn get_outbox(limit: i64, pool: &DbPool) -> Result<Vec<(i64, String)>> {
+    sqlx::query_as("SELECT id, event FROM webhook_outbox ORDER BY id LIMIT ?1")
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+}
+
+/// Removes an event from the outbox once its deliveries are queued.
+pub async fn remove_from_outbox(id: i64, conn: &mut SqliteConnection) -> Result<()> {
+    sqlx::query("DELETE FROM webhook_outbox WHERE id = ?1")
+        .bind(id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_webhooks(pool: &DbPool) -> Result<Vec<Webhook>> {
+    let rows = sqlx::query_as::<_, WebhookRow>(
+        "SELECT id, url, events, active, created_at FROM webhooks ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Webhook::from).collect())
+}
+
+pub async fn get_webhook(id: i64, pool: &DbPool) -> Result<Option<Webhook>> {
+    let row = sqlx::query_as::<_, WebhookRow>(
+        "SELECT id, url, events, active, created_at FROM webhooks WHERE id = ?1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(Webhook::from))
+}
+
+/// Adds a webhook. `webhook.secret` must be set.
+pub async fn create_webhook(webhook: &NewWebhook, pool: &DbPool) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO webhooks (url, events, secret, active, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .bind(&webhook.url)
+    .bind(webhook.events.join(EVENT_SEPARATOR))
+    .bind(&webhook.secret)
+    .bind(webhook.active)
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Replaces a webhook's settings, keeping its secret if no new one is given.
+pub async fn update_webhook(id: i64, webhook: &NewWebhook, pool: &DbPool) -> Result<()> {
+    let result = sqlx::query(
+        "UPDATE webhooks
+         SET url = ?1, events = ?2, secret = COALESCE(?3, secret), active = ?4
+         WHERE id = ?5",
+    )
+    .bind(&webhook.url)
+    .bind(webhook.events.join(EVENT_SEPARATOR))
+    .bind(&webhook.secret)
+    .bind(webhook.active)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
+
+/// Deletes a webhook along with its delivery log.
+pub async fn delete_webhook(id: i64, pool: &DbPool) -> Result<()> {
+    let result = sqlx::query("DELETE FROM webhooks WHERE id = ?1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
+
+pub async fn get_active_targets(conn: &mut SqliteConnection) -> Result<Vec<WebhookTarget>> {
+    sqlx::query_as::<_, WebhookTarget>("SELECT id, events FROM webhooks WHERE active = 1")
+        .fetch_all(conn)
+        .await
+}
+
+/// Queues a payload for a webhook, due straight away.
+pub async fn enqueue_delivery(
+    webhook_id: i64,
+    topic: &str,
+    payload: &str,
+    now: DateTime<Utc>,
+    conn: &mut SqliteConnection,
+) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO webhook_deliveries (webhook_id, topic, payload, next_attempt_at, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?4)",
+    )
+    .bind(webhook_id)
+    .bind(topic)
+    .bind(payload)
+    .bind(now)
+    .execute(conn)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Lists pending deliveries due by `now` to active webhooks, oldest first.
+pub async fn get_due_deliveries(
+    now: DateTime<Utc>,
+    limit: i64,
+    pool: &DbPool,
+) -> Result<Vec<DueDelivery>> {
+    sqlx::query_as::<_, DueDelivery>(
+        "SELECT d.id, w.url, w.secret, d.topic, d.payload, d.attempts
+         FROM webhook_deliveries d
+         JOIN webhooks w ON w.id = d.webhook_id
+         WHERE d.status = 'pending' AND w.active = 1 AND d.next_attempt_at <= ?1
+         ORDER BY d.next_attempt_at, d.id
+         LIMIT ?2",
+    )
+    .bind(now)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Records an attempt at a delivery.
+///
+/// A failed attempt is retried at `retry_at`, or given up on if that's `None`.
+pub async fn record_attempt(
+    id: i64,
+    status_code: Option<u16>,
+    error: Option<&str>,
+    retry_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> Result<()> {
+    let status = match (error, retry_at) {
+        (None, _) => DeliveryStatus::Delivered,
+        (Some(_), Some(_)) => DeliveryStatus::Pending,
+        (Some(_), None) => DeliveryStatus::Failed,
+    };
+
+    sqlx::query(
+        "UPDATE webhook_deliveries
+         SET status = ?1, attempts = attempts + 1, next_attempt_at = ?2,
+             last_status_code = ?3, last_error = ?4,
+             delivered_at = CASE WHEN ?1 = 'delivered' THEN ?5 END
+         WHERE id = ?6",
+    )
+    .bind(status)
+    .bind(if status == DeliveryStatus::Pending {
+        retry_at
+    } else {
+        None
+    })
+    .bind(status_code)
+    .bind(error)
+    .bind(now)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Lists a webhook's deliveries, newest first.
+pub async fn get_deliveries(
+    webhook_id: i64,
+    status: Option<DeliveryStatus>,
+    limit: i64,
+    pool: &DbPool,
+) -> Result<Vec<WebhookDelivery>> {
+    sqlx::query_as::<_, WebhookDelivery>(
+        "SELECT id, webhook_id, topic, payload, status, attempts, next_attempt_at,
+                last_status_code, last_error, created_at, delivered_at
+         FROM webhook_deliveries
+         WHERE webhook_id = ?1 AND (?2 IS NULL OR status = ?2)
+         ORDER BY id DESC
+         LIMIT ?3",
+    )
+    .bind(webhook_id)
+    .bind(status)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,58 @@
+use chrono::TimeDelta;
+use std::future::Future;
+
+/// How long to wait between attempts at something that failed, and when to give up on it.
+///
+/// The first retry waits `first_delay`, and each one after waits twice as long as the last,
+/// up to `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// Attempts made before giving up.
+    pub max_attempts: i64,
+    pub first_delay: TimeDelta,
+    pub max_delay: TimeDelta,
+}
+
+impl Backoff {
+    /// How long to wait before retrying something that's failed `attempts` times, or `None`
+    /// if it's failed too often to try again.
+    pub fn delay(&self, attempts: i64) -> Option<TimeDelta> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+
+        let doublings = (attempts - 1).clamp(0, 16) as u32;
+        Some((self.first_delay * 2i32.pow(doublings)).min(self.max_delay))
+    }
+}
+
+/// Works through everything that's due, a batch at a time, returning how much was
+/// attempted.
+///
+/// `due` fetches up to `batch_size` items, and `attempt` handles one, recording how it went
+/// so it isn't fetched again until it's next due. Stops once a batch comes back short.
+pub async fn work_through_due<T, E, D, A>(
+    batch_size: i64,
+    mut due: impl FnMut(i64) -> D,
+    mut attempt: impl FnMut(T) -> A,
+) -> Result<usize, E>
+where
+    D: Future<Output = Result<Vec<T>, E>>,
+    A: Future<Output = Result<(), E>>,
+{
+    let mut attempted = 0;
+
+    loop {
+        let batch = due(batch_size).await?;
+        let count = batch.len();
+
+        for item in batch {
+            attempt(item).await?;
+            attempted += 1;
+        }
+
+        if count < batch_size as usize {
+            return Ok(attempted);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::models::{Snapshot, SNAPSHOT_FORMAT_VERSION};
+use log::error;
+use rocket::serde::json::serde_json;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const ATTACHMENTS_DIR: &str = "attachments/";
+const IMAGES_DIR: &str = "images/";
+
+/// The most a single entry of an uploaded archive may unpack to.
+const MAX_ENTRY_BYTES: u64 = 512 * 1024 * 1024;
+/// The most all entries of an uploaded archive may unpack to together.
+const MAX_TOTAL_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// The contents of a snapshot archive, as read by `read_archive`.
+///
+/// The archive is a zip holding `snapshot.json`, each note attachment's contents as
+/// `attachments/<id>`, and every uploaded image as `images/<file name>`. Images can be
+/// large, so they're unpacked to a staging directory rather than held in memory.
+#[derive(Debug)]
+pub struct SnapshotArchive {
+    pub snapshot: Snapshot,
+    /// Note attachment contents, keyed by attachment ID in the snapshot.
+    pub attachments: HashMap<i64, Vec<u8>>,
+    /// The file names of the images unpacked to the staging directory.
+    pub images: Vec<String>,
+}
+
+/// Whether `name` is a plain file name that's safe to write into the images directory.
+pub(crate) fn is_plain_file_name(name: &str) -> bool {
+    !name.starts_with('.') && Path::new(name).file_name().is_some_and(|n| n == name)
+}
+
+/// Writes a snapshot, its attachments and every image in `images_dir` into a zip archive at
+/// `path`. Images are copied straight from their files. A missing images directory just
+/// means there are no images yet.
+pub fn write_archive(
+    snapshot: &Snapshot,
+    attachments: &HashMap<i64, Vec<u8>>,
+    images_dir: &Path,
+    path: &Path,
+) -> zip::result::ZipResult<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    zip.start_file(SNAPSHOT_FILE, options)?;
+    serde_json::to_writer(&mut zip, snapshot).map_err(io::Error::from)?;
+
+    let mut attachment_ids: Vec<_> = attachments.keys().collect();
+    attachment_ids.sort();
+    for id in attachment_ids {
+        zip.start_file(format!("{}{}", ATTACHMENTS_DIR, id), options)?;
+        zip.write_all(&attachments[id])?;
+    }
+
+    for name in image_names(images_dir)? {
+        zip.start_file(format!("{}{}", IMAGES_DIR, name), options)?;
+        io::copy(&mut File::open(images_dir.join(name))?, &mut zip)?;
+    }
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+/// The names of the files in the images directory, in order.
+fn image_names(dir: &Path) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(names),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if entry.file_type()?.is_file() && is_plain_file_name(&name) {
+            names.push(name);
+        }
+    }
+
+    names.sort();
+
+    Ok(names)
+}
+
+/// Reads the snapshot archive at `path`, made by `write_archive`, unpacking its images into
+/// `staging_dir`.
+///
+/// Returns an error message if the file isn't a snapshot archive, was written by a newer
+/// version of the app, is missing an attachment's contents, or unpacks to more than the
+/// size limits allow. Entries that aren't part of the format, and images with unsafe file
+/// names, are ignored.
+pub fn read_archive(path: &Path, staging_dir: &Path) -> Result<SnapshotArchive, String> {
+    read_archive_within(path, staging_dir, MAX_ENTRY_BYTES, MAX_TOTAL_BYTES)
+}
+
+/// Like `read_archive`, but with the given limits on how much one entry, and all of them
+/// together, may unpack to.
+pub(crate) fn read_archive_within(
+    path: &Path,
+    staging_dir: &Path,
+    max_entry_bytes: u64,
+    max_total_bytes: u64,
+) -> Result<SnapshotArchive, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open the archive: {}", e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Invalid snapshot archive: {}", e))?;
+    let mut snapshot = None;
+    let mut attachments = HashMap::new();
+    let mut images = Vec::new();
+    let mut total_bytes = 0;
+
+    for index in 0..zip.len() {
+        let mut entry = zip
+            .by_index(index)
+            .map_err(|e| format!("Invalid snapshot archive: {}", e))?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        let name = entry.name().to_string();
+        let image = name
+            .strip_prefix(IMAGES_DIR)
+            .filter(|file_name| is_plain_file_name(file_name))
+            .map(str::to_string);
+        // The sizes in the archive can't be trusted, so stop reading just past the limit
+        let limit = max_entry_bytes.min(max_total_bytes - total_bytes);
+        let mut contents = Vec::new();
+        let read = match &image {
+            Some(file_name) => File::create(staging_dir.join(file_name))
+                .and_then(|mut file| io::copy(&mut (&mut entry).take(limit + 1), &mut file)),
+            None => (&mut entry)
+                .take(limit + 1)
+                .read_to_end(&mut contents)
+                .map(|len| len as u64),
+        }
+        .map_err(|e| format!("Failed to read `{}` from the archive: {}", name, e))?;
+
+        if read > max_entry_bytes {
+            return Err(format!(
+                "`{}` in the archive is larger than the {} bytes allowed",
+                name, max_entry_bytes
+            ));
+        }
+        total_bytes += read;
+        if total_bytes > max_total_bytes {
+            return Err(format!(
+                "The archive unpacks to more than the {} bytes allowed",
+                max_total_bytes
+            ));
+        }
+
+        if let Some(file_name) = image {
+            images.push(file_name);
+        } else if name == SNAPSHOT_FILE {
+            snapshot = Some(
+                serde_json::from_slice::<Snapshot>(&contents)
+                    .map_err(|e| format!("Invalid {}: {}", SNAPSHOT_FILE, e))?,
+            );
+        } else if let Some(id) = name.strip_prefix(ATTACHMENTS_DIR) {
+            if let Ok(id) = id.parse() {
+                attachments.insert(id, contents);
+            }
+        }
+    }
+
+    let snapshot = snapshot.ok_or_else(|| format!("The archive has no {}", SNAPSHOT_FILE))?;
+
+    if snapshot.format_version > SNAPSHOT_FORMAT_VERSION {
+        return Err(format!(
+            "Snapshot format {} is newer than this server understands ({})",
+            snapshot.format_version, SNAPSHOT_FORMAT_VERSION
+        ));
+    }
+
+    if let Some(attachment) = snapshot
+        .note_attachments
+        .iter()
+        .find(|attachment| !attachments.contains_key(&attachment.id))
+    {
+        return Err(format!(
+            "The archive is missing the contents of attachment {}",
+            attachment.id
+        ));
+    }
+
+    images.sort();
+    images.dedup();
+
+    Ok(SnapshotArchive {
+        snapshot,
+        attachments,
+        images,
+    })
+}
+
+/// Moves staged images into the images directory, replacing any with the same name.
+/// Returns the names of any that couldn't be moved.
+pub fn move_images(staging_dir: &Path, images_dir: &Path, images: &[String]) -> Vec<String> {
+    if let Err(e) = fs::create_dir_all(images_dir) {
+        error!("Failed to create {}: {}", images_dir.display(), e);
+        return images.to_vec();
+    }
+
+    let mut not_moved = Vec::new();
+    for name in images {
+        let (from, to) = (staging_dir.join(name), images_dir.join(name));
+        // Renaming fails across file systems, where copying still works
+        let moved = fs::rename(&from, &to).or_else(|_| fs::copy(&from, &to).map(|_| ()));
+        if let Err(e) = moved {
+            error!("Failed to move the image {} into place: {}", name, e);
+            not_moved.push(name.clone());
+        }
+    }
+
+    not_moved
+}
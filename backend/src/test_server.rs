@@ -0,0 +1,69 @@
+use rocket::tokio::io::{AsyncReadExt, AsyncWriteExt};
+use rocket::tokio::net::TcpListener;
+use rocket::tokio::{self};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// A request the stand-in server got.
+pub struct Received {
+    pub request_line: String,
+    /// Header names are lowercased.
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// A local stand-in for an HTTP server the app calls out to, like a webhook receiver or
+/// the GitHub API. Answers each request with the next of `responses`, then with empty 200s,
+/// and keeps what it was sent. Returns the server's base URL.
+pub async fn start(responses: Vec<(u16, &'static str)>) -> (String, Arc<Mutex<Vec<Received>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let responses = Arc::new(Mutex::new(VecDeque::from(responses)));
+
+    let log = received.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut data = Vec::new();
+            let mut buf = [0; 4096];
+
+            // Read the headers, then as much body as they say there is
+            let (head_len, head) = loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                data.extend_from_slice(&buf[..n]);
+                if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break (end + 4, String::from_utf8_lossy(&data[..end]).to_string());
+                }
+            };
+            let headers: HashMap<String, String> = head
+                .lines()
+                .skip(1)
+                .filter_map(|line| line.split_once(':'))
+                .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+                .collect();
+            let length: usize = headers["content-length"].parse().unwrap();
+            while data.len() < head_len + length {
+                let n = socket.read(&mut buf).await.unwrap();
+                data.extend_from_slice(&buf[..n]);
+            }
+
+            log.lock().unwrap().push(Received {
+                request_line: head.lines().next().unwrap_or_default().to_string(),
+                headers,
+                body: String::from_utf8_lossy(&data[head_len..]).to_string(),
+            });
+
+            let (status, body) = responses.lock().unwrap().pop_front().unwrap_or((200, ""));
+            let response = format!(
+                "HTTP/1.1 {} Whatever\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (base_url, received)
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use log::warn;
+use std::sync::OnceLock;
+
+/// The site's time zone when `site_timezone` isn't configured.
+pub const DEFAULT_SITE_TIME_ZONE: Tz = chrono_tz::America::Chicago;
+
+static SITE_TIME_ZONE: OnceLock<Tz> = OnceLock::new();
+
+/// Sets the site time zone from the config. Only the first call has any effect, and it
+/// must come before anything reads the time zone.
+pub fn set_site_time_zone(time_zone: Tz) {
+    if SITE_TIME_ZONE.set(time_zone).is_err() {
+        warn!(
+            "The site time zone was already set, keeping {}",
+            site_time_zone()
+        );
+    }
+}
+
+/// The IANA time zone (e.g. `America/Chicago`) the museum operates in.
+///
+/// Set once at startup from the config. Times are always stored as UTC; the site time zone
+/// decides where days start and end (due dates, expiring sponsorships) and how times are
+/// shown to people.
+pub fn site_time_zone() -> Tz {
+    *SITE_TIME_ZONE.get_or_init(|| DEFAULT_SITE_TIME_ZONE)
+}
+
+/// Today's date at the site.
+pub fn site_today() -> NaiveDate {
+    to_site_date(Utc::now())
+}
+
+/// The date at the site when `at` happened.
+pub fn to_site_date(at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&site_time_zone()).date_naive()
+}
+
+/// Converts a wall-clock time at the site to UTC.
+///
+/// Times repeated when clocks fall back resolve to the earlier instant. Times skipped when
+/// clocks spring forward don't exist, so `None` is returned.
+pub fn site_local_to_utc(local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    match site_time_zone().from_local_datetime(&local) {
+        LocalResult::Single(at) | LocalResult::Ambiguous(at, _) => Some(at.with_timezone(&Utc)),
+        LocalResult::None => None,
+    }
+}
@@ -0,0 +1,226 @@
+#[cfg(test)]
+mod tests;
+
+use crate::db::DbPool;
+use crate::events::{self, ChangeEvent};
+use crate::logging;
+use crate::repo::webhook_repo::{self, DueDelivery};
+use crate::retry::{self, Backoff};
+use chrono::{DateTime, TimeDelta, Utc};
+use hmac::{Hmac, Mac};
+use log::{error, info, warn};
+use rocket::serde::json::serde_json;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::tokio::time::sleep;
+use sha2::Sha256;
+use sqlx::sqlite::SqliteConnection;
+use std::time::Duration;
+
+/// The header holding `sha256=` and the hex HMAC-SHA256 of the body, keyed by the secret.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature-256";
+
+/// The header holding the event's topic, e.g. `exhibit.updated`.
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// The header holding the delivery's ID, which stays the same across retries.
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+/// How long to wait between attempts at a delivery, and when to give up on it.
+pub const BACKOFF: Backoff = Backoff {
+    max_attempts: 8,
+    first_delay: TimeDelta::seconds(30),
+    max_delay: TimeDelta::hours(6),
+};
+
+/// How often to check for retries that have come due, and for outbox events that weren't
+/// published.
+const RETRY_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long a receiver gets to answer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many deliveries are sent per batch.
+const BATCH_SIZE: i64 = 50;
+
+/// Signs a payload, giving the value of the `X-Webhook-Signature-256` header.
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body.as_bytes());
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Queues an event for every active webhook with a filter it matches, returning how many
+/// deliveries were queued.
+async fn enqueue(
+    event: &ChangeEvent,
+    now: DateTime<Utc>,
+    conn: &mut SqliteConnection,
+) -> sqlx::Result<usize> {
+    let targets = webhook_repo::get_active_targets(&mut *conn).await?;
+    let mut queued = 0;
+    let mut payload = None;
+
+    for target in targets {
+        if !target
+            .filters()
+            .iter()
+            .any(|filter| event.filter_matches(filter))
+        {
+            continue;
+        }
+
+        let payload = payload.get_or_insert_with(|| {
+            serde_json::to_string(event).expect("change events always serialize")
+        });
+        webhook_repo::enqueue_delivery(target.id, &event.topic, payload, now, &mut *conn).await?;
+        queued += 1;
+    }
+
+    Ok(queued)
+}
+
+/// Queues deliveries for every event in the outbox, returning how many were queued.
+///
+/// Each event's deliveries are queued in the same transaction that takes it out of the
+/// outbox, so an event is never lost or queued twice.
+pub async fn enqueue_outbox(now: DateTime<Utc>, pool: &DbPool) -> sqlx::Result<usize> {
+    let mut queued = 0;
+
+    loop {
+        let outbox = webhook_repo::get_outbox(BATCH_SIZE, pool).await?;
+        let batch = outbox.len();
+
+        for (id, event) in outbox {
+            let mut tx = pool.begin().await?;
+            match serde_json::from_str::<ChangeEvent>(&event) {
+                Ok(event) => queued += enqueue(&event, now, &mut tx).await?,
+                Err(e) => error!(outbox_id = id; "Dropping an unreadable change event: {}", e),
+            }
+            webhook_repo::remove_from_outbox(id, &mut tx).await?;
+            tx.commit().await?;
+        }
+
+        if batch < BATCH_SIZE as usize {
+            return Ok(queued);
+        }
+    }
+}
+
+/// Sends every delivery that's due by `now`, returning how many were attempted.
+///
+/// Failed deliveries are rescheduled with backoff, or marked failed once they've been tried
+/// `BACKOFF.max_attempts` times.
+pub async fn deliver_due(
+    client: &reqwest::Client,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> sqlx::Result<usize> {
+    retry::work_through_due(
+        BATCH_SIZE,
+        |limit| webhook_repo::get_due_deliveries(now, limit, pool),
+        |delivery| attempt_delivery(client, delivery, now, pool),
+    )
+    .await
+}
+
+/// Sends one delivery and records how it went.
+async fn attempt_delivery(
+    client: &reqwest::Client,
+    delivery: DueDelivery,
+    now: DateTime<Utc>,
+    pool: &DbPool,
+) -> sqlx::Result<()> {
+    let (status_code, failure) = send(client, &delivery).await;
+    let retry_at = match &failure {
+        Some(_) => BACKOFF
+            .delay(delivery.attempts + 1)
+            .map(|delay| now + delay),
+        None => None,
+    };
+
+    match (&failure, retry_at) {
+        (None, _) => {}
+        (Some(e), Some(retry_at)) => warn!(
+            delivery_id = delivery.id,
+            topic = delivery.topic.as_str(),
+            retry_at = retry_at.to_rfc3339().as_str();
+            "Webhook delivery failed, will retry: {}", e
+        ),
+        (Some(e), None) => error!(
+            delivery_id = delivery.id,
+            topic = delivery.topic.as_str();
+            "Webhook delivery failed, giving up: {}", e
+        ),
+    }
+
+    webhook_repo::record_attempt(
+        delivery.id,
+        status_code,
+        failure.as_deref(),
+        retry_at,
+        now,
+        pool,
+    )
+    .await
+}
+
+/// POSTs a delivery, returning the receiver's status code, if it answered, and why the
+/// attempt failed, if it did.
+async fn send(client: &reqwest::Client, delivery: &DueDelivery) -> (Option<u16>, Option<String>) {
+    let response = client
+        .post(&delivery.url)
+        .header("Content-Type", "application/json")
+        .header(SIGNATURE_HEADER, sign(&delivery.secret, &delivery.payload))
+        .header(EVENT_HEADER, &delivery.topic)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => (
+            Some(response.status().as_u16()),
+            Some(format!("Receiver answered {}", response.status())),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Sends change events to webhooks until the app shuts down.
+///
+/// Changes are written to the outbox along with the changes themselves. Their deliveries
+/// are queued and sent as soon as they're published, and retries are sent as they come
+/// due. Anything left in the outbox or the queue before a restart is picked up again.
+pub async fn run(pool: DbPool) {
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Failed to create the webhook client: {}", e);
+            return;
+        }
+    };
+
+    let mut changes = events::subscribe();
+    loop {
+        let sent = logging::in_span("webhook_delivery", async {
+            enqueue_outbox(Utc::now(), &pool).await?;
+            deliver_due(&client, Utc::now(), &pool).await
+        });
+        if let Err(e) = sent.await {
+            error!("Failed to send webhook deliveries: {}", e);
+        }
+
+        // Published events are only a nudge to check the outbox, so missing some is fine
+        select! {
+            change = changes.recv() => if let Err(RecvError::Closed) = change {
+                info!("Change events closed, no more webhooks will be queued");
+                return;
+            },
+            _ = sleep(RETRY_POLL_INTERVAL) => {}
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use super::*;
+use crate::api::webhook_handlers::NewWebhook;
+use crate::db::setup_database;
+use crate::events::{Action, Entity};
+use crate::exhibit_import::ImportRow;
+use crate::models::DeliveryStatus;
+use crate::repo::exhibit_repo;
+use crate::test_server;
+use rocket::serde::json::{json, Value};
+use rocket::tokio;
+use sqlx::SqlitePool;
+
+const SECRET: &str = "a-long-enough-test-secret";
+
+async fn setup_test_db() -> DbPool {
+    let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+    setup_database(&pool).await.unwrap();
+
+    pool
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .no_proxy()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap()
+}
+
+fn status_change(status: &str) -> ChangeEvent {
+    ChangeEvent::new(Entity::Exhibit, Action::Updated, Some("7".to_string()))
+        .with_data(json!({ "status": status }))
+}
+
+#[test]
+fn test_retry_delay_backs_off_then_gives_up() {
+    assert_eq!(BACKOFF.delay(1), Some(TimeDelta::seconds(30)));
+    assert_eq!(BACKOFF.delay(2), Some(TimeDelta::seconds(60)));
+    assert_eq!(BACKOFF.delay(3), Some(TimeDelta::minutes(2)));
+    assert_eq!(BACKOFF.delay(7), Some(TimeDelta::minutes(32)));
+    assert_eq!(BACKOFF.delay(BACKOFF.max_attempts), None);
+}
+
+#[tokio::test]
+async fn test_matching_events_are_signed_and_retried_until_delivered() {
+    let pool = setup_test_db().await;
+    let (base_url, received) = test_server::start(vec![(500, "")]).await;
+
+    let webhook = NewWebhook {
+        url: format!("{}/hook", base_url),
+        events: vec![
+            "exhibit:status=out of service".to_string(),
+            "jotform.created:priority_level=High".to_string(),
+        ],
+        secret: Some(SECRET.to_string()),
+        active: true,
+    };
+    let webhook_id = webhook_repo::create_webhook(&webhook, &pool).await.unwrap();
+
+    // Only events matching one of the webhook's filters are queued
+    let now = Utc::now();
+    events::record(&status_change("operational"), &pool)
+        .await
+        .unwrap();
+    assert_eq!(enqueue_outbox(now, &pool).await.unwrap(), 0);
+    events::record(&status_change("Out of service"), &pool)
+        .await
+        .unwrap();
+    assert_eq!(enqueue_outbox(now, &pool).await.unwrap(), 1);
+    assert!(webhook_repo::get_outbox(10, &pool)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // The receiver fails the first attempt, so it's retried after a wait
+    let client = client();
+    assert_eq!(deliver_due(&client, now, &pool).await.unwrap(), 1);
+    let deliveries = webhook_repo::get_deliveries(webhook_id, None, 10, &pool)
+        .await
+        .unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].status, DeliveryStatus::Pending);
+    assert_eq!(deliveries[0].attempts, 1);
+    assert_eq!(deliveries[0].last_status_code, Some(500));
+    assert!(deliveries[0].last_error.is_some());
+    assert_eq!(
+        deliveries[0].next_attempt_at,
+        Some(now + BACKOFF.first_delay)
+    );
+
+    assert_eq!(deliver_due(&client, now, &pool).await.unwrap(), 0);
+    let later = now + TimeDelta::minutes(1);
+    assert_eq!(deliver_due(&client, later, &pool).await.unwrap(), 1);
+
+    let delivered =
+        webhook_repo::get_deliveries(webhook_id, Some(DeliveryStatus::Delivered), 10, &pool)
+            .await
+            .unwrap();
+    assert_eq!(delivered.len(), 1);
+    assert_eq!(delivered[0].attempts, 2);
+    assert_eq!(delivered[0].last_status_code, Some(200));
+    assert_eq!(delivered[0].delivered_at, Some(later));
+    assert_eq!(delivered[0].next_attempt_at, None);
+
+    // Both attempts were the same, signed delivery
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0].body, received[1].body);
+    let request = &received[1];
+    assert_eq!(
+        request.headers["x-webhook-signature-256"],
+        sign(SECRET, &request.body)
+    );
+    assert_eq!(request.headers["x-webhook-event"], "exhibit.updated");
+    assert_eq!(
+        request.headers["x-webhook-delivery"],
+        delivered[0].id.to_string()
+    );
+    let body: Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body["topic"], "exhibit.updated");
+    assert_eq!(body["id"], "7");
+    assert_eq!(body["data"]["status"], "Out of service");
+}
+
+#[tokio::test]
+async fn test_inactive_webhooks_are_not_sent_events() {
+    let pool = setup_test_db().await;
+
+    let webhook = NewWebhook {
+        url: "http://127.0.0.1:9/hook".to_string(),
+        events: vec!["exhibit".to_string()],
+        secret: Some(SECRET.to_string()),
+        active: false,
+    };
+    webhook_repo::create_webhook(&webhook, &pool).await.unwrap();
+
+    events::record(&status_change("Out of service"), &pool)
+        .await
+        .unwrap();
+    assert_eq!(enqueue_outbox(Utc::now(), &pool).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_changes_reach_the_outbox_without_a_listener() {
+    let pool = setup_test_db().await;
+
+    let webhook = NewWebhook {
+        url: "http://127.0.0.1:9/hook".to_string(),
+        events: vec!["exhibit.updated:status=out of service".to_string()],
+        secret: Some(SECRET.to_string()),
+        active: true,
+    };
+    webhook_repo::create_webhook(&webhook, &pool).await.unwrap();
+
+    // Nothing is subscribed to published events here, so deliveries can only come from the
+    // outbox the changes were written to
+    let row = |name: &str, status: &str| ImportRow {
+        line: 2,
+        name: name.to_string(),
+        cluster: "Space".to_string(),
+        location: "Deep Space".to_string(),
+        description: String::new(),
+        status: status.to_string(),
+        image_url: None,
+        notes: None,
+    };
+    exhibit_repo::import_exhibits(&[row("Moon Chair", "out of service")], "", false, &pool)
+        .await
+        .unwrap();
+    // Imports publish one event per exhibit, with its status, so they can be filtered
+    exhibit_repo::import_exhibits(
+        &[
+            row("Moon Chair", "out of service"),
+            row("Pendulum", "operational"),
+        ],
+        "",
+        false,
+        &pool,
+    )
+    .await
+    .unwrap();
+
+    let queued = enqueue_outbox(Utc::now(), &pool).await.unwrap();
+    assert_eq!(queued, 1);
+    let due = webhook_repo::get_due_deliveries(Utc::now(), 10, &pool)
+        .await
+        .unwrap();
+    let body: Value = serde_json::from_str(&due[0].payload).unwrap();
+    assert_eq!(body["topic"], "exhibit.updated");
+    assert_eq!(body["data"]["status"], "out of service");
+}